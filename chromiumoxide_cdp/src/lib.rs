@@ -4,7 +4,8 @@ use crate::cdp::browser_protocol::fetch;
 use crate::cdp::browser_protocol::network::{self, CookieParam, DeleteCookiesParams};
 use crate::cdp::browser_protocol::target::CreateTargetParams;
 use crate::cdp::js_protocol::runtime::{
-    CallFunctionOnParams, EvaluateParams, ExceptionDetails, StackTrace,
+    CallArgument, CallFunctionOnParams, EvaluateParams, ExceptionDetails, RemoteObjectId,
+    StackTrace,
 };
 
 pub mod cdp;
@@ -60,6 +61,47 @@ impl From<network::RequestId> for network::InterceptionId {
     }
 }
 
+impl network::ResourceType {
+    /// Whether the resource is a document loaded into a frame
+    pub fn is_document(&self) -> bool {
+        matches!(self, network::ResourceType::Document)
+    }
+
+    /// Whether the resource is a stylesheet
+    pub fn is_stylesheet(&self) -> bool {
+        matches!(self, network::ResourceType::Stylesheet)
+    }
+
+    /// Whether the resource is an image
+    pub fn is_image(&self) -> bool {
+        matches!(self, network::ResourceType::Image)
+    }
+
+    /// Whether the resource is audio or video
+    pub fn is_media(&self) -> bool {
+        matches!(self, network::ResourceType::Media)
+    }
+
+    /// Whether the resource is a font
+    pub fn is_font(&self) -> bool {
+        matches!(self, network::ResourceType::Font)
+    }
+
+    /// Whether the resource is a script
+    pub fn is_script(&self) -> bool {
+        matches!(self, network::ResourceType::Script)
+    }
+
+    /// Whether the resource was requested by a script with `XMLHttpRequest`
+    /// or `fetch`
+    pub fn is_xhr(&self) -> bool {
+        matches!(
+            self,
+            network::ResourceType::Xhr | network::ResourceType::Fetch
+        )
+    }
+}
+
 impl DeleteCookiesParams {
     /// Create a new instance from a `CookieParam`
     pub fn from_cookie(param: &CookieParam) -> Self {
@@ -89,6 +131,28 @@ impl From<EvaluateParams> for CallFunctionOnParams {
     }
 }
 
+/// CallArgument conversion
+
+impl From<serde_json::Value> for CallArgument {
+    fn from(value: serde_json::Value) -> Self {
+        CallArgument {
+            value: Some(value),
+            unserializable_value: None,
+            object_id: None,
+        }
+    }
+}
+
+impl From<RemoteObjectId> for CallArgument {
+    fn from(object_id: RemoteObjectId) -> Self {
+        CallArgument {
+            value: None,
+            unserializable_value: None,
+            object_id: Some(object_id),
+        }
+    }
+}
+
 impl fmt::Display for ExceptionDetails {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         writeln!(
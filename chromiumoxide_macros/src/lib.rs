@@ -0,0 +1,132 @@
+//! Procedural macros for chromiumoxide.
+//!
+//! These are re-exported by `chromiumoxide` behind the `testing` and `derive`
+//! features and should not be used directly.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, DeriveInput, ItemFn, Lit, Meta, NestedMeta, Visibility};
+
+/// Marks an async function as browser test.
+///
+/// The test receives a `Page` that lives in its own incognito
+/// `BrowserContext` of a browser that is shared by all tests of the test
+/// binary. The context is disposed once the test finished, even if the test
+/// panicked.
+///
+/// ```ignore
+/// #[chromiumoxide::test]
+/// async fn loads_page(page: Page) {
+///     page.goto("https://example.com").await.unwrap();
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn test(args: TokenStream, item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as ItemFn);
+
+    if !args.is_empty() {
+        return syn::Error::new(
+            proc_macro2::TokenStream::from(args).span(),
+            "`chromiumoxide::test` does not take any arguments",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if input.sig.asyncness.is_none() {
+        return syn::Error::new(
+            input.sig.fn_token.span(),
+            "the `async` keyword is missing from the function declaration",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    if input.sig.inputs.len() != 1 {
+        return syn::Error::new(
+            input.sig.inputs.span(),
+            "browser tests take exactly one argument of type `Page`",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    let attrs = &input.attrs;
+    let vis = &input.vis;
+    let name = &input.sig.ident;
+    let output = &input.sig.output;
+
+    let mut inner = input.clone();
+    inner.attrs.clear();
+    inner.vis = Visibility::Inherited;
+
+    quote!(
+        #[::core::prelude::v1::test]
+        #(#attrs)*
+        #vis fn #name() #output {
+            #inner
+            ::chromiumoxide::testing::run(#name)
+        }
+    )
+    .into()
+}
+
+/// Implements `MethodType` and `CustomEvent` for a custom event, which also
+/// needs to implement `serde::Deserialize`.
+///
+/// The `method` of the event is set with the `event` attribute, like
+/// `#[event(method = "Vendor.somethingHappened")]`. See the re-export
+/// `chromiumoxide::CustomEvent` for an example.
+#[proc_macro_derive(CustomEvent, attributes(event))]
+pub fn derive_custom_event(item: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(item as DeriveInput);
+
+    let method = match event_method(&input) {
+        Ok(method) => method,
+        Err(err) => return err.to_compile_error().into(),
+    };
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote!(
+        impl #impl_generics ::chromiumoxide::types::MethodType for #name #ty_generics #where_clause {
+            fn method_id() -> ::chromiumoxide::types::MethodId {
+                #method.into()
+            }
+        }
+
+        impl #impl_generics ::chromiumoxide::cdp::CustomEvent for #name #ty_generics #where_clause {}
+    )
+    .into()
+}
+
+/// The `method` of the `#[event(method = "..")]` attribute
+fn event_method(input: &DeriveInput) -> syn::Result<String> {
+    let attr = input
+        .attrs
+        .iter()
+        .find(|attr| attr.path.is_ident("event"))
+        .ok_or_else(|| {
+            syn::Error::new(
+                input.ident.span(),
+                "custom events need an `#[event(method = \"Domain.event\")]` attribute",
+            )
+        })?;
+    if let Meta::List(list) = attr.parse_meta()? {
+        for nested in &list.nested {
+            if let NestedMeta::Meta(Meta::NameValue(value)) = nested {
+                if value.path.is_ident("method") {
+                    if let Lit::Str(method) = &value.lit {
+                        return Ok(method.value());
+                    }
+                }
+            }
+        }
+    }
+    Err(syn::Error::new(
+        attr.span(),
+        "expected `#[event(method = \"Domain.event\")]`",
+    ))
+}
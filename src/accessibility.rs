@@ -0,0 +1,313 @@
+//! Snapshots of the accessibility tree of a page.
+//!
+//! [`Page::accessibility_snapshot`](crate::page::Page::accessibility_snapshot)
+//! returns the tree of [`AccessibilityNode`]s that assistive technology like
+//! screen readers sees, with their roles, names and values.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::accessibility::AccessibilitySnapshotOptions;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let snapshot = page
+//!         .accessibility_snapshot(AccessibilitySnapshotOptions::default())
+//!         .await?;
+//!     if let Some(root) = snapshot {
+//!         for button in root.find_all("button", None) {
+//!             println!("button: {:?}", button.name);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::Serialize;
+
+use chromiumoxide_cdp::cdp::browser_protocol::accessibility::{
+    AxNode, AxNodeId, AxPropertyName, AxValue,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::dom::BackendNodeId;
+
+use crate::element::Element;
+
+/// How to take an accessibility snapshot.
+#[derive(Debug)]
+pub struct AccessibilitySnapshotOptions {
+    /// Whether to only include the nodes that are interesting to assistive
+    /// technology, like controls, focusable nodes and named leaves (defaults
+    /// to `true`)
+    pub interesting_only: bool,
+    /// The element whose subtree is snapshotted instead of the whole page
+    pub root: Option<Element>,
+}
+
+impl Default for AccessibilitySnapshotOptions {
+    fn default() -> Self {
+        Self {
+            interesting_only: true,
+            root: None,
+        }
+    }
+}
+
+/// A node of the accessibility tree.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessibilityNode {
+    /// The role, like `button` or `heading`
+    pub role: String,
+    /// The accessible name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// The value, like the text of a text field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<String>,
+    /// The accessible description
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The states and properties, like `focused`, `checked` or `level`
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, serde_json::Value>,
+    /// The DOM node of this node
+    #[serde(skip)]
+    pub backend_node_id: Option<BackendNodeId>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<AccessibilityNode>,
+}
+
+impl AccessibilityNode {
+    /// This node and all of its descendants in depth first order.
+    pub fn iter(&self) -> impl Iterator<Item = &AccessibilityNode> {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let node = stack.pop()?;
+            stack.extend(node.children.iter().rev());
+            Some(node)
+        })
+    }
+
+    /// All nodes of the subtree with the role and, if set, the name.
+    pub fn find_all(&self, role: &str, name: Option<&str>) -> Vec<&AccessibilityNode> {
+        self.iter()
+            .filter(|node| {
+                node.role == role && name.is_none_or(|name| node.name.as_deref() == Some(name))
+            })
+            .collect()
+    }
+}
+
+/// Roles of nodes that users interact with
+const CONTROL_ROLES: &[&str] = &[
+    "button",
+    "checkbox",
+    "ColorWell",
+    "combobox",
+    "DisclosureTriangle",
+    "listbox",
+    "menu",
+    "menubar",
+    "menuitem",
+    "menuitemcheckbox",
+    "menuitemradio",
+    "radio",
+    "scrollbar",
+    "searchbox",
+    "slider",
+    "spinbutton",
+    "switch",
+    "tab",
+    "textbox",
+    "tree",
+    "treeitem",
+];
+
+/// The text of a value
+fn value_text(value: &Option<AxValue>) -> Option<String> {
+    match value.as_ref()?.value.as_ref()? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        value => Some(value.to_string()),
+    }
+}
+
+/// Builds the tree of the nodes returned by `Accessibility.getFullAXTree`,
+/// starting at the node of `root` or at the first node.
+pub(crate) fn snapshot(
+    nodes: Vec<AxNode>,
+    root: Option<&BackendNodeId>,
+    interesting_only: bool,
+) -> Option<AccessibilityNode> {
+    let root = match root {
+        Some(root) => nodes
+            .iter()
+            .find(|node| node.backend_dom_node_id.as_ref() == Some(root))?,
+        None => nodes.first()?,
+    }
+    .node_id
+    .clone();
+    let nodes: HashMap<_, _> = nodes
+        .into_iter()
+        .map(|node| (node.node_id.clone(), node))
+        .collect();
+    let tree = Tree {
+        nodes: &nodes,
+        interesting_only,
+    };
+    let node = nodes.get(&root)?;
+    Some(tree.node(node, tree.children(node, false)))
+}
+
+struct Tree<'a> {
+    nodes: &'a HashMap<AxNodeId, AxNode>,
+    interesting_only: bool,
+}
+
+impl Tree<'_> {
+    fn property(node: &AxNode, name: AxPropertyName) -> Option<&serde_json::Value> {
+        node.properties
+            .iter()
+            .flatten()
+            .find(|property| property.name == name)
+            .and_then(|property| property.value.value.as_ref())
+    }
+
+    fn is_control(node: &AxNode) -> bool {
+        value_text(&node.role).is_some_and(|role| CONTROL_ROLES.contains(&role.as_str()))
+    }
+
+    fn is_focusable(node: &AxNode) -> bool {
+        Self::property(node, AxPropertyName::Focusable) == Some(&serde_json::Value::Bool(true))
+    }
+
+    fn is_interesting(node: &AxNode, inside_control: bool, is_leaf: bool) -> bool {
+        if node.ignored {
+            return false;
+        }
+        if Self::is_focusable(node) || Self::is_control(node) {
+            return true;
+        }
+        !inside_control && is_leaf && value_text(&node.name).is_some_and(|name| !name.is_empty())
+    }
+
+    /// The snapshotted children of the node, with the children of skipped
+    /// nodes in their place.
+    ///
+    /// The text inside of controls and focusable nodes is already their name
+    /// and skipped.
+    fn children(&self, node: &AxNode, inside_control: bool) -> Vec<AccessibilityNode> {
+        let inside_control = inside_control || Self::is_control(node) || Self::is_focusable(node);
+        node.child_ids
+            .iter()
+            .flatten()
+            .filter_map(|id| self.nodes.get(id))
+            .flat_map(|child| {
+                let children = self.children(child, inside_control);
+                let keep = if self.interesting_only {
+                    Self::is_interesting(child, inside_control, children.is_empty())
+                } else {
+                    !child.ignored
+                };
+                if keep {
+                    vec![self.node(child, children)]
+                } else {
+                    children
+                }
+            })
+            .collect()
+    }
+
+    fn node(&self, node: &AxNode, children: Vec<AccessibilityNode>) -> AccessibilityNode {
+        let properties = node
+            .properties
+            .iter()
+            .flatten()
+            .filter_map(|property| {
+                Some((
+                    property.name.as_ref().to_string(),
+                    property.value.value.clone()?,
+                ))
+            })
+            .collect();
+        AccessibilityNode {
+            role: value_text(&node.role).unwrap_or_default(),
+            name: value_text(&node.name).filter(|name| !name.is_empty()),
+            value: value_text(&node.value),
+            description: value_text(&node.description).filter(|d| !d.is_empty()),
+            properties,
+            backend_node_id: node.backend_dom_node_id,
+            children,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chromiumoxide_cdp::cdp::browser_protocol::accessibility::{AxProperty, AxValueType};
+
+    fn value(s: &str) -> AxValue {
+        let mut value = AxValue::new(AxValueType::String);
+        value.value = Some(s.into());
+        value
+    }
+
+    fn node(id: &str, role: &str, name: &str, children: &[&str]) -> AxNode {
+        AxNode {
+            node_id: AxNodeId::new(id),
+            ignored: role == "none",
+            ignored_reasons: None,
+            role: Some(value(role)),
+            name: Some(value(name)),
+            description: None,
+            value: None,
+            properties: None,
+            child_ids: Some(children.iter().map(|id| AxNodeId::new(*id)).collect()),
+            backend_dom_node_id: Some(BackendNodeId::new(id.parse::<i64>().unwrap())),
+        }
+    }
+
+    fn nodes() -> Vec<AxNode> {
+        let mut link = node("4", "link", "Home", &["5"]);
+        link.properties = Some(vec![AxProperty::new(
+            AxPropertyName::Focusable,
+            AxValue {
+                value: Some(true.into()),
+                ..AxValue::new(AxValueType::Boolean)
+            },
+        )]);
+        vec![
+            node("1", "RootWebArea", "Page", &["2", "4"]),
+            node("2", "none", "", &["3"]),
+            node("3", "heading", "Welcome", &[]),
+            link,
+            node("5", "StaticText", "Home", &[]),
+        ]
+    }
+
+    #[test]
+    fn snapshots_interesting_nodes() {
+        let root = snapshot(nodes(), None, true).unwrap();
+        assert_eq!(root.role, "RootWebArea");
+        let roles: Vec<_> = root.iter().map(|node| node.role.as_str()).collect();
+        assert_eq!(roles, ["RootWebArea", "heading", "link"]);
+        let link = root.find_all("link", Some("Home"));
+        assert_eq!(link[0].properties["focusable"], true);
+        assert!(link[0].children.is_empty());
+
+        let all = snapshot(nodes(), None, false).unwrap();
+        assert_eq!(all.iter().count(), 4);
+    }
+
+    #[test]
+    fn snapshots_subtrees() {
+        let root = BackendNodeId::new(4);
+        let link = snapshot(nodes(), Some(&root), false).unwrap();
+        assert_eq!(link.role, "link");
+        assert_eq!(link.children[0].role, "StaticText");
+        assert!(snapshot(nodes(), Some(&BackendNodeId::new(9)), true).is_none());
+    }
+}
@@ -0,0 +1,208 @@
+//! Auto-retrying assertions for end-to-end tests.
+//!
+//! Every assertion is re-evaluated until it holds or the timeout elapsed,
+//! which removes the need for sleep-and-check loops when the page is still
+//! loading or updating.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::assertions::expect;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.goto("https://example.com").await?;
+//!     expect(&page).to_have_title("Example Domain").await?;
+//!     expect(&page).to_have_count("h1", 1).await?;
+//!
+//!     let heading = page.find_element("h1").await?;
+//!     expect(&heading).to_have_text("Example Domain").await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::time::{Duration, Instant};
+
+use futures::Future;
+use futures_timer::Delay;
+
+use crate::element::Element;
+use crate::error::{CdpError, Result};
+use crate::page::Page;
+
+/// The default time after which an assertion fails
+pub const DEFAULT_ASSERTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The default interval between two evaluations of an assertion
+pub const DEFAULT_POLLING_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Start an assertion on a [`Page`] or an [`Element`]
+pub fn expect<T>(subject: &T) -> Expect<'_, T> {
+    Expect {
+        subject,
+        timeout: DEFAULT_ASSERTION_TIMEOUT,
+        interval: DEFAULT_POLLING_INTERVAL,
+    }
+}
+
+/// Assertions about the `subject` that are retried until they hold or the
+/// `timeout` elapsed.
+#[derive(Debug, Clone)]
+pub struct Expect<'a, T> {
+    subject: &'a T,
+    timeout: Duration,
+    interval: Duration,
+}
+
+impl<'a, T> Expect<'a, T> {
+    /// How long to retry the assertion before it fails.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// How long to wait between two evaluations of the assertion.
+    pub fn with_polling_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    async fn retry<F, Fut, V>(&self, expected: V, mut actual: F) -> Result<()>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<V>>,
+        V: PartialEq + std::fmt::Debug,
+    {
+        poll_until(self.timeout, self.interval, || {
+            let fut = actual();
+            let expected = &expected;
+            async move {
+                match fut.await {
+                    Ok(actual) if actual == *expected => Ok(()),
+                    Ok(actual) => Err(format!("expected {expected:?}, found {actual:?}")),
+                    Err(err) => Err(format!("expected {expected:?}, but failed with: {err}")),
+                }
+            }
+        })
+        .await
+    }
+}
+
+impl<'a> Expect<'a, Page> {
+    /// Asserts that the url of the page's main frame equals `url`.
+    pub async fn to_have_url(&self, url: impl Into<String>) -> Result<()> {
+        self.retry(Some(url.into()), || self.subject.url()).await
+    }
+
+    /// Asserts that the title of the document equals `title`.
+    pub async fn to_have_title(&self, title: impl Into<String>) -> Result<()> {
+        self.retry(Some(title.into()), || self.subject.get_title())
+            .await
+    }
+
+    /// Asserts that exactly `count` elements in the document match the
+    /// selector.
+    pub async fn to_have_count(&self, selector: impl Into<String>, count: usize) -> Result<()> {
+        let selector = selector.into();
+        self.retry(count, || async {
+            Ok(self.subject.find_elements(selector.clone()).await?.len())
+        })
+        .await
+    }
+}
+
+impl<'a> Expect<'a, Element> {
+    /// Asserts that the inner text of the element equals `text`.
+    pub async fn to_have_text(&self, text: impl Into<String>) -> Result<()> {
+        self.retry(Some(text.into()), || self.subject.inner_text())
+            .await
+    }
+
+    /// Asserts that the element's attribute `name` is set to `value`.
+    pub async fn to_have_attribute(
+        &self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> Result<()> {
+        let name = name.into();
+        self.retry(Some(value.into()), || self.subject.attribute(name.as_str()))
+            .await
+    }
+
+    /// Asserts that exactly `count` descendants of the element match the
+    /// selector.
+    pub async fn to_have_count(&self, selector: impl Into<String>, count: usize) -> Result<()> {
+        let selector = selector.into();
+        self.retry(count, || async {
+            Ok(self.subject.find_elements(selector.clone()).await?.len())
+        })
+        .await
+    }
+}
+
+/// Evaluates `check` every `interval` until it succeeds or the `timeout`
+/// elapsed, in which case the last failure is returned as
+/// `CdpError::AssertionFailed`.
+pub(crate) async fn poll_until<F, Fut>(
+    timeout: Duration,
+    interval: Duration,
+    mut check: F,
+) -> Result<()>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<(), String>>,
+{
+    let deadline = Instant::now() + timeout;
+    loop {
+        match check().await {
+            Ok(()) => return Ok(()),
+            Err(msg) => {
+                if Instant::now() + interval > deadline {
+                    return Err(CdpError::AssertionFailed(format!(
+                        "Assertion timed out after {timeout:?}: {msg}"
+                    )));
+                }
+            }
+        }
+        Delay::new(interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[async_std::test]
+    async fn retries_until_success() {
+        let mut attempts = 0;
+        poll_until(Duration::from_secs(1), Duration::from_millis(1), || {
+            attempts += 1;
+            let attempt = attempts;
+            async move {
+                if attempt < 3 {
+                    Err(format!("attempt {attempt}"))
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(attempts, 3);
+    }
+
+    #[async_std::test]
+    async fn fails_after_timeout() {
+        let err = poll_until(
+            Duration::from_millis(20),
+            Duration::from_millis(5),
+            || async { Err("never".to_string()) },
+        )
+        .await
+        .unwrap_err();
+        match err {
+            CdpError::AssertionFailed(msg) => assert!(msg.ends_with("never")),
+            err => panic!("unexpected error {err:?}"),
+        }
+    }
+}
@@ -0,0 +1,390 @@
+//! Exposing Rust callbacks to the scripts of a page.
+//!
+//! [`Page::expose_binding`](crate::page::Page::expose_binding) installs a
+//! function on `window` that forwards its arguments to a Rust handler and
+//! returns a `Promise` that settles with the handler's result. Every call is
+//! handed to the handler as a [`BindingCall`], which carries the arguments
+//! together with the execution context and frame the call originated from.
+//!
+//! A handler that returns a [`BindingError`] rejects the promise with a
+//! javascript `Error` of the same name and message, so page scripts can
+//! `try`/`catch` failures of the Rust side like any other exception.
+//!
+//! Handlers are invoked by the `Handler` while it processes the page's
+//! events, so they must return right away and must not block. Work that
+//! needs to wait, like I/O or calls back into the page, belongs in an async
+//! handler exposed with
+//! [`Page::expose_function`](crate::page::Page::expose_function), whose
+//! futures are driven by the `Handler` alongside the page's events.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::binding::BindingError;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.expose_binding("add", |call| {
+//!         let (a, b): (i64, i64) = call.args_as()?;
+//!         if a < 0 || b < 0 {
+//!             return Err(BindingError::new("negative numbers are not supported"));
+//!         }
+//!         Ok((a + b).into())
+//!     })
+//!     .await?;
+//!     let sum: i64 = page.evaluate("window.add(1, 2)").await?.into_value()?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use chromiumoxide_cdp::cdp::browser_protocol::page::FrameId;
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{EventBindingCalled, ExecutionContextId};
+
+/// A single call of an exposed binding by a page script.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindingCall {
+    name: String,
+    args: Vec<Value>,
+    execution_context_id: ExecutionContextId,
+    frame_id: Option<FrameId>,
+    frame_url: Option<String>,
+}
+
+impl BindingCall {
+    /// The name of the binding that was called.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The arguments the binding was called with.
+    pub fn args(&self) -> &[Value] {
+        &self.args
+    }
+
+    /// Deserializes the argument at `index`, missing arguments are `null`.
+    pub fn arg<T: serde::de::DeserializeOwned>(&self, index: usize) -> Result<T, BindingError> {
+        let arg = self.args.get(index).cloned().unwrap_or(Value::Null);
+        Ok(serde_json::from_value(arg)?)
+    }
+
+    /// Deserializes all arguments at once, e.g. into a tuple.
+    pub fn args_as<T: serde::de::DeserializeOwned>(&self) -> Result<T, BindingError> {
+        Ok(serde_json::from_value(Value::Array(self.args.clone()))?)
+    }
+
+    /// The execution context the binding was called in.
+    pub fn execution_context_id(&self) -> ExecutionContextId {
+        self.execution_context_id
+    }
+
+    /// The frame the binding was called in, if the execution context belongs
+    /// to a known frame.
+    pub fn frame_id(&self) -> Option<&FrameId> {
+        self.frame_id.as_ref()
+    }
+
+    /// The url of the frame the binding was called in.
+    pub fn frame_url(&self) -> Option<&str> {
+        self.frame_url.as_deref()
+    }
+}
+
+/// An error returned by a binding's handler that rejects the page's promise.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BindingError {
+    name: String,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    data: Option<Value>,
+}
+
+impl BindingError {
+    /// An `Error` with the given message.
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            name: "Error".to_string(),
+            message: message.into(),
+            data: None,
+        }
+    }
+
+    /// Sets the `name` of the javascript error, like `TypeError`.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Attaches structured data, available as the error's `data` property.
+    pub fn with_data(mut self, data: impl Into<Value>) -> Self {
+        self.data = Some(data.into());
+        self
+    }
+
+    /// The `name` of the javascript error.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The message of the error.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The data attached to the error.
+    pub fn data(&self) -> Option<&Value> {
+        self.data.as_ref()
+    }
+}
+
+impl fmt::Display for BindingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+impl From<serde_json::Error> for BindingError {
+    fn from(err: serde_json::Error) -> Self {
+        BindingError::new(err.to_string()).with_name("TypeError")
+    }
+}
+
+impl From<String> for BindingError {
+    fn from(message: String) -> Self {
+        BindingError::new(message)
+    }
+}
+
+impl From<&str> for BindingError {
+    fn from(message: &str) -> Self {
+        BindingError::new(message)
+    }
+}
+
+/// The handler of an exposed binding.
+pub type BindingHandler = Box<dyn FnMut(BindingCall) -> Result<Value, BindingError> + Send>;
+
+/// The handler of an exposed binding that answers calls asynchronously.
+pub type AsyncBindingHandler =
+    Box<dyn FnMut(BindingCall) -> BoxFuture<'static, Result<Value, BindingError>> + Send>;
+
+enum Handler {
+    Sync(BindingHandler),
+    Async(AsyncBindingHandler),
+}
+
+/// The expression that settles the page's promise of a binding call, once
+/// the handler's result is available.
+pub(crate) enum Settlement {
+    Ready(String),
+    Pending(BoxFuture<'static, String>),
+}
+
+/// The name of an exposed binding and the handler for its calls.
+pub struct Binding {
+    pub(crate) name: String,
+    handler: Handler,
+}
+
+impl Binding {
+    pub fn new(name: impl Into<String>, handler: BindingHandler) -> Self {
+        Self {
+            name: name.into(),
+            handler: Handler::Sync(handler),
+        }
+    }
+
+    /// A binding whose handler returns a future that resolves to the result.
+    pub fn new_async(name: impl Into<String>, handler: AsyncBindingHandler) -> Self {
+        Self {
+            name: name.into(),
+            handler: Handler::Async(handler),
+        }
+    }
+
+    /// The name of this binding.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Invokes the handler for the `event` and returns the expression that
+    /// settles the page's promise with the result, `None` if the event was
+    /// not emitted by the installed wrapper.
+    pub(crate) fn call(
+        &mut self,
+        event: &EventBindingCalled,
+        frame_id: Option<FrameId>,
+        frame_url: Option<String>,
+    ) -> Option<Settlement> {
+        let payload: BindingPayload = match serde_json::from_str(&event.payload) {
+            Ok(payload) => payload,
+            Err(err) => {
+                tracing::warn!("Invalid payload for binding {}: {}", self.name, err);
+                return None;
+            }
+        };
+        let call = BindingCall {
+            name: event.name.clone(),
+            args: payload.args,
+            execution_context_id: event.execution_context_id,
+            frame_id,
+            frame_url,
+        };
+        let seq = payload.seq;
+        match &mut self.handler {
+            Handler::Sync(handler) => Some(Settlement::Ready(settle_expression(
+                &self.name,
+                seq,
+                handler(call),
+            ))),
+            Handler::Async(handler) => {
+                let name = self.name.clone();
+                Some(Settlement::Pending(
+                    handler(call)
+                        .map(move |result| settle_expression(&name, seq, result))
+                        .boxed(),
+                ))
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Binding").field("name", &self.name).finish()
+    }
+}
+
+/// What the wrapper installed by [`EXPOSE_BINDING_JS`] sends to the binding.
+#[derive(Debug, Deserialize)]
+struct BindingPayload {
+    seq: u64,
+    #[serde(default)]
+    args: Vec<Value>,
+}
+
+/// Replaces the raw `Runtime` binding `name` with a function that tags every
+/// call with a sequence number and returns a promise that is settled by
+/// `settle_expression`.
+pub(crate) const EXPOSE_BINDING_JS: &str = r#"function(name) {
+    const binding = globalThis[name];
+    if (typeof binding !== 'function' || binding.__settle) {
+        return;
+    }
+    const callbacks = new Map();
+    let lastSeq = 0;
+    const exposed = (...args) => new Promise((resolve, reject) => {
+        const seq = ++lastSeq;
+        callbacks.set(seq, { resolve, reject });
+        binding(JSON.stringify({ seq, args }));
+    });
+    exposed.__settle = (seq, result) => {
+        const callback = callbacks.get(seq);
+        if (!callback) {
+            return;
+        }
+        callbacks.delete(seq);
+        if ('error' in result) {
+            const error = new Error(result.error.message);
+            error.name = result.error.name;
+            if ('data' in result.error) {
+                error.data = result.error.data;
+            }
+            callback.reject(error);
+        } else {
+            callback.resolve(result.value);
+        }
+    };
+    globalThis[name] = exposed;
+}"#;
+
+/// The expression that settles the promise of the call `seq` of the binding.
+fn settle_expression(name: &str, seq: u64, result: Result<Value, BindingError>) -> String {
+    let result = match result {
+        Ok(value) => serde_json::json!({ "value": value }),
+        Err(err) => serde_json::json!({ "error": err }),
+    };
+    format!(
+        "globalThis[{}].__settle({}, {})",
+        Value::String(name.to_string()),
+        seq,
+        result
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready(settlement: Option<Settlement>) -> Option<String> {
+        match settlement? {
+            Settlement::Ready(expr) => Some(expr),
+            Settlement::Pending(_) => panic!("sync bindings settle right away"),
+        }
+    }
+
+    fn event(payload: &str) -> EventBindingCalled {
+        EventBindingCalled {
+            name: "add".to_string(),
+            payload: payload.to_string(),
+            execution_context_id: ExecutionContextId::new(3),
+        }
+    }
+
+    #[test]
+    fn passes_call_context() {
+        let mut binding = Binding::new(
+            "add",
+            Box::new(|call| {
+                assert_eq!(call.execution_context_id(), ExecutionContextId::new(3));
+                assert_eq!(call.frame_url(), Some("https://example.com/"));
+                let (a, b): (i64, i64) = call.args_as()?;
+                Ok((a + b).into())
+            }),
+        );
+        let expr = ready(binding.call(
+            &event(r#"{"seq":7,"args":[1,2]}"#),
+            None,
+            Some("https://example.com/".to_string()),
+        ));
+        assert_eq!(
+            expr.as_deref(),
+            Some(r#"globalThis["add"].__settle(7, {"value":3})"#)
+        );
+    }
+
+    #[test]
+    fn rejects_with_errors() {
+        let mut binding = Binding::new("add", Box::new(|call| call.arg::<i64>(0).map(Value::from)));
+        let expr = ready(binding.call(&event(r#"{"seq":1,"args":["one"]}"#), None, None)).unwrap();
+        assert!(expr.starts_with(r#"globalThis["add"].__settle(1, {"error":{"#));
+        assert!(expr.contains(r#""name":"TypeError""#));
+
+        assert!(binding.call(&event("not json"), None, None).is_none());
+    }
+
+    #[test]
+    fn settles_async_calls() {
+        let mut binding = Binding::new_async(
+            "double",
+            Box::new(|call| {
+                async move {
+                    let n: i64 = call.arg(0)?;
+                    Ok((n * 2).into())
+                }
+                .boxed()
+            }),
+        );
+        let expr = match binding.call(&event(r#"{"seq":2,"args":[21]}"#), None, None) {
+            Some(Settlement::Pending(fut)) => fut.now_or_never().unwrap(),
+            _ => panic!("async bindings settle later"),
+        };
+        assert_eq!(expr, r#"globalThis["double"].__settle(2, {"value":42})"#);
+    }
+}
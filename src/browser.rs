@@ -1,31 +1,58 @@
-use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
+    fs,
     io::{self, BufRead, BufReader},
     path::{Path, PathBuf},
-    process::{self, Child, Stdio},
+    process::{self, Child, ChildStderr, ExitStatus, Stdio},
 };
 
-use futures::channel::mpsc::{channel, unbounded, Sender};
+use futures::channel::mpsc::{channel, unbounded, Sender, UnboundedReceiver, UnboundedSender};
 use futures::channel::oneshot::channel as oneshot_channel;
-use futures::SinkExt;
+use futures::stream::{self, BoxStream};
+use futures::{Future, FutureExt, SinkExt, StreamExt};
 
+use chromiumoxide_cdp::cdp::browser_protocol::browser::{
+    Bounds, GetWindowForTargetParams, SetWindowBoundsParams, WindowId, WindowState,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::io::{self as cdp_io, ReadParams, StreamHandle};
+use chromiumoxide_cdp::cdp::browser_protocol::network::{Cookie, CookieParam};
+use chromiumoxide_cdp::cdp::browser_protocol::storage::{
+    ClearCookiesParams, GetCookiesParams, SetCookiesParams,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::target::{
-    CreateBrowserContextParams, CreateTargetParams, DisposeBrowserContextParams, TargetId,
+    CreateBrowserContextParams, CreateTargetParams, DisposeBrowserContextParams,
+    GetTargetInfoParams, TargetId,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::tracing::{
+    EndParams, EventTracingComplete, StartParams, StartTransferMode, StreamFormat, TraceConfig,
+};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::CallArgument;
 use chromiumoxide_cdp::cdp::{CdpEventMessage, IntoEventKind};
 use chromiumoxide_types::*;
 
+use crate::client_cert::{self, ClientCertificate};
 use crate::cmd::{to_command_response, CommandMessage};
-use crate::conn::Connection;
+use crate::conn::{Connection, ConnectionState, Transport};
+use crate::context::{self, ContextOptions};
 use crate::error::{CdpError, Result};
 use crate::handler::browser::BrowserContext;
 use crate::handler::viewport::Viewport;
-use crate::handler::{Handler, HandlerConfig, HandlerMessage, REQUEST_TIMEOUT};
+use crate::handler::{Handler, HandlerConfig, HandlerMessage, PageHook, REQUEST_TIMEOUT};
 use crate::listeners::{EventListenerRequest, EventStream};
-use crate::page::Page;
+use crate::page::{Page, ScreenshotParams, IO_CHUNK_SIZE};
+use crate::permission::Permission;
+use crate::route::{
+    ContextRoute, InterceptedRequest, RouteAction, RouteResponse, SharedRouteHandler,
+};
+use crate::storage_state::{self, OriginState, StorageState};
+use crate::utils;
+use crate::worker::Worker;
 use chromiumoxide_cdp::cdp::browser_protocol::browser::{
-    CloseReturns, GetVersionParams, GetVersionReturns,
+    BrowserContextId, CloseReturns, GetVersionParams, GetVersionReturns, GrantPermissionsParams,
+    ResetPermissionsParams,
 };
 
 /// A [`Browser`] is created when chromiumoxide connects to a Chromium instance.
@@ -42,29 +69,97 @@ pub struct Browser {
     debug_ws_url: String,
     /// The context of the browser
     browser_context: BrowserContext,
+    /// The lines the spawned chromium instance writes to its stderr
+    stderr: Option<Arc<Mutex<StderrLogs>>>,
+    /// The user data directory created for the spawned chromium instance,
+    /// removed once it exited
+    temp_user_data_dir: Option<PathBuf>,
 }
 
 impl Browser {
     /// Connect to an already running chromium instance via websocket
     pub async fn connect(debug_ws_url: impl Into<String>) -> Result<(Self, Handler)> {
+        Self::connect_with_config(debug_ws_url, HandlerConfig::default()).await
+    }
+
+    /// Connect to an already running chromium instance via websocket with a
+    /// custom handler configuration.
+    ///
+    /// With a `HandlerConfig::reconnect` policy the handler re-dials the
+    /// websocket once the connection is lost, attaches to the pages again
+    /// and resumes their event subscriptions, see
+    /// [`Browser::connection_state`].
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::conn::ReconnectPolicy;
+    /// # use chromiumoxide::handler::HandlerConfig;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo() -> Result<()> {
+    ///     let config = HandlerConfig {
+    ///         reconnect: Some(ReconnectPolicy::default()),
+    ///         ..Default::default()
+    ///     };
+    ///     let (browser, handler) =
+    ///         Browser::connect_with_config("ws://127.0.0.1:9222/devtools/browser/id", config)
+    ///             .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn connect_with_config(
+        debug_ws_url: impl Into<String>,
+        config: HandlerConfig,
+    ) -> Result<(Self, Handler)> {
         let debug_ws_url = debug_ws_url.into();
         let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
 
         let (tx, rx) = channel(1);
 
-        let fut = Handler::new(conn, rx, HandlerConfig::default());
+        let fut = Handler::new(conn, rx, config);
         let browser_context = fut.default_browser_context().clone();
 
         let browser = Self {
             sender: tx,
             config: None,
             child: None,
+            temp_user_data_dir: None,
             debug_ws_url,
             browser_context,
+            stderr: None,
         };
         Ok((browser, fut))
     }
 
+    /// Connect to a chromium instance over a custom transport, like a
+    /// TLS-wrapped or tunneled connection, see [`Transport`].
+    ///
+    /// The browser has no websocket address and the handler doesn't
+    /// reconnect once the transport ends.
+    pub fn connect_with_transport(
+        transport: impl Transport + 'static,
+        config: HandlerConfig,
+    ) -> (Self, Handler) {
+        let conn = Connection::<CdpEventMessage>::with_transport(transport);
+
+        let (tx, rx) = channel(1);
+
+        let fut = Handler::new(conn, rx, config);
+        let browser_context = fut.default_browser_context().clone();
+
+        let browser = Self {
+            sender: tx,
+            config: None,
+            child: None,
+            temp_user_data_dir: None,
+            debug_ws_url: String::new(),
+            browser_context,
+            stderr: None,
+        };
+        (browser, fut)
+    }
+
     /// Launches a new instance of `chromium` in the background and attaches to
     /// its debug web socket.
     ///
@@ -72,27 +167,53 @@ impl Browser {
     ///
     /// This fails if no web socket url could be detected from the child
     /// processes stderr for more than 20 seconds.
-    pub async fn launch(config: BrowserConfig) -> Result<(Self, Handler)> {
+    ///
+    /// Unless the config sets a [`BrowserConfigBuilder::user_data_dir`],
+    /// chromium runs with a new temporary profile directory, which is removed
+    /// once the browser was closed or dropped.
+    pub async fn launch(mut config: BrowserConfig) -> Result<(Self, Handler)> {
+        // use a fresh user data directory for every instance unless specified
+        let temp_user_data_dir = if config.user_data_dir.is_none() {
+            let dir = temp_user_data_dir();
+            config.user_data_dir = Some(dir.clone());
+            Some(dir)
+        } else {
+            None
+        };
+
         // launch a new chromium instance
         let mut child = config.launch()?;
 
         // extract the ws:
-        let get_ws_url = ws_url_from_output(&mut child);
-
-        let dur = Duration::from_secs(20);
-
-        cfg_if::cfg_if! {
-            if #[cfg(feature = "async-std-runtime")] {
-                let debug_ws_url = async_std::future::timeout(dur, get_ws_url)
-            .await
-            .map_err(|_| CdpError::Timeout)?;
-            } else if #[cfg(feature = "tokio-runtime")] {
-                let debug_ws_url = tokio::time::timeout(dur, get_ws_url).await
-            .map_err(|_| CdpError::Timeout)?;
+        let stderr = Arc::new(Mutex::new(StderrLogs::default()));
+        let attach: Result<_> = async {
+            let get_ws_url = ws_url_from_output(&mut child, Arc::clone(&stderr));
+
+            let dur = Duration::from_secs(20);
+
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "async-std-runtime")] {
+                    let debug_ws_url = async_std::future::timeout(dur, get_ws_url)
+                .await
+                .map_err(|_| CdpError::Timeout)?;
+                } else if #[cfg(feature = "tokio-runtime")] {
+                    let debug_ws_url = tokio::time::timeout(dur, get_ws_url).await
+                .map_err(|_| CdpError::Timeout)?;
+                }
             }
-        }
 
-        let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
+            let conn = Connection::<CdpEventMessage>::connect(&debug_ws_url).await?;
+            Ok((debug_ws_url, conn))
+        }
+        .await;
+        let (debug_ws_url, conn) = match attach {
+            Ok(attached) => attached,
+            Err(err) => {
+                // don't leave the instance running
+                kill(child, temp_user_data_dir);
+                return Err(err);
+            }
+        };
 
         let (tx, rx) = channel(1);
 
@@ -103,6 +224,10 @@ impl Browser {
             request_timeout: config.request_timeout,
             request_intercept: config.request_intercept,
             cache_enabled: config.cache_enabled,
+            timezone_id: config.timezone_id.clone(),
+            locale: config.locale.clone(),
+            init_scripts: config.init_scripts.clone(),
+            reconnect: None,
         };
 
         let fut = Handler::new(conn, rx, handler_config);
@@ -114,12 +239,41 @@ impl Browser {
             child: Some(child),
             debug_ws_url,
             browser_context,
+            stderr: Some(stderr),
+            temp_user_data_dir,
         };
 
         Ok((browser, fut))
     }
 
+    /// Closes the browser.
+    ///
+    /// For a launched browser this also waits for the process to exit. If it
+    /// doesn't within the [`BrowserConfigBuilder::close_timeout`] it's
+    /// terminated and eventually killed. The process is reaped and its
+    /// temporary user data directory removed, even if sending `Browser.close`
+    /// failed.
     pub async fn close(&mut self) -> Result<CloseReturns> {
+        let res = self.send_close().await;
+
+        if let Some(child) = self.child.take() {
+            let timeout = self.close_timeout();
+            let user_data_dir = self.temp_user_data_dir.take();
+            let shutdown = move || shutdown(child, timeout, user_data_dir);
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "async-std-runtime")] {
+                    async_std::task::spawn_blocking(shutdown).await?;
+                } else if #[cfg(feature = "tokio-runtime")] {
+                    tokio::task::spawn_blocking(shutdown)
+                        .await
+                        .map_err(|err| CdpError::msg(err.to_string()))??;
+                }
+            }
+        }
+        res
+    }
+
+    async fn send_close(&self) -> Result<CloseReturns> {
         let (tx, rx) = oneshot_channel();
 
         self.sender
@@ -130,6 +284,14 @@ impl Browser {
         rx.await?
     }
 
+    /// How long to wait for the spawned chromium instance to exit
+    fn close_timeout(&self) -> Duration {
+        self.config
+            .as_ref()
+            .map(|config| config.close_timeout)
+            .unwrap_or(Duration::from_millis(CLOSE_TIMEOUT))
+    }
+
     /// If not launched as incognito this creates a new incognito browser
     /// context. After that this browser exists within the incognito session.
     /// New pages created while being in incognito mode will also run in the
@@ -137,15 +299,10 @@ impl Browser {
     /// other browser contexts.
     pub async fn start_incognito_context(&mut self) -> Result<&mut Self> {
         if !self.is_incognito_configured() {
-            let resp = self
-                .execute(CreateBrowserContextParams::default())
-                .await?
-                .result;
-            self.browser_context = BrowserContext::from(resp.browser_context_id);
-            self.sender
-                .clone()
-                .send(HandlerMessage::InsertContext(self.browser_context.clone()))
+            let id = self
+                .create_browser_context(CreateBrowserContextParams::default())
                 .await?;
+            self.browser_context = BrowserContext::from(id);
         }
 
         Ok(self)
@@ -168,6 +325,350 @@ impl Browser {
         Ok(self)
     }
 
+    /// Creates a new browser context that won't share cookies/cache with other
+    /// browser contexts and returns its identifier.
+    ///
+    /// Pages can be created within this context by setting
+    /// `CreateTargetParams::browser_context_id`.
+    /// `Browser::new_context` returns a handle to the context instead.
+    pub async fn create_browser_context(
+        &self,
+        params: CreateBrowserContextParams,
+    ) -> Result<BrowserContextId> {
+        let id = self.execute(params).await?.result.browser_context_id;
+        self.sender
+            .clone()
+            .send(HandlerMessage::InsertContext(BrowserContext::from(
+                id.clone(),
+            )))
+            .await?;
+        Ok(id)
+    }
+
+    /// Disposes the browser context with the given identifier.
+    ///
+    /// # Note This will also dispose all pages that were running within the
+    /// context.
+    pub async fn dispose_browser_context(&self, id: BrowserContextId) -> Result<&Self> {
+        self.execute(DisposeBrowserContextParams::new(id.clone()))
+            .await?;
+        self.sender
+            .clone()
+            .send(HandlerMessage::DisposeContext(BrowserContext::from(id)))
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates a loss of connectivity for all pages of this browser's
+    /// current context, including pages that are created while offline.
+    pub async fn set_offline(&self, offline: bool) -> Result<&Self> {
+        self.sender
+            .clone()
+            .send(HandlerMessage::SetOffline(
+                self.browser_context.clone(),
+                offline,
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates a loss of connectivity for all pages of the browser context
+    /// with the given identifier, see `Browser::set_offline`.
+    pub async fn set_browser_context_offline(
+        &self,
+        id: BrowserContextId,
+        offline: bool,
+    ) -> Result<&Self> {
+        self.sender
+            .clone()
+            .send(HandlerMessage::SetOffline(
+                BrowserContext::from(id),
+                offline,
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Grants the permissions to the `origin`, like `https://example.com`, in
+    /// this browser's current context, or to all origins if `None`.
+    ///
+    /// Permissions that aren't granted are denied without a prompt until
+    /// `Browser::clear_permission_overrides` is called.
+    pub async fn grant_permissions(
+        &self,
+        permissions: &[Permission],
+        origin: Option<&str>,
+    ) -> Result<&Self> {
+        let mut params =
+            GrantPermissionsParams::new(permissions.iter().map(|p| (*p).into()).collect());
+        params.origin = origin.map(str::to_string);
+        params.browser_context_id = self.browser_context.id().cloned();
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Resets all permissions of this browser's current context to their
+    /// defaults, so pages prompt for them again.
+    pub async fn clear_permission_overrides(&self) -> Result<&Self> {
+        self.execute(ResetPermissionsParams {
+            browser_context_id: self.browser_context.id().cloned(),
+        })
+        .await?;
+        Ok(self)
+    }
+
+    /// Intercepts the requests that match the pattern in all pages of this
+    /// browser's current context, including pages that are created later and
+    /// popups, see `Page::route`.
+    ///
+    /// Routes of a page take precedence over the routes of its context.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     browser
+    ///         .route("*://ads.example.com/*", |req| {
+    ///             req.abort(ErrorReason::BlockedByClient)
+    ///         })
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn route<F>(&self, pattern: impl Into<String>, handler: F) -> Result<&Self>
+    where
+        F: Fn(InterceptedRequest) -> RouteAction + Send + Sync + 'static,
+    {
+        self.add_context_route(self.browser_context.clone(), pattern, Arc::new(handler))
+            .await
+    }
+
+    /// Removes the routes of this browser's current context that were added
+    /// with this `pattern`, see `Browser::route`.
+    pub async fn unroute(&self, pattern: impl Into<String>) -> Result<&Self> {
+        self.sender
+            .clone()
+            .send(HandlerMessage::RemoveContextRoute(
+                self.browser_context.clone(),
+                pattern.into(),
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Intercepts the requests that match the pattern in all pages of the
+    /// browser context with the given identifier, see `Browser::route`.
+    pub async fn route_browser_context<F>(
+        &self,
+        id: BrowserContextId,
+        pattern: impl Into<String>,
+        handler: F,
+    ) -> Result<&Self>
+    where
+        F: Fn(InterceptedRequest) -> RouteAction + Send + Sync + 'static,
+    {
+        self.add_context_route(BrowserContext::from(id), pattern, Arc::new(handler))
+            .await
+    }
+
+    /// Removes the routes of the browser context with the given identifier
+    /// that were added with this `pattern`.
+    pub async fn unroute_browser_context(
+        &self,
+        id: BrowserContextId,
+        pattern: impl Into<String>,
+    ) -> Result<&Self> {
+        self.sender
+            .clone()
+            .send(HandlerMessage::RemoveContextRoute(
+                BrowserContext::from(id),
+                pattern.into(),
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    async fn add_context_route(
+        &self,
+        context: BrowserContext,
+        pattern: impl Into<String>,
+        handler: SharedRouteHandler,
+    ) -> Result<&Self> {
+        self.sender
+            .clone()
+            .send(HandlerMessage::AddContextRoute(
+                context,
+                ContextRoute::new(pattern, handler),
+            ))
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns all cookies of this browser's current context.
+    pub async fn get_cookies(&self) -> Result<Vec<Cookie>> {
+        let params = GetCookiesParams {
+            browser_context_id: self.browser_context.id().cloned(),
+        };
+        Ok(self.execute(params).await?.result.cookies)
+    }
+
+    /// Sets the cookies in this browser's current context.
+    ///
+    /// In contrast to `Page::set_cookies` every cookie needs either a `url` or
+    /// a `domain`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::network::{CookieParam, CookieSameSite};
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     let cookie = CookieParam::builder()
+    ///         .name("session")
+    ///         .value("secret")
+    ///         .domain("example.com")
+    ///         .secure(true)
+    ///         .same_site(CookieSameSite::Strict)
+    ///         .build()
+    ///         .unwrap();
+    ///     browser.set_cookies(vec![cookie]).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_cookies(&self, cookies: Vec<CookieParam>) -> Result<&Self> {
+        let mut params = SetCookiesParams::new(cookies);
+        params.browser_context_id = self.browser_context.id().cloned();
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Deletes all cookies of this browser's current context.
+    pub async fn clear_cookies(&self) -> Result<&Self> {
+        let params = ClearCookiesParams {
+            browser_context_id: self.browser_context.id().cloned(),
+        };
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Captures the cookies of this browser's current context and the
+    /// `localStorage` of the origins of its open pages, see the
+    /// [`storage_state`](crate::storage_state) module.
+    pub async fn storage_state(&self) -> Result<StorageState> {
+        let cookies = self.get_cookies().await?;
+        let mut origins: Vec<OriginState> = Vec::new();
+        for page in self.pages().await? {
+            if let Some(context) = self.browser_context.id() {
+                let info = page
+                    .execute(GetTargetInfoParams {
+                        target_id: Some(page.target_id().clone()),
+                    })
+                    .await?
+                    .result
+                    .target_info;
+                if info.browser_context_id.as_ref() != Some(context) {
+                    continue;
+                }
+            }
+            // documents with an opaque origin, like `about:blank`, have no
+            // `localStorage`
+            let origin: OriginState = match page
+                .call_js_fn_into(storage_state::CAPTURE_ORIGIN_JS, Vec::<CallArgument>::new())
+                .await
+            {
+                Ok(origin) => origin,
+                Err(_) => continue,
+            };
+            if origin.origin != "null" && !origins.iter().any(|o| o.origin == origin.origin) {
+                origins.push(origin);
+            }
+        }
+        Ok(StorageState { cookies, origins })
+    }
+
+    /// Creates a new browser context with the cookies and `localStorage` of
+    /// the `state` and returns its identifier.
+    pub async fn new_context_with_storage_state(
+        &self,
+        state: &StorageState,
+    ) -> Result<BrowserContextId> {
+        let id = self
+            .create_browser_context(CreateBrowserContextParams::default())
+            .await?;
+        self.restore_storage_state(&id, state).await?;
+        Ok(id)
+    }
+
+    /// Sets the cookies and `localStorage` of the `state` in the context
+    async fn restore_storage_state(
+        &self,
+        id: &BrowserContextId,
+        state: &StorageState,
+    ) -> Result<()> {
+        if !state.cookies.is_empty() {
+            let mut params = SetCookiesParams::new(state.cookie_params());
+            params.browser_context_id = Some(id.clone());
+            self.execute(params).await?;
+        }
+        if !state.origins.is_empty() {
+            let mut params = CreateTargetParams::new("about:blank");
+            params.browser_context_id = Some(id.clone());
+            let page = self.new_page(params).await?;
+            // the origins only need to be loaded to access their storage, so
+            // no request has to reach the server
+            page.route("*", |req| {
+                req.fulfill(RouteResponse::new(200).header("Content-Type", "text/html"))
+            })
+            .await?;
+            for origin in &state.origins {
+                page.goto(origin.origin.clone()).await?;
+                page.call_js_fn(
+                    storage_state::RESTORE_ORIGIN_JS,
+                    vec![serde_json::to_value(&origin.local_storage)?],
+                )
+                .await?;
+            }
+            page.close().await?;
+        }
+        Ok(())
+    }
+
+    /// Creates a new incognito browser context, see the
+    /// [`context`](crate::context) module.
+    pub async fn new_context(&self, options: ContextOptions) -> Result<context::BrowserContext> {
+        let mut params = CreateBrowserContextParams::default();
+        if let Some(proxy) = &options.proxy {
+            params.proxy_server = Some(proxy.server.clone());
+            params.proxy_bypass_list = proxy.bypass_list.clone();
+        }
+        let id = self.create_browser_context(params).await?;
+        if let Some(credentials) = options.proxy.and_then(|proxy| proxy.credentials) {
+            self.sender
+                .clone()
+                .send(HandlerMessage::SetProxyCredentials(
+                    BrowserContext::from(id.clone()),
+                    credentials,
+                ))
+                .await?;
+        }
+        if let Some(state) = &options.storage_state {
+            self.restore_storage_state(&id, state).await?;
+        }
+        let context = context::BrowserContext::new(id, self.sender.clone());
+        if !options.permissions.is_empty() {
+            context
+                .grant_permissions(&options.permissions, None)
+                .await?;
+        }
+        if options.offline {
+            context.set_offline(true).await?;
+        }
+        Ok(context)
+    }
+
     /// Whether incognito mode was configured from the start
     fn is_incognito_configured(&self) -> bool {
         self.config
@@ -219,6 +720,103 @@ impl Browser {
         Ok(self.version().await?.user_agent)
     }
 
+    /// Starts recording a trace of the browser with the given categories,
+    /// like `devtools.timeline` or `v8`, or with the default categories of
+    /// the browser if there are none.
+    ///
+    /// The trace is collected with [`Browser::stop_tracing`].
+    pub async fn start_tracing<I, S>(&self, categories: I) -> Result<&Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let categories: Vec<String> = categories.into_iter().map(Into::into).collect();
+        let trace_config = TraceConfig {
+            included_categories: (!categories.is_empty()).then_some(categories),
+            ..Default::default()
+        };
+        let params = StartParams {
+            transfer_mode: Some(StartTransferMode::ReturnAsStream),
+            stream_format: Some(StreamFormat::Json),
+            trace_config: Some(trace_config),
+            ..Default::default()
+        };
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Stops the trace started with [`Browser::start_tracing`] and returns
+    /// the stream of its chunks.
+    ///
+    /// Together the chunks are a json trace that can be loaded in
+    /// `chrome://tracing` or Perfetto.
+    pub async fn stop_tracing_stream(&self) -> Result<BoxStream<'_, Result<Vec<u8>>>> {
+        let mut complete = self.event_listener::<EventTracingComplete>().await?;
+        self.execute(EndParams::default()).await?;
+        let handle = complete
+            .next()
+            .await
+            .ok_or(CdpError::NoResponse)?
+            .stream
+            .clone()
+            .ok_or_else(|| CdpError::msg("No stream returned for the trace"))?;
+        Ok(self.read_io_stream(handle))
+    }
+
+    /// Stops the trace started with [`Browser::start_tracing`] and returns
+    /// its json.
+    pub async fn stop_tracing(&self) -> Result<Vec<u8>> {
+        let mut chunks = self.stop_tracing_stream().await?;
+        let mut trace = Vec::new();
+        while let Some(chunk) = chunks.next().await {
+            trace.extend(chunk?);
+        }
+        Ok(trace)
+    }
+
+    /// Stops the trace started with [`Browser::start_tracing`] and saves its
+    /// json chunk by chunk to the `output` path, like `trace.json`, and
+    /// returns its size in bytes.
+    pub async fn stop_tracing_to_file(&self, output: impl AsRef<Path>) -> Result<u64> {
+        let chunks = self.stop_tracing_stream().await?;
+        utils::write_chunks(output.as_ref(), chunks).await
+    }
+
+    /// Reads the chunks of a stream of the IO domain and closes it at its
+    /// end or after an error.
+    fn read_io_stream(&self, handle: StreamHandle) -> BoxStream<'_, Result<Vec<u8>>> {
+        stream::unfold(Some(handle), move |handle| async move {
+            let handle = handle?;
+            let mut read = ReadParams::new(handle.clone());
+            read.size = Some(IO_CHUNK_SIZE);
+            let chunk = match self.execute(read).await {
+                Ok(resp) => {
+                    let resp = resp.result;
+                    if resp.base64_encoded.unwrap_or_default() {
+                        base64::decode(&resp.data).map_err(CdpError::from)
+                    } else {
+                        Ok(resp.data.into_bytes())
+                    }
+                    .map(|chunk| (chunk, resp.eof))
+                }
+                Err(err) => Err(err),
+            };
+            match chunk {
+                Ok((chunk, false)) => Some((Ok(chunk), Some(handle))),
+                Ok((chunk, true)) => match self.execute(cdp_io::CloseParams::new(handle)).await {
+                    Err(err) => Some((Err(err), None)),
+                    Ok(_) if chunk.is_empty() => None,
+                    Ok(_) => Some((Ok(chunk), None)),
+                },
+                Err(err) => {
+                    let _ = self.execute(cdp_io::CloseParams::new(handle)).await;
+                    Some((Err(err), None))
+                }
+            }
+        })
+        .boxed()
+    }
+
     /// Call a browser method.
     pub async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
         let (tx, rx) = oneshot_channel();
@@ -233,6 +831,121 @@ impl Browser {
         to_command_response::<T>(resp, method)
     }
 
+    /// Return the workers of all pages of the browser, see
+    /// `Page::workers`
+    pub async fn workers(&self) -> Result<Vec<Worker>> {
+        let mut workers = Vec::new();
+        for page in self.pages().await? {
+            workers.extend(page.workers().await?);
+        }
+        Ok(workers)
+    }
+
+    /// Registers a hook that is called with every new page, including
+    /// popups and other pages opened by the sites, e.g. to set up emulation,
+    /// routes and init scripts.
+    ///
+    /// Pages created with [`Browser::new_page`] are created blank and only
+    /// navigate to their url once all hooks are done, and their creation
+    /// fails if a hook fails. Pages opened by the sites can't be held back
+    /// and may have started loading before the hooks run.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::browser::Browser;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(browser: Browser) -> Result<()> {
+    ///     browser
+    ///         .on_new_page(|page| async move {
+    ///             page.add_init_script("window.automated = true").await?;
+    ///             Ok(())
+    ///         })
+    ///         .await?;
+    ///     let page = browser.new_page("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn on_new_page<F, Fut>(&self, hook: F) -> Result<&Self>
+    where
+        F: Fn(Page) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let hook = PageHook(Arc::new(move |page| hook(page).boxed()));
+        self.sender
+            .clone()
+            .send(HandlerMessage::AddPageHook(hook))
+            .await?;
+        Ok(self)
+    }
+
+    /// Return the windows of the browser with their bounds and the pages
+    /// they show.
+    pub async fn windows(&self) -> Result<Vec<BrowserWindow>> {
+        let mut windows: Vec<BrowserWindow> = Vec::new();
+        for page in self.pages().await? {
+            let resp = self
+                .execute(
+                    GetWindowForTargetParams::builder()
+                        .target_id(page.target_id().clone())
+                        .build(),
+                )
+                .await?;
+            let target_id = page.target_id().clone();
+            match windows.iter_mut().find(|w| w.id == resp.result.window_id) {
+                Some(window) => window.targets.push(target_id),
+                None => windows.push(BrowserWindow {
+                    id: resp.result.window_id,
+                    bounds: resp.result.bounds.clone(),
+                    targets: vec![target_id],
+                }),
+            }
+        }
+        Ok(windows)
+    }
+
+    /// Moves, resizes, maximizes or minimizes a window, see
+    /// [`Browser::windows`].
+    pub async fn set_window_bounds(&self, id: WindowId, bounds: Bounds) -> Result<&Self> {
+        self.execute(SetWindowBoundsParams::new(id, bounds)).await?;
+        Ok(self)
+    }
+
+    /// Captures the tab a window shows as a PNG, from the view of the
+    /// window rather than the page's surface, e.g. to debug headful runs.
+    ///
+    /// The protocol can't capture the browser's own ui or dialogs of the
+    /// operating system, only the content area of the window.
+    pub async fn capture_window_screenshot(&self, id: WindowId) -> Result<Vec<u8>> {
+        let window = self
+            .windows()
+            .await?
+            .into_iter()
+            .find(|window| window.id == id)
+            .ok_or(CdpError::NotFound)?;
+        if window.bounds.window_state == Some(WindowState::Minimized) {
+            return Err(CdpError::msg("The window is minimized."));
+        }
+        // the window shows the one of its tabs that is visible
+        let mut shown = None;
+        for target_id in window.targets {
+            let page = self.get_page(target_id).await?;
+            let visible: bool = page
+                .evaluate("document.visibilityState === 'visible'")
+                .await?
+                .into_value()?;
+            if visible || shown.is_none() {
+                shown = Some(page);
+            }
+            if visible {
+                break;
+            }
+        }
+        let page = shown.ok_or(CdpError::NotFound)?;
+        page.screenshot(ScreenshotParams::builder().from_surface(false).build())
+            .await
+    }
+
     /// Return all of the pages of the browser
     pub async fn pages(&self) -> Result<Vec<Page>> {
         let (tx, rx) = oneshot_channel();
@@ -256,49 +969,271 @@ impl Browser {
     //Set listener for browser event
     pub async fn event_listener<T: IntoEventKind>(&self) -> Result<EventStream<T>> {
         let (tx, rx) = unbounded();
+        let req = EventListenerRequest::new::<T>(tx);
+        let control = req.control().clone();
         self.sender
             .clone()
-            .send(HandlerMessage::AddEventListener(
-                EventListenerRequest::new::<T>(tx),
-            ))
+            .send(HandlerMessage::AddEventListener(req))
             .await?;
 
-        Ok(EventStream::new(rx))
+        Ok(EventStream::new(rx).with_control(control))
+    }
+
+    /// Returns a stream of the lines the launched chromium instance writes to
+    /// its stderr, like crash reports and GPU warnings.
+    ///
+    /// The stream starts with the recent lines and ends once chromium exits.
+    /// It's empty for a browser that was connected to instead of launched.
+    pub fn stderr_logs(&self) -> UnboundedReceiver<String> {
+        match &self.stderr {
+            Some(logs) => logs.lock().unwrap().subscribe(),
+            None => unbounded().1,
+        }
+    }
+
+    /// Returns a stream of the changes of the connection to the browser.
+    ///
+    /// The connection only changes once it's lost, with a
+    /// `HandlerConfig::reconnect` policy it's re-established, see
+    /// [`Browser::connect_with_config`]. The stream ends once the handler
+    /// stops.
+    pub async fn connection_state(&self) -> Result<UnboundedReceiver<ConnectionState>> {
+        let (tx, rx) = unbounded();
+        self.sender
+            .clone()
+            .send(HandlerMessage::AddConnectionListener(tx))
+            .await?;
+        Ok(rx)
     }
 }
 
 impl Drop for Browser {
+    /// Kills the spawned chromium instance if it wasn't closed with
+    /// [`Browser::close`], reaps it and removes its temporary profile.
     fn drop(&mut self) {
-        if let Some(child) = self.child.as_mut() {
-            child.kill().expect("!kill");
+        if let Some(child) = self.child.take() {
+            kill(child, self.temp_user_data_dir.take());
         }
     }
 }
 
-async fn ws_url_from_output(child_process: &mut Child) -> String {
+/// How long to wait for chromium to exit after it was closed or terminated by
+/// default, in milliseconds
+pub const CLOSE_TIMEOUT: u64 = 5_000;
+
+/// Waits up to `timeout` for chromium to exit, then terminates it and kills it
+/// if it's still running after another `timeout`. Reaps the process and
+/// removes the user data directory afterwards.
+fn shutdown(
+    mut child: Child,
+    timeout: Duration,
+    user_data_dir: Option<PathBuf>,
+) -> io::Result<ExitStatus> {
+    let status = match wait_timeout(&mut child, timeout)? {
+        Some(status) => status,
+        None => {
+            terminate(&child);
+            match wait_timeout(&mut child, timeout)? {
+                Some(status) => status,
+                None => {
+                    tracing::debug!("Killing browser process {}", child.id());
+                    child.kill()?;
+                    child.wait()?
+                }
+            }
+        }
+    };
+    remove_user_data_dir(user_data_dir);
+    Ok(status)
+}
+
+/// Removes the temporary user data directory of an exited chromium instance
+fn remove_user_data_dir(user_data_dir: Option<PathBuf>) {
+    if let Some(dir) = user_data_dir {
+        if let Err(err) = fs::remove_dir_all(&dir) {
+            tracing::debug!("Failed to remove {}: {}", dir.display(), err);
+        }
+    }
+}
+
+/// Kills chromium right away, reaps the process and removes the user data
+/// directory, which returns almost immediately after the kill
+fn kill(mut child: Child, user_data_dir: Option<PathBuf>) {
+    if let Err(err) = child.kill() {
+        tracing::warn!("Failed to kill browser process {}: {}", child.id(), err);
+    }
+    if let Err(err) = child.wait() {
+        tracing::warn!("Failed to reap the browser process: {}", err);
+    }
+    remove_user_data_dir(user_data_dir);
+}
+
+/// Waits up to `timeout` for the process to exit
+fn wait_timeout(child: &mut Child, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep((deadline - now).min(Duration::from_millis(50)));
+    }
+}
+
+/// Asks the process to exit, chromium shuts down cleanly on `SIGTERM`
+#[cfg(unix)]
+fn terminate(child: &Child) {
+    // SAFETY: the process wasn't reaped yet, so its pid wasn't reused
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+}
+
+/// There is no graceful way to terminate a process, it's killed right away
+#[cfg(not(unix))]
+fn terminate(_: &Child) {}
+
+/// A new directory in the temp dir for the user data of a chromium instance
+fn temp_user_data_dir() -> PathBuf {
+    static COUNT: AtomicUsize = AtomicUsize::new(0);
+    std::env::temp_dir().join(format!(
+        "chromiumoxide-runner-{}-{}",
+        process::id(),
+        COUNT.fetch_add(1, Ordering::Relaxed)
+    ))
+}
+
+async fn ws_url_from_output(child_process: &mut Child, logs: Arc<Mutex<StderrLogs>>) -> String {
     let stdout = child_process.stderr.take().expect("no stderror");
 
-    fn read_debug_url(stdout: std::process::ChildStderr) -> String {
+    fn read_debug_url(stdout: ChildStderr, logs: Arc<Mutex<StderrLogs>>) -> String {
         let mut buf = BufReader::new(stdout);
         let mut line = String::new();
         loop {
-            if buf.read_line(&mut line).is_ok() {
+            line.clear();
+            if let Ok(n) = buf.read_line(&mut line) {
+                if n > 0 {
+                    logs.lock().unwrap().push(line.trim_end());
+                }
                 // check for ws in line
                 if let Some(ws) = line.rsplit("listening on ").next() {
                     if ws.starts_with("ws") && ws.contains("devtools/browser") {
-                        return ws.trim().to_string();
+                        let ws = ws.trim().to_string();
+                        // keep draining stderr, chrome fails to write to a closed pipe
+                        std::thread::spawn(move || forward_stderr(buf, logs));
+                        return ws;
                     }
                 }
-            } else {
-                line = String::new();
             }
         }
     }
     cfg_if::cfg_if! {
         if #[cfg(feature = "async-std-runtime")] {
-            async_std::task::spawn_blocking(|| read_debug_url(stdout)).await
+            async_std::task::spawn_blocking(|| read_debug_url(stdout, logs)).await
         } else if #[cfg(feature = "tokio-runtime")] {
-            tokio::task::spawn_blocking(move || read_debug_url(stdout)).await.expect("Failed to read debug url from process output")
+            tokio::task::spawn_blocking(move || read_debug_url(stdout, logs)).await.expect("Failed to read debug url from process output")
+        }
+    }
+}
+
+/// Records the lines of chrome's stderr until it exits
+fn forward_stderr(mut stderr: BufReader<ChildStderr>, logs: Arc<Mutex<StderrLogs>>) {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        match stderr.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(_) => logs.lock().unwrap().push(line.trim_end()),
+            // not valid utf-8
+            Err(err) if err.kind() == io::ErrorKind::InvalidData => {}
+            Err(_) => break,
+        }
+    }
+    logs.lock().unwrap().close();
+}
+
+/// The recent lines of chrome's stderr and the streams they are sent to, see
+/// [`Browser::stderr_logs`]
+#[derive(Debug, Default)]
+struct StderrLogs {
+    recent: VecDeque<String>,
+    listeners: Vec<UnboundedSender<String>>,
+    /// Whether chrome closed its stderr
+    closed: bool,
+}
+
+impl StderrLogs {
+    /// How many of the recent lines are kept for new streams
+    const RECENT_LINES: usize = 1000;
+
+    fn push(&mut self, line: &str) {
+        if self.recent.len() == Self::RECENT_LINES {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(line.to_string());
+        self.listeners
+            .retain(|tx| tx.unbounded_send(line.to_string()).is_ok());
+    }
+
+    fn subscribe(&mut self) -> UnboundedReceiver<String> {
+        let (tx, rx) = unbounded();
+        for line in &self.recent {
+            let _ = tx.unbounded_send(line.clone());
+        }
+        if !self.closed {
+            self.listeners.push(tx);
+        }
+        rx
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        self.listeners.clear();
+    }
+}
+
+/// A window of the browser, see [`Browser::windows`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BrowserWindow {
+    pub id: WindowId,
+    /// The position, size and state of the window
+    pub bounds: Bounds,
+    /// The pages shown in the window, as tabs
+    pub targets: Vec<TargetId>,
+}
+
+/// How the browser opens its windows.
+///
+/// All modes but `Normal` only take effect with a head, see
+/// [`BrowserConfigBuilder::with_head`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum WindowMode {
+    #[default]
+    Normal,
+    /// Maximized windows
+    Maximized,
+    /// Fullscreen windows
+    Fullscreen,
+    /// A fullscreen window without any browser ui that can't be left, for
+    /// digital signage
+    Kiosk,
+    /// A window without tabs and address bar that shows the url, like an
+    /// installed app
+    App(String),
+}
+
+impl WindowMode {
+    /// The command line arguments of the mode
+    fn args(&self) -> Vec<String> {
+        match self {
+            WindowMode::Normal => Vec::new(),
+            WindowMode::Maximized => vec!["--start-maximized".to_string()],
+            WindowMode::Fullscreen => vec!["--start-fullscreen".to_string()],
+            WindowMode::Kiosk => vec!["--kiosk".to_string()],
+            WindowMode::App(url) => vec![format!("--app={url}")],
         }
     }
 }
@@ -312,6 +1247,10 @@ pub struct BrowserConfig {
     sandbox: bool,
     /// Launch the browser with a specific window width and height.
     window_size: Option<(u32, u32)>,
+    /// Launch the browser with its window at a specific position.
+    window_position: Option<(i32, i32)>,
+    /// How the browser opens its windows.
+    window_mode: WindowMode,
     /// Launch the browser with a specific debugging port.
     port: u16,
     /// Path for Chrome or Chromium.
@@ -333,6 +1272,10 @@ pub struct BrowserConfig {
     /// Passes value through to std::process::Command::envs.
     pub process_envs: Option<HashMap<String, String>>,
 
+    /// The working directory of the Chromium process, the one of this
+    /// process if `None`
+    pub current_dir: Option<PathBuf>,
+
     /// Data dir for user data
     pub user_data_dir: Option<PathBuf>,
 
@@ -345,6 +1288,9 @@ pub struct BrowserConfig {
     /// The duration after a request with no response should time out
     request_timeout: Duration,
 
+    /// How long to wait for the browser to exit on close before it's killed
+    close_timeout: Duration,
+
     /// Additional command line arguments to pass to the browser instance.
     args: Vec<String>,
 
@@ -356,6 +1302,27 @@ pub struct BrowserConfig {
 
     /// Whether to enable cache
     pub cache_enabled: bool,
+
+    /// Whether media is allowed to play without a user gesture
+    autoplay: bool,
+
+    /// Whether the same-origin policy and CORS are disabled
+    disable_web_security: bool,
+
+    /// The timezone every page emulates
+    timezone_id: Option<String>,
+
+    /// The locale every page emulates
+    locale: Option<String>,
+
+    /// Hosts and the host, ip address or `host:port` they resolve to
+    host_resolver_rules: Vec<(String, String)>,
+
+    /// Scripts to evaluate in every new document of every page
+    init_scripts: Vec<String>,
+
+    /// The client certificates selected for mTLS-protected hosts
+    client_certificates: Vec<ClientCertificate>,
 }
 
 #[derive(Debug, Clone)]
@@ -363,19 +1330,30 @@ pub struct BrowserConfigBuilder {
     headless: bool,
     sandbox: bool,
     window_size: Option<(u32, u32)>,
+    window_position: Option<(i32, i32)>,
+    window_mode: WindowMode,
     port: u16,
     executable: Option<PathBuf>,
     extensions: Vec<String>,
     process_envs: Option<HashMap<String, String>>,
+    current_dir: Option<PathBuf>,
     user_data_dir: Option<PathBuf>,
     incognito: bool,
     ignore_https_errors: bool,
     viewport: Viewport,
     request_timeout: Duration,
+    close_timeout: Duration,
     args: Vec<String>,
     disable_default_args: bool,
     request_intercept: bool,
     cache_enabled: bool,
+    autoplay: bool,
+    disable_web_security: bool,
+    timezone_id: Option<String>,
+    locale: Option<String>,
+    host_resolver_rules: Vec<(String, String)>,
+    init_scripts: Vec<String>,
+    client_certificates: Vec<ClientCertificate>,
 }
 
 impl BrowserConfig {
@@ -394,19 +1372,30 @@ impl Default for BrowserConfigBuilder {
             headless: true,
             sandbox: true,
             window_size: None,
+            window_position: None,
+            window_mode: WindowMode::Normal,
             port: 0,
             executable: None,
             extensions: Vec::new(),
             process_envs: None,
+            current_dir: None,
             user_data_dir: None,
             incognito: false,
             ignore_https_errors: true,
             viewport: Default::default(),
             request_timeout: Duration::from_millis(REQUEST_TIMEOUT),
+            close_timeout: Duration::from_millis(CLOSE_TIMEOUT),
             args: Vec::new(),
             disable_default_args: false,
             request_intercept: false,
             cache_enabled: true,
+            autoplay: false,
+            disable_web_security: false,
+            timezone_id: None,
+            locale: None,
+            host_resolver_rules: Vec::new(),
+            init_scripts: Vec::new(),
+            client_certificates: Vec::new(),
         }
     }
 }
@@ -417,6 +1406,35 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Launches the browser with its window at the position on the screen.
+    pub fn window_position(mut self, x: i32, y: i32) -> Self {
+        self.window_position = Some((x, y));
+        self
+    }
+
+    /// Sets how the browser opens its windows, see [`WindowMode`].
+    pub fn window_mode(mut self, mode: WindowMode) -> Self {
+        self.window_mode = mode;
+        self
+    }
+
+    /// Launches the browser with a head in kiosk mode, see
+    /// [`WindowMode::Kiosk`].
+    pub fn kiosk(self) -> Self {
+        self.with_head().window_mode(WindowMode::Kiosk)
+    }
+
+    /// Launches the browser with a head in app mode showing the url, see
+    /// [`WindowMode::App`].
+    pub fn app(self, url: impl Into<String>) -> Self {
+        self.with_head().window_mode(WindowMode::App(url.into()))
+    }
+
+    /// Launches the browser with a head and maximized windows.
+    pub fn start_maximized(self) -> Self {
+        self.with_head().window_mode(WindowMode::Maximized)
+    }
+
     pub fn no_sandbox(mut self) -> Self {
         self.sandbox = false;
         self
@@ -447,11 +1465,23 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// How long [`Browser::close`] waits for the browser to exit before it's
+    /// terminated, and again before it's killed.
+    pub fn close_timeout(mut self, timeout: Duration) -> Self {
+        self.close_timeout = timeout;
+        self
+    }
+
     pub fn viewport(mut self, viewport: Viewport) -> Self {
         self.viewport = viewport;
         self
     }
 
+    /// Runs chromium with this profile directory, which is kept after the
+    /// browser closed.
+    ///
+    /// Without one, `Browser::launch` creates a temporary profile directory
+    /// that is removed once the browser was closed or dropped.
     pub fn user_data_dir(mut self, data_dir: impl AsRef<Path>) -> Self {
         self.user_data_dir = Some(data_dir.as_ref().to_path_buf());
         self
@@ -462,6 +1492,14 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Launches the default Chrome for Testing build, which is downloaded
+    /// unless it's cached already, see [`BrowserFetcher`](crate::fetcher::BrowserFetcher).
+    #[cfg(feature = "fetcher")]
+    pub async fn with_fetched_browser(self) -> Result<Self> {
+        let fetched = crate::fetcher::BrowserFetcher::default().fetch().await?;
+        Ok(self.chrome_executable(fetched.executable_path))
+    }
+
     pub fn extension(mut self, extension: impl Into<String>) -> Self {
         self.extensions.push(extension.into());
         self
@@ -497,6 +1535,12 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Sets the working directory of the Chromium process.
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
     pub fn arg(mut self, arg: impl Into<String>) -> Self {
         self.args.push(arg.into());
         self
@@ -528,6 +1572,80 @@ impl BrowserConfigBuilder {
         self
     }
 
+    /// Allows media to play without a user gesture, which is required to
+    /// autoplay `<audio>` and `<video>` elements in headless mode.
+    pub fn allow_autoplay(mut self) -> Self {
+        self.autoplay = true;
+        self
+    }
+
+    /// Disables the same-origin policy and CORS checks, so scripts can fetch
+    /// from and access the frames of other origins.
+    ///
+    /// This makes the browser unsafe for browsing untrusted sites, see
+    /// `Page::set_bypass_csp` to only ignore the sites' Content Security
+    /// Policies.
+    pub fn disable_web_security(mut self) -> Self {
+        self.disable_web_security = true;
+        self
+    }
+
+    /// Emulates the timezone, like `Europe/Berlin`, in every page, see
+    /// `Page::set_timezone`.
+    pub fn timezone(mut self, timezone_id: impl Into<String>) -> Self {
+        self.timezone_id = Some(timezone_id.into());
+        self
+    }
+
+    /// Emulates the locale, like `de-DE`, in every page, see
+    /// `Page::set_locale`.
+    pub fn locale(mut self, locale: impl Into<String>) -> Self {
+        self.locale = Some(locale.into());
+        self
+    }
+
+    /// Evaluates `source` in every new document and frame of every page
+    /// before any of its own scripts run, see `Page::add_init_script`.
+    pub fn with_init_script(mut self, source: impl Into<String>) -> Self {
+        self.init_scripts.push(source.into());
+        self
+    }
+
+    /// Resolves `host` to `target`, which is a host, an ip address or
+    /// `host:port`, for all requests of the browser. `host` may start with
+    /// `*.` to match all subdomains.
+    ///
+    /// The page still sees the original host, so cookies and the `Host`
+    /// header stay the same.
+    pub fn host_resolver_rule(
+        mut self,
+        host: impl Into<String>,
+        target: impl Into<String>,
+    ) -> Self {
+        self.host_resolver_rules.push((host.into(), target.into()));
+        self
+    }
+
+    /// Adds the mapping of each host to its target, see
+    /// `BrowserConfigBuilder::host_resolver_rule`.
+    pub fn host_resolver_rules<I, K, V>(mut self, rules: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.host_resolver_rules
+            .extend(rules.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Selects the client certificate for the hosts of its pattern whenever
+    /// they request one, see the [`client_cert`](crate::client_cert) module.
+    pub fn client_certificate(mut self, certificate: ClientCertificate) -> Self {
+        self.client_certificates.push(certificate);
+        self
+    }
+
     pub fn build(self) -> std::result::Result<BrowserConfig, String> {
         let executable = if let Some(e) = self.executable {
             e
@@ -539,19 +1657,30 @@ impl BrowserConfigBuilder {
             headless: self.headless,
             sandbox: self.sandbox,
             window_size: self.window_size,
+            window_position: self.window_position,
+            window_mode: self.window_mode,
             port: self.port,
             executable,
             extensions: self.extensions,
             process_envs: self.process_envs,
+            current_dir: self.current_dir,
             user_data_dir: self.user_data_dir,
             incognito: self.incognito,
             ignore_https_errors: self.ignore_https_errors,
             viewport: self.viewport,
             request_timeout: self.request_timeout,
+            close_timeout: self.close_timeout,
             args: self.args,
             disable_default_args: self.disable_default_args,
             request_intercept: self.request_intercept,
             cache_enabled: self.cache_enabled,
+            autoplay: self.autoplay,
+            disable_web_security: self.disable_web_security,
+            timezone_id: self.timezone_id,
+            locale: self.locale,
+            host_resolver_rules: self.host_resolver_rules,
+            init_scripts: self.init_scripts,
+            client_certificates: self.client_certificates,
         })
     }
 }
@@ -580,22 +1709,26 @@ impl BrowserConfig {
                 .map(|e| format!("--load-extension={e}")),
         );
 
-        if let Some(ref user_data) = self.user_data_dir {
-            cmd.arg(format!("--user-data-dir={}", user_data.display()));
-        } else {
-            // If the user did not specify a data directory, this would default to the systems default
-            // data directory. In most cases, we would rather have a fresh instance of Chromium. Specify
-            // a temp dir just for chromiumoxide instead.
-            cmd.arg(format!(
-                "--user-data-dir={}",
-                std::env::temp_dir().join("chromiumoxide-runner").display()
-            ));
-        }
+        // If the user did not specify a data directory, this would default to the systems default
+        // data directory. In most cases, we would rather have a fresh instance of Chromium. Specify
+        // a temp dir just for chromiumoxide instead.
+        let user_data_dir = self
+            .user_data_dir
+            .clone()
+            .unwrap_or_else(|| std::env::temp_dir().join("chromiumoxide-runner"));
+        client_cert::write_preferences(&user_data_dir, &self.client_certificates)?;
+        cmd.arg(format!("--user-data-dir={}", user_data_dir.display()));
 
         if let Some((width, height)) = self.window_size {
             cmd.arg(format!("--window-size={width},{height}"));
         }
 
+        if let Some((x, y)) = self.window_position {
+            cmd.arg(format!("--window-position={x},{y}"));
+        }
+
+        cmd.args(self.window_mode.args());
+
         if !self.sandbox {
             cmd.args(["--no-sandbox", "--disable-setuid-sandbox"]);
         }
@@ -608,9 +1741,30 @@ impl BrowserConfig {
             cmd.arg("--incognito");
         }
 
+        if self.autoplay {
+            cmd.arg("--autoplay-policy=no-user-gesture-required");
+        }
+
+        if self.disable_web_security {
+            // frames of other origins are only accessible within the same process
+            cmd.args(["--disable-web-security", "--disable-site-isolation-trials"]);
+        }
+
+        if !self.host_resolver_rules.is_empty() {
+            let rules: Vec<_> = self
+                .host_resolver_rules
+                .iter()
+                .map(|(host, target)| format!("MAP {host} {target}"))
+                .collect();
+            cmd.arg(format!("--host-resolver-rules={}", rules.join(",")));
+        }
+
         if let Some(ref envs) = self.process_envs {
             cmd.envs(envs);
         }
+        if let Some(ref dir) = self.current_dir {
+            cmd.current_dir(dir);
+        }
         cmd.stderr(Stdio::piped()).spawn()
     }
 }
@@ -701,3 +1855,78 @@ static DEFAULT_ARGS: [&str; 24] = [
     "--use-mock-keychain",
     "--enable-blink-features=IdleDetection",
 ];
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::process::ExitStatusExt;
+
+    use crate::conn::tests::Pipe;
+
+    use super::*;
+
+    /// A browser with a running process instead of chromium and a handler
+    /// that is gone
+    fn browser(dir: &Path) -> Browser {
+        let (conn, _, _) = Pipe::connection();
+        let (tx, rx) = channel(1);
+        let handler = Handler::new(conn, rx, Default::default());
+        let browser_context = handler.default_browser_context().clone();
+        drop(handler);
+        fs::create_dir_all(dir).unwrap();
+        Browser {
+            sender: tx,
+            config: Some(
+                BrowserConfig::builder()
+                    .chrome_executable("sleep")
+                    .close_timeout(Duration::from_millis(100))
+                    .build()
+                    .unwrap(),
+            ),
+            child: Some(process::Command::new("sleep").arg("30").spawn().unwrap()),
+            debug_ws_url: String::new(),
+            browser_context,
+            stderr: None,
+            temp_user_data_dir: Some(dir.to_path_buf()),
+        }
+    }
+
+    /// Whether the process exited and was reaped
+    fn is_reaped(pid: u32) -> bool {
+        // SAFETY: signal 0 only checks whether the process exists
+        unsafe { libc::kill(pid as libc::pid_t, 0) != 0 }
+    }
+
+    #[test]
+    fn kills_processes_that_ignore_sigterm() {
+        let child = process::Command::new("sh")
+            .args(["-c", "trap '' TERM; while :; do sleep 1; done"])
+            .spawn()
+            .unwrap();
+        let status = shutdown(child, Duration::from_millis(100), None).unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    }
+
+    #[async_std::test]
+    async fn close_reaps_the_process() {
+        let dir = temp_user_data_dir();
+        let mut browser = browser(&dir);
+        let pid = browser.child.as_ref().unwrap().id();
+        // the handler is gone, the process is shut down anyway
+        assert!(browser.close().await.is_err());
+        assert!(browser.child.is_none());
+        assert!(is_reaped(pid));
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn drop_kills_the_process() {
+        let dir = temp_user_data_dir();
+        let browser = browser(&dir);
+        let pid = browser.child.as_ref().unwrap().id();
+        let start = Instant::now();
+        drop(browser);
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert!(is_reaped(pid));
+        assert!(!dir.exists());
+    }
+}
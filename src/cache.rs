@@ -0,0 +1,418 @@
+//! An on-disk cache of responses for repeated crawls.
+//!
+//! A [`ResponseCache`] stores the responses of `GET` requests in a directory
+//! and serves them on subsequent runs without contacting the server, until
+//! they are older than the cache's time to live. Responses are keyed by their
+//! url and the values of the request headers listed in their `Vary` header.
+//!
+//! Cached responses are served through a route matching all urls, see
+//! [`Page::route`](crate::page::Page::route), so routes of the page added
+//! before the cache was attached no longer receive requests. Responses are
+//! stored by the [`CacheRecorder`] returned when attaching the cache, which
+//! needs to be flushed to persist them.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::cache::ResponseCache;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let cache = ResponseCache::open(".cache/responses")
+//!         .await?
+//!         .with_ttl(Duration::from_secs(24 * 60 * 60));
+//!     let mut recorder = cache.attach(&page).await?;
+//!     page.goto("https://example.com").await?;
+//!     recorder.flush().await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fnv::FnvHasher;
+use futures::{FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{EventLoadingFailed, RequestId};
+
+use crate::error::Result;
+use crate::listeners::EventStream;
+use crate::page::Page;
+use crate::response::{Response, ResponseStream};
+use crate::route::RouteResponse;
+use crate::utils;
+
+/// How long cached responses are served by default
+pub const DEFAULT_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// The headers that describe the body as it was sent, which don't apply to the
+/// decoded body that is stored
+const WIRE_HEADERS: &[&str] = &["content-encoding", "content-length", "transfer-encoding"];
+
+/// A response stored in the cache.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedResponse {
+    url: String,
+    /// The request headers listed in the `Vary` header and their values
+    vary: Vec<(String, Option<String>)>,
+    status: i64,
+    headers: Vec<(String, String)>,
+    /// The base64 encoded body
+    body: String,
+    /// When the response was stored, in seconds since the unix epoch
+    stored_at: u64,
+}
+
+impl CachedResponse {
+    fn is_fresh(&self, ttl: Duration, now: u64) -> bool {
+        now.saturating_sub(self.stored_at) < ttl.as_secs()
+    }
+
+    /// Whether the request headers, looked up by `header`, have the same
+    /// values as those of the cached response's request.
+    fn matches(&self, header: impl Fn(&str) -> Option<String>) -> bool {
+        self.vary.iter().all(|(name, value)| header(name) == *value)
+    }
+
+    /// The name of the file the response is stored in, which stays the same
+    /// across runs and builds.
+    fn file_name(&self) -> String {
+        let mut hasher = FnvHasher::default();
+        hasher.write(self.url.as_bytes());
+        for (name, value) in &self.vary {
+            hasher.write(&[0]);
+            hasher.write(name.as_bytes());
+            if let Some(value) = value {
+                hasher.write(&[1]);
+                hasher.write(value.as_bytes());
+            }
+        }
+        format!("{:016x}.json", hasher.finish())
+    }
+
+    fn to_route_response(&self) -> RouteResponse {
+        let body = base64::decode(&self.body).unwrap_or_default();
+        self.headers
+            .iter()
+            .fold(
+                RouteResponse::new(self.status),
+                |response, (name, value)| response.header(name.clone(), value.clone()),
+            )
+            .body(body)
+    }
+}
+
+type Entries = Arc<Mutex<HashMap<String, Vec<CachedResponse>>>>;
+
+/// An on-disk cache of responses.
+#[derive(Debug, Clone)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    ttl: Duration,
+    entries: Entries,
+}
+
+impl ResponseCache {
+    /// Opens the cache stored in `dir`, creating the directory if it doesn't
+    /// exist.
+    pub async fn open(dir: impl AsRef<Path>) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        utils::create_dir_all(&dir).await?;
+        let mut entries: HashMap<String, Vec<CachedResponse>> = HashMap::new();
+        for path in utils::read_dir(&dir).await? {
+            if path.extension().is_none_or(|ext| ext != "json") {
+                continue;
+            }
+            match serde_json::from_slice::<CachedResponse>(&utils::read(&path).await?) {
+                Ok(response) => entries
+                    .entry(response.url.clone())
+                    .or_default()
+                    .push(response),
+                Err(err) => tracing::warn!("Ignoring invalid cache entry {:?}: {}", path, err),
+            }
+        }
+        Ok(Self {
+            dir,
+            ttl: DEFAULT_TTL,
+            entries: Arc::new(Mutex::new(entries)),
+        })
+    }
+
+    /// How long stored responses are served, one hour by default.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// The number of cached responses.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().values().map(Vec::len).sum()
+    }
+
+    /// Whether no responses are cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Serves the `GET` requests of the page from the cache and returns the
+    /// recorder that stores the responses of requests that missed it.
+    pub async fn attach(&self, page: &Page) -> Result<CacheRecorder> {
+        let responses = page.responses().await?;
+        let failed = page.event_listener::<EventLoadingFailed>().await?;
+        let misses: Arc<Mutex<HashSet<RequestId>>> = Default::default();
+
+        let entries = Arc::clone(&self.entries);
+        let ttl = self.ttl;
+        let pending = Arc::clone(&misses);
+        page.route("*", move |req| {
+            if req.method() != "GET" {
+                return req.continue_request();
+            }
+            let now = unix_time();
+            let hit = entries.lock().unwrap().get(req.url()).and_then(|cached| {
+                cached
+                    .iter()
+                    .find(|response| {
+                        response.is_fresh(ttl, now)
+                            && response.matches(|name| req.header(name).map(str::to_string))
+                    })
+                    .map(CachedResponse::to_route_response)
+            });
+            match hit {
+                Some(response) => req.fulfill(response),
+                None => {
+                    // the fetch domain reports the network id with its own type
+                    if let Some(id) = req.event().network_id.as_ref() {
+                        pending
+                            .lock()
+                            .unwrap()
+                            .insert(RequestId::new(AsRef::<str>::as_ref(id)));
+                    }
+                    req.continue_request()
+                }
+            }
+        })
+        .await?;
+
+        Ok(CacheRecorder {
+            cache: self.clone(),
+            responses,
+            failed,
+            misses,
+        })
+    }
+
+    /// Stores the response in memory and on disk.
+    async fn store(&self, response: &Response) -> Result<()> {
+        let vary = response
+            .header("Vary")
+            .map(|vary| {
+                let request_headers = response.raw().request_headers.as_ref();
+                vary.split(',')
+                    .map(|name| name.trim().to_string())
+                    .filter(|name| !name.is_empty())
+                    .map(|name| {
+                        let value = request_headers
+                            .and_then(|headers| headers.inner().as_object())
+                            .and_then(|headers| {
+                                headers
+                                    .iter()
+                                    .find(|(key, _)| key.eq_ignore_ascii_case(&name))
+                            })
+                            .and_then(|(_, value)| value.as_str().map(str::to_string));
+                        (name, value)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut headers: Vec<_> = response
+            .headers()
+            .into_iter()
+            .filter(|(name, _)| !WIRE_HEADERS.contains(&name.to_ascii_lowercase().as_str()))
+            .collect();
+        headers.sort();
+        let cached = CachedResponse {
+            url: response.url().to_string(),
+            vary,
+            status: response.status(),
+            headers,
+            body: base64::encode(response.body().await?),
+            stored_at: unix_time(),
+        };
+        utils::write(
+            self.dir.join(cached.file_name()),
+            serde_json::to_vec(&cached)?,
+        )
+        .await?;
+
+        let mut entries = self.entries.lock().unwrap();
+        let cached_for_url = entries.entry(cached.url.clone()).or_default();
+        cached_for_url.retain(|c| c.vary != cached.vary);
+        cached_for_url.push(cached);
+        Ok(())
+    }
+}
+
+/// Stores the responses of a page's requests that missed the cache.
+pub struct CacheRecorder {
+    cache: ResponseCache,
+    responses: ResponseStream,
+    /// The requests that failed or were aborted, which never get a response
+    failed: EventStream<EventLoadingFailed>,
+    misses: Arc<Mutex<HashSet<RequestId>>>,
+}
+
+impl CacheRecorder {
+    /// Stores all responses that finished loading so far and returns how many
+    /// were stored.
+    ///
+    /// Only successful responses that don't forbid storing them with
+    /// `Cache-Control: no-store` and don't vary on everything with `Vary: *`
+    /// are cached.
+    pub async fn flush(&mut self) -> Result<usize> {
+        let mut stored = 0;
+        while let Some(Some(event)) = self.failed.next().now_or_never() {
+            self.misses.lock().unwrap().remove(&event.request_id);
+        }
+        while let Some(Some(response)) = self.responses.next().now_or_never() {
+            if !self.misses.lock().unwrap().remove(response.request_id()) {
+                continue;
+            }
+            if !is_storable(response.status(), |name| response.header(name)) {
+                continue;
+            }
+            self.cache.store(&response).await?;
+            stored += 1;
+        }
+        Ok(stored)
+    }
+}
+
+impl fmt::Debug for CacheRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheRecorder")
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+/// Whether a response with the status and headers, looked up by `header`,
+/// may be cached
+fn is_storable<'a>(status: i64, header: impl Fn(&str) -> Option<&'a str>) -> bool {
+    let no_store = header("Cache-Control").is_some_and(|value| value.contains("no-store"));
+    let varies_on_everything =
+        header("Vary").is_some_and(|value| value.split(',').any(|name| name.trim() == "*"));
+    (200..300).contains(&status) && status != 206 && !no_store && !varies_on_everything
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|time| time.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cached(vary: Vec<(String, Option<String>)>) -> CachedResponse {
+        CachedResponse {
+            url: "https://example.com/".to_string(),
+            vary,
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "text/html".to_string())],
+            body: base64::encode("<p>cached</p>"),
+            stored_at: 1_000,
+        }
+    }
+
+    #[test]
+    fn expires_after_ttl() {
+        let response = cached(Vec::new());
+        assert!(response.is_fresh(Duration::from_secs(60), 1_059));
+        assert!(!response.is_fresh(Duration::from_secs(60), 1_060));
+    }
+
+    #[test]
+    fn matches_vary_headers() {
+        let response = cached(vec![
+            ("Accept-Language".to_string(), Some("en".to_string())),
+            ("Cookie".to_string(), None),
+        ]);
+        assert!(response.matches(|name| (name == "Accept-Language").then(|| "en".to_string())));
+        assert!(!response.matches(|name| (name == "Accept-Language").then(|| "de".to_string())));
+        assert!(!response.matches(|_| Some("en".to_string())));
+        assert_ne!(response.file_name(), cached(Vec::new()).file_name());
+    }
+
+    #[test]
+    fn keeps_file_names_stable() {
+        assert_eq!(cached(Vec::new()).file_name(), "0c8b41cfdcb3c914.json");
+    }
+
+    #[test]
+    fn skips_unstorable_responses() {
+        let headers = |headers: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                headers
+                    .iter()
+                    .find(|(key, _)| key.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| *value)
+            }
+        };
+        assert!(is_storable(200, headers(&[("Vary", "Accept-Language")])));
+        assert!(!is_storable(
+            200,
+            headers(&[("Vary", "Accept-Language, *")])
+        ));
+        assert!(!is_storable(200, headers(&[("Cache-Control", "no-store")])));
+        assert!(!is_storable(206, headers(&[])));
+        assert!(!is_storable(404, headers(&[])));
+    }
+
+    #[test]
+    fn loads_stored_responses() {
+        let dir = std::env::temp_dir()
+            .join(format!("chromiumoxide-cache-{}", std::process::id()))
+            .join("responses");
+        let cache = futures::executor::block_on(async {
+            let cache = ResponseCache::open(&dir).await.unwrap();
+            assert!(cache.is_empty());
+            let response = cached(Vec::new());
+            utils::write(
+                dir.join(response.file_name()),
+                serde_json::to_vec(&response).unwrap(),
+            )
+            .await
+            .unwrap();
+            utils::write(dir.join("notes.txt"), "not an entry")
+                .await
+                .unwrap();
+            utils::write(dir.join("invalid.json"), "{}").await.unwrap();
+            ResponseCache::open(&dir).await.unwrap()
+        });
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn restores_route_response() {
+        let params = cached(Vec::new())
+            .to_route_response()
+            .into_params(chromiumoxide_cdp::cdp::browser_protocol::fetch::RequestId::new("1"));
+        assert_eq!(params.response_code, 200);
+        assert_eq!(
+            params.body.as_ref().map(AsRef::<str>::as_ref),
+            Some(base64::encode("<p>cached</p>").as_str())
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Selecting TLS client certificates for mTLS-protected hosts.
+//!
+//! Chromium asks the user to pick a certificate whenever a server requests
+//! one, which blocks headless browsers. A [`ClientCertificate`] registered
+//! with
+//! [`BrowserConfigBuilder::client_certificate`](crate::browser::BrowserConfigBuilder::client_certificate)
+//! selects the certificate for the matching hosts automatically instead, by
+//! writing the `auto_select_certificate` content setting to the profile of
+//! the user data dir before the browser is launched.
+//!
+//! The certificate and its private key have to be installed in the platform
+//! store: the NSS database in `~/.pki/nssdb` on Linux, e.g. with
+//! `pk12util -d sql:$HOME/.pki/nssdb -i client.p12`, the keychain on macOS and
+//! the certificate store on Windows.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::browser::{Browser, BrowserConfig};
+//! # use chromiumoxide::client_cert::ClientCertificate;
+//! # use chromiumoxide::error::Result;
+//! # async fn demo() -> Result<()> {
+//!     let config = BrowserConfig::builder()
+//!         .client_certificate(
+//!             ClientCertificate::new("https://[*.]internal.example.com").issuer("Example CA"),
+//!         )
+//!         .build()
+//!         .unwrap();
+//!     let (browser, handler) = Browser::launch(config).await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::{json, Map, Value};
+
+/// Selects a client certificate for the hosts that match a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientCertificate {
+    /// The pattern of the urls, like `https://[*.]example.com`, see
+    /// <https://chromeenterprise.google/policies/url-patterns/>
+    pub pattern: String,
+    /// The common name of the certificate's issuer
+    pub issuer: Option<String>,
+    /// The common name of the certificate's subject
+    pub subject: Option<String>,
+}
+
+impl ClientCertificate {
+    /// Selects the first certificate that the servers of the urls accept,
+    /// unless narrowed down by `issuer` or `subject`.
+    pub fn new(pattern: impl Into<String>) -> Self {
+        Self {
+            pattern: pattern.into(),
+            issuer: None,
+            subject: None,
+        }
+    }
+
+    /// Only selects certificates issued by the common name.
+    pub fn issuer(mut self, common_name: impl Into<String>) -> Self {
+        self.issuer = Some(common_name.into());
+        self
+    }
+
+    /// Only selects certificates of the common name.
+    pub fn subject(mut self, common_name: impl Into<String>) -> Self {
+        self.subject = Some(common_name.into());
+        self
+    }
+
+    /// The filter of the content setting
+    fn filter(&self) -> Value {
+        let mut filter = Map::new();
+        if let Some(issuer) = &self.issuer {
+            filter.insert("ISSUER".to_string(), json!({ "CN": issuer }));
+        }
+        if let Some(subject) = &self.subject {
+            filter.insert("SUBJECT".to_string(), json!({ "CN": subject }));
+        }
+        filter.into()
+    }
+}
+
+/// Adds the content settings of the certificates to the preferences of a
+/// profile, keeping all other preferences.
+fn add_to_preferences(preferences: &mut Value, certificates: &[ClientCertificate]) {
+    if !preferences.is_object() {
+        *preferences = json!({});
+    }
+    let mut exceptions = &mut *preferences;
+    for key in [
+        "profile",
+        "content_settings",
+        "exceptions",
+        "auto_select_certificate",
+    ] {
+        if !exceptions.get(key).is_some_and(Value::is_object) {
+            exceptions[key] = json!({});
+        }
+        exceptions = &mut exceptions[key];
+    }
+    for certificate in certificates {
+        let key = format!("{},*", certificate.pattern);
+        let mut filters = exceptions[&key]["setting"]["filters"]
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+        let filter = certificate.filter();
+        filters.retain(|f| *f != filter);
+        filters.push(filter);
+        exceptions[&key] = json!({ "setting": { "filters": filters } });
+    }
+}
+
+/// Writes the certificates to the preferences of the default profile of the
+/// user data dir.
+pub(crate) fn write_preferences(
+    user_data_dir: &Path,
+    certificates: &[ClientCertificate],
+) -> io::Result<()> {
+    if certificates.is_empty() {
+        return Ok(());
+    }
+    let profile = user_data_dir.join("Default");
+    fs::create_dir_all(&profile)?;
+    let path = profile.join("Preferences");
+    let mut preferences = fs::read(&path)
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .unwrap_or_else(|| json!({}));
+    add_to_preferences(&mut preferences, certificates);
+    fs::write(path, serde_json::to_vec(&preferences)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn adds_content_settings() {
+        let mut preferences = json!({"profile": {"name": "Person 1"}});
+        let certificate = ClientCertificate::new("https://[*.]example.com").issuer("Example CA");
+        add_to_preferences(&mut preferences, std::slice::from_ref(&certificate));
+        // adding the same certificate again doesn't duplicate its filter
+        add_to_preferences(
+            &mut preferences,
+            &[
+                certificate,
+                ClientCertificate::new("https://internal.test").subject("robot"),
+            ],
+        );
+        assert_eq!(
+            preferences,
+            json!({"profile": {
+                "name": "Person 1",
+                "content_settings": {"exceptions": {"auto_select_certificate": {
+                    "https://[*.]example.com,*": {"setting": {"filters": [
+                        {"ISSUER": {"CN": "Example CA"}}
+                    ]}},
+                    "https://internal.test,*": {"setting": {"filters": [
+                        {"SUBJECT": {"CN": "robot"}}
+                    ]}},
+                }}}
+            }})
+        );
+    }
+}
@@ -1,8 +1,11 @@
 use std::collections::VecDeque;
+use std::fmt;
 use std::marker::PhantomData;
 use std::pin::Pin;
+use std::time::Duration;
 
-use async_tungstenite::{tungstenite::protocol::WebSocketConfig, WebSocketStream};
+use async_tungstenite::tungstenite::{self, protocol::WebSocketConfig};
+use async_tungstenite::WebSocketStream;
 use futures::stream::Stream;
 use futures::task::{Context, Poll};
 use futures::Sink;
@@ -20,23 +23,32 @@ cfg_if::cfg_if! {
         use async_tungstenite::tokio::ConnectStream;
     }
 }
-/// Exchanges the messages with the websocket
-#[must_use = "streams do nothing unless polled"]
+
+/// Carries the json encoded CDP messages between a [`Connection`] and the
+/// browser.
+///
+/// Every `Stream` of received messages that is also a `Sink` of the messages
+/// to send is a transport, like a TLS-wrapped or tunneled connection or an
+/// in-memory channel in tests. The transport ends once the browser closed
+/// the connection.
+pub trait Transport:
+    Stream<Item = Result<String>> + Sink<String, Error = CdpError> + Send + Unpin
+{
+}
+
+impl<T> Transport for T where
+    T: Stream<Item = Result<String>> + Sink<String, Error = CdpError> + Send + Unpin
+{
+}
+
+/// The websocket transport of the browser's DevTools endpoint
 #[derive(Debug)]
-pub struct Connection<T: EventMessage> {
-    /// Queue of commands to send.
-    pending_commands: VecDeque<MethodCall>,
-    /// The websocket of the chromium instance
+pub struct WebSocketTransport {
     ws: WebSocketStream<ConnectStream>,
-    /// The identifier for a specific command
-    next_id: usize,
-    needs_flush: bool,
-    /// The message that is currently being proceessed
-    pending_flush: Option<MethodCall>,
-    _marker: PhantomData<T>,
 }
 
-impl<T: EventMessage + Unpin> Connection<T> {
+impl WebSocketTransport {
+    /// Dials the websocket of the DevTools endpoint
     pub async fn connect(debug_ws_url: impl AsRef<str>) -> Result<Self> {
         let config = WebSocketConfig {
             max_message_size: None,
@@ -52,19 +64,147 @@ impl<T: EventMessage + Unpin> Connection<T> {
                  let (ws, _) = async_tungstenite::tokio::connect_async_with_config(debug_ws_url.as_ref(), Some(config)).await?;
             }
         }
+        Ok(Self { ws })
+    }
+}
+
+impl Stream for WebSocketTransport {
+    type Item = Result<String>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        loop {
+            return match Stream::poll_next(Pin::new(&mut pin.ws), cx) {
+                Poll::Ready(Some(Ok(tungstenite::Message::Text(text)))) => {
+                    Poll::Ready(Some(Ok(text)))
+                }
+                Poll::Ready(Some(Ok(tungstenite::Message::Binary(data)))) => {
+                    match String::from_utf8(data) {
+                        Ok(text) => Poll::Ready(Some(Ok(text))),
+                        Err(err) => Poll::Ready(Some(Err(CdpError::msg(err.to_string())))),
+                    }
+                }
+                // pings are answered by the websocket itself
+                Poll::Ready(Some(Ok(_))) => continue,
+                Poll::Ready(Some(Err(err))) => Poll::Ready(Some(Err(CdpError::Ws(err)))),
+                Poll::Ready(None) => Poll::Ready(None),
+                Poll::Pending => Poll::Pending,
+            };
+        }
+    }
+}
+
+impl Sink<String> for WebSocketTransport {
+    type Error = CdpError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Sink::poll_ready(Pin::new(&mut self.get_mut().ws), cx).map_err(CdpError::Ws)
+    }
 
-        Ok(Self {
+    fn start_send(self: Pin<&mut Self>, item: String) -> Result<()> {
+        Sink::start_send(Pin::new(&mut self.get_mut().ws), item.into()).map_err(CdpError::Ws)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Sink::poll_flush(Pin::new(&mut self.get_mut().ws), cx).map_err(CdpError::Ws)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Sink::poll_close(Pin::new(&mut self.get_mut().ws), cx).map_err(CdpError::Ws)
+    }
+}
+
+/// Exchanges the messages with the browser over its transport
+#[must_use = "streams do nothing unless polled"]
+pub struct Connection<T: EventMessage> {
+    /// Queue of commands to send.
+    pending_commands: VecDeque<MethodCall>,
+    /// The transport to the chromium instance
+    transport: Box<dyn Transport>,
+    /// The identifier for a specific command
+    next_id: usize,
+    needs_flush: bool,
+    /// The message that is currently being proceessed
+    pending_flush: Option<MethodCall>,
+    /// The url of the websocket, if the connection was dialed
+    url: Option<String>,
+    _marker: PhantomData<T>,
+}
+
+impl<T: EventMessage> fmt::Debug for Connection<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Connection")
+            .field("pending_commands", &self.pending_commands)
+            .field("next_id", &self.next_id)
+            .field("url", &self.url)
+            .finish()
+    }
+}
+
+/// How the `Handler` re-dials the browser after the websocket connection was
+/// lost, see `Browser::connect_with_config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// How often to try to connect again before giving up
+    pub max_attempts: u32,
+    /// How long to wait before each attempt
+    pub delay: Duration,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            delay: Duration::from_secs(1),
+        }
+    }
+}
+
+/// The state of the connection to the browser, see
+/// `Browser::connection_state`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The websocket connection was lost, pending requests failed with
+    /// `CdpError::Disconnected`
+    Disconnected,
+    /// Connecting to the browser again, starting with attempt `1`
+    Reconnecting { attempt: u32 },
+    /// The connection is re-established and the pages are attached again
+    Reconnected,
+    /// All attempts to connect again failed, the handler stops
+    Closed,
+}
+
+impl<T: EventMessage + Unpin> Connection<T> {
+    /// Dials the websocket of the DevTools endpoint
+    pub async fn connect(debug_ws_url: impl AsRef<str>) -> Result<Self> {
+        let transport = WebSocketTransport::connect(debug_ws_url.as_ref()).await?;
+        let mut conn = Self::with_transport(transport);
+        conn.url = Some(debug_ws_url.as_ref().to_string());
+        Ok(conn)
+    }
+}
+
+impl<T: EventMessage> Connection<T> {
+    /// Exchanges the messages over the transport instead of a websocket
+    pub fn with_transport(transport: impl Transport + 'static) -> Self {
+        Self {
             pending_commands: Default::default(),
-            ws,
+            transport: Box::new(transport),
             next_id: 0,
             needs_flush: false,
             pending_flush: None,
+            url: None,
             _marker: Default::default(),
-        })
+        }
+    }
+
+    /// The url of the websocket this connection was dialed with, `None` for
+    /// a custom transport
+    pub fn url(&self) -> Option<&str> {
+        self.url.as_deref()
     }
-}
 
-impl<T: EventMessage> Connection<T> {
     fn next_call_id(&mut self) -> CallId {
         let id = CallId::new(self.next_id);
         self.next_id = self.next_id.wrapping_add(1);
@@ -94,7 +234,7 @@ impl<T: EventMessage> Connection<T> {
     /// sink
     fn start_send_next(&mut self, cx: &mut Context<'_>) -> Result<()> {
         if self.needs_flush {
-            if let Poll::Ready(Ok(())) = Sink::poll_flush(Pin::new(&mut self.ws), cx) {
+            if let Poll::Ready(Ok(())) = Sink::poll_flush(Pin::new(&mut self.transport), cx) {
                 self.needs_flush = false;
             }
         }
@@ -106,7 +246,7 @@ impl<T: EventMessage> Connection<T> {
                 // }
                 tracing::trace!("Sending {:?}", cmd);
                 let msg = serde_json::to_string(&cmd)?;
-                Sink::start_send(Pin::new(&mut self.ws), msg.into())?;
+                Sink::start_send(Pin::new(&mut self.transport), msg)?;
                 self.pending_flush = Some(cmd);
             }
         }
@@ -127,16 +267,16 @@ impl<T: EventMessage + Unpin> Stream for Connection<T> {
 
         // send the message
         if let Some(call) = pin.pending_flush.take() {
-            if Sink::poll_ready(Pin::new(&mut pin.ws), cx).is_ready() {
+            if Sink::poll_ready(Pin::new(&mut pin.transport), cx).is_ready() {
                 pin.needs_flush = true;
             } else {
                 pin.pending_flush = Some(call);
             }
         }
-        // read from the ws
-        match Stream::poll_next(Pin::new(&mut pin.ws), cx) {
+        // read from the transport
+        match Stream::poll_next(Pin::new(&mut pin.transport), cx) {
             Poll::Ready(Some(Ok(msg))) => {
-                return match serde_json::from_slice::<Message<T>>(&msg.into_data()) {
+                return match serde_json::from_str::<Message<T>>(&msg) {
                     Ok(msg) => {
                         tracing::trace!("Received {:?}", msg);
                         Poll::Ready(Some(Ok(msg)))
@@ -148,10 +288,100 @@ impl<T: EventMessage + Unpin> Stream for Connection<T> {
                 };
             }
             Poll::Ready(Some(Err(err))) => {
-                return Poll::Ready(Some(Err(CdpError::Ws(err))));
+                return Poll::Ready(Some(Err(err)));
             }
+            Poll::Ready(None) => return Poll::Ready(None),
             _ => {}
         }
         Poll::Pending
     }
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+    use futures::StreamExt;
+
+    use chromiumoxide_cdp::cdp::CdpEventMessage;
+
+    use super::*;
+
+    /// An in-memory transport
+    pub(crate) struct Pipe {
+        rx: UnboundedReceiver<Result<String>>,
+        tx: UnboundedSender<String>,
+    }
+
+    impl Pipe {
+        /// A connection over a pipe, with the ends to send it messages and
+        /// receive the messages it sends
+        pub(crate) fn connection() -> (
+            Connection<CdpEventMessage>,
+            UnboundedSender<Result<String>>,
+            UnboundedReceiver<String>,
+        ) {
+            let (to_conn, rx) = unbounded();
+            let (tx, from_conn) = unbounded();
+            (
+                Connection::with_transport(Pipe { rx, tx }),
+                to_conn,
+                from_conn,
+            )
+        }
+    }
+
+    impl Stream for Pipe {
+        type Item = Result<String>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            self.get_mut().rx.poll_next_unpin(cx)
+        }
+    }
+
+    impl Sink<String> for Pipe {
+        type Error = CdpError;
+
+        fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+            Sink::poll_ready(Pin::new(&mut self.get_mut().tx), cx)
+                .map_err(|err| CdpError::msg(err.to_string()))
+        }
+
+        fn start_send(self: Pin<&mut Self>, item: String) -> Result<()> {
+            Sink::start_send(Pin::new(&mut self.get_mut().tx), item)
+                .map_err(|err| CdpError::msg(err.to_string()))
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[async_std::test]
+    async fn exchanges_messages_over_transport() {
+        let (mut conn, to_conn, mut from_conn) = Pipe::connection();
+        assert_eq!(conn.url(), None);
+
+        let id = conn
+            .submit_command("Browser.getVersion".into(), None, serde_json::json!({}))
+            .unwrap();
+        to_conn
+            .unbounded_send(Ok(serde_json::json!({"id": 0, "result": {}}).to_string()))
+            .unwrap();
+        match conn.next().await {
+            Some(Ok(Message::Response(resp))) => assert_eq!(resp.id, id),
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        let sent: serde_json::Value =
+            serde_json::from_str(&from_conn.next().await.unwrap()).unwrap();
+        assert_eq!(sent["method"], "Browser.getVersion");
+        assert_eq!(sent["id"], 0);
+
+        drop(to_conn);
+        assert!(conn.next().await.is_none());
+    }
+}
@@ -0,0 +1,242 @@
+//! Structured console messages.
+//!
+//! [`Page::console_messages`](crate::page::Page::console_messages) returns a
+//! [`ConsoleStream`] of the [`ConsoleMessage`]s logged by the page, with the
+//! arguments of the console calls resolved to JSON and the location of the
+//! call.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::console::ConsoleLevel;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut messages = page.console_messages().await?;
+//!     while let Some(message) = messages.next().await {
+//!         if message.level == ConsoleLevel::Error {
+//!             eprintln!("{} at {:?}", message.text, message.location);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::{BoxStream, Stream, StreamExt};
+
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    CallFunctionOnParams, ConsoleApiCalledType, EventConsoleApiCalled, ExecutionContextId,
+    RemoteObject, RemoteObjectType, StackTrace,
+};
+
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+
+/// The severity of a console message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConsoleLevel {
+    Debug,
+    Info,
+    Warning,
+    Error,
+}
+
+impl From<&ConsoleApiCalledType> for ConsoleLevel {
+    fn from(r#type: &ConsoleApiCalledType) -> Self {
+        match r#type {
+            ConsoleApiCalledType::Debug => ConsoleLevel::Debug,
+            ConsoleApiCalledType::Warning => ConsoleLevel::Warning,
+            ConsoleApiCalledType::Error | ConsoleApiCalledType::Assert => ConsoleLevel::Error,
+            _ => ConsoleLevel::Info,
+        }
+    }
+}
+
+/// Where a console method was called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleLocation {
+    pub url: String,
+    /// 0-based line number
+    pub line_number: i64,
+    /// 0-based column number
+    pub column_number: i64,
+}
+
+/// A message logged through the console of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConsoleMessage {
+    /// The console method that was called, like `log` or `table`
+    pub r#type: ConsoleApiCalledType,
+    pub level: ConsoleLevel,
+    /// The arguments joined by spaces, strings without quotes
+    pub text: String,
+    /// The arguments as JSON, their description if they can't be
+    /// serialized
+    pub args: Vec<serde_json::Value>,
+    /// The location of the call, if known
+    pub location: Option<ConsoleLocation>,
+    pub stack_trace: Option<StackTrace>,
+    pub execution_context_id: ExecutionContextId,
+    /// Milliseconds since the epoch
+    pub timestamp: f64,
+}
+
+impl ConsoleMessage {
+    /// Creates the message of the event with its resolved arguments.
+    pub(crate) fn new(event: &EventConsoleApiCalled, args: Vec<serde_json::Value>) -> Self {
+        let text = args
+            .iter()
+            .map(|arg| match arg {
+                serde_json::Value::String(s) => s.clone(),
+                arg => arg.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        let location = event
+            .stack_trace
+            .as_ref()
+            .and_then(|trace| trace.call_frames.first())
+            .map(|frame| ConsoleLocation {
+                url: frame.url.clone(),
+                line_number: frame.line_number,
+                column_number: frame.column_number,
+            });
+        Self {
+            r#type: event.r#type.clone(),
+            level: ConsoleLevel::from(&event.r#type),
+            text,
+            args,
+            location,
+            stack_trace: event.stack_trace.clone(),
+            execution_context_id: event.execution_context_id,
+            timestamp: *event.timestamp.inner(),
+        }
+    }
+}
+
+/// The value of an argument that doesn't need to be resolved
+fn arg_value(arg: &RemoteObject) -> Option<serde_json::Value> {
+    if let Some(value) = &arg.value {
+        return Some(value.clone());
+    }
+    if let Some(value) = &arg.unserializable_value {
+        return Some(value.inner().clone().into());
+    }
+    if arg.r#type == RemoteObjectType::Undefined {
+        return Some(serde_json::Value::Null);
+    }
+    None
+}
+
+/// Resolves the argument to JSON, its description if it can't be serialized
+async fn resolve_arg(page: &PageInner, arg: &RemoteObject) -> serde_json::Value {
+    if let Some(value) = arg_value(arg) {
+        return value;
+    }
+    let description = || arg.description.clone().unwrap_or_default().into();
+    let Some(object_id) = arg.object_id.clone() else {
+        return description();
+    };
+    let mut params = CallFunctionOnParams::new("function() { return this; }");
+    params.object_id = Some(object_id);
+    params.return_by_value = Some(true);
+    match page.execute(params).await {
+        Ok(resp) if resp.result.exception_details.is_none() => {
+            resp.result.result.value.clone().unwrap_or_else(description)
+        }
+        _ => description(),
+    }
+}
+
+/// A stream of the messages logged through the console of a page.
+#[must_use = "streams do nothing unless polled"]
+pub struct ConsoleStream {
+    messages: BoxStream<'static, ConsoleMessage>,
+}
+
+impl ConsoleStream {
+    pub(crate) fn new(page: Arc<PageInner>, events: EventStream<EventConsoleApiCalled>) -> Self {
+        let messages = events
+            .then(move |event| {
+                let page = page.clone();
+                async move {
+                    let mut args = Vec::with_capacity(event.args.len());
+                    for arg in &event.args {
+                        args.push(resolve_arg(&page, arg).await);
+                    }
+                    ConsoleMessage::new(&event, args)
+                }
+            })
+            .boxed();
+        Self { messages }
+    }
+}
+
+impl fmt::Debug for ConsoleStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConsoleStream").finish()
+    }
+}
+
+impl Stream for ConsoleStream {
+    type Item = ConsoleMessage;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().messages.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_messages() {
+        let event: EventConsoleApiCalled = serde_json::from_value(serde_json::json!({
+            "type": "warning",
+            "args": [
+                {"type": "string", "value": "count"},
+                {"type": "number", "unserializableValue": "NaN", "description": "NaN"},
+                {"type": "undefined"},
+                {"type": "object", "objectId": "1", "description": "Object"},
+            ],
+            "executionContextId": 1,
+            "timestamp": 1000.5,
+            "stackTrace": {"callFrames": [{
+                "functionName": "run",
+                "scriptId": "3",
+                "url": "https://example.com/app.js",
+                "lineNumber": 4,
+                "columnNumber": 10,
+            }]},
+        }))
+        .unwrap();
+        let args: Vec<_> = event.args.iter().map(arg_value).collect();
+        assert_eq!(
+            args,
+            [
+                Some("count".into()),
+                Some("NaN".into()),
+                Some(serde_json::Value::Null),
+                None
+            ]
+        );
+
+        let message = ConsoleMessage::new(
+            &event,
+            vec!["count".into(), serde_json::json!({"a": [1, 2]})],
+        );
+        assert_eq!(message.level, ConsoleLevel::Warning);
+        assert_eq!(message.text, r#"count {"a":[1,2]}"#);
+        let location = message.location.unwrap();
+        assert_eq!(location.url, "https://example.com/app.js");
+        assert_eq!((location.line_number, location.column_number), (4, 10));
+        assert_eq!(message.timestamp, 1000.5);
+    }
+}
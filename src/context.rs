@@ -0,0 +1,229 @@
+//! Isolated browser contexts.
+//!
+//! A [`BrowserContext`] created with
+//! [`Browser::new_context`](crate::browser::Browser::new_context) is an
+//! incognito session of its own: its pages share cookies, storage, cache and
+//! permissions with each other but not with the pages of any other context.
+//! This makes it cheap to run independent jobs in parallel within a single
+//! browser process.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::browser::Browser;
+//! # use chromiumoxide::context::ContextOptions;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::permission::Permission;
+//! # use chromiumoxide::proxy::Proxy;
+//! # async fn demo(browser: Browser) -> Result<()> {
+//!     let context = browser
+//!         .new_context(
+//!             ContextOptions::default()
+//!                 .permission(Permission::ClipboardRead)
+//!                 .proxy(Proxy::new("http://proxy.example.com:8080").with_credentials("user", "pass")),
+//!         )
+//!         .await?;
+//!     let page = context.new_page("https://example.com").await?;
+//!     let cookies = context.get_cookies().await?;
+//!     context.close().await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use futures::channel::mpsc::Sender;
+use futures::channel::oneshot::channel as oneshot_channel;
+use futures::SinkExt;
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::{
+    BrowserContextId, GrantPermissionsParams, ResetPermissionsParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::network::{Cookie, CookieParam};
+use chromiumoxide_cdp::cdp::browser_protocol::storage::{
+    ClearCookiesParams, GetCookiesParams, SetCookiesParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::target::{
+    CreateTargetParams, DisposeBrowserContextParams,
+};
+use chromiumoxide_types::{Command, CommandResponse};
+
+use crate::cmd::{to_command_response, CommandMessage};
+use crate::error::Result;
+use crate::handler::{browser, HandlerMessage};
+use crate::page::Page;
+use crate::permission::Permission;
+use crate::proxy::Proxy;
+use crate::storage_state::StorageState;
+
+/// How to set up a new browser context.
+#[derive(Debug, Clone, Default)]
+pub struct ContextOptions {
+    /// The cookies and `localStorage` the context starts with
+    pub storage_state: Option<StorageState>,
+    /// The permissions granted to all origins
+    pub permissions: Vec<Permission>,
+    /// Whether the pages of the context start offline
+    pub offline: bool,
+    /// The proxy the requests of the context are sent through
+    pub proxy: Option<Proxy>,
+}
+
+impl ContextOptions {
+    pub fn storage_state(mut self, state: StorageState) -> Self {
+        self.storage_state = Some(state);
+        self
+    }
+
+    pub fn permission(mut self, permission: Permission) -> Self {
+        self.permissions.push(permission);
+        self
+    }
+
+    pub fn permissions(mut self, permissions: impl IntoIterator<Item = Permission>) -> Self {
+        self.permissions.extend(permissions);
+        self
+    }
+
+    pub fn offline(mut self, offline: bool) -> Self {
+        self.offline = offline;
+        self
+    }
+
+    /// Sends the requests of the context through the proxy, answering its
+    /// authentication challenges with its credentials, if any.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+}
+
+/// An incognito browser context, see the [`context`](crate::context) module.
+///
+/// The context stays open until [`BrowserContext::close`] is called or the
+/// browser is closed.
+#[derive(Debug, Clone)]
+pub struct BrowserContext {
+    id: BrowserContextId,
+    sender: Sender<HandlerMessage>,
+}
+
+impl BrowserContext {
+    pub(crate) fn new(id: BrowserContextId, sender: Sender<HandlerMessage>) -> Self {
+        Self { id, sender }
+    }
+
+    /// The identifier of this context
+    pub fn id(&self) -> &BrowserContextId {
+        &self.id
+    }
+
+    /// Creates a new page within this context.
+    pub async fn new_page(&self, params: impl Into<CreateTargetParams>) -> Result<Page> {
+        let (tx, rx) = oneshot_channel();
+        let mut params = params.into();
+        params.browser_context_id = Some(self.id.clone());
+        self.sender
+            .clone()
+            .send(HandlerMessage::CreatePage(params, tx))
+            .await?;
+        rx.await?
+    }
+
+    /// Returns the open pages of this context, including its popups.
+    pub async fn pages(&self) -> Result<Vec<Page>> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(HandlerMessage::GetContextPages(self.key(), tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns all cookies of this context.
+    pub async fn get_cookies(&self) -> Result<Vec<Cookie>> {
+        let params = GetCookiesParams {
+            browser_context_id: Some(self.id.clone()),
+        };
+        Ok(self.execute(params).await?.result.cookies)
+    }
+
+    /// Sets the cookies in this context, every cookie needs either a `url` or
+    /// a `domain`.
+    pub async fn set_cookies(&self, cookies: Vec<CookieParam>) -> Result<&Self> {
+        let mut params = SetCookiesParams::new(cookies);
+        params.browser_context_id = Some(self.id.clone());
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Deletes all cookies of this context.
+    pub async fn clear_cookies(&self) -> Result<&Self> {
+        let params = ClearCookiesParams {
+            browser_context_id: Some(self.id.clone()),
+        };
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Grants the permissions to the `origin` in this context, or to all
+    /// origins if `None`, see `Browser::grant_permissions`.
+    pub async fn grant_permissions(
+        &self,
+        permissions: &[Permission],
+        origin: Option<&str>,
+    ) -> Result<&Self> {
+        let mut params =
+            GrantPermissionsParams::new(permissions.iter().map(|p| (*p).into()).collect());
+        params.origin = origin.map(str::to_string);
+        params.browser_context_id = Some(self.id.clone());
+        self.execute(params).await?;
+        Ok(self)
+    }
+
+    /// Resets all permissions of this context to their defaults.
+    pub async fn clear_permission_overrides(&self) -> Result<&Self> {
+        self.execute(ResetPermissionsParams {
+            browser_context_id: Some(self.id.clone()),
+        })
+        .await?;
+        Ok(self)
+    }
+
+    /// Emulates a loss of connectivity for all pages of this context,
+    /// including pages that are created while offline.
+    pub async fn set_offline(&self, offline: bool) -> Result<&Self> {
+        self.sender
+            .clone()
+            .send(HandlerMessage::SetOffline(self.key(), offline))
+            .await?;
+        Ok(self)
+    }
+
+    /// Call a browser method.
+    pub async fn execute<T: Command>(&self, cmd: T) -> Result<CommandResponse<T::Response>> {
+        let (tx, rx) = oneshot_channel();
+        let method = cmd.identifier();
+        let msg = CommandMessage::new(cmd, tx)?;
+        self.sender
+            .clone()
+            .send(HandlerMessage::Command(msg))
+            .await?;
+        let resp = rx.await??;
+        to_command_response::<T>(resp, method)
+    }
+
+    /// Disposes this context and closes all of its pages.
+    pub async fn close(self) -> Result<()> {
+        self.execute(DisposeBrowserContextParams::new(self.id.clone()))
+            .await?;
+        self.sender
+            .clone()
+            .send(HandlerMessage::DisposeContext(self.key()))
+            .await?;
+        Ok(())
+    }
+
+    /// The key of this context in the handler
+    fn key(&self) -> browser::BrowserContext {
+        browser::BrowserContext::from(self.id.clone())
+    }
+}
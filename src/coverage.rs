@@ -0,0 +1,193 @@
+//! Coverage of the JavaScript and CSS of a page.
+//!
+//! [`Page::start_js_coverage`](crate::page::Page::start_js_coverage) and
+//! [`Page::start_css_coverage`](crate::page::Page::start_css_coverage) start
+//! recording which parts of the page's scripts and stylesheets are used, the
+//! matching `stop_*` methods return a [`CoverageEntry`] for each of them.
+//! [`to_lcov`] serializes the entries as an lcov tracefile for the usual
+//! coverage tools.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::coverage;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.start_js_coverage().await?;
+//!     page.goto("https://example.com").await?;
+//!     let entries = page.stop_js_coverage().await?;
+//!     for entry in &entries {
+//!         println!("{}: {:.1}% used", entry.url, entry.used_ratio() * 100.);
+//!     }
+//!     std::fs::write("coverage.lcov", coverage::to_lcov(&entries))?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use chromiumoxide_cdp::cdp::browser_protocol::css::RuleUsage;
+use chromiumoxide_cdp::cdp::js_protocol::profiler::FunctionCoverage;
+
+/// The used parts of a script or stylesheet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageEntry {
+    /// The url of the script or stylesheet, empty for inline ones
+    pub url: String,
+    /// The source of the script or stylesheet
+    pub text: String,
+    /// The disjoint, sorted byte ranges of `text` that were used
+    pub ranges: Vec<Range<usize>>,
+}
+
+impl CoverageEntry {
+    /// The share of the bytes of the text that were used, from 0 to 1.
+    pub fn used_ratio(&self) -> f64 {
+        if self.text.is_empty() {
+            return 0.;
+        }
+        let used: usize = self.ranges.iter().map(|range| range.len()).sum();
+        used as f64 / self.text.len() as f64
+    }
+}
+
+/// The byte offset of every UTF-16 offset of the text, the browser reports
+/// offsets in UTF-16 code units.
+fn byte_offsets(text: &str) -> Vec<usize> {
+    let mut offsets = Vec::with_capacity(text.len() + 1);
+    for (idx, c) in text.char_indices() {
+        offsets.extend(std::iter::repeat_n(idx, c.len_utf16()));
+    }
+    offsets.push(text.len());
+    offsets
+}
+
+/// The runs of used bytes
+fn used_runs(used: &[bool]) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut start = None;
+    for (idx, used) in used
+        .iter()
+        .copied()
+        .chain(std::iter::once(false))
+        .enumerate()
+    {
+        match (used, start) {
+            (true, None) => start = Some(idx),
+            (false, Some(from)) => {
+                ranges.push(from..idx);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    ranges
+}
+
+/// Applies the UTF-16 ranges in order, later ranges override the used state
+/// of the ranges they are nested in.
+fn apply_ranges(text: &str, ranges: impl Iterator<Item = (f64, f64, bool)>) -> Vec<Range<usize>> {
+    let offsets = byte_offsets(text);
+    let to_byte = |offset: f64| offsets[(offset.max(0.) as usize).min(offsets.len() - 1)];
+    let mut used = vec![false; text.len()];
+    for (start, end, is_used) in ranges {
+        used[to_byte(start)..to_byte(end)].fill(is_used);
+    }
+    used_runs(&used)
+}
+
+/// The used byte ranges of a script from its precise coverage.
+///
+/// Block ranges are nested in the ranges of their function, which are
+/// nested in the script's range, and come after them.
+pub(crate) fn js_ranges(text: &str, functions: &[FunctionCoverage]) -> Vec<Range<usize>> {
+    apply_ranges(
+        text,
+        functions.iter().flat_map(|function| {
+            function.ranges.iter().map(|range| {
+                (
+                    range.start_offset as f64,
+                    range.end_offset as f64,
+                    range.count > 0,
+                )
+            })
+        }),
+    )
+}
+
+/// The used byte ranges of a stylesheet from the usage of its rules.
+pub(crate) fn css_ranges<'a>(
+    text: &str,
+    rules: impl Iterator<Item = &'a RuleUsage>,
+) -> Vec<Range<usize>> {
+    apply_ranges(
+        text,
+        rules
+            .filter(|rule| rule.used)
+            .map(|rule| (rule.start_offset, rule.end_offset, true)),
+    )
+}
+
+/// Serializes the entries as an lcov tracefile, where a line counts as hit
+/// if any part of it was used. Entries without a url are skipped.
+pub fn to_lcov(entries: &[CoverageEntry]) -> String {
+    let mut lcov = String::new();
+    for entry in entries.iter().filter(|entry| !entry.url.is_empty()) {
+        let _ = writeln!(lcov, "SF:{}", entry.url);
+        let (mut found, mut hit) = (0, 0);
+        let mut start = 0;
+        for (idx, line) in entry.text.split_inclusive('\n').enumerate() {
+            let end = start + line.len();
+            if !line.trim().is_empty() {
+                let used = entry
+                    .ranges
+                    .iter()
+                    .any(|range| range.start < end && range.end > start);
+                found += 1;
+                hit += used as usize;
+                let _ = writeln!(lcov, "DA:{},{}", idx + 1, used as u8);
+            }
+            start = end;
+        }
+        let _ = writeln!(lcov, "LF:{found}\nLH:{hit}\nend_of_record");
+    }
+    lcov
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chromiumoxide_cdp::cdp::js_protocol::profiler::CoverageRange;
+
+    #[test]
+    fn applies_nested_ranges() {
+        let text = "function a() { if (x) { b(); } }\na();";
+        let script = CoverageRange::new(0, text.len() as i64, 1);
+        let block = CoverageRange::new(22, 30, 0);
+        let functions = vec![FunctionCoverage::new("", vec![script, block], true)];
+        assert_eq!(js_ranges(text, &functions), vec![0..22, 30..text.len()]);
+    }
+
+    #[test]
+    fn maps_utf16_offsets_to_bytes() {
+        let text = "ä{}b";
+        let ranges = apply_ranges(text, std::iter::once((1., 3., true)));
+        assert_eq!(ranges, vec![2..4]);
+        assert_eq!(&text[ranges[0].clone()], "{}");
+    }
+
+    #[test]
+    fn serializes_lcov() {
+        let entry = CoverageEntry {
+            url: "https://example.com/app.js".to_string(),
+            text: "a();\n\nb();\n".to_string(),
+            ranges: std::iter::once(0..4).collect(),
+        };
+        assert_eq!(
+            to_lcov(&[entry]),
+            "SF:https://example.com/app.js\nDA:1,1\nDA:3,0\nLF:2\nLH:1\nend_of_record\n"
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Presets of common mobile devices and tablets.
+//!
+//! A [`Device`] bundles the viewport, device scale factor, user agent and
+//! touch capabilities of a device. [`Page::emulate`](crate::page::Page::emulate)
+//! applies all of them to a page, and a device converts into a
+//! [`Viewport`] to configure the browser with it from the start.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::devices;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.emulate(&devices::IPHONE_14).await?;
+//!     page.goto("https://example.com").await?;
+//!
+//!     page.emulate(&devices::IPAD.landscape()).await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use crate::handler::viewport::Viewport;
+
+/// The characteristics of a device to emulate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    /// The name of the device, like `iPhone 14`
+    pub name: &'static str,
+    /// The user agent of the device's default browser
+    pub user_agent: &'static str,
+    /// The width of the viewport in CSS pixels
+    pub width: u32,
+    /// The height of the viewport in CSS pixels
+    pub height: u32,
+    /// The ratio of physical pixels to CSS pixels
+    pub device_scale_factor: f64,
+    /// Whether the device is a mobile device, which enables the `meta
+    /// viewport` tag and overlay scrollbars
+    pub is_mobile: bool,
+    /// Whether the device has a touch screen
+    pub has_touch: bool,
+    /// Whether the device is held in landscape orientation
+    pub is_landscape: bool,
+}
+
+impl Device {
+    /// The device rotated to landscape orientation.
+    pub fn landscape(&self) -> Device {
+        if self.is_landscape {
+            return self.clone();
+        }
+        Device {
+            width: self.height,
+            height: self.width,
+            is_landscape: true,
+            ..self.clone()
+        }
+    }
+}
+
+impl From<&Device> for Viewport {
+    fn from(device: &Device) -> Self {
+        Viewport {
+            width: device.width,
+            height: device.height,
+            device_scale_factor: Some(device.device_scale_factor),
+            emulating_mobile: device.is_mobile,
+            is_landscape: device.is_landscape,
+            has_touch: device.has_touch,
+        }
+    }
+}
+
+const IOS_16_USER_AGENT: &str = "Mozilla/5.0 (iPhone; CPU iPhone OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1";
+
+const IPADOS_16_USER_AGENT: &str = "Mozilla/5.0 (iPad; CPU OS 16_0 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/16.0 Mobile/15E148 Safari/604.1";
+
+/// Apple iPhone SE (3rd generation)
+pub const IPHONE_SE: Device = Device {
+    name: "iPhone SE",
+    user_agent: IOS_16_USER_AGENT,
+    width: 375,
+    height: 667,
+    device_scale_factor: 2.,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// Apple iPhone 14
+pub const IPHONE_14: Device = Device {
+    name: "iPhone 14",
+    user_agent: IOS_16_USER_AGENT,
+    width: 390,
+    height: 844,
+    device_scale_factor: 3.,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// Apple iPhone 14 Pro Max
+pub const IPHONE_14_PRO_MAX: Device = Device {
+    name: "iPhone 14 Pro Max",
+    user_agent: IOS_16_USER_AGENT,
+    width: 430,
+    height: 932,
+    device_scale_factor: 3.,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// Google Pixel 7
+pub const PIXEL_7: Device = Device {
+    name: "Pixel 7",
+    user_agent: "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36",
+    width: 412,
+    height: 915,
+    device_scale_factor: 2.625,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// Samsung Galaxy S22
+pub const GALAXY_S22: Device = Device {
+    name: "Galaxy S22",
+    user_agent: "Mozilla/5.0 (Linux; Android 13; SM-S901B) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36",
+    width: 360,
+    height: 780,
+    device_scale_factor: 3.,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// Apple iPad (9th generation)
+pub const IPAD: Device = Device {
+    name: "iPad",
+    user_agent: IPADOS_16_USER_AGENT,
+    width: 810,
+    height: 1080,
+    device_scale_factor: 2.,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// Apple iPad Pro 11
+pub const IPAD_PRO_11: Device = Device {
+    name: "iPad Pro 11",
+    user_agent: IPADOS_16_USER_AGENT,
+    width: 834,
+    height: 1194,
+    device_scale_factor: 2.,
+    is_mobile: true,
+    has_touch: true,
+    is_landscape: false,
+};
+
+/// All device presets of this module.
+pub const ALL: &[Device] = &[
+    IPHONE_SE,
+    IPHONE_14,
+    IPHONE_14_PRO_MAX,
+    PIXEL_7,
+    GALAXY_S22,
+    IPAD,
+    IPAD_PRO_11,
+];
+
+/// Returns the preset with the given name, compared case-insensitively.
+pub fn find(name: &str) -> Option<&'static Device> {
+    ALL.iter()
+        .find(|device| device.name.eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_to_landscape() {
+        let device = IPHONE_14.landscape();
+        assert_eq!((device.width, device.height), (844, 390));
+        assert!(device.is_landscape);
+        assert_eq!(device.landscape(), device);
+
+        let viewport = Viewport::from(&device);
+        assert!(viewport.is_landscape);
+        assert_eq!(viewport.device_scale_factor, Some(3.));
+    }
+
+    #[test]
+    fn finds_presets_by_name() {
+        assert_eq!(find("pixel 7"), Some(&PIXEL_7));
+        assert_eq!(find("Nokia 3310"), None);
+    }
+}
@@ -0,0 +1,165 @@
+//! Handling JavaScript dialogs.
+//!
+//! A page that opens an `alert`, `confirm`, `prompt` or `beforeunload` dialog
+//! is blocked until the dialog is closed, including any navigation waiting
+//! for it. [`Page::on_dialog`](crate::page::Page::on_dialog) returns a
+//! [`DialogStream`] of the [`Dialog`]s the page opens, which can be accepted
+//! or dismissed. Alternatively a [`DialogPolicy`] set with
+//! [`Page::set_dialog_policy`](crate::page::Page::set_dialog_policy) closes
+//! all dialogs as soon as they open.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::cdp::browser_protocol::page::DialogType;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut dialogs = page.on_dialog().await?;
+//!     while let Some(dialog) = dialogs.next().await {
+//!         if *dialog.r#type() == DialogType::Prompt {
+//!             dialog.accept(Some("chromiumoxide")).await?;
+//!         } else {
+//!             dialog.dismiss().await?;
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::{Stream, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    DialogType, EventJavascriptDialogOpening, HandleJavaScriptDialogParams,
+};
+
+use crate::error::Result;
+use crate::listeners::EventStream;
+use crate::page::Page;
+
+/// How dialogs are closed without handling them through a [`DialogStream`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DialogPolicy {
+    /// Dialogs stay open until they are accepted or dismissed
+    #[default]
+    Manual,
+    /// Dialogs are accepted as soon as they open, prompts with their default
+    /// text
+    Accept,
+    /// Dialogs are dismissed as soon as they open
+    Dismiss,
+}
+
+impl DialogPolicy {
+    /// The command that closes a dialog according to the policy, `None` if
+    /// it's left open
+    pub(crate) fn command(&self) -> Option<HandleJavaScriptDialogParams> {
+        match self {
+            DialogPolicy::Manual => None,
+            DialogPolicy::Accept => Some(HandleJavaScriptDialogParams::new(true)),
+            DialogPolicy::Dismiss => Some(HandleJavaScriptDialogParams::new(false)),
+        }
+    }
+}
+
+/// A JavaScript dialog opened by a page.
+#[derive(Debug, Clone)]
+pub struct Dialog {
+    page: Page,
+    event: Arc<EventJavascriptDialogOpening>,
+}
+
+impl Dialog {
+    /// The message shown by the dialog.
+    pub fn message(&self) -> &str {
+        &self.event.message
+    }
+
+    /// Whether this is an `alert`, `confirm`, `prompt` or `beforeunload`
+    /// dialog.
+    pub fn r#type(&self) -> &DialogType {
+        &self.event.r#type
+    }
+
+    /// The default text of a prompt.
+    pub fn default_prompt(&self) -> Option<&str> {
+        self.event.default_prompt.as_deref()
+    }
+
+    /// The url of the frame that opened the dialog.
+    pub fn url(&self) -> &str {
+        &self.event.url
+    }
+
+    /// The event that announced the dialog.
+    pub fn event(&self) -> &EventJavascriptDialogOpening {
+        &self.event
+    }
+
+    /// Accepts the dialog, entering `prompt_text` into a prompt.
+    pub async fn accept(&self, prompt_text: Option<&str>) -> Result<()> {
+        let mut params = HandleJavaScriptDialogParams::new(true);
+        params.prompt_text = prompt_text.map(str::to_string);
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Dismisses the dialog.
+    pub async fn dismiss(&self) -> Result<()> {
+        self.page
+            .execute(HandleJavaScriptDialogParams::new(false))
+            .await?;
+        Ok(())
+    }
+}
+
+/// A stream of the dialogs a page opens.
+#[must_use = "streams do nothing unless polled"]
+pub struct DialogStream {
+    page: Page,
+    events: EventStream<EventJavascriptDialogOpening>,
+}
+
+impl DialogStream {
+    pub(crate) fn new(page: Page, events: EventStream<EventJavascriptDialogOpening>) -> Self {
+        Self { page, events }
+    }
+}
+
+impl fmt::Debug for DialogStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DialogStream").finish()
+    }
+}
+
+impl Stream for DialogStream {
+    type Item = Dialog;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.events.poll_next_unpin(cx).map(|event| {
+            event.map(|event| Dialog {
+                page: this.page.clone(),
+                event,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn closes_dialogs_by_policy() {
+        assert!(DialogPolicy::default().command().is_none());
+        assert!(DialogPolicy::Accept.command().unwrap().accept);
+        assert!(!DialogPolicy::Dismiss.command().unwrap().accept);
+    }
+}
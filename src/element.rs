@@ -3,24 +3,35 @@ use std::path::Path;
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::Duration;
 
-use futures::{future, Future, FutureExt, Stream};
+use futures::channel::mpsc::unbounded;
+use futures::{future, Future, FutureExt, SinkExt, Stream};
+use serde::de::DeserializeOwned;
 
+use chromiumoxide_cdp::cdp::browser_protocol::css::{self, GetComputedStyleForNodeParams};
 use chromiumoxide_cdp::cdp::browser_protocol::dom::{
-    BackendNodeId, DescribeNodeParams, GetBoxModelParams, GetContentQuadsParams, Node, NodeId,
-    ResolveNodeParams,
+    self, BackendNodeId, DescribeNodeParams, GetBoxModelParams, GetContentQuadsParams, Node,
+    NodeId, ResolveNodeParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::overlay::{
+    self, HighlightConfig, HighlightNodeParams,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::page::{
     CaptureScreenshotFormat, CaptureScreenshotParams, Viewport,
 };
 use chromiumoxide_cdp::cdp::js_protocol::runtime::{
-    CallFunctionOnReturns, GetPropertiesParams, PropertyDescriptor, RemoteObjectId,
-    RemoteObjectType,
+    AddBindingParams, CallArgument, CallFunctionOnParams, CallFunctionOnReturns,
+    EventBindingCalled, GetPropertiesParams, PropertyDescriptor, RemoteObjectId, RemoteObjectType,
 };
 
 use crate::error::{CdpError, Result};
+use crate::handler::target::TargetMessage;
 use crate::handler::PageInner;
 use crate::layout::{BoundingBox, BoxModel, ElementQuad, Point};
+use crate::listeners::{EventListenerRequest, EventStream};
+use crate::mutation::{self, MutationObserverOptions, MutationStream};
+use crate::page::ClickOptions;
 use crate::utils;
 
 /// Represents a [DOM Element](https://developer.mozilla.org/en-US/docs/Web/API/Element).
@@ -35,6 +46,12 @@ pub struct Element {
     tab: Arc<PageInner>,
 }
 
+impl From<&Element> for CallArgument {
+    fn from(element: &Element) -> Self {
+        element.remote_object_id.clone().into()
+    }
+}
+
 impl Element {
     pub(crate) async fn new(tab: Arc<PageInner>, node_id: NodeId) -> Result<Self> {
         let backend_node_id = tab
@@ -198,6 +215,108 @@ impl Element {
             .await
     }
 
+    /// Submits a javascript function to the page that is called with the
+    /// given arguments and `this` bound to the element, and returns the
+    /// evaluated result.
+    ///
+    /// Arguments are serialized as they are, so there is no need to format
+    /// them into the function's body. Other elements can be passed as
+    /// arguments too.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::element::Element;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(element: Element) -> Result<()> {
+    ///     let js_fn = "function(name, value) { this.setAttribute(name, value); }";
+    ///     element
+    ///         .call_js_fn_with_args(
+    ///             js_fn,
+    ///             vec![serde_json::json!("title"), serde_json::json!("it's \"quoted\"")],
+    ///             false,
+    ///         )
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_js_fn_with_args<I, A>(
+        &self,
+        function_declaration: impl Into<String>,
+        args: I,
+        await_promise: bool,
+    ) -> Result<CallFunctionOnReturns>
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<CallArgument>,
+    {
+        let resp = self
+            .tab
+            .execute(
+                CallFunctionOnParams::builder()
+                    .object_id(self.remote_object_id.clone())
+                    .function_declaration(function_declaration)
+                    .arguments(args.into_iter().map(Into::into))
+                    .generate_preview(true)
+                    .await_promise(await_promise)
+                    .build()
+                    .unwrap(),
+            )
+            .await?;
+        Ok(resp.result)
+    }
+
+    /// Calls the javascript function with the given arguments and `this`
+    /// bound to the element and deserializes its return value.
+    ///
+    /// Returned promises are awaited. A function that returns nothing
+    /// deserializes from `null`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::element::Element;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(element: Element) -> Result<()> {
+    ///     let js_fn = "function(offset) { return this.childElementCount + offset; }";
+    ///     let count: usize = element
+    ///         .call_js_fn_into(js_fn, vec![serde_json::json!(1)])
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_js_fn_into<T, I, A>(
+        &self,
+        function_declaration: impl Into<String>,
+        args: I,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator<Item = A>,
+        A: Into<CallArgument>,
+    {
+        let resp = self
+            .tab
+            .execute(
+                CallFunctionOnParams::builder()
+                    .object_id(self.remote_object_id.clone())
+                    .function_declaration(function_declaration)
+                    .arguments(args.into_iter().map(Into::into))
+                    .return_by_value(true)
+                    .await_promise(true)
+                    .build()
+                    .unwrap(),
+            )
+            .await?
+            .result;
+        if let Some(exception) = resp.exception_details {
+            return Err(CdpError::JavascriptException(Box::new(exception)));
+        }
+        Ok(serde_json::from_value(
+            resp.result.value.unwrap_or_default(),
+        )?)
+    }
+
     /// Returns a JSON representation of this element.
     pub async fn json_value(&self) -> Result<serde_json::Value> {
         let element_json = self
@@ -213,6 +332,239 @@ impl Element {
         Ok(self)
     }
 
+    /// Calls [requestFullscreen](https://developer.mozilla.org/en-US/docs/Web/API/Element/requestFullscreen)
+    /// on the element as if triggered by a user gesture and waits until the
+    /// element is displayed in fullscreen mode.
+    pub async fn request_full_screen(&self) -> Result<&Self> {
+        self.call_js_fn_as_user_gesture("async function() { await this.requestFullscreen(); }")
+            .await?;
+        Ok(self)
+    }
+
+    /// Calls the function with the element as `this` as if it was triggered
+    /// by a user gesture, awaits the returned promise and fails if the
+    /// function threw.
+    async fn call_js_fn_as_user_gesture(&self, function_declaration: &str) -> Result<()> {
+        let resp = self
+            .tab
+            .execute(
+                CallFunctionOnParams::builder()
+                    .object_id(self.remote_object_id.clone())
+                    .function_declaration(function_declaration)
+                    .user_gesture(true)
+                    .await_promise(true)
+                    .build()
+                    .unwrap(),
+            )
+            .await?
+            .result;
+        if let Some(exception) = resp.exception_details {
+            return Err(CdpError::JavascriptException(Box::new(exception)));
+        }
+        Ok(())
+    }
+
+    /// Leaves fullscreen mode if this element is currently displayed in
+    /// fullscreen.
+    pub async fn exit_full_screen(&self) -> Result<&Self> {
+        self.call_js_fn(
+            "async function() {
+                if (document.fullscreenElement === this) {
+                    await document.exitFullscreen();
+                }
+            }",
+            true,
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Whether this element is currently displayed in fullscreen mode.
+    pub async fn is_full_screen(&self) -> Result<bool> {
+        self.call_js_fn_into(
+            "function() { return document.fullscreenElement === this; }",
+            Vec::<CallArgument>::new(),
+        )
+        .await
+    }
+
+    /// Starts the playback of this `<audio>` or `<video>` element as if
+    /// triggered by a user gesture and waits until playback has begun.
+    ///
+    /// Fails if the browser refused to play the media, for example due to its
+    /// autoplay policy, see `BrowserConfigBuilder::allow_autoplay`.
+    pub async fn play(&self) -> Result<&Self> {
+        self.call_js_fn_as_user_gesture("async function() { await this.play(); }")
+            .await?;
+        Ok(self)
+    }
+
+    /// Pauses the playback of this `<audio>` or `<video>` element.
+    pub async fn pause(&self) -> Result<&Self> {
+        self.call_js_fn("function() { this.pause(); }", false)
+            .await?;
+        Ok(self)
+    }
+
+    /// Whether the playback of this `<audio>` or `<video>` element is paused.
+    pub async fn is_paused(&self) -> Result<bool> {
+        self.call_js_fn_into(
+            "function() { return this.paused; }",
+            Vec::<CallArgument>::new(),
+        )
+        .await
+    }
+
+    /// The current playback position of this `<audio>` or `<video>` element
+    /// in seconds.
+    pub async fn current_time(&self) -> Result<f64> {
+        self.call_js_fn_into(
+            "function() { return this.currentTime; }",
+            Vec::<CallArgument>::new(),
+        )
+        .await
+    }
+
+    /// Sets the rate at which this `<audio>` or `<video>` element is played
+    /// back, `1.0` being the normal speed.
+    pub async fn set_playback_rate(&self, rate: f64) -> Result<&Self> {
+        self.call_js_fn_with_args(
+            "function(rate) { this.playbackRate = rate; }",
+            [serde_json::json!(rate)],
+            false,
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Waits until this `<audio>` or `<video>` element loaded enough data to
+    /// start its playback.
+    ///
+    /// Fails if loading the media fails.
+    pub async fn wait_for_media_ready(&self) -> Result<&Self> {
+        self.call_js_fn_into::<(), _, _>(MEDIA_READY_JS, Vec::<CallArgument>::new())
+            .await?;
+        Ok(self)
+    }
+
+    /// Observes changes to this element and, depending on the `options`, its
+    /// descendants and returns a stream of the recorded mutations.
+    ///
+    /// See the [`mutation`](crate::mutation) module.
+    pub async fn observe_mutations(
+        &self,
+        options: MutationObserverOptions,
+    ) -> Result<MutationStream> {
+        let binding = mutation::next_binding_name();
+        let (tx, rx) = unbounded();
+        self.tab
+            .sender()
+            .clone()
+            .send(TargetMessage::AddEventListener(
+                EventListenerRequest::new::<EventBindingCalled>(tx),
+            ))
+            .await?;
+        self.tab
+            .execute(AddBindingParams::new(binding.clone()))
+            .await?;
+        self.call_js_fn_into::<(), _, _>(
+            mutation::OBSERVE_MUTATIONS_JS,
+            [serde_json::json!(binding), serde_json::to_value(options)?],
+        )
+        .await?;
+        Ok(MutationStream::new(
+            binding,
+            EventStream::new(rx),
+            Arc::clone(&self.tab),
+        ))
+    }
+
+    /// Waits until the value of the attribute `name` satisfies the
+    /// `predicate` and returns it, `None` meaning the attribute is not set.
+    ///
+    /// Fails with `CdpError::Timeout` after `mutation::DEFAULT_WAIT_TIMEOUT`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::element::Element;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(element: Element) -> Result<()> {
+    ///     element
+    ///         .wait_for_attribute("class", |class| {
+    ///             class.map_or(false, |class| class.contains("loaded"))
+    ///         })
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_attribute<F>(
+        &self,
+        name: impl Into<String>,
+        predicate: F,
+    ) -> Result<Option<String>>
+    where
+        F: Fn(Option<&str>) -> bool,
+    {
+        self.wait_for_attribute_with_timeout(name, predicate, mutation::DEFAULT_WAIT_TIMEOUT)
+            .await
+    }
+
+    /// Same as `Element::wait_for_attribute` but fails after `timeout`.
+    pub async fn wait_for_attribute_with_timeout<F>(
+        &self,
+        name: impl Into<String>,
+        predicate: F,
+        timeout: Duration,
+    ) -> Result<Option<String>>
+    where
+        F: Fn(Option<&str>) -> bool,
+    {
+        let name = name.into();
+        let mutations = self
+            .observe_mutations(MutationObserverOptions {
+                child_list: false,
+                attributes: true,
+                subtree: false,
+                attribute_filter: Some(vec![name.clone()]),
+                ..Default::default()
+            })
+            .await?;
+        mutation::wait_for(mutations, timeout, || async {
+            let value = self.attribute(name.as_str()).await?;
+            Ok(predicate(value.as_deref()).then_some(value))
+        })
+        .await
+    }
+
+    /// Waits until a descendant of this element matches the selector and
+    /// returns it.
+    ///
+    /// Fails with `CdpError::Timeout` after `mutation::DEFAULT_WAIT_TIMEOUT`.
+    pub async fn wait_for_child_matching(&self, selector: impl Into<String>) -> Result<Element> {
+        self.wait_for_child_matching_with_timeout(selector, mutation::DEFAULT_WAIT_TIMEOUT)
+            .await
+    }
+
+    /// Same as `Element::wait_for_child_matching` but fails after `timeout`.
+    pub async fn wait_for_child_matching_with_timeout(
+        &self,
+        selector: impl Into<String>,
+        timeout: Duration,
+    ) -> Result<Element> {
+        let selector = selector.into();
+        let mutations = self
+            .observe_mutations(MutationObserverOptions {
+                attributes: false,
+                ..Default::default()
+            })
+            .await?;
+        mutation::wait_for(mutations, timeout, || async {
+            Ok(self.find_element(selector.as_str()).await.ok())
+        })
+        .await
+    }
+
     /// Scrolls the element into view and uses a mouse event to move the mouse
     /// over the center of this element.
     pub async fn hover(&self) -> Result<&Self> {
@@ -261,6 +613,43 @@ impl Element {
         Ok(self)
     }
 
+    /// Returns how far the content of this scrollable element is scrolled.
+    pub async fn scroll_position(&self) -> Result<Point> {
+        let (x, y): (f64, f64) = self
+            .call_js_fn_into(
+                "function() { return [this.scrollLeft, this.scrollTop]; }",
+                Vec::<CallArgument>::new(),
+            )
+            .await?;
+        Ok(Point::new(x, y))
+    }
+
+    /// Scrolls the content of this scrollable element to the position,
+    /// without smooth scrolling.
+    ///
+    /// Unlike `Page::set_scroll_position` this reaches containers that scroll
+    /// on their own, like an overflowing `<div>`.
+    pub async fn scroll_to(&self, position: Point) -> Result<&Self> {
+        self.scroll_with("scrollTo", position).await
+    }
+
+    /// Scrolls the content of this scrollable element by `delta`, positive
+    /// values scroll down and right.
+    pub async fn scroll_by(&self, delta: Point) -> Result<&Self> {
+        self.scroll_with("scrollBy", delta).await
+    }
+
+    async fn scroll_with(&self, method: &str, point: Point) -> Result<&Self> {
+        self.call_js_fn_into::<serde_json::Value, _, _>(
+            format!(
+                "function(left, top) {{ this.{method}({{ left, top, behavior: 'instant' }}); }}"
+            ),
+            [serde_json::json!(point.x), serde_json::json!(point.y)],
+        )
+        .await?;
+        Ok(self)
+    }
+
     /// This focuses the element by click on it
     ///
     /// Bear in mind that if `click()` triggers a navigation this element may be
@@ -271,6 +660,34 @@ impl Element {
         Ok(self)
     }
 
+    /// Scrolls the element into view and taps on it by dispatching touch
+    /// events at its center.
+    pub async fn tap(&self) -> Result<&Self> {
+        let center = self.scroll_into_view().await?.clickable_point().await?;
+        self.tab.tap(center).await?;
+        Ok(self)
+    }
+
+    /// Clicks on the element as configured by the `options`, e.g. a right
+    /// click, a double click or a click while modifier keys are held down.
+    ///
+    /// # Example double click the element
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::element::Element;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::page::ClickOptions;
+    /// # async fn demo(element: Element) -> Result<()> {
+    ///     element.click_with(&ClickOptions::builder().click_count(2).build()).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn click_with(&self, options: &ClickOptions) -> Result<&Self> {
+        let center = self.scroll_into_view().await?.clickable_point().await?;
+        self.tab.click_with(center, options).await?;
+        Ok(self)
+    }
+
     /// Type the input
     ///
     /// # Example type text into an input element
@@ -411,6 +828,83 @@ impl Element {
             .collect())
     }
 
+    /// Returns the computed style of this element keyed by the names of the
+    /// css properties.
+    ///
+    /// # Example check whether the element is displayed
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::element::Element;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(element: Element) -> Result<()> {
+    ///     let style = element.computed_style().await?;
+    ///     let hidden = style.get("display").map(String::as_str) == Some("none");
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn computed_style(&self) -> Result<HashMap<String, String>> {
+        // the css domain needs to be enabled before computed styles are available
+        self.tab.execute(css::EnableParams::default()).await?;
+        let resp = self
+            .tab
+            .execute(GetComputedStyleForNodeParams::new(self.node_id))
+            .await?;
+        Ok(resp
+            .result
+            .computed_style
+            .into_iter()
+            .map(|p| (p.name, p.value))
+            .collect())
+    }
+
+    /// Returns the computed value of a single css property of this element,
+    /// like `display` or `visibility`.
+    pub async fn computed_style_property(
+        &self,
+        property: impl AsRef<str>,
+    ) -> Result<Option<String>> {
+        let property = property.as_ref();
+        Ok(self.computed_style().await?.remove(property))
+    }
+
+    /// Highlights the element in the browser window until
+    /// `Page::hide_highlight` is called or another node is highlighted.
+    ///
+    /// Only visible when running headful, this is useful to mark the node
+    /// that is about to be interacted with while debugging.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::element::Element;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::dom::Rgba;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::overlay::HighlightConfig;
+    /// # async fn demo(element: Element) -> Result<()> {
+    ///     let config = HighlightConfig::builder()
+    ///         .show_info(true)
+    ///         .content_color(Rgba::builder().r(111).g(168).b(220).a(0.66).build().unwrap())
+    ///         .build();
+    ///     element.highlight(config).await?.click().await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn highlight(&self, config: impl Into<HighlightConfig>) -> Result<&Self> {
+        // the overlay domain requires the dom domain to be enabled
+        self.tab.execute(dom::EnableParams::default()).await?;
+        self.tab.execute(overlay::EnableParams::default()).await?;
+        self.tab
+            .execute(
+                HighlightNodeParams::builder()
+                    .highlight_config(config)
+                    .backend_node_id(self.backend_node_id)
+                    .build()
+                    .unwrap(),
+            )
+            .await?;
+        Ok(self)
+    }
+
     /// Scrolls the element into and takes a screenshot of it
     pub async fn screenshot(&self, format: CaptureScreenshotFormat) -> Result<Vec<u8>> {
         let mut bounding_box = self.scroll_into_view().await?.bounding_box().await?;
@@ -437,6 +931,24 @@ impl Element {
             .await
     }
 
+    /// Scrolls the element into view, takes a screenshot of it and decodes it
+    /// into an image that can be cropped, resized or thumbnailed further.
+    #[cfg(feature = "image")]
+    pub async fn screenshot_image(
+        &self,
+        format: CaptureScreenshotFormat,
+    ) -> Result<image::DynamicImage> {
+        Ok(image::load_from_memory(&self.screenshot(format).await?)?)
+    }
+
+    /// Computes a perceptual hash of a screenshot of the element, see the
+    /// [`visual_hash`](crate::visual_hash) module.
+    #[cfg(feature = "image")]
+    pub async fn visual_hash(&self) -> Result<crate::visual_hash::VisualHash> {
+        let image = self.screenshot_image(CaptureScreenshotFormat::Png).await?;
+        Ok(crate::visual_hash::VisualHash::of(&image))
+    }
+
     /// Save a screenshot of the element and write it to `output`
     pub async fn save_screenshot(
         &self,
@@ -449,6 +961,23 @@ impl Element {
     }
 }
 
+/// Resolves once the media element can start playing, i.e. its `readyState`
+/// is at least `HAVE_FUTURE_DATA`, and rejects if loading the media failed.
+const MEDIA_READY_JS: &str = r#"function() {
+    if (this.readyState >= HTMLMediaElement.HAVE_FUTURE_DATA) {
+        return;
+    }
+    if (this.error) {
+        throw new Error(`Failed to load media: ${this.error.message}`);
+    }
+    return new Promise((resolve, reject) => {
+        this.addEventListener('canplay', () => resolve(), { once: true });
+        this.addEventListener('error', () => {
+            reject(new Error(`Failed to load media: ${this.error && this.error.message}`));
+        }, { once: true });
+    });
+}"#;
+
 pub type AttributeValueFuture<'a> = Option<(
     String,
     Pin<Box<dyn Future<Output = Result<Option<String>>> + 'a>>,
@@ -9,7 +9,8 @@ use thiserror::Error;
 
 use chromiumoxide_cdp::cdp::browser_protocol::page::FrameId;
 
-use crate::handler::frame::NavigationError;
+use crate::handler::frame::{NavigationError, NavigationLoop};
+use crate::handler::network::BudgetExceeded;
 use chromiumoxide_cdp::cdp::js_protocol::runtime::ExceptionDetails;
 
 pub type Result<T, E = CdpError> = std::result::Result<T, E>;
@@ -48,6 +49,25 @@ pub enum CdpError {
     JavascriptException(Box<ExceptionDetails>),
     #[error("{0}")]
     Url(#[from] url::ParseError),
+    /// An assertion did not hold before its timeout elapsed
+    #[error("{0}")]
+    AssertionFailed(String),
+    /// A limit of the page's request budget was exceeded
+    #[error("{0}")]
+    BudgetExceeded(#[from] BudgetExceeded),
+    /// A navigation guard stopped a navigation loop of the page
+    #[error("{0}")]
+    NavigationLoop(#[from] NavigationLoop),
+    /// The renderer process of the target crashed
+    #[error("Target crashed.")]
+    TargetCrashed,
+    /// The connection to the browser was lost before the response arrived
+    #[error("Connection to the browser lost.")]
+    Disconnected,
+    /// A screenshot could not be decoded into an image
+    #[cfg(feature = "image")]
+    #[error("{0}")]
+    Image(#[from] image::ImageError),
 }
 impl CdpError {
     pub fn msg(msg: impl Into<String>) -> Self {
@@ -80,6 +100,9 @@ impl From<NavigationError> for CdpError {
         match err {
             NavigationError::Timeout { .. } => CdpError::Timeout,
             NavigationError::FrameNotFound { frame, .. } => CdpError::FrameNotFound(frame),
+            NavigationError::BudgetExceeded { exceeded, .. } => CdpError::BudgetExceeded(exceeded),
+            NavigationError::TargetCrashed { .. } => CdpError::TargetCrashed,
+            NavigationError::Loop { err, .. } => CdpError::NavigationLoop(err),
         }
     }
 }
@@ -0,0 +1,213 @@
+//! Sending HTTP requests from within a page.
+//!
+//! [`Page::fetch`](crate::page::Page::fetch) performs a
+//! [fetch](https://developer.mozilla.org/en-US/docs/Web/API/fetch) in the
+//! page's context, so the request carries the page's cookies, origin and
+//! CORS context. This is often more reliable than replicating an
+//! authenticated session with an external HTTP client.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::fetch::FetchOptions;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let response = page
+//!         .fetch(
+//!             "/api/orders",
+//!             FetchOptions::new()
+//!                 .method("POST")
+//!                 .header("Content-Type", "application/json")
+//!                 .body(r#"{"item":42}"#),
+//!         )
+//!         .await?;
+//!     if response.ok() {
+//!         let order: serde_json::Value = response.json()?;
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+
+/// How to send a request with `Page::fetch`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FetchOptions {
+    method: String,
+    headers: HashMap<String, String>,
+    /// The base64 encoded body
+    #[serde(skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    credentials: &'static str,
+}
+
+impl FetchOptions {
+    /// A `GET` request that includes the page's credentials.
+    pub fn new() -> Self {
+        Self {
+            method: "GET".to_string(),
+            headers: HashMap::new(),
+            body: None,
+            credentials: "include",
+        }
+    }
+
+    /// Sets the HTTP method of the request.
+    pub fn method(mut self, method: impl Into<String>) -> Self {
+        self.method = method.into();
+        self
+    }
+
+    /// Adds the request header `name`.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.insert(name.into(), value.into());
+        self
+    }
+
+    /// Sets the body of the request.
+    pub fn body(mut self, body: impl AsRef<[u8]>) -> Self {
+        self.body = Some(base64::encode(body));
+        self
+    }
+
+    /// Whether to send the page's cookies and HTTP authentication with the
+    /// request, `true` by default.
+    pub fn with_credentials(mut self, credentials: bool) -> Self {
+        self.credentials = if credentials { "include" } else { "omit" };
+        self
+    }
+}
+
+impl Default for FetchOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The response to a request sent with `Page::fetch`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FetchResponse {
+    /// The final url of the response, after following redirects
+    pub url: String,
+    /// The HTTP status code
+    pub status: u16,
+    /// The HTTP status text
+    pub status_text: String,
+    /// The response headers, with lowercase names
+    pub headers: HashMap<String, String>,
+    /// The body of the response
+    #[serde(deserialize_with = "deserialize_base64")]
+    pub body: Vec<u8>,
+}
+
+impl FetchResponse {
+    /// Whether the status code is in the range 200-299.
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// The value of the header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body of the response as text, decoded with the charset of the
+    /// `Content-Type` header or `<meta>` tag if the `charset` feature is
+    /// enabled, and as UTF-8 otherwise.
+    pub fn text(&self) -> String {
+        utils::decode_text(&self.body, self.header("Content-Type"))
+    }
+
+    /// Deserializes the body of the response from JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(&self.body)
+    }
+}
+
+fn deserialize_base64<'de, D>(deserializer: D) -> std::result::Result<Vec<u8>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let body = String::deserialize(deserializer)?;
+    base64::decode(body).map_err(serde::de::Error::custom)
+}
+
+/// Sends the request and returns the response with a base64 encoded body.
+pub(crate) const FETCH_JS: &str = r#"async function(url, options) {
+    const init = {
+        method: options.method,
+        headers: options.headers,
+        credentials: options.credentials,
+    };
+    if (options.body !== undefined) {
+        init.body = Uint8Array.from(atob(options.body), (c) => c.charCodeAt(0));
+    }
+    const response = await fetch(url, init);
+    const bytes = new Uint8Array(await response.arrayBuffer());
+    let binary = '';
+    for (let i = 0; i < bytes.length; i += 0x8000) {
+        binary += String.fromCharCode.apply(null, bytes.subarray(i, i + 0x8000));
+    }
+    const headers = {};
+    response.headers.forEach((value, name) => {
+        headers[name] = value;
+    });
+    return {
+        url: response.url,
+        status: response.status,
+        statusText: response.statusText,
+        headers,
+        body: btoa(binary),
+    };
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn serializes_options() {
+        let options = FetchOptions::new()
+            .method("POST")
+            .header("Content-Type", "text/plain")
+            .body("hello")
+            .with_credentials(false);
+        assert_eq!(
+            serde_json::to_value(options).unwrap(),
+            serde_json::json!({
+                "method": "POST",
+                "headers": {"Content-Type": "text/plain"},
+                "body": base64::encode("hello"),
+                "credentials": "omit",
+            })
+        );
+    }
+
+    #[test]
+    fn decodes_response() {
+        let response: FetchResponse = serde_json::from_value(serde_json::json!({
+            "url": "https://example.com/api",
+            "status": 200,
+            "statusText": "OK",
+            "headers": {"content-type": "application/json"},
+            "body": base64::encode(r#"{"id":1}"#),
+        }))
+        .unwrap();
+        assert!(response.ok());
+        assert_eq!(response.header("Content-Type"), Some("application/json"));
+        assert_eq!(
+            response.json::<serde_json::Value>().unwrap(),
+            serde_json::json!({"id": 1})
+        );
+    }
+}
@@ -1 +1,517 @@
+//! Downloading a pinned Chrome for Testing build.
+//!
+//! Requires the `fetcher` feature. A [`BrowserFetcher`] downloads the
+//! [Chrome for Testing](https://googlechromelabs.github.io/chrome-for-testing/)
+//! build of a version for the current platform once, keeps it in a cache
+//! directory and resolves its executable, so no installed chrome is needed.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::browser::{Browser, BrowserConfig};
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::fetcher::{BrowserFetcher, BrowserFetcherOptions};
+//! # async fn demo() -> Result<()> {
+//!     let fetched = BrowserFetcher::new(BrowserFetcherOptions::default().version("120.0.6099.109"))
+//!         .fetch()
+//!         .await?;
+//!     let config = BrowserConfig::builder()
+//!         .chrome_executable(fetched.executable_path)
+//!         .build()
+//!         .unwrap();
+//!     let (browser, handler) = Browser::launch(config).await?;
+//!     # Ok(())
+//! # }
+//! ```
 
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+
+use crate::error::{CdpError, Result};
+
+/// The Chrome for Testing version that is downloaded by default
+pub const DEFAULT_VERSION: &str = "120.0.6099.109";
+
+/// The hex encoded SHA-256 digests of the [`DEFAULT_VERSION`] archives,
+/// which are verified unless another digest is set
+const DEFAULT_SHA256: &[(Platform, &str)] = &[];
+
+/// Where the Chrome for Testing archives are downloaded from by default
+pub const DEFAULT_HOST: &str = "https://storage.googleapis.com/chrome-for-testing-public";
+
+/// A platform Chrome for Testing is built for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Platform {
+    Linux64,
+    MacX64,
+    MacArm64,
+    Win32,
+    Win64,
+}
+
+impl Platform {
+    /// The platform of this process, if there is a build for it
+    pub fn current() -> Option<Self> {
+        if cfg!(all(target_os = "linux", target_arch = "x86_64")) {
+            Some(Platform::Linux64)
+        } else if cfg!(all(target_os = "macos", target_arch = "x86_64")) {
+            Some(Platform::MacX64)
+        } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+            Some(Platform::MacArm64)
+        } else if cfg!(all(target_os = "windows", target_arch = "x86")) {
+            Some(Platform::Win32)
+        } else if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+            Some(Platform::Win64)
+        } else {
+            None
+        }
+    }
+
+    /// The name of the platform in the download urls, like `linux64`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Platform::Linux64 => "linux64",
+            Platform::MacX64 => "mac-x64",
+            Platform::MacArm64 => "mac-arm64",
+            Platform::Win32 => "win32",
+            Platform::Win64 => "win64",
+        }
+    }
+
+    /// The path of the executable within the extracted archive
+    fn executable(&self) -> PathBuf {
+        let dir = PathBuf::from(format!("chrome-{}", self.name()));
+        match self {
+            Platform::Linux64 => dir.join("chrome"),
+            Platform::MacX64 | Platform::MacArm64 => dir
+                .join("Google Chrome for Testing.app")
+                .join("Contents")
+                .join("MacOS")
+                .join("Google Chrome for Testing"),
+            Platform::Win32 | Platform::Win64 => dir.join("chrome.exe"),
+        }
+    }
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// Which build a [`BrowserFetcher`] downloads and where it keeps it.
+#[derive(Debug, Clone)]
+pub struct BrowserFetcherOptions {
+    /// The Chrome for Testing version, like `120.0.6099.109`
+    pub version: String,
+    /// The platform to download the build for, the current one if `None`
+    pub platform: Option<Platform>,
+    /// The directory the downloaded builds are kept in
+    pub cache_dir: PathBuf,
+    /// The host the archives are downloaded from, like a mirror
+    pub host: String,
+    /// The hex encoded SHA-256 digest the downloaded archive must have,
+    /// defaults to the known digest of the [`DEFAULT_VERSION`] build. `None`
+    /// skips the verification.
+    pub sha256: Option<String>,
+}
+
+impl Default for BrowserFetcherOptions {
+    fn default() -> Self {
+        Self {
+            version: DEFAULT_VERSION.to_string(),
+            platform: None,
+            cache_dir: default_cache_dir(),
+            host: DEFAULT_HOST.to_string(),
+            sha256: known_sha256(DEFAULT_VERSION, None),
+        }
+    }
+}
+
+impl BrowserFetcherOptions {
+    /// Sets the version, the known digest is replaced unless another one
+    /// was set
+    pub fn version(mut self, version: impl Into<String>) -> Self {
+        let known = known_sha256(&self.version, self.platform);
+        self.version = version.into();
+        if self.sha256 == known {
+            self.sha256 = known_sha256(&self.version, self.platform);
+        }
+        self
+    }
+
+    /// Sets the platform, the known digest is replaced unless another one
+    /// was set
+    pub fn platform(mut self, platform: Platform) -> Self {
+        let known = known_sha256(&self.version, self.platform);
+        self.platform = Some(platform);
+        if self.sha256 == known {
+            self.sha256 = known_sha256(&self.version, self.platform);
+        }
+        self
+    }
+
+    pub fn cache_dir(mut self, cache_dir: impl AsRef<Path>) -> Self {
+        self.cache_dir = cache_dir.as_ref().to_path_buf();
+        self
+    }
+
+    pub fn host(mut self, host: impl Into<String>) -> Self {
+        self.host = host.into();
+        self
+    }
+
+    /// Rejects downloaded archives with another SHA-256 digest
+    pub fn sha256(mut self, sha256: impl Into<String>) -> Self {
+        self.sha256 = Some(sha256.into());
+        self
+    }
+
+    /// Skips verifying the SHA-256 digest of the downloaded archive
+    pub fn skip_sha256(mut self) -> Self {
+        self.sha256 = None;
+        self
+    }
+}
+
+/// The known digest of the build of the version for the platform, the
+/// current one if `None`
+fn known_sha256(version: &str, platform: Option<Platform>) -> Option<String> {
+    let platform = platform.or_else(Platform::current)?;
+    DEFAULT_SHA256
+        .iter()
+        .find(|(known, _)| version == DEFAULT_VERSION && *known == platform)
+        .map(|(_, sha256)| sha256.to_string())
+}
+
+/// A downloaded browser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FetchedBrowser {
+    pub version: String,
+    pub platform: Platform,
+    /// The path of the chrome executable
+    pub executable_path: PathBuf,
+}
+
+/// Downloads and caches Chrome for Testing builds, see the
+/// [`fetcher`](crate::fetcher) module.
+#[derive(Debug, Clone, Default)]
+pub struct BrowserFetcher {
+    options: BrowserFetcherOptions,
+}
+
+impl BrowserFetcher {
+    pub fn new(options: BrowserFetcherOptions) -> Self {
+        Self { options }
+    }
+
+    pub fn options(&self) -> &BrowserFetcherOptions {
+        &self.options
+    }
+
+    fn platform(&self) -> Result<Platform> {
+        self.options
+            .platform
+            .or_else(Platform::current)
+            .ok_or_else(|| CdpError::msg("No Chrome for Testing build for this platform"))
+    }
+
+    /// The url of the archive of the build
+    pub fn download_url(&self) -> Result<String> {
+        let platform = self.platform()?;
+        Ok(format!(
+            "{}/{}/{platform}/chrome-{platform}.zip",
+            self.options.host.trim_end_matches('/'),
+            self.options.version
+        ))
+    }
+
+    /// The directory the build is extracted to
+    fn install_dir(&self, platform: Platform) -> PathBuf {
+        self.options
+            .cache_dir
+            .join(format!("{}-{platform}", self.options.version))
+    }
+
+    /// The path of the executable of the build, which only exists once it
+    /// was fetched
+    pub fn executable_path(&self) -> Result<PathBuf> {
+        let platform = self.platform()?;
+        Ok(self.install_dir(platform).join(platform.executable()))
+    }
+
+    /// Downloads the build unless it's cached already and returns its
+    /// executable.
+    pub async fn fetch(&self) -> Result<FetchedBrowser> {
+        let platform = self.platform()?;
+        let executable_path = self.executable_path()?;
+        if !executable_path.exists() {
+            let url = self.download_url()?;
+            let install_dir = self.install_dir(platform);
+            let sha256 = self.options.sha256.clone();
+            let install = move || install(&url, sha256.as_deref(), &install_dir);
+            cfg_if::cfg_if! {
+                if #[cfg(feature = "async-std-runtime")] {
+                    async_std::task::spawn_blocking(install).await?;
+                } else if #[cfg(feature = "tokio-runtime")] {
+                    tokio::task::spawn_blocking(install)
+                        .await
+                        .map_err(|err| CdpError::msg(err.to_string()))??;
+                }
+            }
+            if !executable_path.exists() {
+                return Err(CdpError::msg(format!(
+                    "The build has no executable at {}",
+                    executable_path.display()
+                )));
+            }
+        }
+        Ok(FetchedBrowser {
+            version: self.options.version.clone(),
+            platform,
+            executable_path,
+        })
+    }
+}
+
+/// Downloads the archive and extracts it to the directory
+fn install(url: &str, sha256: Option<&str>, dir: &Path) -> Result<()> {
+    let archive = sibling(dir, "zip");
+    if let Some(parent) = archive.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    tracing::info!("Downloading {}", url);
+    if sha256.is_none() {
+        tracing::warn!("The SHA-256 digest of {} is not verified", url);
+    }
+    let digest = download(url, &archive);
+    let res = digest.and_then(|digest| unpack(&archive, &digest, sha256, dir));
+    let _ = fs::remove_file(&archive);
+    res
+}
+
+/// Downloads the url to the file and returns its hex encoded SHA-256 digest
+fn download(url: &str, path: &Path) -> Result<String> {
+    let response = ureq::get(url)
+        .call()
+        .map_err(|err| CdpError::msg(format!("Failed to download {url}: {err}")))?;
+    let mut reader = response.into_reader();
+    let mut file = File::create(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        file.write_all(&buf[..n])?;
+    }
+    file.flush()?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Verifies the archive and extracts it to the directory, replacing a
+/// partially extracted one
+fn unpack(archive: &Path, digest: &str, sha256: Option<&str>, dir: &Path) -> Result<()> {
+    if let Some(expected) = sha256 {
+        if !digest.eq_ignore_ascii_case(expected) {
+            return Err(CdpError::msg(format!(
+                "The archive has the SHA-256 digest {digest}, expected {expected}"
+            )));
+        }
+    }
+    let staging = sibling(dir, "part");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    extract(archive, &staging)
+        .map_err(|err| CdpError::msg(format!("Failed to extract the archive: {err}")))?;
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    if let Err(err) = fs::rename(&staging, dir) {
+        // another process installed the build in the meantime
+        let _ = fs::remove_dir_all(&staging);
+        if !dir.exists() {
+            return Err(err.into());
+        }
+    }
+    Ok(())
+}
+
+/// Extracts the archive to the directory, like `ZipArchive::extract` but
+/// restoring symlinks, which the macOS app bundles rely on
+fn extract(archive: &Path, dir: &Path) -> zip::result::ZipResult<()> {
+    let mut zip = zip::ZipArchive::new(File::open(archive)?)?;
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let path = file
+            .enclosed_name()
+            .map(|path| dir.join(path))
+            .ok_or(zip::result::ZipError::InvalidArchive("Invalid file path"))?;
+        if file.is_dir() {
+            fs::create_dir_all(&path)?;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            const S_IFMT: u32 = 0o170000;
+            const S_IFLNK: u32 = 0o120000;
+            match file.unix_mode() {
+                Some(mode) if mode & S_IFMT == S_IFLNK => {
+                    let mut target = String::new();
+                    file.read_to_string(&mut target)?;
+                    std::os::unix::fs::symlink(target, &path)?;
+                }
+                mode => {
+                    std::io::copy(&mut file, &mut File::create(&path)?)?;
+                    if let Some(mode) = mode {
+                        fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+                    }
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        std::io::copy(&mut file, &mut File::create(&path)?)?;
+    }
+    Ok(())
+}
+
+/// A path next to the install directory that is unique to this process, as
+/// the version in the name of the directory contains dots
+fn sibling(dir: &Path, extension: &str) -> PathBuf {
+    let mut name = dir.file_name().map(OsString::from).unwrap_or_default();
+    name.push(format!(".{}.{extension}", std::process::id()));
+    dir.with_file_name(name)
+}
+
+/// The user's cache directory
+fn default_cache_dir() -> PathBuf {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let dir = if cfg!(target_os = "windows") {
+        std::env::var_os("LOCALAPPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library").join("Caches"))
+    } else {
+        std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.map(|home| home.join(".cache")))
+    };
+    dir.unwrap_or_else(std::env::temp_dir).join("chromiumoxide")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_builds() {
+        let fetcher = BrowserFetcher::new(
+            BrowserFetcherOptions::default()
+                .platform(Platform::MacArm64)
+                .cache_dir("/cache")
+                .host("https://mirror.example.com/cft/"),
+        );
+        assert_eq!(
+            fetcher.download_url().unwrap(),
+            format!(
+                "https://mirror.example.com/cft/{DEFAULT_VERSION}/mac-arm64/chrome-mac-arm64.zip"
+            )
+        );
+        assert_eq!(
+            fetcher.executable_path().unwrap(),
+            Path::new("/cache")
+                .join(format!("{DEFAULT_VERSION}-mac-arm64"))
+                .join("chrome-mac-arm64/Google Chrome for Testing.app/Contents/MacOS/Google Chrome for Testing")
+        );
+    }
+
+    #[test]
+    fn keeps_explicit_digests() {
+        let options = BrowserFetcherOptions::default()
+            .platform(Platform::Linux64)
+            .sha256("abc")
+            .version("121.0.6167.85")
+            .platform(Platform::Win64);
+        assert_eq!(options.sha256.as_deref(), Some("abc"));
+        assert_eq!(
+            BrowserFetcherOptions::default()
+                .platform(Platform::Linux64)
+                .sha256,
+            known_sha256(DEFAULT_VERSION, Some(Platform::Linux64))
+        );
+        let options = BrowserFetcherOptions::default().skip_sha256();
+        assert_eq!(options.platform(Platform::MacX64).sha256, None);
+    }
+
+    #[test]
+    fn unpacks_verified_archives() {
+        let root =
+            std::env::temp_dir().join(format!("chromiumoxide-fetcher-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let archive = root.join("chrome.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("chrome-linux64/chrome", Default::default())
+            .unwrap();
+        zip.write_all(b"#!/bin/sh").unwrap();
+        zip.finish().unwrap();
+
+        let dir = root.join("build");
+        assert!(unpack(&archive, "abc", Some("def"), &dir).is_err());
+        assert!(!dir.exists());
+        unpack(&archive, "ABC", Some("abc"), &dir).unwrap();
+        assert_eq!(
+            fs::read(dir.join(Platform::Linux64.executable())).unwrap(),
+            b"#!/bin/sh"
+        );
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn restores_symlinks() {
+        let root = std::env::temp_dir().join(format!(
+            "chromiumoxide-fetcher-symlinks-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&root).unwrap();
+        let archive = root.join("chrome.zip");
+        let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+        zip.start_file("Framework/Versions/A/lib", Default::default())
+            .unwrap();
+        zip.write_all(b"lib").unwrap();
+        zip.add_symlink("Framework/Versions/Current", "A", Default::default())
+            .unwrap();
+        zip.finish().unwrap();
+
+        let dir = root.join("build");
+        extract(&archive, &dir).unwrap();
+        let current = dir.join("Framework/Versions/Current");
+        assert_eq!(fs::read_link(&current).unwrap(), Path::new("A"));
+        assert_eq!(fs::read(current.join("lib")).unwrap(), b"lib");
+        fs::remove_dir_all(root).unwrap();
+    }
+
+    #[test]
+    fn keeps_version_and_platform_in_sibling_paths() {
+        let dir = Path::new("/cache").join(format!("{DEFAULT_VERSION}-linux64"));
+        assert_eq!(
+            sibling(&dir, "zip"),
+            Path::new("/cache").join(format!(
+                "{DEFAULT_VERSION}-linux64.{}.zip",
+                std::process::id()
+            ))
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Intercepting file chooser dialogs.
+//!
+//! Upload widgets often open the file chooser from JavaScript instead of a
+//! plain `<input type="file">` the files could be set on directly.
+//! [`Page::wait_for_file_chooser`](crate::page::Page::wait_for_file_chooser)
+//! intercepts the next file chooser the page opens, so that its files can be
+//! set with [`FileChooser::set_files`] instead of showing the dialog.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let upload = page.find_element("#upload").await?;
+//!     // wait for the chooser while the click opens it
+//!     let (chooser, _) = futures::join!(page.wait_for_file_chooser(), upload.click());
+//!     chooser?.set_files(["./report.pdf"]).await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+use std::sync::Arc;
+
+use chromiumoxide_cdp::cdp::browser_protocol::dom::{BackendNodeId, SetFileInputFilesParams};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    EventFileChooserOpened, FileChooserOpenedMode, FrameId,
+};
+
+use crate::element::Element;
+use crate::error::{CdpError, Result};
+use crate::page::Page;
+
+/// Dispatched on the input when the chooser is cancelled, like browsers do
+/// when the dialog is closed without selecting files
+const CANCEL_JS: &str =
+    "function() { this.dispatchEvent(new Event('cancel', { bubbles: true })); }";
+
+/// A file chooser opened by a page that was intercepted before it was
+/// shown.
+#[derive(Debug, Clone)]
+pub struct FileChooser {
+    page: Page,
+    event: Arc<EventFileChooserOpened>,
+}
+
+impl FileChooser {
+    pub(crate) fn new(page: Page, event: Arc<EventFileChooserOpened>) -> Self {
+        Self { page, event }
+    }
+
+    /// The frame that opened the file chooser.
+    pub fn frame_id(&self) -> &FrameId {
+        &self.event.frame_id
+    }
+
+    /// The input element the files are selected for.
+    pub fn backend_node_id(&self) -> BackendNodeId {
+        self.event.backend_node_id
+    }
+
+    /// Whether multiple files can be selected.
+    pub fn is_multiple(&self) -> bool {
+        self.event.mode == FileChooserOpenedMode::SelectMultiple
+    }
+
+    /// The event that announced the file chooser.
+    pub fn event(&self) -> &EventFileChooserOpened {
+        &self.event
+    }
+
+    /// The input element the files are selected for.
+    pub async fn element(&self) -> Result<Element> {
+        self.page
+            .element_from_backend_node(self.event.backend_node_id)
+            .await
+    }
+
+    /// Selects the files, relative paths are resolved against the current
+    /// directory.
+    ///
+    /// Fails if more than one file is selected for a chooser that isn't
+    /// [`FileChooser::is_multiple`].
+    pub async fn set_files<I, P>(&self, paths: I) -> Result<()>
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        let files = resolve_files(paths, self.is_multiple()).map_err(CdpError::msg)?;
+        let mut params = SetFileInputFilesParams::new(files);
+        params.backend_node_id = Some(self.event.backend_node_id);
+        self.page.execute(params).await?;
+        Ok(())
+    }
+
+    /// Closes the file chooser without selecting any files.
+    pub async fn cancel(&self) -> Result<()> {
+        self.element().await?.call_js_fn(CANCEL_JS, false).await?;
+        Ok(())
+    }
+}
+
+/// The absolute paths of the files to select.
+fn resolve_files<I, P>(paths: I, multiple: bool) -> Result<Vec<String>, String>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    let files = paths
+        .into_iter()
+        .map(|path| {
+            std::path::absolute(path.as_ref())
+                .map(|path| path.to_string_lossy().into_owned())
+                .map_err(|err| format!("Invalid file {}: {err}", path.as_ref().display()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if files.is_empty() {
+        return Err("No files to select, use `FileChooser::cancel` instead".to_string());
+    }
+    if files.len() > 1 && !multiple {
+        return Err("The file chooser accepts only a single file".to_string());
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_files() {
+        let files = resolve_files(["report.pdf"], false).unwrap();
+        assert!(Path::new(&files[0]).is_absolute());
+        assert!(files[0].ends_with("report.pdf"));
+
+        assert!(resolve_files(["a", "b"], false).is_err());
+        assert_eq!(resolve_files(["a", "b"], true).unwrap().len(), 2);
+        assert!(resolve_files(Vec::<&str>::new(), true).is_err());
+    }
+}
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+use std::time::Instant;
+
+use futures_timer::Delay;
+
+use chromiumoxide_cdp::cdp::browser_protocol::page::{FrameId, NavigateParams};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    CallArgument, CallFunctionOnParams, EvaluateParams, ExecutionContextId, RemoteObjectSubtype,
+};
+
+use crate::element::Element;
+use crate::error::{CdpError, Result};
+use crate::handler::domworld::DOMWorldKind;
+use crate::handler::frame::FrameInfo;
+use crate::handler::PageInner;
+use crate::js::{Evaluation, EvaluationResult};
+use crate::page::{self, NavigationOptions, Page, PollingOptions};
+
+/// A handle to a frame of a [`Page`](crate::page::Page), like an `<iframe>`.
+///
+/// Cross-origin iframes running in their own process are part of the page's
+/// frames as well, their commands are sent within their own session.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Cdp identifier of the frame
+    id: FrameId,
+    inner: Arc<PageInner>,
+}
+
+impl Frame {
+    pub(crate) fn new(id: FrameId, inner: Arc<PageInner>) -> Self {
+        Self { id, inner }
+    }
+
+    /// The identifier of this frame
+    pub fn id(&self) -> &FrameId {
+        &self.id
+    }
+
+    /// Navigates this frame to the given URL.
+    ///
+    /// This resolves after the frame reached the `wait_until` lifecycle state
+    /// of the `options`, other frames of the page are not affected.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{NavigationOptions, Page};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let tree = page.frame_tree().await?.unwrap();
+    ///     if let Some(child) = tree.children.first() {
+    ///         page.frame(child.id.clone())
+    ///             .goto("https://example.com", NavigationOptions::default())
+    ///             .await?;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn goto(&self, url: impl Into<String>, options: NavigationOptions) -> Result<&Self> {
+        let mut params = NavigateParams::new(url);
+        params.frame_id = Some(self.id.clone());
+        let wait_until = options.apply(&mut params);
+
+        let res = self.inner.navigate(params, wait_until).await?;
+        if let Some(err) = res.result.error_text {
+            return Err(CdpError::ChromeMessage(err));
+        }
+        Ok(self)
+    }
+
+    /// A snapshot of this frame, `None` if the frame is detached.
+    pub async fn info(&self) -> Result<Option<FrameInfo>> {
+        let tree = self.inner.frame_tree().await?;
+        Ok(tree.and_then(|tree| tree.find(&self.id).cloned()))
+    }
+
+    /// The frame's name as specified in the tag
+    pub async fn name(&self) -> Result<Option<String>> {
+        Ok(self.info().await?.and_then(|info| info.name))
+    }
+
+    /// The url of the document loaded in the frame
+    pub async fn url(&self) -> Result<Option<String>> {
+        Ok(self.info().await?.and_then(|info| info.url))
+    }
+
+    /// The frame containing this frame, `None` for the main frame
+    pub async fn parent(&self) -> Result<Option<Frame>> {
+        Ok(self
+            .info()
+            .await?
+            .and_then(|info| info.parent_id)
+            .map(|id| Frame::new(id, Arc::clone(&self.inner))))
+    }
+
+    /// The page for the frame's commands, which are sent within the frame's
+    /// own session for out-of-process iframes
+    async fn scoped(&self) -> Result<Arc<PageInner>> {
+        Ok(match self.inner.frame_session(self.id.clone()).await? {
+            Some(session_id) => Arc::new(self.inner.with_session(session_id)),
+            None => Arc::clone(&self.inner),
+        })
+    }
+
+    /// The execution context of the document loaded in the frame
+    pub async fn execution_context(&self) -> Result<ExecutionContextId> {
+        self.inner
+            .frame_execution_context(Some(self.id.clone()), DOMWorldKind::Main)
+            .await?
+            .ok_or_else(|| CdpError::FrameNotFound(self.id.clone()))
+    }
+
+    /// Evaluates an expression or function in the frame's context and
+    /// returns the result, like `Page::evaluate`.
+    pub async fn evaluate(&self, evaluate: impl Into<Evaluation>) -> Result<EvaluationResult> {
+        let inner = self.scoped().await?;
+        match evaluate.into() {
+            Evaluation::Expression(mut expr) => {
+                expr.context_id = Some(self.execution_context().await?);
+                inner.evaluate_expression(expr).await
+            }
+            Evaluation::Function(mut fun) => {
+                fun.execution_context_id = Some(self.execution_context().await?);
+                inner.evaluate_function(fun).await
+            }
+        }
+    }
+
+    /// Returns the first element in the frame's document which matches the
+    /// given CSS selector.
+    pub async fn find_element(&self, selector: impl Into<String>) -> Result<Element> {
+        let call = CallFunctionOnParams::builder()
+            .function_declaration("(selector) => document.querySelector(selector)")
+            .argument(CallArgument::from(serde_json::Value::from(selector.into())))
+            .execution_context_id(self.execution_context().await?)
+            .return_by_value(false)
+            .build()
+            .map_err(CdpError::msg)?;
+        let inner = self.scoped().await?;
+        let result = inner.evaluate_function(call).await?;
+        let object = result.object();
+        if object.subtype == Some(RemoteObjectSubtype::Null) {
+            return Err(CdpError::NotFound);
+        }
+        let object_id = object.object_id.clone().ok_or(CdpError::NotFound)?;
+        Page::from(inner).element_from_object(object_id).await
+    }
+
+    /// Waits until an element in the frame's document matches the selector
+    /// and returns it.
+    ///
+    /// Fails with `CdpError::Timeout` if no element matched within the
+    /// default `PollingOptions` timeout.
+    pub async fn wait_for_selector(&self, selector: impl Into<String>) -> Result<Element> {
+        self.wait_for_selector_with(selector, PollingOptions::default())
+            .await
+    }
+
+    /// Same as `Frame::wait_for_selector` but polls according to `options`.
+    pub async fn wait_for_selector_with(
+        &self,
+        selector: impl Into<String>,
+        options: PollingOptions,
+    ) -> Result<Element> {
+        let selector = selector.into();
+        let deadline = Instant::now() + options.timeout;
+        loop {
+            match self.find_element(selector.as_str()).await {
+                Ok(element) => return Ok(element),
+                // the frame may not have loaded a document yet
+                Err(CdpError::NotFound) | Err(CdpError::FrameNotFound(_)) => {}
+                Err(err) => return Err(err),
+            }
+            if Instant::now() + options.interval > deadline {
+                return Err(CdpError::Timeout);
+            }
+            Delay::new(options.interval).await;
+        }
+    }
+
+    /// Returns the HTML content of the frame's document
+    pub async fn content(&self) -> Result<String> {
+        Ok(self
+            .evaluate(EvaluateParams::new(page::CONTENT_JS))
+            .await?
+            .into_value()?)
+    }
+}
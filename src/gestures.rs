@@ -0,0 +1,293 @@
+//! Multi-finger touch gestures.
+//!
+//! The gestures of [`Page::pinch`](crate::page::Page::pinch),
+//! [`Page::rotate`](crate::page::Page::rotate) and
+//! [`Page::long_press`](crate::page::Page::long_press) are synthesized from
+//! `Input.dispatchTouchEvent` sequences: the fingers touch the screen, move
+//! along their paths in a number of steps spread over the gesture's duration
+//! and are lifted again. Pages only receive touch events while touch is
+//! emulated, e.g. by emulating a mobile [`Device`](crate::devices::Device).
+//!
+//! Chromium can also synthesize gestures itself:
+//! [`Page::scroll_gesture`](crate::page::Page::scroll_gesture),
+//! [`Page::pinch_gesture`](crate::page::Page::pinch_gesture) and
+//! [`Page::tap_gesture`](crate::page::Page::tap_gesture) produce the same
+//! composited scrolling and zooming as real input, which e.g. triggers lazy
+//! loading more reliably than mouse wheel events.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::gestures::GestureOptions;
+//! # use chromiumoxide::layout::Point;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let center = Point::new(200., 300.);
+//!     page.pinch(center, 2., GestureOptions::default()).await?;
+//!     page.rotate(center, 90., GestureOptions::default()).await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::f64::consts::PI;
+use std::time::Duration;
+
+use chromiumoxide_cdp::cdp::browser_protocol::input::{
+    GestureSourceType, SynthesizePinchGestureParams, SynthesizeScrollGestureParams,
+    SynthesizeTapGestureParams, TouchPoint,
+};
+
+use crate::layout::Point;
+
+/// How far each finger of a two finger gesture starts from its center
+pub(crate) const FINGER_DISTANCE: f64 = 50.;
+
+/// How the progress of a gesture is spread over its duration.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Easing {
+    /// The fingers move at a constant speed
+    Linear,
+    /// The fingers speed up at the start and slow down at the end
+    #[default]
+    EaseInOut,
+}
+
+impl Easing {
+    /// The progress of the gesture, from 0 to 1, at the time `t`, from 0 to
+    /// 1.
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0., 1.);
+        match self {
+            Easing::Linear => t,
+            Easing::EaseInOut => (1. - (t * PI).cos()) / 2.,
+        }
+    }
+}
+
+/// The timing of a gesture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GestureOptions {
+    /// How long the fingers move (defaults to 300ms)
+    pub duration: Duration,
+    /// In how many touch moves the fingers move (defaults to 10)
+    pub steps: usize,
+    pub easing: Easing,
+}
+
+impl Default for GestureOptions {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_millis(300),
+            steps: 10,
+            easing: Easing::default(),
+        }
+    }
+}
+
+impl GestureOptions {
+    /// The eased progress of every step, the first frame at 0 is not
+    /// included.
+    fn progress(&self) -> impl Iterator<Item = f64> + '_ {
+        let steps = self.steps.max(1);
+        (1..=steps).map(move |step| self.easing.apply(step as f64 / steps as f64))
+    }
+
+    /// The delay between two steps.
+    pub(crate) fn step_delay(&self) -> Duration {
+        self.duration / self.steps.max(1) as u32
+    }
+}
+
+/// Options of a scroll gesture synthesized by Chromium.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScrollGestureOptions {
+    /// Scroll speed in pixels per second (defaults to 800)
+    pub speed: u32,
+    /// Whether to stop scrolling when the gesture ends instead of flinging
+    /// on (defaults to true)
+    pub prevent_fling: bool,
+    /// How often the gesture is repeated after the first one
+    pub repeat_count: u32,
+    /// The delay between repetitions (defaults to 250ms)
+    pub repeat_delay: Duration,
+    /// Which input events are generated, the platform's preferred ones by
+    /// default
+    pub source: GestureSourceType,
+}
+
+impl Default for ScrollGestureOptions {
+    fn default() -> Self {
+        Self {
+            speed: 800,
+            prevent_fling: true,
+            repeat_count: 0,
+            repeat_delay: Duration::from_millis(250),
+            source: GestureSourceType::Default,
+        }
+    }
+}
+
+impl ScrollGestureOptions {
+    /// Scrolls the content by `delta` starting at `point`, positive values
+    /// scroll down and right like the mouse wheel.
+    pub(crate) fn params(&self, point: Point, delta: Point) -> SynthesizeScrollGestureParams {
+        let mut params = SynthesizeScrollGestureParams::new(point.x, point.y);
+        // the gesture's distances are those the fingers move
+        params.x_distance = Some(-delta.x);
+        params.y_distance = Some(-delta.y);
+        params.speed = Some(self.speed as i64);
+        params.prevent_fling = Some(self.prevent_fling);
+        params.repeat_count = Some(self.repeat_count as i64);
+        params.repeat_delay_ms = Some(self.repeat_delay.as_millis() as i64);
+        params.gesture_source_type = Some(self.source.clone());
+        params
+    }
+}
+
+/// Options of a pinch gesture synthesized by Chromium.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinchGestureOptions {
+    /// Speed of the fingers in pixels per second (defaults to 800)
+    pub speed: u32,
+    /// Which input events are generated, the platform's preferred ones by
+    /// default
+    pub source: GestureSourceType,
+}
+
+impl Default for PinchGestureOptions {
+    fn default() -> Self {
+        Self {
+            speed: 800,
+            source: GestureSourceType::Default,
+        }
+    }
+}
+
+impl PinchGestureOptions {
+    pub(crate) fn params(&self, center: Point, scale: f64) -> SynthesizePinchGestureParams {
+        let mut params = SynthesizePinchGestureParams::new(center.x, center.y, scale);
+        params.relative_speed = Some(self.speed as i64);
+        params.gesture_source_type = Some(self.source.clone());
+        params
+    }
+}
+
+/// Options of a tap gesture synthesized by Chromium.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TapGestureOptions {
+    /// How long each tap touches the screen (defaults to 50ms)
+    pub duration: Duration,
+    /// How often to tap, e.g. 2 for a double tap (defaults to 1)
+    pub tap_count: u32,
+    /// Which input events are generated, the platform's preferred ones by
+    /// default
+    pub source: GestureSourceType,
+}
+
+impl Default for TapGestureOptions {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_millis(50),
+            tap_count: 1,
+            source: GestureSourceType::Default,
+        }
+    }
+}
+
+impl TapGestureOptions {
+    pub(crate) fn params(&self, point: Point) -> SynthesizeTapGestureParams {
+        let mut params = SynthesizeTapGestureParams::new(point.x, point.y);
+        params.duration = Some(self.duration.as_millis() as i64);
+        params.tap_count = Some(self.tap_count as i64);
+        params.gesture_source_type = Some(self.source.clone());
+        params
+    }
+}
+
+/// The touch points of two fingers on opposite sides of `center`, at
+/// `distance` from it and `angle` radians from the x-axis.
+fn two_fingers(center: Point, distance: f64, angle: f64) -> Vec<TouchPoint> {
+    let (sin, cos) = angle.sin_cos();
+    let offset = Point::new(cos * distance, sin * distance);
+    [(center + offset, 0.), (center - offset, 1.)]
+        .into_iter()
+        .map(|(point, id)| {
+            let mut touch = TouchPoint::new(point.x, point.y);
+            touch.id = Some(id);
+            touch
+        })
+        .collect()
+}
+
+/// The touch points of every frame of a pinch that scales the distance of
+/// two fingers by `scale`.
+pub(crate) fn pinch_frames(
+    center: Point,
+    scale: f64,
+    options: &GestureOptions,
+) -> Vec<Vec<TouchPoint>> {
+    let end = FINGER_DISTANCE * scale;
+    std::iter::once(0.)
+        .chain(options.progress())
+        .map(|p| two_fingers(center, FINGER_DISTANCE + (end - FINGER_DISTANCE) * p, 0.))
+        .collect()
+}
+
+/// The touch points of every frame of a clockwise rotation of two fingers
+/// around `center` by `degrees`.
+pub(crate) fn rotate_frames(
+    center: Point,
+    degrees: f64,
+    options: &GestureOptions,
+) -> Vec<Vec<TouchPoint>> {
+    let radians = degrees.to_radians();
+    std::iter::once(0.)
+        .chain(options.progress())
+        .map(|p| two_fingers(center, FINGER_DISTANCE, radians * p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(frame: &[TouchPoint]) -> f64 {
+        ((frame[0].x - frame[1].x).powi(2) + (frame[0].y - frame[1].y).powi(2)).sqrt()
+    }
+
+    #[test]
+    fn eases_progress() {
+        assert_eq!(Easing::EaseInOut.apply(0.), 0.);
+        assert!((Easing::EaseInOut.apply(0.5) - 0.5).abs() < 1e-9);
+        assert_eq!(Easing::EaseInOut.apply(1.), 1.);
+        assert!(Easing::EaseInOut.apply(0.1) < Easing::Linear.apply(0.1));
+    }
+
+    #[test]
+    fn pinches_and_rotates_fingers() {
+        let center = Point::new(100., 100.);
+        let options = GestureOptions::default();
+
+        let frames = pinch_frames(center, 2., &options);
+        assert_eq!(frames.len(), options.steps + 1);
+        assert!((distance(&frames[0]) - 100.).abs() < 1e-9);
+        assert!((distance(frames.last().unwrap()) - 200.).abs() < 1e-9);
+
+        let frames = rotate_frames(center, 90., &options);
+        let last = frames.last().unwrap();
+        assert!((last[0].x - 100.).abs() < 1e-9);
+        assert!((last[0].y - 150.).abs() < 1e-9);
+        assert!((distance(last) - 100.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scrolls_like_the_wheel() {
+        let params =
+            ScrollGestureOptions::default().params(Point::new(10., 20.), Point::new(0., 500.));
+        assert_eq!(params.x_distance, Some(0.));
+        assert_eq!(params.y_distance, Some(-500.));
+        assert_eq!(params.repeat_delay_ms, Some(250));
+        assert_eq!(params.prevent_fling, Some(true));
+    }
+}
@@ -1,10 +1,12 @@
 use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
-    ScreenOrientation, ScreenOrientationType, SetDeviceMetricsOverrideParams,
-    SetTouchEmulationEnabledParams,
+    ClearDeviceMetricsOverrideParams, MediaFeature, ScreenOrientation, ScreenOrientationType,
+    SetDeviceMetricsOverrideParams, SetEmulatedMediaParams, SetLocaleOverrideParams,
+    SetTimezoneOverrideParams, SetTouchEmulationEnabledParams,
 };
-use chromiumoxide_types::Method;
+use chromiumoxide_types::{Command, MethodId};
 
 use crate::cmd::CommandChain;
+use crate::handler::target::TargetConfig;
 use crate::handler::viewport::Viewport;
 use std::time::Duration;
 
@@ -14,6 +16,8 @@ pub struct EmulationManager {
     pub has_touch: bool,
     pub needs_reload: bool,
     pub request_timeout: Duration,
+    /// The device metrics the page currently emulates
+    pub device_metrics: Option<SetDeviceMetricsOverrideParams>,
 }
 
 impl EmulationManager {
@@ -23,10 +27,44 @@ impl EmulationManager {
             has_touch: false,
             needs_reload: false,
             request_timeout,
+            device_metrics: None,
         }
     }
 
-    pub fn init_commands(&mut self, viewport: &Viewport) -> CommandChain {
+    /// Keeps track of the emulated device metrics from the commands that are
+    /// sent on behalf of the page
+    pub fn on_command(&mut self, method: &str, params: &serde_json::Value) {
+        if method == SetDeviceMetricsOverrideParams::IDENTIFIER {
+            self.device_metrics = serde_json::from_value(params.clone()).ok();
+        } else if method == ClearDeviceMetricsOverrideParams::IDENTIFIER {
+            self.device_metrics = None;
+        }
+    }
+
+    /// The commands to emulate the viewport, timezone and locale of the
+    /// config, `None` if there is nothing to emulate
+    pub fn init_commands(&mut self, config: &TargetConfig) -> Option<CommandChain> {
+        let mut cmds = config
+            .viewport
+            .as_ref()
+            .map(|viewport| self.viewport_commands(viewport))
+            .unwrap_or_default();
+        if let Some(timezone_id) = config.timezone_id.clone() {
+            cmds.push(command(SetTimezoneOverrideParams::new(timezone_id)));
+        }
+        if let Some(locale) = config.locale.clone() {
+            cmds.push(command(SetLocaleOverrideParams {
+                locale: Some(locale),
+            }));
+        }
+        if cmds.is_empty() {
+            None
+        } else {
+            Some(CommandChain::new(cmds, self.request_timeout))
+        }
+    }
+
+    fn viewport_commands(&mut self, viewport: &Viewport) -> Vec<(MethodId, serde_json::Value)> {
         let orientation = if viewport.is_landscape {
             ScreenOrientation::new(ScreenOrientationType::LandscapePrimary, 90)
         } else {
@@ -44,22 +82,180 @@ impl EmulationManager {
 
         let set_touch = SetTouchEmulationEnabledParams::new(true);
 
-        let chain = CommandChain::new(
-            vec![
-                (
-                    set_device.identifier(),
-                    serde_json::to_value(set_device).unwrap(),
-                ),
-                (
-                    set_touch.identifier(),
-                    serde_json::to_value(set_touch).unwrap(),
-                ),
-            ],
-            self.request_timeout,
-        );
-
         self.needs_reload = self.emulating_mobile != viewport.emulating_mobile
             || self.has_touch != viewport.has_touch;
-        chain
+        self.device_metrics = Some(set_device.clone());
+        vec![command(set_device), command(set_touch)]
+    }
+}
+
+/// The CSS media type and media features to emulate, see
+/// `Page::emulate_media`.
+///
+/// Settings left at `None` are not emulated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MediaOptions {
+    /// The media type, `print` or `screen`
+    pub media_type: Option<MediaType>,
+    /// The value of the `prefers-color-scheme` media feature
+    pub color_scheme: Option<ColorScheme>,
+    /// The value of the `prefers-reduced-motion` media feature
+    pub reduced_motion: Option<ReducedMotion>,
+    /// The value of the `forced-colors` media feature
+    pub forced_colors: Option<ForcedColors>,
+}
+
+impl From<MediaOptions> for SetEmulatedMediaParams {
+    fn from(options: MediaOptions) -> Self {
+        let features = [
+            (
+                "prefers-color-scheme",
+                options.color_scheme.map(ColorScheme::as_str),
+            ),
+            (
+                "prefers-reduced-motion",
+                options.reduced_motion.map(ReducedMotion::as_str),
+            ),
+            (
+                "forced-colors",
+                options.forced_colors.map(ForcedColors::as_str),
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|value| MediaFeature::new(name, value)))
+        .collect();
+        SetEmulatedMediaParams {
+            // an empty media type disables the override
+            media: Some(
+                options
+                    .media_type
+                    .map(MediaType::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+            ),
+            features: Some(features),
+        }
+    }
+}
+
+/// A CSS media type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    Print,
+    Screen,
+}
+
+impl MediaType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MediaType::Print => "print",
+            MediaType::Screen => "screen",
+        }
+    }
+}
+
+/// A value of the `prefers-color-scheme` media feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+    NoPreference,
+}
+
+impl ColorScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ColorScheme::Light => "light",
+            ColorScheme::Dark => "dark",
+            ColorScheme::NoPreference => "no-preference",
+        }
+    }
+}
+
+/// A value of the `prefers-reduced-motion` media feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReducedMotion {
+    Reduce,
+    NoPreference,
+}
+
+impl ReducedMotion {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ReducedMotion::Reduce => "reduce",
+            ReducedMotion::NoPreference => "no-preference",
+        }
+    }
+}
+
+/// A value of the `forced-colors` media feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForcedColors {
+    Active,
+    None,
+}
+
+impl ForcedColors {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ForcedColors::Active => "active",
+            ForcedColors::None => "none",
+        }
+    }
+}
+
+fn command<T: Command>(cmd: T) -> (MethodId, serde_json::Value) {
+    (cmd.identifier(), serde_json::to_value(cmd).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emulates_only_configured_settings() {
+        let mut manager = EmulationManager::new(Duration::from_secs(1));
+        assert!(manager.init_commands(&TargetConfig::default()).is_none());
+
+        let config = TargetConfig {
+            timezone_id: Some("Europe/Berlin".to_string()),
+            ..Default::default()
+        };
+        assert!(manager.init_commands(&config).is_some());
+        assert!(!manager.needs_reload);
+    }
+
+    #[test]
+    fn tracks_device_metrics() {
+        let mut manager = EmulationManager::new(Duration::from_secs(1));
+        let metrics = SetDeviceMetricsOverrideParams::new(800, 600, 2., true);
+        manager.on_command(
+            SetDeviceMetricsOverrideParams::IDENTIFIER,
+            &serde_json::to_value(&metrics).unwrap(),
+        );
+        assert_eq!(manager.device_metrics, Some(metrics));
+        manager.on_command(
+            ClearDeviceMetricsOverrideParams::IDENTIFIER,
+            &serde_json::json!({}),
+        );
+        assert!(manager.device_metrics.is_none());
+    }
+
+    #[test]
+    fn converts_media_options() {
+        let params = SetEmulatedMediaParams::from(MediaOptions {
+            media_type: Some(MediaType::Print),
+            color_scheme: Some(ColorScheme::Dark),
+            ..Default::default()
+        });
+        assert_eq!(params.media.as_deref(), Some("print"));
+        assert_eq!(
+            params.features,
+            Some(vec![MediaFeature::new("prefers-color-scheme", "dark")])
+        );
+
+        let params = SetEmulatedMediaParams::from(MediaOptions::default());
+        assert_eq!(params.media.as_deref(), Some(""));
+        assert_eq!(params.features, Some(Vec::new()));
     }
 }
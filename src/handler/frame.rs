@@ -1,17 +1,24 @@
 use std::collections::VecDeque;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
 use serde_json::map::Entry;
 
-use chromiumoxide_cdp::cdp::browser_protocol::network::LoaderId;
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    EventRequestWillBeSent, LoaderId, RequestId, ResourceType,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::page::{
-    AddScriptToEvaluateOnNewDocumentParams, CreateIsolatedWorldParams, EventFrameDetached,
-    EventFrameStartedLoading, EventFrameStoppedLoading, EventLifecycleEvent,
-    EventNavigatedWithinDocument, Frame as CdpFrame, FrameTree,
+    AddScriptToEvaluateOnNewDocumentParams, ClientNavigationReason, CreateIsolatedWorldParams,
+    EventFrameDetached, EventFrameRequestedNavigation, EventFrameStartedLoading,
+    EventFrameStoppedLoading, EventLifecycleEvent, EventNavigatedWithinDocument, Frame as CdpFrame,
+    FrameTree, StopLoadingParams,
 };
-use chromiumoxide_cdp::cdp::browser_protocol::target::EventAttachedToTarget;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{EventAttachedToTarget, SessionId};
 use chromiumoxide_cdp::cdp::js_protocol::runtime::*;
 use chromiumoxide_cdp::cdp::{
     browser_protocol::page::{self, FrameId},
@@ -19,9 +26,11 @@ use chromiumoxide_cdp::cdp::{
 };
 use chromiumoxide_types::{Method, MethodId, Request};
 
+use crate::binding::{Binding, Settlement};
 use crate::error::DeadlineExceeded;
 use crate::handler::domworld::DOMWorld;
 use crate::handler::http::HttpRequest;
+use crate::handler::network::BudgetExceeded;
 use crate::handler::REQUEST_TIMEOUT;
 use crate::{cmd::CommandChain, ArcHttpRequest};
 
@@ -44,6 +53,8 @@ pub struct Frame {
     /// The frames contained in this frame
     child_frames: HashSet<FrameId>,
     name: Option<String>,
+    /// The security origin of the document loaded in this frame
+    security_origin: Option<String>,
     /// The received lifecycle events
     lifecycle_events: HashSet<MethodId>,
 }
@@ -60,6 +71,7 @@ impl Frame {
             http_request: None,
             child_frames: Default::default(),
             name: None,
+            security_origin: None,
             lifecycle_events: Default::default(),
         }
     }
@@ -76,6 +88,7 @@ impl Frame {
             http_request: None,
             child_frames: Default::default(),
             name: None,
+            security_origin: None,
             lifecycle_events: Default::default(),
         }
     }
@@ -92,6 +105,14 @@ impl Frame {
         self.name.as_deref()
     }
 
+    pub fn security_origin(&self) -> Option<&str> {
+        self.security_origin.as_deref()
+    }
+
+    pub fn parent_id(&self) -> Option<&FrameId> {
+        self.parent_frame.as_ref()
+    }
+
     pub fn main_world(&self) -> &DOMWorld {
         &self.main_world
     }
@@ -110,6 +131,7 @@ impl Frame {
 
     fn navigated(&mut self, frame: &CdpFrame) {
         self.name = frame.name.clone();
+        self.security_origin = Some(frame.security_origin.clone());
         let url = if let Some(ref fragment) = frame.url_fragment {
             format!("{}{fragment}", frame.url)
         } else {
@@ -136,6 +158,12 @@ impl Frame {
         self.lifecycle_events.contains("load")
     }
 
+    /// Whether the frame received the lifecycle event that completes
+    /// `wait_until`
+    pub fn has_reached(&self, wait_until: WaitUntil) -> bool {
+        self.lifecycle_events.contains(wait_until.as_ref())
+    }
+
     pub fn clear_contexts(&mut self) {
         self.main_world.take_context();
         self.secondary_world.take_context();
@@ -170,6 +198,7 @@ impl From<CdpFrame> for Frame {
             http_request: None,
             child_frames: Default::default(),
             name: frame.name,
+            security_origin: Some(frame.security_origin),
             lifecycle_events: Default::default(),
         }
     }
@@ -183,7 +212,7 @@ pub struct FrameManager {
     main_frame: Option<FrameId>,
     frames: HashMap<FrameId, Frame>,
     /// The contexts mapped with their frames
-    context_ids: HashMap<ExecutionContextId, FrameId>,
+    context_ids: HashMap<SessionContext, FrameId>,
     isolated_worlds: HashSet<String>,
     /// Timeout after which an anticipated event (related to navigation) doesn't
     /// arrive results in an error
@@ -192,6 +221,17 @@ pub struct FrameManager {
     pending_navigations: VecDeque<(FrameNavigationRequest, NavigationWatcher)>,
     /// The currently ongoing navigation
     navigation: Option<(NavigationWatcher, Instant)>,
+    /// The bindings exposed to the page's scripts
+    bindings: HashMap<String, Binding>,
+    /// Requests to send to the browser, like the results of binding calls,
+    /// with the session of the out-of-process iframe they are sent in
+    queued_requests: VecDeque<(Option<SessionId>, (MethodId, serde_json::Value))>,
+    /// The calls of async bindings whose results are still pending
+    pending_binding_calls: FuturesUnordered<BoxFuture<'static, (SessionContext, String)>>,
+    /// Detects navigation loops of the main frame
+    guard: GuardState,
+    /// The loop that stopped the page, the ongoing navigation fails with it
+    navigation_loop: Option<NavigationLoop>,
 }
 
 impl FrameManager {
@@ -204,6 +244,11 @@ impl FrameManager {
             request_timeout,
             pending_navigations: Default::default(),
             navigation: None,
+            bindings: Default::default(),
+            queued_requests: Default::default(),
+            pending_binding_calls: Default::default(),
+            guard: Default::default(),
+            navigation_loop: None,
         }
     }
 
@@ -253,6 +298,29 @@ impl FrameManager {
         self.frames.get(id)
     }
 
+    /// A snapshot of the frame hierarchy starting at the main frame
+    pub fn frame_tree(&self) -> Option<FrameInfo> {
+        self.main_frame().map(|frame| self.frame_info(frame))
+    }
+
+    fn frame_info(&self, frame: &Frame) -> FrameInfo {
+        let mut children: Vec<_> = frame
+            .child_frames
+            .iter()
+            .filter_map(|id| self.frames.get(id))
+            .map(|child| self.frame_info(child))
+            .collect();
+        children.sort_by(|a, b| a.id.inner().cmp(b.id.inner()));
+        FrameInfo {
+            id: frame.id.clone(),
+            parent_id: frame.parent_frame.clone(),
+            url: frame.url.clone(),
+            name: frame.name.clone(),
+            security_origin: frame.security_origin.clone(),
+            children,
+        }
+    }
+
     fn check_lifecycle(&self, watcher: &NavigationWatcher, frame: &Frame) -> bool {
         watcher
             .expected_lifecycle
@@ -295,6 +363,18 @@ impl FrameManager {
     }
 
     pub fn poll(&mut self, now: Instant) -> Option<FrameEvent> {
+        if let Some(req) = self.queued_requests.pop_front() {
+            let (session_id, req) = req;
+            return Some(FrameEvent::SendCdpRequest(session_id, req));
+        }
+        if let Some(err) = self.navigation_loop.take() {
+            if let Some((watcher, _)) = self.navigation.take() {
+                return Some(FrameEvent::NavigationResult(Err(NavigationError::Loop {
+                    id: watcher.id,
+                    err,
+                })));
+            }
+        }
         // check if the navigation completed
         if let Some((watcher, deadline)) = self.navigation.take() {
             if now > deadline {
@@ -332,9 +412,82 @@ impl FrameManager {
         None
     }
 
+    /// Aborts the ongoing navigation because the request budget of the page
+    /// was exceeded
+    pub fn fail_navigation(&mut self, exceeded: BudgetExceeded) -> Option<NavigationError> {
+        let (watcher, _) = self.navigation.take()?;
+        Some(NavigationError::BudgetExceeded {
+            id: watcher.id,
+            exceeded,
+        })
+    }
+
+    /// Replaces the guard against navigation loops of the main frame
+    pub fn set_navigation_guard(&mut self, guard: NavigationGuard) {
+        self.guard = GuardState {
+            guard,
+            ..Default::default()
+        };
+    }
+
+    /// Stops the page and fails the ongoing navigation with the loop
+    fn on_navigation_loop(&mut self, err: NavigationLoop) {
+        tracing::debug!("{}", err);
+        let stop = StopLoadingParams::default();
+        self.queued_requests.push_back((
+            None,
+            (stop.identifier(), serde_json::to_value(stop).unwrap()),
+        ));
+        self.navigation_loop = Some(err);
+    }
+
+    /// Counts the redirects of the navigation requests of the main frame
+    pub fn on_request_will_be_sent(&mut self, event: &EventRequestWillBeSent) {
+        let is_navigation = event.r#type == Some(ResourceType::Document)
+            && (self.main_frame.is_none() || self.main_frame == event.frame_id);
+        if !is_navigation || event.redirect_response.is_none() {
+            return;
+        }
+        if let Some(err) = self
+            .guard
+            .on_redirect(&event.request_id, &event.request.url)
+        {
+            self.on_navigation_loop(err);
+        }
+    }
+
+    /// Detects meta refreshes of the main frame
+    pub fn on_frame_requested_navigation(&mut self, event: &EventFrameRequestedNavigation) {
+        if self.main_frame.as_ref() != Some(&event.frame_id) {
+            return;
+        }
+        if let Some(err) = self.guard.on_requested_navigation(event) {
+            self.on_navigation_loop(err);
+        }
+    }
+
+    /// Aborts the ongoing and all queued navigations because the target
+    /// crashed
+    pub fn on_target_crashed(&mut self) -> Vec<NavigationError> {
+        self.navigation
+            .take()
+            .map(|(watcher, _)| watcher)
+            .into_iter()
+            .chain(
+                self.pending_navigations
+                    .drain(..)
+                    .map(|(_, watcher)| watcher),
+            )
+            .map(|watcher| NavigationError::TargetCrashed { id: watcher.id })
+            .collect()
+    }
+
     /// Entrypoint for page navigation
+    ///
+    /// Navigates the frame set in the request's `frameId` or the main frame
+    /// if none is set.
     pub fn goto(&mut self, req: FrameNavigationRequest) {
-        if let Some(frame_id) = self.main_frame.clone() {
+        if let Some(frame_id) = req.frame_id().or_else(|| self.main_frame.clone()) {
             self.navigate_frame(frame_id, req);
         }
     }
@@ -342,7 +495,7 @@ impl FrameManager {
     /// Navigate a specific frame
     pub fn navigate_frame(&mut self, frame_id: FrameId, mut req: FrameNavigationRequest) {
         let loader_id = self.frames.get(&frame_id).and_then(|f| f.loader_id.clone());
-        let watcher = NavigationWatcher::until_page_load(req.id, frame_id.clone(), loader_id);
+        let watcher = NavigationWatcher::until(req.id, frame_id.clone(), loader_id, req.wait_until);
         // insert the frame_id in the request if not present
         req.set_frame_id(frame_id);
         self.pending_navigations.push_back((req, watcher))
@@ -411,6 +564,9 @@ impl FrameManager {
             f.navigated(frame);
             self.main_frame = Some(f.id.clone());
             self.frames.insert(f.id.clone(), f);
+            if let Some(err) = self.guard.on_load(&frame.url) {
+                self.on_navigation_loop(err);
+            }
         }
     }
 
@@ -436,11 +592,75 @@ impl FrameManager {
         }
     }
 
-    /// Notification is issued every time when binding is called
-    pub fn on_runtime_binding_called(&mut self, _ev: &EventBindingCalled) {}
+    /// Notification is issued every time when binding is called, within the
+    /// session of an out-of-process iframe or `None` for the page's session
+    pub fn on_runtime_binding_called(
+        &mut self,
+        ev: &EventBindingCalled,
+        session_id: Option<SessionId>,
+    ) {
+        if let Some(binding) = self.bindings.get_mut(&ev.name) {
+            let source = (session_id, ev.execution_context_id);
+            let frame = self
+                .context_ids
+                .get(&source)
+                .and_then(|id| self.frames.get(id));
+            let frame_id = frame.map(|frame| frame.id.clone());
+            let frame_url = frame.and_then(|frame| frame.url.clone());
+            match binding.call(ev, frame_id, frame_url) {
+                Some(Settlement::Ready(expression)) => self.settle_binding_call(source, expression),
+                Some(Settlement::Pending(fut)) => {
+                    self.pending_binding_calls
+                        .push(fut.map(move |expression| (source, expression)).boxed());
+                }
+                None => {}
+            }
+        }
+    }
+
+    fn settle_binding_call(&mut self, source: SessionContext, expression: String) {
+        let (session_id, context_id) = source;
+        let params = EvaluateParams::builder()
+            .expression(expression)
+            .context_id(context_id)
+            .build()
+            .unwrap();
+        self.queued_requests.push_back((
+            session_id,
+            (
+                params.identifier(),
+                serde_json::to_value(params).expect("Command should not panic"),
+            ),
+        ));
+    }
+
+    /// Drives the calls of async bindings and queues the results of the
+    /// finished ones
+    pub fn poll_binding_calls(&mut self, cx: &mut Context<'_>) {
+        while let Poll::Ready(Some((source, expression))) =
+            self.pending_binding_calls.poll_next_unpin(cx)
+        {
+            self.settle_binding_call(source, expression);
+        }
+    }
+
+    /// Handle the calls of the binding
+    pub fn add_binding(&mut self, binding: Binding) {
+        self.bindings.insert(binding.name.clone(), binding);
+    }
+
+    /// Stop handling the calls of the binding with this name
+    pub fn remove_binding(&mut self, name: &str) {
+        self.bindings.remove(name);
+    }
 
-    /// Issued when new execution context is created
-    pub fn on_frame_execution_context_created(&mut self, event: &EventExecutionContextCreated) {
+    /// Issued when new execution context is created, within the session of
+    /// an out-of-process iframe or `None` for the page's session
+    pub fn on_frame_execution_context_created(
+        &mut self,
+        event: &EventExecutionContextCreated,
+        session_id: Option<SessionId>,
+    ) {
         if let Some(frame_id) = event
             .context
             .aux_data
@@ -461,7 +681,8 @@ impl FrameManager {
                 {
                     frame.secondary_world.set_context(event.context.id);
                 }
-                self.context_ids.insert(event.context.id, frame.id.clone());
+                self.context_ids
+                    .insert((session_id, event.context.id), frame.id.clone());
             }
         }
         if event
@@ -475,23 +696,36 @@ impl FrameManager {
         }
     }
 
-    /// Issued when execution context is destroyed
-    pub fn on_frame_execution_context_destroyed(&mut self, event: &EventExecutionContextDestroyed) {
-        if let Some(id) = self.context_ids.remove(&event.execution_context_id) {
+    /// Issued when execution context is destroyed, within the session of an
+    /// out-of-process iframe or `None` for the page's session
+    pub fn on_frame_execution_context_destroyed(
+        &mut self,
+        event: &EventExecutionContextDestroyed,
+        session_id: Option<SessionId>,
+    ) {
+        if let Some(id) = self
+            .context_ids
+            .remove(&(session_id, event.execution_context_id))
+        {
             if let Some(frame) = self.frames.get_mut(&id) {
                 frame.destroy_context(event.execution_context_id);
             }
         }
     }
 
-    /// Issued when all executionContexts were cleared
-    pub fn on_execution_contexts_cleared(&mut self) {
-        for id in self.context_ids.values() {
-            if let Some(frame) = self.frames.get_mut(id) {
+    /// Issued when all executionContexts of the session were cleared, the
+    /// one of an out-of-process iframe or `None` for the page's session
+    pub fn on_execution_contexts_cleared(&mut self, session_id: Option<SessionId>) {
+        let frames = &mut self.frames;
+        self.context_ids.retain(|(session, _), id| {
+            if *session != session_id {
+                return true;
+            }
+            if let Some(frame) = frames.get_mut(id) {
                 frame.clear_contexts();
             }
-        }
-        self.context_ids.clear()
+            false
+        });
     }
 
     /// Fired for top level page lifecycle events (nav, load, paint, etc.)
@@ -550,12 +784,140 @@ impl FrameManager {
     }
 }
 
+/// A snapshot of a frame and all its child frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameInfo {
+    /// Cdp identifier of the frame
+    pub id: FrameId,
+    /// The identifier of the parent frame, `None` for the main frame
+    pub parent_id: Option<FrameId>,
+    /// The url of the document loaded in the frame
+    pub url: Option<String>,
+    /// The frame's name as specified in the tag
+    pub name: Option<String>,
+    /// The security origin of the document loaded in the frame
+    pub security_origin: Option<String>,
+    /// The frames contained in this frame
+    pub children: Vec<FrameInfo>,
+}
+
+impl FrameInfo {
+    /// Iterates over this frame and all its descendants in depth-first order
+    pub fn iter(&self) -> impl Iterator<Item = &FrameInfo> + '_ {
+        let mut stack = vec![self];
+        std::iter::from_fn(move || {
+            let frame = stack.pop()?;
+            stack.extend(frame.children.iter().rev());
+            Some(frame)
+        })
+    }
+
+    /// Returns the frame with the given identifier within this tree
+    pub fn find(&self, id: &FrameId) -> Option<&FrameInfo> {
+        self.iter().find(|frame| &frame.id == id)
+    }
+}
+
+/// Guards against navigations of the main frame that never settle, see
+/// `Page::set_navigation_guard`.
+///
+/// Once a limit is exceeded the page stops loading and its ongoing
+/// navigation fails with `CdpError::NavigationLoop`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NavigationGuard {
+    /// The most redirects a navigation may follow
+    pub max_redirects: Option<usize>,
+    /// The most times the same url may be loaded again in a row, including
+    /// reloads
+    pub max_reloads: Option<usize>,
+    /// Whether to stop the navigations of `<meta http-equiv="refresh">`
+    /// tags
+    pub deny_meta_refresh: bool,
+}
+
+/// The navigation loop a `NavigationGuard` stopped.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NavigationLoop {
+    #[error("Exceeded the limit of {max} redirects at {url}.")]
+    Redirects { url: String, max: usize },
+    #[error("Exceeded the limit of {max} reloads of {url}.")]
+    Reloads { url: String, max: usize },
+    #[error("Denied the meta refresh to {url}.")]
+    MetaRefresh { url: String },
+}
+
+/// A `NavigationGuard` and the navigations it has seen.
+#[derive(Debug, Default)]
+struct GuardState {
+    guard: NavigationGuard,
+    /// The navigation request that is redirected and its number of redirects
+    redirects: Option<(RequestId, usize)>,
+    /// The url the main frame loaded last and how often it was loaded again
+    loads: Option<(String, usize)>,
+}
+
+impl GuardState {
+    fn on_redirect(&mut self, request_id: &RequestId, url: &str) -> Option<NavigationLoop> {
+        let max = self.guard.max_redirects?;
+        let redirects = match &mut self.redirects {
+            Some((id, redirects)) if id == request_id => {
+                *redirects += 1;
+                *redirects
+            }
+            redirects => {
+                *redirects = Some((request_id.clone(), 1));
+                1
+            }
+        };
+        (redirects > max).then(|| NavigationLoop::Redirects {
+            url: url.to_string(),
+            max,
+        })
+    }
+
+    fn on_load(&mut self, url: &str) -> Option<NavigationLoop> {
+        let reloads = match &mut self.loads {
+            Some((last, reloads)) if last == url => {
+                *reloads += 1;
+                *reloads
+            }
+            loads => {
+                *loads = Some((url.to_string(), 0));
+                0
+            }
+        };
+        let max = self.guard.max_reloads?;
+        (reloads > max).then(|| NavigationLoop::Reloads {
+            url: url.to_string(),
+            max,
+        })
+    }
+
+    fn on_requested_navigation(
+        &self,
+        event: &EventFrameRequestedNavigation,
+    ) -> Option<NavigationLoop> {
+        (self.guard.deny_meta_refresh && event.reason == ClientNavigationReason::MetaTagRefresh)
+            .then(|| NavigationLoop::MetaRefresh {
+                url: event.url.clone(),
+            })
+    }
+}
+
+/// An execution context and the session of the out-of-process iframe it
+/// belongs to, or `None` for the page's session, as context ids are only
+/// unique within their session
+type SessionContext = (Option<SessionId>, ExecutionContextId);
+
 #[derive(Debug)]
 pub enum FrameEvent {
     /// A previously submitted navigation has finished
     NavigationResult(Result<NavigationOk, NavigationError>),
     /// A new navigation request needs to be submitted
     NavigationRequest(NavigationId, Request),
+    /// A request that needs to be sent to the browser, within the session of
+    /// an out-of-process iframe or `None` for the page's session
+    SendCdpRequest(Option<SessionId>, (MethodId, serde_json::Value)),
     /* /// The initial page of the target has been loaded
      * InitialPageLoadFinished */
 }
@@ -570,6 +932,17 @@ pub enum NavigationError {
         id: NavigationId,
         frame: FrameId,
     },
+    BudgetExceeded {
+        id: NavigationId,
+        exceeded: BudgetExceeded,
+    },
+    TargetCrashed {
+        id: NavigationId,
+    },
+    Loop {
+        id: NavigationId,
+        err: NavigationLoop,
+    },
 }
 
 impl NavigationError {
@@ -577,6 +950,9 @@ impl NavigationError {
         match self {
             NavigationError::Timeout { id, .. } => id,
             NavigationError::FrameNotFound { id, .. } => id,
+            NavigationError::BudgetExceeded { id, .. } => id,
+            NavigationError::TargetCrashed { id } => id,
+            NavigationError::Loop { id, .. } => id,
         }
     }
 }
@@ -612,9 +988,20 @@ pub struct NavigationWatcher {
 
 impl NavigationWatcher {
     pub fn until_page_load(id: NavigationId, frame: FrameId, loader_id: Option<LoaderId>) -> Self {
+        Self::until(id, frame, loader_id, WaitUntil::Load)
+    }
+
+    /// Watches the navigation until the frame and all its child frames
+    /// received the lifecycle event that completes `wait_until`
+    pub fn until(
+        id: NavigationId,
+        frame: FrameId,
+        loader_id: Option<LoaderId>,
+        wait_until: WaitUntil,
+    ) -> Self {
         Self {
             id,
-            expected_lifecycle: std::iter::once("load".into()).collect(),
+            expected_lifecycle: std::iter::once(wait_until.as_ref().to_string().into()).collect(),
             loader_id,
             frame_id: frame,
             same_document_navigation: false,
@@ -646,6 +1033,8 @@ pub struct FrameNavigationRequest {
     pub req: Request,
     /// The timeout after which the request will be considered timed out
     pub timeout: Duration,
+    /// When the navigation is considered finished
+    pub wait_until: WaitUntil,
 }
 
 impl FrameNavigationRequest {
@@ -654,9 +1043,23 @@ impl FrameNavigationRequest {
             id,
             req,
             timeout: Duration::from_millis(REQUEST_TIMEOUT),
+            wait_until: WaitUntil::default(),
         }
     }
 
+    /// Sets when the navigation is considered finished
+    pub fn with_wait_until(mut self, wait_until: WaitUntil) -> Self {
+        self.wait_until = wait_until;
+        self
+    }
+
+    /// The id of the frame set in the `params` `frameId` field, if any.
+    pub fn frame_id(&self) -> Option<FrameId> {
+        self.req.params["frameId"]
+            .as_str()
+            .map(|id| id.to_string().into())
+    }
+
     /// This will set the id of the frame into the `params` `frameId` field.
     pub fn set_frame_id(&mut self, frame_id: FrameId) {
         if let Some(params) = self.req.params.as_object_mut() {
@@ -681,6 +1084,31 @@ impl Default for LifecycleEvent {
     }
 }
 
+/// When a navigation is considered to be finished.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum WaitUntil {
+    /// The `load` event was fired.
+    #[default]
+    Load,
+    /// The `DOMContentLoaded` event was fired.
+    DomContentLoaded,
+    /// There were no network connections for at least 500ms.
+    NetworkIdle0,
+    /// There were no more than 2 network connections for at least 500ms.
+    NetworkIdle2,
+}
+
+impl AsRef<str> for WaitUntil {
+    fn as_ref(&self) -> &str {
+        match self {
+            WaitUntil::Load => "load",
+            WaitUntil::DomContentLoaded => "DOMContentLoaded",
+            WaitUntil::NetworkIdle0 => "networkIdle",
+            WaitUntil::NetworkIdle2 => "networkAlmostIdle",
+        }
+    }
+}
+
 impl AsRef<str> for LifecycleEvent {
     fn as_ref(&self) -> &str {
         match self {
@@ -691,3 +1119,148 @@ impl AsRef<str> for LifecycleEvent {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chromiumoxide_cdp::cdp::browser_protocol::page::{
+        CrossOriginIsolatedContextType, SecureContextType,
+    };
+
+    use super::*;
+
+    fn cdp_frame(id: &str, parent: Option<&str>, url: &str) -> CdpFrame {
+        CdpFrame::builder()
+            .id(id.to_string())
+            .parent_id(parent.map(str::to_string).unwrap_or_default())
+            .loader_id(format!("loader-{id}"))
+            .url(url)
+            .domain_and_registry("example.com")
+            .security_origin("https://example.com")
+            .mime_type("text/html")
+            .secure_context_type(SecureContextType::Secure)
+            .cross_origin_isolated_context_type(CrossOriginIsolatedContextType::NotIsolated)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn separates_contexts_of_sessions() {
+        let context_created = |frame: &str| -> EventExecutionContextCreated {
+            serde_json::from_value(serde_json::json!({
+                "context": {
+                    "id": 1,
+                    "origin": "https://example.com",
+                    "name": "",
+                    "auxData": { "frameId": frame, "isDefault": true }
+                }
+            }))
+            .unwrap()
+        };
+        let mut manager = FrameManager::new(Duration::from_millis(REQUEST_TIMEOUT));
+        let mut main = cdp_frame("main", None, "https://example.com/");
+        main.parent_id = None;
+        manager.on_frame_navigated(&main);
+        manager.on_frame_attached("child".to_string().into(), Some("main".to_string().into()));
+        let session: Option<SessionId> = Some("frame-session".to_string().into());
+        let context = |manager: &FrameManager, frame: &str| {
+            manager.frames[&FrameId::from(frame.to_string())].execution_context()
+        };
+
+        manager.on_frame_execution_context_created(&context_created("main"), None);
+        manager.on_frame_execution_context_created(&context_created("child"), session.clone());
+        assert_eq!(manager.context_ids.len(), 2);
+        assert_eq!(context(&manager, "main"), Some(ExecutionContextId::new(1)));
+        assert_eq!(context(&manager, "child"), Some(ExecutionContextId::new(1)));
+
+        let destroyed: EventExecutionContextDestroyed =
+            serde_json::from_value(serde_json::json!({ "executionContextId": 1 })).unwrap();
+        manager.on_frame_execution_context_destroyed(&destroyed, session.clone());
+        assert_eq!(context(&manager, "main"), Some(ExecutionContextId::new(1)));
+        assert_eq!(context(&manager, "child"), None);
+
+        manager.on_frame_execution_context_created(&context_created("child"), session);
+        manager.on_execution_contexts_cleared(None);
+        assert_eq!(context(&manager, "main"), None);
+        assert_eq!(context(&manager, "child"), Some(ExecutionContextId::new(1)));
+    }
+
+    #[test]
+    fn guards_against_navigation_loops() {
+        let mut state = GuardState {
+            guard: NavigationGuard {
+                max_redirects: Some(2),
+                max_reloads: Some(1),
+                deny_meta_refresh: false,
+            },
+            ..Default::default()
+        };
+        let request: RequestId = "1".to_string().into();
+        assert_eq!(state.on_redirect(&request, "https://example.com/a"), None);
+        assert_eq!(state.on_redirect(&request, "https://example.com/b"), None);
+        assert_eq!(
+            state.on_redirect(&request, "https://example.com/a"),
+            Some(NavigationLoop::Redirects {
+                url: "https://example.com/a".to_string(),
+                max: 2
+            })
+        );
+        // a new navigation starts counting again
+        assert_eq!(
+            state.on_redirect(&"2".to_string().into(), "https://example.com/a"),
+            None
+        );
+
+        assert_eq!(state.on_load("https://example.com/"), None);
+        assert_eq!(state.on_load("https://example.com/"), None);
+        assert!(state.on_load("https://example.com/").is_some());
+        assert_eq!(state.on_load("https://example.com/other"), None);
+    }
+
+    #[test]
+    fn frame_tree_snapshot() {
+        let mut manager = FrameManager::new(Duration::from_millis(REQUEST_TIMEOUT));
+        let mut main = cdp_frame("main", None, "https://example.com/");
+        main.parent_id = None;
+        manager.on_frame_navigated(&main);
+        manager.on_frame_attached("child".to_string().into(), Some("main".to_string().into()));
+        manager.on_frame_navigated(&cdp_frame(
+            "child",
+            Some("main"),
+            "https://example.com/frame",
+        ));
+
+        let tree = manager.frame_tree().unwrap();
+        assert_eq!(tree.url.as_deref(), Some("https://example.com/"));
+        assert_eq!(tree.security_origin.as_deref(), Some("https://example.com"));
+        assert_eq!(tree.children.len(), 1);
+
+        let child = tree.find(&"child".to_string().into()).unwrap();
+        assert_eq!(child.parent_id, Some(tree.id.clone()));
+        assert_eq!(child.url.as_deref(), Some("https://example.com/frame"));
+        assert_eq!(tree.iter().count(), 2);
+    }
+
+    #[test]
+    fn navigates_requested_frame() {
+        let mut manager = FrameManager::new(Duration::from_millis(REQUEST_TIMEOUT));
+        let mut main = cdp_frame("main", None, "https://example.com/");
+        main.parent_id = None;
+        manager.on_frame_navigated(&main);
+        manager.on_frame_attached("child".to_string().into(), Some("main".to_string().into()));
+
+        let req = Request::new(
+            page::NavigateParams::IDENTIFIER.into(),
+            serde_json::json!({ "url": "https://example.com/frame", "frameId": "child" }),
+        );
+        manager.goto(FrameNavigationRequest::new(NavigationId(0), req));
+
+        match manager.poll(Instant::now()) {
+            Some(FrameEvent::NavigationRequest(_, req)) => {
+                assert_eq!(req.params["frameId"], "child")
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+        let (watcher, _) = manager.navigation.as_ref().unwrap();
+        assert_eq!(watcher.frame_id.inner(), "child");
+    }
+}
@@ -7,6 +7,7 @@ use std::pin::Pin;
 use std::task::{Context, Poll};
 
 use crate::handler::commandfuture::CommandFuture;
+use crate::handler::frame::WaitUntil;
 use crate::handler::target::TargetMessage;
 use crate::handler::target_message_future::TargetMessageFuture;
 use crate::{ArcHttpRequest, Result};
@@ -27,7 +28,7 @@ impl<T: Command> HttpFuture<T> {
     pub fn new(sender: mpsc::Sender<TargetMessage>, command: CommandFuture<T>) -> Self {
         Self {
             command: command.fuse(),
-            navigation: TargetMessageFuture::<T>::wait_for_navigation(sender),
+            navigation: TargetMessageFuture::<T>::wait_for_navigation(sender, WaitUntil::default()),
         }
     }
 }
@@ -1,12 +1,17 @@
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use fnv::FnvHashMap;
-use futures::channel::mpsc::Receiver;
-use futures::channel::oneshot::Sender as OneshotSender;
-use futures::stream::{Fuse, Stream, StreamExt};
+use futures::channel::mpsc::{Receiver, UnboundedSender};
+use futures::channel::oneshot::{channel as oneshot_channel, Sender as OneshotSender};
+use futures::future::BoxFuture;
+use futures::stream::{Fuse, FuturesUnordered, Stream, StreamExt};
 use futures::task::{Context, Poll};
+use futures::{FutureExt, SinkExt};
+use futures_timer::Delay;
 
 use crate::listeners::{EventListenerRequest, EventListeners};
 use chromiumoxide_cdp::cdp::browser_protocol::browser::*;
@@ -15,20 +20,22 @@ use chromiumoxide_cdp::cdp::events::CdpEvent;
 use chromiumoxide_cdp::cdp::events::CdpEventMessage;
 use chromiumoxide_types::{CallId, Message, Method, Response};
 use chromiumoxide_types::{MethodId, Request as CdpRequest};
-pub(crate) use page::PageInner;
+pub(crate) use page::{PageInner, PageToken};
 
+use crate::auth::Credentials;
 use crate::cmd::{to_command_response, CommandMessage};
-use crate::conn::Connection;
+use crate::conn::{Connection, ConnectionState, ReconnectPolicy};
 use crate::error::{CdpError, Result};
 use crate::handler::browser::BrowserContext;
-use crate::handler::frame::FrameNavigationRequest;
+use crate::handler::frame::{FrameNavigationRequest, WaitUntil};
 use crate::handler::frame::{NavigationError, NavigationId, NavigationOk};
 use crate::handler::job::PeriodicJob;
 use crate::handler::session::Session;
-use crate::handler::target::TargetEvent;
 use crate::handler::target::{Target, TargetConfig};
+use crate::handler::target::{TargetEvent, TargetMessage};
 use crate::handler::viewport::Viewport;
 use crate::page::Page;
+use crate::route::ContextRoute;
 
 /// Standard timeout in MS
 pub const REQUEST_TIMEOUT: u64 = 30_000;
@@ -61,6 +68,12 @@ pub struct Handler {
     from_browser: Fuse<Receiver<HandlerMessage>>,
     default_browser_context: BrowserContext,
     browser_contexts: HashSet<BrowserContext>,
+    /// The browser contexts whose pages emulate being offline
+    offline_contexts: HashSet<BrowserContext>,
+    /// The routes of the browser contexts that apply to all of their pages
+    context_routes: HashMap<BrowserContext, Vec<ContextRoute>>,
+    /// The credentials for the proxies of the browser contexts
+    proxy_credentials: HashMap<BrowserContext, Credentials>,
     /// Used to loop over all targets in a consistent manner
     target_ids: Vec<TargetId>,
     /// The created and attached targets
@@ -81,6 +94,45 @@ pub struct Handler {
     config: HandlerConfig,
     /// All registered event subscriptions
     event_listeners: EventListeners,
+    /// The hooks that set up every new page
+    page_hooks: Vec<PageHook>,
+    /// The page hooks that are running and the navigations of new pages
+    /// that wait for them
+    running_hooks: FuturesUnordered<BoxFuture<'static, ()>>,
+    /// The attempt to connect to the browser again after the connection was
+    /// lost
+    reconnecting: Option<Reconnecting>,
+    /// Notified about the changes of the connection
+    connection_listeners: Vec<UnboundedSender<ConnectionState>>,
+    /// The call that reports the targets again after a reconnect, the
+    /// targets it didn't report are gone
+    rediscovering: Option<CallId>,
+}
+
+/// An attempt to connect to the browser again
+struct Reconnecting {
+    attempt: u32,
+    conn: BoxFuture<'static, Result<Connection<CdpEventMessage>>>,
+}
+
+impl fmt::Debug for Reconnecting {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reconnecting")
+            .field("attempt", &self.attempt)
+            .finish()
+    }
+}
+
+/// A hook that sets up new pages, see `Browser::on_new_page`
+#[derive(Clone)]
+pub(crate) struct PageHook(
+    pub(crate) Arc<dyn Fn(Page) -> BoxFuture<'static, Result<()>> + Send + Sync>,
+);
+
+impl fmt::Debug for PageHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("PageHook")
+    }
 }
 
 impl Handler {
@@ -91,12 +143,7 @@ impl Handler {
         rx: Receiver<HandlerMessage>,
         config: HandlerConfig,
     ) -> Self {
-        let discover = SetDiscoverTargetsParams::new(true);
-        let _ = conn.submit_command(
-            discover.identifier(),
-            None,
-            serde_json::to_value(discover).unwrap(),
-        );
+        Self::discover_targets(&mut conn);
 
         let browser_contexts = config
             .context_ids
@@ -109,6 +156,9 @@ impl Handler {
             from_browser: rx.fuse(),
             default_browser_context: Default::default(),
             browser_contexts,
+            offline_contexts: Default::default(),
+            proxy_credentials: Default::default(),
+            context_routes: Default::default(),
             target_ids: Default::default(),
             targets: Default::default(),
             navigations: Default::default(),
@@ -118,9 +168,26 @@ impl Handler {
             next_navigation_id: 0,
             config,
             event_listeners: Default::default(),
+            page_hooks: Vec::new(),
+            running_hooks: Default::default(),
+            reconnecting: None,
+            connection_listeners: Vec::new(),
+            rediscovering: None,
         }
     }
 
+    /// Subscribes to the creation of the targets, which reports all existing
+    /// targets first
+    fn discover_targets(conn: &mut Connection<CdpEventMessage>) -> Option<CallId> {
+        let discover = SetDiscoverTargetsParams::new(true);
+        conn.submit_command(
+            discover.identifier(),
+            None,
+            serde_json::to_value(discover).unwrap(),
+        )
+        .ok()
+    }
+
     /// Return the target with the matching `target_id`
     pub fn get_target(&self, target_id: &TargetId) -> Option<&Target> {
         self.targets.get(target_id)
@@ -191,6 +258,12 @@ impl Handler {
 
     /// Received a response to a request.
     fn on_response(&mut self, resp: Response) {
+        if self.rediscovering == Some(resp.id) {
+            // all targets that still exist were reported before the response
+            self.rediscovering = None;
+            self.remove_detached_targets();
+            return;
+        }
         if let Some((req, method, _)) = self.pending_commands.remove(&resp.id) {
             match req {
                 PendingRequest::CreateTarget(tx) => {
@@ -279,18 +352,29 @@ impl Handler {
     fn on_target_message(&mut self, target: &mut Target, msg: CommandMessage, now: Instant) {
         // if let some
         if msg.is_navigation() {
-            let (req, tx) = msg.split();
-            let id = self.next_navigation_id();
-            target.goto(FrameNavigationRequest::new(id, req));
-            self.navigations.insert(
-                id,
-                NavigationRequest::Navigate(NavigationInProgress::new(tx)),
-            );
+            self.on_target_navigation(target, msg, WaitUntil::default());
         } else {
             let _ = self.submit_external_command(msg, now);
         }
     }
 
+    /// Queue in a navigation request of the target's page that is finished
+    /// once the page reached `wait_until`
+    fn on_target_navigation(
+        &mut self,
+        target: &mut Target,
+        msg: CommandMessage,
+        wait_until: WaitUntil,
+    ) {
+        let (req, tx) = msg.split();
+        let id = self.next_navigation_id();
+        target.goto(FrameNavigationRequest::new(id, req).with_wait_until(wait_until));
+        self.navigations.insert(
+            id,
+            NavigationRequest::Navigate(NavigationInProgress::new(tx)),
+        );
+    }
+
     /// An identifier for queued `NavigationRequest`s.
     fn next_navigation_id(&mut self) -> NavigationId {
         let id = NavigationId(self.next_navigation_id);
@@ -308,9 +392,28 @@ impl Handler {
     /// `CreateTargetParams` has finished loading (The `Target`'s `Page` is
     /// ready and idle), the `Target` sends its newly created `Page` as response
     /// to the initiator (`tx`) of the `CreateTargetParams` request.
-    fn create_page(&mut self, params: CreateTargetParams, tx: OneshotSender<Result<Page>>) {
+    ///
+    /// If there are page hooks the target is created blank and navigated to
+    /// the `url` once they're done.
+    fn create_page(&mut self, mut params: CreateTargetParams, mut tx: OneshotSender<Result<Page>>) {
         match url::Url::parse(&params.url) {
             Ok(_) => {
+                if !self.page_hooks.is_empty() && params.url != "about:blank" {
+                    let url = std::mem::replace(&mut params.url, "about:blank".to_string());
+                    let (page_tx, page_rx) = oneshot_channel();
+                    let initiator = std::mem::replace(&mut tx, page_tx);
+                    self.running_hooks.push(
+                        async move {
+                            let page = match page_rx.await {
+                                Ok(Ok(page)) => page.goto(url).await.map(|_| ()).map(|()| page),
+                                Ok(Err(err)) => Err(err),
+                                Err(err) => Err(err.into()),
+                            };
+                            let _ = initiator.send(page);
+                        }
+                        .boxed(),
+                    );
+                }
                 let method = params.identifier();
                 match serde_json::to_value(params) {
                     Ok(params) => match self.conn.submit_command(method.clone(), None, params) {
@@ -335,11 +438,49 @@ impl Handler {
         }
     }
 
+    /// Runs the page hooks on the initialized page and notifies its target
+    /// once they're done
+    fn run_page_hooks(&mut self, inner: Arc<PageInner>) {
+        let hooks = self.page_hooks.clone();
+        self.running_hooks.push(
+            async move {
+                let page = Page::from(inner.clone());
+                let mut error = None;
+                for hook in hooks {
+                    if let Err(err) = (hook.0)(page.clone()).await {
+                        tracing::error!("Page hook failed: {:?}", err);
+                        error = Some(err);
+                        break;
+                    }
+                }
+                let _ = inner
+                    .sender()
+                    .clone()
+                    .send(TargetMessage::PageHooksDone(error))
+                    .await;
+            }
+            .boxed(),
+        );
+    }
+
     /// Process an incoming event read from the websocket
     fn on_event(&mut self, event: CdpEventMessage) {
         if let Some(ref session_id) = event.session_id {
             if let Some(session) = self.sessions.get(session_id.as_str()) {
                 if let Some(target) = self.targets.get_mut(session.target_id()) {
+                    // the sessions of targets attached to a page, like
+                    // out-of-process iframes, are routed to the page
+                    match &event.params {
+                        CdpEvent::TargetAttachedToTarget(ev) => {
+                            let session =
+                                Session::new(ev.session_id.clone(), target.target_id().clone());
+                            self.sessions.insert(ev.session_id.clone(), session);
+                        }
+                        CdpEvent::TargetDetachedFromTarget(ev) => {
+                            self.sessions.remove(&ev.session_id);
+                        }
+                        _ => {}
+                    }
                     return target.on_event(event);
                 }
             }
@@ -349,6 +490,7 @@ impl Handler {
             CdpEvent::TargetTargetCreated(ev) => self.on_target_created(ev),
             CdpEvent::TargetAttachedToTarget(ev) => self.on_attached_to_target(ev),
             CdpEvent::TargetTargetDestroyed(ev) => self.on_target_destroyed(ev),
+            CdpEvent::TargetTargetInfoChanged(ev) => self.on_target_info_changed(ev),
             CdpEvent::TargetDetachedFromTarget(ev) => self.on_detached_from_target(ev),
             _ => {}
         }
@@ -362,6 +504,14 @@ impl Handler {
     ///
     /// Creates a new `Target` instance and keeps track of it
     fn on_target_created(&mut self, event: EventTargetCreated) {
+        if let Some(target) = self.targets.get_mut(&event.target_info.target_id) {
+            // the target is reported again after a reconnect
+            target.set_info(event.target_info);
+            if target.is_detached() {
+                target.reattach();
+            }
+            return;
+        }
         let browser_ctx = event
             .target_info
             .browser_context_id
@@ -369,7 +519,7 @@ impl Handler {
             .map(BrowserContext::from)
             .filter(|id| self.browser_contexts.contains(id))
             .unwrap_or_else(|| self.default_browser_context.clone());
-        let target = Target::new(
+        let mut target = Target::new(
             event.target_info,
             TargetConfig::new(
                 self.config.ignore_https_errors,
@@ -377,9 +527,29 @@ impl Handler {
                 self.config.viewport.clone(),
                 self.config.request_intercept,
                 self.config.cache_enabled,
+                self.config.timezone_id.clone(),
+                self.config.locale.clone(),
+                self.config.init_scripts.clone(),
             ),
             browser_ctx,
         );
+        if self.offline_contexts.contains(target.browser_context()) {
+            target.set_offline_mode(true);
+        }
+        if let Some(credentials) = self.proxy_credentials.get(target.browser_context()) {
+            target.set_proxy_credentials(Some(credentials.clone()));
+        }
+        for route in self
+            .context_routes
+            .get(target.browser_context())
+            .into_iter()
+            .flatten()
+        {
+            target.add_context_route(route);
+        }
+        if target.is_page() && !self.page_hooks.is_empty() {
+            target.run_page_hooks();
+        }
         self.target_ids.push(target.target_id().clone());
         self.targets.insert(target.target_id().clone(), target);
     }
@@ -405,6 +575,13 @@ impl Handler {
         }
     }
 
+    /// Fired when the url, title or attachment of a target changed
+    fn on_target_info_changed(&mut self, event: EventTargetInfoChanged) {
+        if let Some(target) = self.targets.get_mut(&event.target_info.target_id) {
+            target.set_info(event.target_info);
+        }
+    }
+
     /// Fired when the target was destroyed in the browser
     fn on_target_destroyed(&mut self, event: EventTargetDestroyed) {
         if let Some(target) = self.targets.remove(&event.target_id) {
@@ -428,28 +605,236 @@ impl Handler {
             .collect::<Vec<_>>();
         for call in timed_out {
             if let Some((req, _, _)) = self.pending_commands.remove(&call) {
-                match req {
-                    PendingRequest::CreateTarget(tx) => {
-                        let _ = tx.send(Err(CdpError::Timeout));
-                    }
-                    PendingRequest::Navigate(nav) => {
-                        if let Some(nav) = self.navigations.remove(&nav) {
-                            match nav {
-                                NavigationRequest::Navigate(nav) => {
-                                    let _ = nav.tx.send(Err(CdpError::Timeout));
-                                }
-                            }
+                self.fail_request(req, || CdpError::Timeout);
+            }
+        }
+    }
+
+    /// Hands the error to the sender of the request
+    fn fail_request(&mut self, req: PendingRequest, err: fn() -> CdpError) {
+        match req {
+            PendingRequest::CreateTarget(tx) => {
+                let _ = tx.send(Err(err()));
+            }
+            PendingRequest::Navigate(nav) => {
+                if let Some(nav) = self.navigations.remove(&nav) {
+                    match nav {
+                        NavigationRequest::Navigate(nav) => {
+                            let _ = nav.tx.send(Err(err()));
                         }
                     }
-                    PendingRequest::ExternalCommand(tx) => {
-                        let _ = tx.send(Err(CdpError::Timeout));
-                    }
-                    PendingRequest::InternalCommand(_) => {}
                 }
             }
+            PendingRequest::ExternalCommand(tx) => {
+                let _ = tx.send(Err(err()));
+            }
+            PendingRequest::InternalCommand(_) => {}
         }
     }
 
+    /// Notifies the listeners about the change of the connection
+    fn set_connection_state(&mut self, state: ConnectionState) {
+        self.connection_listeners
+            .retain(|tx| tx.unbounded_send(state).is_ok());
+    }
+
+    /// Whether the handler dials the websocket again once the connection is
+    /// lost, custom transports can't be dialed again
+    fn can_reconnect(&self) -> bool {
+        self.config.reconnect.is_some() && self.conn.url().is_some()
+    }
+
+    /// The websocket connection was lost, the requests that await a response
+    /// fail and the targets wait to be attached again
+    fn on_connection_lost(&mut self) {
+        tracing::warn!("Lost the connection to {:?}", self.conn.url());
+        self.set_connection_state(ConnectionState::Disconnected);
+        for (_, (req, _, _)) in std::mem::take(&mut self.pending_commands) {
+            self.fail_request(req, || CdpError::Disconnected);
+        }
+        for (_, nav) in self.navigations.drain() {
+            match nav {
+                NavigationRequest::Navigate(nav) => {
+                    let _ = nav.tx.send(Err(CdpError::Disconnected));
+                }
+            }
+        }
+        self.sessions.clear();
+        for target in self.targets.values_mut() {
+            target.on_connection_lost();
+        }
+        self.reconnect(1);
+    }
+
+    /// Dials the websocket of the browser again after the delay of the
+    /// reconnect policy
+    fn reconnect(&mut self, attempt: u32) {
+        let delay = self
+            .config
+            .reconnect
+            .as_ref()
+            .map(|policy| policy.delay)
+            .unwrap_or_default();
+        let url = self.conn.url().unwrap_or_default().to_string();
+        self.set_connection_state(ConnectionState::Reconnecting { attempt });
+        self.reconnecting = Some(Reconnecting {
+            attempt,
+            conn: async move {
+                Delay::new(delay).await;
+                Connection::connect(url).await
+            }
+            .boxed(),
+        });
+    }
+
+    /// The connection is re-established, the targets that are reported again
+    /// are attached again
+    fn on_reconnected(&mut self, mut conn: Connection<CdpEventMessage>) {
+        tracing::info!("Reconnected to {:?}", conn.url());
+        self.rediscovering = Self::discover_targets(&mut conn);
+        self.conn = conn;
+        self.set_connection_state(ConnectionState::Reconnected);
+    }
+
+    /// Removes the targets that were closed while the connection was lost
+    fn remove_detached_targets(&mut self) {
+        self.targets.retain(|id, target| {
+            if target.is_detached() {
+                tracing::debug!("Target {:?} is gone after the reconnect", id);
+            }
+            !target.is_detached()
+        });
+    }
+
+    /// Handles the messages of the browser that don't need the connection
+    /// and returns the others
+    fn on_browser_message(&mut self, msg: HandlerMessage) -> Option<HandlerMessage> {
+        match msg {
+            HandlerMessage::GetPages(tx) => {
+                let pages: Vec<_> = self
+                    .targets
+                    .values_mut()
+                    .filter(|p| p.is_page())
+                    .filter_map(|target| target.get_or_create_page())
+                    .map(|page| Page::from(page.clone()))
+                    .collect();
+                let _ = tx.send(pages);
+            }
+            HandlerMessage::GetContextPages(ctx, tx) => {
+                let pages: Vec<_> = self
+                    .targets
+                    .values_mut()
+                    .filter(|p| p.is_page() && *p.browser_context() == ctx)
+                    .filter_map(|target| target.get_or_create_page())
+                    .map(|page| Page::from(page.clone()))
+                    .collect();
+                let _ = tx.send(pages);
+            }
+            HandlerMessage::InsertContext(ctx) => {
+                self.browser_contexts.insert(ctx);
+            }
+            HandlerMessage::DisposeContext(ctx) => {
+                self.offline_contexts.remove(&ctx);
+                self.context_routes.remove(&ctx);
+                self.proxy_credentials.remove(&ctx);
+                self.browser_contexts.remove(&ctx);
+            }
+            HandlerMessage::SetOffline(ctx, offline) => {
+                for target in self
+                    .targets
+                    .values_mut()
+                    .filter(|target| target.browser_context() == &ctx)
+                {
+                    target.set_offline_mode(offline);
+                }
+                if offline {
+                    self.offline_contexts.insert(ctx);
+                } else {
+                    self.offline_contexts.remove(&ctx);
+                }
+            }
+            HandlerMessage::SetProxyCredentials(ctx, credentials) => {
+                for target in self
+                    .targets
+                    .values_mut()
+                    .filter(|target| target.browser_context() == &ctx)
+                {
+                    target.set_proxy_credentials(Some(credentials.clone()));
+                }
+                self.proxy_credentials.insert(ctx, credentials);
+            }
+            HandlerMessage::AddContextRoute(ctx, route) => {
+                for target in self
+                    .targets
+                    .values_mut()
+                    .filter(|target| target.browser_context() == &ctx)
+                {
+                    target.add_context_route(&route);
+                }
+                self.context_routes.entry(ctx).or_default().push(route);
+            }
+            HandlerMessage::RemoveContextRoute(ctx, pattern) => {
+                for target in self
+                    .targets
+                    .values_mut()
+                    .filter(|target| target.browser_context() == &ctx)
+                {
+                    target.remove_context_route(&pattern);
+                }
+                if let Some(routes) = self.context_routes.get_mut(&ctx) {
+                    routes.retain(|route| route.pattern != pattern);
+                }
+            }
+            HandlerMessage::GetPage(target_id, tx) => {
+                let page = self
+                    .targets
+                    .get_mut(&target_id)
+                    .and_then(|target| target.get_or_create_page())
+                    .map(|page| Page::from(page.clone()));
+                let _ = tx.send(page);
+            }
+            HandlerMessage::AddEventListener(req) => {
+                self.event_listeners.add_listener(req);
+            }
+            HandlerMessage::AddPageHook(hook) => {
+                self.page_hooks.push(hook);
+            }
+            HandlerMessage::AddConnectionListener(tx) => {
+                self.connection_listeners.push(tx);
+            }
+            msg => return Some(msg),
+        }
+        None
+    }
+
+    /// Fails the commands of the browser while the connection is lost and
+    /// stops the handler if the browser is closed
+    fn poll_browser_while_reconnecting(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<()>>> {
+        while let Poll::Ready(Some(msg)) = Pin::new(&mut self.from_browser).poll_next(cx) {
+            match msg {
+                HandlerMessage::Command(cmd) => {
+                    let _ = cmd.sender.send(Err(CdpError::Disconnected));
+                }
+                HandlerMessage::CreatePage(_, tx) => {
+                    let _ = tx.send(Err(CdpError::Disconnected));
+                }
+                HandlerMessage::CloseBrowser(tx) => {
+                    self.reconnecting = None;
+                    self.set_connection_state(ConnectionState::Closed);
+                    let _ = tx.send(Err(CdpError::Disconnected));
+                    return Poll::Ready(None);
+                }
+                msg => {
+                    self.on_browser_message(msg);
+                }
+            }
+        }
+        Poll::Pending
+    }
+
     pub fn event_listeners_mut(&mut self) -> &mut EventListeners {
         &mut self.event_listeners
     }
@@ -462,6 +847,44 @@ impl Stream for Handler {
         let pin = self.get_mut();
 
         loop {
+            if let Some(reconnecting) = pin.reconnecting.as_mut() {
+                match reconnecting.conn.poll_unpin(cx) {
+                    Poll::Pending => {
+                        // fail the commands of the pages and the browser until
+                        // the connection is back, detached targets don't emit
+                        // events
+                        let now = Instant::now();
+                        for target in pin.targets.values_mut() {
+                            let _ = target.poll(cx, now);
+                        }
+                        return pin.poll_browser_while_reconnecting(cx);
+                    }
+                    Poll::Ready(Ok(conn)) => {
+                        pin.reconnecting = None;
+                        pin.on_reconnected(conn);
+                    }
+                    Poll::Ready(Err(err)) => {
+                        let attempt = reconnecting.attempt;
+                        pin.reconnecting = None;
+                        tracing::error!("Failed to reconnect: {:?}", err);
+                        let max_attempts = pin
+                            .config
+                            .reconnect
+                            .as_ref()
+                            .map(|policy| policy.max_attempts)
+                            .unwrap_or_default();
+                        if attempt < max_attempts {
+                            pin.reconnect(attempt + 1);
+                            continue;
+                        }
+                        // give up, the handler stops like without a policy
+                        pin.config.reconnect = None;
+                        pin.set_connection_state(ConnectionState::Closed);
+                        return Poll::Ready(Some(Err(err)));
+                    }
+                }
+            }
+
             let now = Instant::now();
             // temporary pinning of the browser receiver should be safe as we are pinning
             // through the already pinned self. with the receivers we can also
@@ -486,36 +909,14 @@ impl Stream for Handler {
                     HandlerMessage::CreatePage(params, tx) => {
                         pin.create_page(params, tx);
                     }
-                    HandlerMessage::GetPages(tx) => {
-                        let pages: Vec<_> = pin
-                            .targets
-                            .values_mut()
-                            .filter(|p| p.is_page())
-                            .filter_map(|target| target.get_or_create_page())
-                            .map(|page| Page::from(page.clone()))
-                            .collect();
-                        let _ = tx.send(pages);
-                    }
-                    HandlerMessage::InsertContext(ctx) => {
-                        pin.browser_contexts.insert(ctx);
-                    }
-                    HandlerMessage::DisposeContext(ctx) => {
-                        pin.browser_contexts.remove(&ctx);
-                    }
-                    HandlerMessage::GetPage(target_id, tx) => {
-                        let page = pin
-                            .targets
-                            .get_mut(&target_id)
-                            .and_then(|target| target.get_or_create_page())
-                            .map(|page| Page::from(page.clone()));
-                        let _ = tx.send(page);
-                    }
-                    HandlerMessage::AddEventListener(req) => {
-                        pin.event_listeners.add_listener(req);
+                    msg => {
+                        pin.on_browser_message(msg);
                     }
                 }
             }
 
+            while let Poll::Ready(Some(())) = pin.running_hooks.poll_next_unpin(cx) {}
+
             for n in (0..pin.target_ids.len()).rev() {
                 let target_id = pin.target_ids.swap_remove(n);
                 if let Some((id, mut target)) = pin.targets.remove_entry(&target_id) {
@@ -531,17 +932,30 @@ impl Stream for Handler {
                             TargetEvent::Command(msg) => {
                                 pin.on_target_message(&mut target, msg, now);
                             }
+                            TargetEvent::Navigate(msg, wait_until) => {
+                                pin.on_target_navigation(&mut target, msg, wait_until);
+                            }
                             TargetEvent::NavigationRequest(id, req) => {
                                 pin.submit_navigation(id, req, now);
                             }
                             TargetEvent::NavigationResult(res) => {
                                 pin.on_navigation_lifecycle_completed(res)
                             }
+                            TargetEvent::RunPageHooks(page) => pin.run_page_hooks(page),
+                            TargetEvent::GetPage(target_id, tx) => {
+                                let page = pin
+                                    .targets
+                                    .get_mut(&target_id)
+                                    .and_then(|target| target.get_or_create_page())
+                                    .map(|page| Page::from(page.clone()));
+                                let _ = tx.send(page);
+                            }
                         }
                     }
 
                     // poll the target's event listeners
                     target.event_listeners_mut().poll(cx);
+                    target.poll_worker_listeners(cx);
                     // poll the handler's event listeners
                     pin.event_listeners_mut().poll(cx);
 
@@ -551,21 +965,37 @@ impl Stream for Handler {
             }
 
             let mut done = true;
+            let mut connection_lost = false;
 
-            while let Poll::Ready(Some(ev)) = Pin::new(&mut pin.conn).poll_next(cx) {
-                match ev {
-                    Ok(Message::Response(resp)) => pin.on_response(resp),
-                    Ok(Message::Event(ev)) => {
+            loop {
+                match Pin::new(&mut pin.conn).poll_next(cx) {
+                    Poll::Ready(Some(Ok(Message::Response(resp)))) => pin.on_response(resp),
+                    Poll::Ready(Some(Ok(Message::Event(ev)))) => {
                         pin.on_event(ev);
                     }
-                    Err(err) => {
+                    Poll::Ready(Some(Err(CdpError::Ws(err)))) if pin.can_reconnect() => {
+                        tracing::error!("WS Connection error: {:?}", err);
+                        connection_lost = true;
+                        break;
+                    }
+                    Poll::Ready(Some(Err(err))) => {
                         tracing::error!("WS Connection error: {:?}", err);
                         return Poll::Ready(Some(Err(err)));
                     }
+                    Poll::Ready(None) => {
+                        connection_lost = pin.can_reconnect();
+                        break;
+                    }
+                    Poll::Pending => break,
                 }
                 done = false;
             }
 
+            if connection_lost {
+                pin.on_connection_lost();
+                continue;
+            }
+
             if pin.evict_command_timeout.poll_ready(cx) {
                 // evict all commands that timed out
                 pin.evict_timed_out_commands(now);
@@ -594,6 +1024,21 @@ pub struct HandlerConfig {
     pub request_intercept: bool,
     /// Whether to enable cache
     pub cache_enabled: bool,
+    /// The timezone every page emulates
+    pub timezone_id: Option<String>,
+    /// The locale every page emulates
+    pub locale: Option<String>,
+    /// Scripts to evaluate in every new document of every page
+    pub init_scripts: Vec<String>,
+    /// Whether and how to connect to the browser again once the connection
+    /// is lost.
+    ///
+    /// The pages are attached and initialized again and their event
+    /// subscriptions resume. Commands that await a response while the
+    /// connection is lost fail with `CdpError::Disconnected`, and state a
+    /// page set up with raw commands, like scripts added with
+    /// `Page.addScriptToEvaluateOnNewDocument`, needs to be set up again.
+    pub reconnect: Option<ReconnectPolicy>,
 }
 
 impl Default for HandlerConfig {
@@ -605,6 +1050,10 @@ impl Default for HandlerConfig {
             request_timeout: Duration::from_millis(REQUEST_TIMEOUT),
             request_intercept: false,
             cache_enabled: true,
+            timezone_id: None,
+            locale: None,
+            init_scripts: Vec::new(),
+            reconnect: None,
         }
     }
 }
@@ -677,9 +1126,166 @@ pub(crate) enum HandlerMessage {
     CreatePage(CreateTargetParams, OneshotSender<Result<Page>>),
     InsertContext(BrowserContext),
     DisposeContext(BrowserContext),
+    SetOffline(BrowserContext, bool),
+    SetProxyCredentials(BrowserContext, Credentials),
+    AddContextRoute(BrowserContext, ContextRoute),
+    RemoveContextRoute(BrowserContext, String),
     GetPages(OneshotSender<Vec<Page>>),
+    GetContextPages(BrowserContext, OneshotSender<Vec<Page>>),
     Command(CommandMessage),
     GetPage(TargetId, OneshotSender<Option<Page>>),
     AddEventListener(EventListenerRequest),
+    AddPageHook(PageHook),
+    AddConnectionListener(UnboundedSender<ConnectionState>),
     CloseBrowser(OneshotSender<Result<CloseReturns>>),
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use futures::channel::mpsc::channel;
+
+    use chromiumoxide_cdp::cdp::browser_protocol::page::EnableParams;
+
+    use crate::conn::tests::Pipe;
+
+    use super::*;
+
+    /// The `TargetInfo` of a target with the given id and type
+    pub(crate) fn target_info(id: &str, r#type: &str) -> serde_json::Value {
+        serde_json::json!({
+            "targetId": id,
+            "type": r#type,
+            "title": "",
+            "url": "https://example.com/",
+            "attached": true,
+            "canAccessOpener": false,
+        })
+    }
+
+    /// A page whose handle is dropped, so it can't send any commands
+    pub(crate) fn detached_page() -> crate::page::Page {
+        let handle =
+            page::PageHandle::new("target".to_string().into(), "session".to_string().into());
+        Arc::clone(handle.inner()).into()
+    }
+
+    #[test]
+    fn detached_pages_fail_commands() {
+        let page = detached_page();
+        assert!(futures::executor::block_on(page.execute(EnableParams::default())).is_err());
+    }
+
+    fn create_target(handler: &mut Handler, id: &str) {
+        handler.on_target_created(
+            serde_json::from_value(serde_json::json!({ "targetInfo": target_info(id, "page") }))
+                .unwrap(),
+        );
+    }
+
+    #[test]
+    fn reattaches_targets_after_reconnect() {
+        let (conn, _to_conn, _from_conn) = Pipe::connection();
+        let (_tx, rx) = channel(1);
+        let mut handler = Handler::new(conn, rx, HandlerConfig::default());
+        let (a, b): (TargetId, TargetId) = ("a".to_string().into(), "b".to_string().into());
+        for id in ["a", "b"] {
+            create_target(&mut handler, id);
+            handler.on_attached_to_target(
+                serde_json::from_value(serde_json::json!({
+                    "sessionId": format!("session-{id}"),
+                    "targetInfo": target_info(id, "page"),
+                    "waitingForDebugger": false,
+                }))
+                .unwrap(),
+            );
+        }
+        let page = handler
+            .targets
+            .get_mut(&a)
+            .unwrap()
+            .get_or_create_page()
+            .unwrap()
+            .clone();
+
+        handler.on_connection_lost();
+        assert!(handler.targets.values().all(Target::is_detached));
+
+        // the page's commands fail while disconnected
+        let (tx, mut command) = oneshot_channel();
+        page.sender()
+            .clone()
+            .try_send(TargetMessage::Command(
+                CommandMessage::new(EnableParams::default(), tx).unwrap(),
+            ))
+            .unwrap();
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        assert!(handler
+            .targets
+            .get_mut(&a)
+            .unwrap()
+            .poll(&mut cx, Instant::now())
+            .is_none());
+        assert!(matches!(
+            command.try_recv(),
+            Ok(Some(Err(CdpError::Disconnected)))
+        ));
+
+        let (conn, _to_conn, _from_conn) = Pipe::connection();
+        handler.reconnecting = None;
+        handler.on_reconnected(conn);
+        create_target(&mut handler, "a");
+        assert!(!handler.targets[&a].is_detached());
+        assert!(handler.targets[&b].is_detached());
+
+        // the target that wasn't reported again was closed meanwhile
+        let id = handler.rediscovering.unwrap();
+        handler.on_response(Response {
+            id,
+            result: Some(serde_json::json!({})),
+            error: None,
+        });
+        assert!(handler.targets.contains_key(&a));
+        assert!(!handler.targets.contains_key(&b));
+    }
+    #[test]
+    fn fails_browser_commands_while_reconnecting() {
+        let (conn, _to_conn, _from_conn) = Pipe::connection();
+        let (mut tx, rx) = channel(3);
+        let mut handler = Handler::new(conn, rx, HandlerConfig::default());
+        handler.reconnecting = Some(Reconnecting {
+            attempt: 1,
+            conn: futures::future::pending().boxed(),
+        });
+
+        let (cmd_tx, mut command) = oneshot_channel();
+        tx.try_send(HandlerMessage::Command(
+            CommandMessage::new(EnableParams::default(), cmd_tx).unwrap(),
+        ))
+        .unwrap();
+        let (page_tx, mut page) = oneshot_channel();
+        tx.try_send(HandlerMessage::CreatePage(
+            CreateTargetParams::new("about:blank"),
+            page_tx,
+        ))
+        .unwrap();
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        assert!(handler.poll_next_unpin(&mut cx).is_pending());
+        assert!(matches!(
+            command.try_recv(),
+            Ok(Some(Err(CdpError::Disconnected)))
+        ));
+        assert!(matches!(
+            page.try_recv(),
+            Ok(Some(Err(CdpError::Disconnected)))
+        ));
+
+        // closing the browser gives up reconnecting
+        let (close_tx, _close) = oneshot_channel();
+        tx.try_send(HandlerMessage::CloseBrowser(close_tx)).unwrap();
+        assert!(matches!(
+            handler.poll_next_unpin(&mut cx),
+            Poll::Ready(None)
+        ));
+        assert!(handler.reconnecting.is_none());
+    }
+}
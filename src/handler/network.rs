@@ -1,22 +1,27 @@
 use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
-    self, AuthChallengeResponse, AuthChallengeResponseResponse, ContinueRequestParams,
-    ContinueWithAuthParams, DisableParams, EventAuthRequired, EventRequestPaused, RequestPattern,
+    self, AuthChallengeResponse, AuthChallengeResponseResponse, AuthChallengeSource,
+    ContinueRequestParams, ContinueWithAuthParams, DisableParams, EventAuthRequired,
+    EventRequestPaused, FailRequestParams, HeaderEntry, RequestPattern,
 };
+pub use chromiumoxide_cdp::cdp::browser_protocol::network::ResourceType;
 use chromiumoxide_cdp::cdp::browser_protocol::network::{
-    EmulateNetworkConditionsParams, EventLoadingFailed, EventLoadingFinished,
-    EventRequestServedFromCache, EventRequestWillBeSent, EventResponseReceived, Headers,
-    InterceptionId, RequestId, Response, SetCacheDisabledParams, SetExtraHttpHeadersParams,
+    ConnectionType, EmulateNetworkConditionsParams, ErrorReason, EventLoadingFailed,
+    EventLoadingFinished, EventRequestServedFromCache, EventRequestWillBeSent,
+    EventResponseReceived, Headers, InterceptionId, RequestId, Response, SetCacheDisabledParams,
+    SetExtraHttpHeadersParams,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::{
-    network::EnableParams, security::SetIgnoreCertificateErrorsParams,
+    network::EnableParams, page::FrameId, security::SetIgnoreCertificateErrorsParams,
+    target::SessionId,
 };
 use chromiumoxide_types::{Command, Method, MethodId};
 
 use crate::auth::Credentials;
 use crate::cmd::CommandChain;
 use crate::handler::http::HttpRequest;
+use crate::route::{InterceptedRequest, NavigationPolicy, Route, RouteAction};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 #[derive(Debug)]
 pub struct NetworkManager {
@@ -30,9 +35,25 @@ pub struct NetworkManager {
     user_cache_disabled: bool,
     attempted_authentications: HashSet<RequestId>,
     credentials: Option<Credentials>,
+    /// The credentials for the proxy of the page's browser context
+    proxy_credentials: Option<Credentials>,
     user_request_interception_enabled: bool,
     protocol_request_interception_enabled: bool,
+    /// The routes of the page, the most recently added one comes last
+    routes: Vec<Route>,
+    /// The routes of the page's browser context, which come after the
+    /// routes of the page
+    context_routes: Vec<Route>,
+    /// The urls the main frame may navigate to
+    navigation_policy: Option<NavigationPolicy>,
     offline: bool,
+    conditions: NetworkConditions,
+    budget: Option<BudgetState>,
+    resource_stats: ResourceStats,
+    /// Hosts whose requests are sent to another host or `host:port`
+    host_mapping: HashMap<String, String>,
+    /// Headers added to the requests to an origin
+    origin_headers: HashMap<String, HashMap<String, String>>,
     request_timeout: Duration,
 }
 
@@ -48,9 +69,18 @@ impl NetworkManager {
             user_cache_disabled: false,
             attempted_authentications: Default::default(),
             credentials: None,
+            proxy_credentials: None,
             user_request_interception_enabled: false,
             protocol_request_interception_enabled: false,
+            routes: Vec::new(),
+            context_routes: Vec::new(),
+            navigation_policy: None,
             offline: false,
+            conditions: NetworkConditions::NO_THROTTLING,
+            budget: None,
+            resource_stats: Default::default(),
+            host_mapping: Default::default(),
+            origin_headers: Default::default(),
             request_timeout,
         }
     }
@@ -69,11 +99,41 @@ impl NetworkManager {
         CommandChain::new(cmds, self.request_timeout)
     }
 
+    /// The requests that enable the network domain in the session of an
+    /// out-of-process iframe and apply the current state to it
+    pub fn frame_session_commands(&self) -> Vec<(MethodId, serde_json::Value)> {
+        let mut cmds = vec![command(EnableParams::default())];
+        if self.ignore_httpserrors {
+            cmds.push(command(SetIgnoreCertificateErrorsParams::new(true)));
+        }
+        cmds.extend(self.state_commands());
+        cmds
+    }
+
+    /// The requests that apply the headers, the cache, the interception and
+    /// the network conditions to a new session
+    fn state_commands(&self) -> Vec<(MethodId, serde_json::Value)> {
+        let mut cmds = Vec::new();
+        if !self.extra_headers.is_empty() {
+            cmds.push(command(self.extra_headers_params()));
+        }
+        cmds.push(command(self.cache_disabled_params()));
+        if self.protocol_request_interception_enabled {
+            cmds.push(command(Self::fetch_enable_params()));
+        }
+        cmds.push(command(self.network_conditions_params()));
+        cmds
+    }
+
     fn push_cdp_request<T: Command>(&mut self, cmd: T) {
-        let method = cmd.identifier();
-        let params = serde_json::to_value(cmd).expect("Command should not panic");
         self.queued_events
-            .push_back(NetworkEvent::SendCdpRequest((method, params)));
+            .push_back(NetworkEvent::SendCdpRequest(command(cmd)));
+    }
+
+    /// Answers an event of the session, `None` for the page's session
+    fn reply<T: Command>(&mut self, session_id: Option<&SessionId>, cmd: T) {
+        self.queued_events
+            .push_back(NetworkEvent::Reply(session_id.cloned(), command(cmd)));
     }
 
     /// The next event to handle
@@ -87,8 +147,12 @@ impl NetworkManager {
 
     pub fn set_extra_headers(&mut self, headers: HashMap<String, String>) {
         self.extra_headers = headers;
+        self.push_cdp_request(self.extra_headers_params());
+    }
+
+    fn extra_headers_params(&self) -> SetExtraHttpHeadersParams {
         let headers = serde_json::to_value(self.extra_headers.clone()).unwrap();
-        self.push_cdp_request(SetExtraHttpHeadersParams::new(Headers::new(headers)));
+        SetExtraHttpHeadersParams::new(Headers::new(headers))
     }
 
     pub fn set_request_interception(&mut self, enabled: bool) {
@@ -96,15 +160,52 @@ impl NetworkManager {
         self.update_protocol_request_interception();
     }
 
+    /// Adds a route that handles all matching requests, taking precedence
+    /// over routes that were added earlier
+    pub fn add_route(&mut self, route: Route) {
+        self.routes.push(route);
+        self.update_protocol_request_interception();
+    }
+
+    /// Removes all routes with the pattern
+    pub fn remove_route(&mut self, pattern: &str) {
+        self.routes.retain(|route| route.pattern != pattern);
+        self.update_protocol_request_interception();
+    }
+
+    /// Adds a route of the page's browser context, the routes of the page
+    /// take precedence
+    pub fn add_context_route(&mut self, route: Route) {
+        self.context_routes.push(route);
+        self.update_protocol_request_interception();
+    }
+
+    /// Removes all routes of the page's browser context with the pattern
+    pub fn remove_context_route(&mut self, pattern: &str) {
+        self.context_routes.retain(|route| route.pattern != pattern);
+        self.update_protocol_request_interception();
+    }
+
+    /// Restricts the urls the main frame may navigate to, an empty policy
+    /// allows all urls
+    pub fn set_navigation_policy(&mut self, policy: NavigationPolicy) {
+        self.navigation_policy = Some(policy).filter(|policy| !policy.is_empty());
+        self.update_protocol_request_interception();
+    }
+
     pub fn set_cache_enabled(&mut self, enabled: bool) {
         self.user_cache_disabled = !enabled;
         self.update_protocol_cache_disabled();
     }
 
     pub fn update_protocol_cache_disabled(&mut self) {
-        self.push_cdp_request(SetCacheDisabledParams::new(
+        self.push_cdp_request(self.cache_disabled_params());
+    }
+
+    fn cache_disabled_params(&self) -> SetCacheDisabledParams {
+        SetCacheDisabledParams::new(
             self.user_cache_disabled || self.protocol_request_interception_enabled,
-        ));
+        )
     }
 
     pub fn authenticate(&mut self, credentials: Credentials) {
@@ -112,28 +213,175 @@ impl NetworkManager {
         self.update_protocol_request_interception()
     }
 
+    /// Answers the authentication challenges of proxies with the
+    /// credentials, which take precedence over those of `authenticate`
+    pub fn set_proxy_credentials(&mut self, credentials: Option<Credentials>) {
+        self.proxy_credentials = credentials;
+        self.update_protocol_request_interception()
+    }
+
     fn update_protocol_request_interception(&mut self) {
-        let enabled = self.user_request_interception_enabled || self.credentials.is_some();
+        let enabled = self.user_request_interception_enabled
+            || self.credentials.is_some()
+            || self.proxy_credentials.is_some()
+            || !self.routes.is_empty()
+            || !self.context_routes.is_empty()
+            || self.navigation_policy.is_some()
+            || self.budget.is_some()
+            || !self.host_mapping.is_empty()
+            || !self.origin_headers.is_empty();
         if enabled == self.protocol_request_interception_enabled {
             return;
         }
+        self.protocol_request_interception_enabled = enabled;
         self.update_protocol_cache_disabled();
         if enabled {
-            self.push_cdp_request(
-                fetch::EnableParams::builder()
-                    .handle_auth_requests(true)
-                    .pattern(RequestPattern::builder().url_pattern("*").build())
-                    .build(),
-            )
+            self.push_cdp_request(Self::fetch_enable_params())
         } else {
             self.push_cdp_request(DisableParams::default())
         }
     }
 
-    pub fn on_fetch_request_paused(&mut self, event: &EventRequestPaused) {
-        if !self.user_request_interception_enabled && self.protocol_request_interception_enabled {
-            self.push_cdp_request(ContinueRequestParams::new(event.request_id.clone()))
+    fn fetch_enable_params() -> fetch::EnableParams {
+        fetch::EnableParams::builder()
+            .handle_auth_requests(true)
+            .pattern(RequestPattern::builder().url_pattern("*").build())
+            .build()
+    }
+
+    /// Forgets the requests of the lost session and queues in the requests
+    /// that restore the state of the network domain in the new session
+    pub fn resume_session(&mut self) {
+        self.queued_events.clear();
+        self.requests.clear();
+        self.requests_will_be_sent.clear();
+        self.request_id_to_interception_id.clear();
+        self.attempted_authentications.clear();
+        for cmd in self.state_commands() {
+            self.queued_events
+                .push_back(NetworkEvent::SendCdpRequest(cmd));
+        }
+    }
+
+    /// Sends the requests for each host to its target instead, replacing the
+    /// previous mapping
+    pub fn set_host_mapping(&mut self, mapping: HashMap<String, String>) {
+        self.host_mapping = mapping;
+        self.update_protocol_request_interception();
+    }
+
+    /// Adds the headers to all requests to the origin, replacing the headers
+    /// previously set for it. Empty headers remove the origin's headers
+    pub fn set_origin_headers(&mut self, origin: String, headers: HashMap<String, String>) {
+        if headers.is_empty() {
+            self.origin_headers.remove(&origin);
+        } else {
+            self.origin_headers.insert(origin, headers);
+        }
+        self.update_protocol_request_interception();
+    }
+
+    /// Applies the host mapping and the headers of the request's origin,
+    /// unless a route already replaced the url or the headers
+    fn rewrite_request(&self, event: &EventRequestPaused, params: &mut ContinueRequestParams) {
+        if params.url.is_none() {
+            params.url = map_host(&event.request.url, &self.host_mapping);
+        }
+        if params.headers.is_none() {
+            params.headers = add_origin_headers(event, &self.origin_headers);
+        }
+    }
+
+    /// Limits the requests of the page from now on, replacing the previous
+    /// budget and its usage
+    pub fn set_request_budget(&mut self, budget: Option<RequestBudget>) {
+        self.budget = budget.map(|budget| BudgetState::new(budget, Instant::now()));
+        self.update_protocol_request_interception();
+    }
+
+    /// Checks whether the time budget ran out
+    pub fn check_request_budget(&mut self, now: Instant) {
+        if let Some(exceeded) = self.budget.as_mut().and_then(|b| b.check_duration(now)) {
+            self.on_budget_exceeded(exceeded);
+        }
+    }
+
+    fn on_budget_exceeded(&mut self, exceeded: BudgetExceeded) {
+        tracing::debug!("{}", exceeded);
+        if self
+            .budget
+            .as_ref()
+            .is_some_and(|budget| budget.budget.fail_navigation)
+        {
+            self.queued_events
+                .push_back(NetworkEvent::BudgetExceeded(exceeded));
         }
+    }
+
+    /// Handles a paused request of the frame or the page's main frame
+    /// `main_frame`, if known. The request is answered within the session
+    /// `session_id` of an out-of-process iframe, `None` for the page's session
+    pub fn on_fetch_request_paused(
+        &mut self,
+        event: &EventRequestPaused,
+        main_frame: Option<&FrameId>,
+        session_id: Option<&SessionId>,
+    ) {
+        let is_navigation = event.resource_type == ResourceType::Document
+            && main_frame.is_none_or(|frame| *frame == event.frame_id);
+        if is_navigation
+            && self
+                .navigation_policy
+                .as_ref()
+                .is_some_and(|policy| !policy.allows(&event.request.url))
+        {
+            tracing::debug!("Navigation to {} denied by policy", event.request.url);
+            self.reply(
+                session_id,
+                FailRequestParams::new(event.request_id.clone(), ErrorReason::BlockedByClient),
+            );
+            self.track_interception(event);
+            return;
+        }
+        let (allowed, exceeded) = match self.budget.as_mut() {
+            Some(budget) => budget.on_request(Instant::now()),
+            None => (true, None),
+        };
+        if let Some(exceeded) = exceeded {
+            self.on_budget_exceeded(exceeded);
+        }
+        if !allowed {
+            self.reply(
+                session_id,
+                FailRequestParams::new(event.request_id.clone(), ErrorReason::BlockedByClient),
+            )
+        } else if let Some(route) = self
+            .routes
+            .iter_mut()
+            .rev()
+            .chain(self.context_routes.iter_mut().rev())
+            .find(|route| route.matches(&event.request.url))
+        {
+            match (route.handler)(InterceptedRequest::new(event.clone())) {
+                RouteAction::Continue(mut params) => {
+                    self.rewrite_request(event, &mut params);
+                    self.reply(session_id, params)
+                }
+                RouteAction::Fulfill(params) => self.reply(session_id, params),
+                RouteAction::Abort(params) => self.reply(session_id, params),
+            }
+        } else if !self.user_request_interception_enabled
+            && self.protocol_request_interception_enabled
+        {
+            let mut params = ContinueRequestParams::new(event.request_id.clone());
+            self.rewrite_request(event, &mut params);
+            self.reply(session_id, params)
+        }
+        self.track_interception(event);
+    }
+
+    /// Associates the paused request with its network request
+    fn track_interception(&mut self, event: &EventRequestPaused) {
         if let Some(network_id) = event.network_id.as_ref() {
             if let Some(request_will_be_sent) =
                 self.requests_will_be_sent.remove(network_id.as_ref())
@@ -146,13 +394,26 @@ impl NetworkManager {
         }
     }
 
-    pub fn on_fetch_auth_required(&mut self, event: &EventAuthRequired) {
+    /// Answers the authentication challenge within the session it was
+    /// received in, `None` for the page's session
+    pub fn on_fetch_auth_required(
+        &mut self,
+        event: &EventAuthRequired,
+        session_id: Option<&SessionId>,
+    ) {
+        let credentials = match event.auth_challenge.source {
+            Some(AuthChallengeSource::Proxy) => self
+                .proxy_credentials
+                .as_ref()
+                .or(self.credentials.as_ref()),
+            _ => self.credentials.as_ref(),
+        };
         let response = if self
             .attempted_authentications
             .contains(event.request_id.as_ref())
         {
             AuthChallengeResponseResponse::CancelAuth
-        } else if self.credentials.is_some() {
+        } else if credentials.is_some() {
             self.attempted_authentications
                 .insert(event.request_id.clone().into());
             AuthChallengeResponseResponse::ProvideCredentials
@@ -161,11 +422,14 @@ impl NetworkManager {
         };
 
         let mut auth = AuthChallengeResponse::new(response);
-        if let Some(creds) = self.credentials.clone() {
+        if let Some(creds) = credentials.cloned() {
             auth.username = Some(creds.username);
             auth.password = Some(creds.password);
         }
-        self.push_cdp_request(ContinueWithAuthParams::new(event.request_id.clone(), auth));
+        self.reply(
+            session_id,
+            ContinueWithAuthParams::new(event.request_id.clone(), auth),
+        );
     }
 
     pub fn set_offline_mode(&mut self, value: bool) {
@@ -173,19 +437,46 @@ impl NetworkManager {
             return;
         }
         self.offline = value;
-        self.push_cdp_request(
-            EmulateNetworkConditionsParams::builder()
-                .offline(self.offline)
-                .latency(0)
-                .download_throughput(-1.)
-                .upload_throughput(-1.)
-                .build()
-                .unwrap(),
-        );
+        self.update_network_conditions();
+    }
+
+    /// Emulates the network conditions until they are replaced, going
+    /// offline with `set_offline_mode` keeps them
+    pub fn set_network_conditions(&mut self, conditions: NetworkConditions) {
+        self.conditions = conditions;
+        self.update_network_conditions();
+    }
+
+    fn update_network_conditions(&mut self) {
+        self.push_cdp_request(self.network_conditions_params());
+    }
+
+    fn network_conditions_params(&self) -> EmulateNetworkConditionsParams {
+        let NetworkConditions {
+            offline,
+            latency,
+            download_throughput,
+            upload_throughput,
+            connection_type,
+        } = self.conditions.clone();
+        EmulateNetworkConditionsParams {
+            offline: self.offline || offline,
+            latency,
+            download_throughput,
+            upload_throughput,
+            connection_type,
+        }
+    }
+
+    /// How many requests the page issued per type of resource
+    pub fn resource_stats(&self) -> &ResourceStats {
+        &self.resource_stats
     }
 
     /// Request interception doesn't happen for data URLs with Network Service.
     pub fn on_request_will_be_sent(&mut self, event: &EventRequestWillBeSent) {
+        self.resource_stats
+            .record(event.r#type.clone().unwrap_or(ResourceType::Other));
         if self.protocol_request_interception_enabled && !event.request.url.starts_with("data:") {
             if let Some(interception_id) = self
                 .request_id_to_interception_id
@@ -217,6 +508,13 @@ impl NetworkManager {
     }
 
     pub fn on_network_loading_finished(&mut self, event: &EventLoadingFinished) {
+        if let Some(exceeded) = self
+            .budget
+            .as_mut()
+            .and_then(|budget| budget.on_bytes(event.encoded_data_length as u64))
+        {
+            self.on_budget_exceeded(exceeded);
+        }
         if let Some(request) = self.requests.remove(event.request_id.as_ref()) {
             if let Some(interception_id) = request.interception_id.as_ref() {
                 self.attempted_authentications
@@ -274,11 +572,605 @@ impl NetworkManager {
     }
 }
 
+/// Network conditions to emulate, see `Page::emulate_network_conditions`.
+///
+/// The presets match the ones of the Chrome DevTools.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NetworkConditions {
+    /// Whether to emulate a lost connection
+    pub offline: bool,
+    /// The minimum latency of a request in milliseconds
+    pub latency: f64,
+    /// The maximum download throughput in bytes per second, `-1` disables
+    /// download throttling
+    pub download_throughput: f64,
+    /// The maximum upload throughput in bytes per second, `-1` disables
+    /// upload throttling
+    pub upload_throughput: f64,
+    /// The connection type reported by `navigator.connection`
+    pub connection_type: Option<ConnectionType>,
+}
+
+impl NetworkConditions {
+    /// No throttling at all.
+    pub const NO_THROTTLING: Self = Self {
+        offline: false,
+        latency: 0.,
+        download_throughput: -1.,
+        upload_throughput: -1.,
+        connection_type: None,
+    };
+
+    /// A slow 3G connection with 400kb/s throughput and 2s latency.
+    pub const SLOW_3G: Self = Self {
+        offline: false,
+        latency: 2000.,
+        download_throughput: 50_000.,
+        upload_throughput: 50_000.,
+        connection_type: Some(ConnectionType::Cellular3g),
+    };
+
+    /// A fast 3G connection with 1.44mb/s download throughput and 562.5ms
+    /// latency.
+    pub const FAST_3G: Self = Self {
+        offline: false,
+        latency: 562.5,
+        download_throughput: 180_000.,
+        upload_throughput: 84_375.,
+        connection_type: Some(ConnectionType::Cellular3g),
+    };
+
+    /// No connection at all.
+    pub const OFFLINE: Self = Self {
+        offline: true,
+        latency: 0.,
+        download_throughput: -1.,
+        upload_throughput: -1.,
+        connection_type: Some(ConnectionType::None),
+    };
+}
+
+impl Default for NetworkConditions {
+    fn default() -> Self {
+        Self::NO_THROTTLING
+    }
+}
+
+/// Replaces the host of the url with its target in the mapping, which is
+/// either a host or `host:port`.
+fn map_host(url: &str, mapping: &HashMap<String, String>) -> Option<String> {
+    if mapping.is_empty() {
+        return None;
+    }
+    let mut url = url::Url::parse(url).ok()?;
+    let target = url::Url::parse(&format!("http://{}", mapping.get(url.host_str()?)?)).ok()?;
+    url.set_host(target.host_str()).ok()?;
+    if target.port().is_some() {
+        url.set_port(target.port()).ok()?;
+    }
+    Some(url.into())
+}
+
+/// The headers of the request together with the headers added for its
+/// origin, which replace request headers of the same name.
+fn add_origin_headers(
+    event: &EventRequestPaused,
+    origin_headers: &HashMap<String, HashMap<String, String>>,
+) -> Option<Vec<HeaderEntry>> {
+    if origin_headers.is_empty() {
+        return None;
+    }
+    let origin = url::Url::parse(&event.request.url)
+        .ok()?
+        .origin()
+        .ascii_serialization();
+    let added = origin_headers.get(&origin)?;
+    let mut headers: Vec<_> = event
+        .request
+        .headers
+        .inner()
+        .as_object()
+        .into_iter()
+        .flatten()
+        .filter(|(name, _)| !added.keys().any(|n| n.eq_ignore_ascii_case(name)))
+        .filter_map(|(name, value)| Some(HeaderEntry::new(name.clone(), value.as_str()?)))
+        .collect();
+    headers.extend(
+        added
+            .iter()
+            .map(|(name, value)| HeaderEntry::new(name.clone(), value.clone())),
+    );
+    Some(headers)
+}
+
+/// The number of requests a page issued per type of resource, see
+/// `Page::resource_stats`.
+///
+/// Every redirect counts as a request of its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceStats {
+    counts: HashMap<ResourceType, usize>,
+}
+
+impl ResourceStats {
+    /// The number of requests for resources of the type.
+    pub fn count(&self, resource_type: &ResourceType) -> usize {
+        self.counts.get(resource_type).copied().unwrap_or_default()
+    }
+
+    /// The number of requests for all resources.
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// The number of requests for each type of resource that was requested.
+    pub fn iter(&self) -> impl Iterator<Item = (&ResourceType, usize)> + '_ {
+        self.counts.iter().map(|(ty, count)| (ty, *count))
+    }
+
+    fn record(&mut self, resource_type: ResourceType) {
+        *self.counts.entry(resource_type).or_default() += 1;
+    }
+}
+
+/// Limits on the requests of a page, see `Page::set_request_budget`.
+///
+/// Once a limit is exceeded, all further requests of the page are aborted.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestBudget {
+    /// The maximum number of requests
+    pub max_requests: Option<usize>,
+    /// The maximum number of bytes received over the network, including
+    /// headers
+    pub max_bytes: Option<u64>,
+    /// How long the page may issue requests
+    pub max_duration: Option<Duration>,
+    /// Whether to fail an ongoing navigation with
+    /// `CdpError::BudgetExceeded` once a limit is exceeded
+    pub fail_navigation: bool,
+}
+
+/// The limit of a `RequestBudget` that was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum BudgetExceeded {
+    #[error("Exceeded the budget of {0} requests.")]
+    Requests(usize),
+    #[error("Exceeded the budget of {0} bytes.")]
+    Bytes(u64),
+    #[error("Exceeded the time budget of {0:?}.")]
+    Duration(Duration),
+}
+
+/// A budget and how much of it is used.
+#[derive(Debug)]
+struct BudgetState {
+    budget: RequestBudget,
+    started: Instant,
+    requests: usize,
+    bytes: u64,
+    exceeded: Option<BudgetExceeded>,
+}
+
+impl BudgetState {
+    fn new(budget: RequestBudget, started: Instant) -> Self {
+        Self {
+            budget,
+            started,
+            requests: 0,
+            bytes: 0,
+            exceeded: None,
+        }
+    }
+
+    /// Marks the budget as exceeded, returns the limit if it wasn't before.
+    fn exceed(&mut self, exceeded: BudgetExceeded) -> Option<BudgetExceeded> {
+        if self.exceeded.is_some() {
+            return None;
+        }
+        self.exceeded = Some(exceeded);
+        Some(exceeded)
+    }
+
+    fn check_duration(&mut self, now: Instant) -> Option<BudgetExceeded> {
+        let max = self.budget.max_duration?;
+        if now.saturating_duration_since(self.started) > max {
+            self.exceed(BudgetExceeded::Duration(max))
+        } else {
+            None
+        }
+    }
+
+    /// Counts a new request, returns whether it may be sent and the limit
+    /// that was exceeded by it.
+    fn on_request(&mut self, now: Instant) -> (bool, Option<BudgetExceeded>) {
+        let mut exceeded = self.check_duration(now);
+        if self.exceeded.is_none() {
+            self.requests += 1;
+            if let Some(max) = self.budget.max_requests {
+                if self.requests > max {
+                    exceeded = self.exceed(BudgetExceeded::Requests(max));
+                }
+            }
+        }
+        (self.exceeded.is_none(), exceeded)
+    }
+
+    fn on_bytes(&mut self, bytes: u64) -> Option<BudgetExceeded> {
+        self.bytes += bytes;
+        let max = self.budget.max_bytes?;
+        if self.bytes > max {
+            self.exceed(BudgetExceeded::Bytes(max))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug)]
 pub enum NetworkEvent {
+    /// A request that changes the state of the network domain, sent within
+    /// the page's session and the sessions of its out-of-process iframes
     SendCdpRequest((MethodId, serde_json::Value)),
+    /// A request that answers an event within the session of the event,
+    /// `None` for the page's session
+    Reply(Option<SessionId>, (MethodId, serde_json::Value)),
     Request(RequestId),
     Response(RequestId),
     RequestFailed(HttpRequest),
     RequestFinished(HttpRequest),
+    /// A limit of the request budget was exceeded and the navigation should
+    /// fail
+    BudgetExceeded(BudgetExceeded),
+}
+
+fn command<T: Command>(cmd: T) -> (MethodId, serde_json::Value) {
+    (cmd.identifier(), serde_json::to_value(cmd).unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request_paused(url: &str, frame: &str, resource_type: &str) -> EventRequestPaused {
+        serde_json::from_value(serde_json::json!({
+            "requestId": "1",
+            "request": {
+                "url": url,
+                "method": "GET",
+                "headers": {},
+                "initialPriority": "High",
+                "referrerPolicy": "no-referrer"
+            },
+            "frameId": frame,
+            "resourceType": resource_type
+        }))
+        .unwrap()
+    }
+
+    fn next_request(manager: &mut NetworkManager) -> (MethodId, serde_json::Value) {
+        match manager.poll() {
+            Some(NetworkEvent::SendCdpRequest(req) | NetworkEvent::Reply(None, req)) => req,
+            ev => panic!("unexpected event {ev:?}"),
+        }
+    }
+
+    #[test]
+    fn offline_mode_keeps_throttling() {
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.set_network_conditions(NetworkConditions::SLOW_3G);
+        let (method, params) = next_request(&mut manager);
+        assert_eq!(method, EmulateNetworkConditionsParams::IDENTIFIER);
+        assert_eq!(params["offline"], false);
+        assert_eq!(params["latency"], 2000.);
+
+        manager.set_offline_mode(true);
+        let (_, params) = next_request(&mut manager);
+        assert_eq!(params["offline"], true);
+        assert_eq!(params["downloadThroughput"], 50_000.);
+        assert!(manager.poll().is_none());
+    }
+
+    #[test]
+    fn toggles_offline_mode() {
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.set_offline_mode(false);
+        assert!(manager.poll().is_none());
+
+        manager.set_offline_mode(true);
+        assert_eq!(next_request(&mut manager).1["offline"], true);
+        manager.set_offline_mode(true);
+        assert!(manager.poll().is_none());
+
+        manager.set_offline_mode(false);
+        let (_, params) = next_request(&mut manager);
+        assert_eq!(params["offline"], false);
+        assert_eq!(params["latency"], 0.);
+    }
+
+    #[test]
+    fn page_routes_precede_context_routes() {
+        use chromiumoxide_cdp::cdp::browser_protocol::fetch::FulfillRequestParams;
+
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.add_context_route(Route::new(
+            "*",
+            Box::new(|req| req.abort(ErrorReason::BlockedByClient)),
+        ));
+        manager.add_route(Route::new(
+            "*/api/*",
+            Box::new(|req| req.fulfill(Default::default())),
+        ));
+        while manager.poll().is_some() {}
+
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.com/api/user", "frame", "Fetch"),
+            None,
+            None,
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            FulfillRequestParams::IDENTIFIER
+        );
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.com/ad.js", "frame", "Fetch"),
+            None,
+            None,
+        );
+        assert_eq!(next_request(&mut manager).0, FailRequestParams::IDENTIFIER);
+
+        manager.remove_context_route("*");
+        while manager.poll().is_some() {}
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.com/ad.js", "frame", "Fetch"),
+            None,
+            None,
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            ContinueRequestParams::IDENTIFIER
+        );
+    }
+
+    #[test]
+    fn denies_navigations() {
+        let main_frame = FrameId::from("main".to_string());
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.set_navigation_policy(NavigationPolicy::allow(["https://example.com/*"]));
+        while manager.poll().is_some() {}
+
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.com/docs", "main", "Document"),
+            Some(&main_frame),
+            None,
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            ContinueRequestParams::IDENTIFIER
+        );
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.net/", "main", "Document"),
+            Some(&main_frame),
+            None,
+        );
+        assert_eq!(next_request(&mut manager).0, FailRequestParams::IDENTIFIER);
+        // iframes and subresources aren't navigations of the main frame
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.net/", "child", "Document"),
+            Some(&main_frame),
+            None,
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            ContinueRequestParams::IDENTIFIER
+        );
+        manager.on_fetch_request_paused(
+            &request_paused("https://example.net/app.js", "main", "Script"),
+            Some(&main_frame),
+            None,
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            ContinueRequestParams::IDENTIFIER
+        );
+    }
+
+    #[test]
+    fn maps_hosts() {
+        let mapping: HashMap<_, _> = [
+            ("example.com".to_string(), "staging.example.com".to_string()),
+            ("api.example.com".to_string(), "localhost:8080".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            map_host("https://example.com/a?b=c", &mapping).as_deref(),
+            Some("https://staging.example.com/a?b=c")
+        );
+        assert_eq!(
+            map_host("https://api.example.com/user", &mapping).as_deref(),
+            Some("https://localhost:8080/user")
+        );
+        assert_eq!(map_host("https://other.com/", &mapping), None);
+        assert_eq!(map_host("data:text/plain,hi", &mapping), None);
+    }
+
+    #[test]
+    fn adds_origin_headers() {
+        let mut event = request_paused("https://api.example.com/user", "frame", "Fetch");
+        event.request.headers = Headers::new(serde_json::json!({
+            "Accept": "*/*",
+            "authorization": "old"
+        }));
+        let mut origin_headers = HashMap::new();
+        assert_eq!(add_origin_headers(&event, &origin_headers), None);
+
+        origin_headers.insert(
+            "https://example.com".to_string(),
+            [("X-Token".to_string(), "1".to_string())].into(),
+        );
+        assert_eq!(add_origin_headers(&event, &origin_headers), None);
+
+        origin_headers.insert(
+            "https://api.example.com".to_string(),
+            [("Authorization".to_string(), "Bearer token".to_string())].into(),
+        );
+        let mut headers = add_origin_headers(&event, &origin_headers).unwrap();
+        headers.sort_by(|a, b| a.name.cmp(&b.name));
+        assert_eq!(
+            headers,
+            vec![
+                HeaderEntry::new("Accept", "*/*"),
+                HeaderEntry::new("Authorization", "Bearer token"),
+            ]
+        );
+    }
+
+    #[test]
+    fn counts_resource_types() {
+        let mut stats = ResourceStats::default();
+        stats.record(ResourceType::Document);
+        stats.record(ResourceType::Image);
+        stats.record(ResourceType::Image);
+        assert_eq!(stats.count(&ResourceType::Image), 2);
+        assert_eq!(stats.count(&ResourceType::Font), 0);
+        assert_eq!(stats.total(), 3);
+        assert!(stats
+            .iter()
+            .all(|(ty, _)| ty.is_document() || ty.is_image()));
+    }
+
+    #[test]
+    fn enforces_request_budget() {
+        let now = Instant::now();
+        let mut state = BudgetState::new(
+            RequestBudget {
+                max_requests: Some(2),
+                max_bytes: Some(100),
+                ..Default::default()
+            },
+            now,
+        );
+        assert_eq!(state.on_request(now), (true, None));
+        assert_eq!(state.on_bytes(100), None);
+        assert_eq!(state.on_request(now), (true, None));
+        assert_eq!(
+            state.on_request(now),
+            (false, Some(BudgetExceeded::Requests(2)))
+        );
+        assert_eq!(state.on_request(now), (false, None));
+        assert_eq!(state.on_bytes(1), None);
+
+        let mut state = BudgetState::new(
+            RequestBudget {
+                max_bytes: Some(100),
+                ..Default::default()
+            },
+            now,
+        );
+        assert_eq!(state.on_bytes(101), Some(BudgetExceeded::Bytes(100)));
+        assert_eq!(state.on_request(now), (false, None));
+    }
+
+    #[test]
+    fn enforces_time_budget() {
+        let now = Instant::now();
+        let max = Duration::from_secs(1);
+        let mut state = BudgetState::new(
+            RequestBudget {
+                max_duration: Some(max),
+                ..Default::default()
+            },
+            now,
+        );
+        assert_eq!(state.check_duration(now + max), None);
+        assert_eq!(
+            state.on_request(now + 2 * max),
+            (false, Some(BudgetExceeded::Duration(max)))
+        );
+        assert_eq!(state.check_duration(now + 3 * max), None);
+    }
+
+    #[test]
+    fn answers_proxy_challenges() {
+        let auth_required = |source: &str| -> EventAuthRequired {
+            let paused = request_paused("https://example.com/", "main", "Document");
+            EventAuthRequired {
+                request_id: paused.request_id,
+                request: paused.request,
+                frame_id: paused.frame_id,
+                resource_type: paused.resource_type,
+                auth_challenge: serde_json::from_value(serde_json::json!({
+                    "source": source,
+                    "origin": "http://proxy.example.com:8080",
+                    "scheme": "basic",
+                    "realm": ""
+                }))
+                .unwrap(),
+            }
+        };
+        let answer = |manager: &mut NetworkManager, source: &str| {
+            manager.attempted_authentications.clear();
+            manager.on_fetch_auth_required(&auth_required(source), None);
+            let (method, params) = next_request(manager);
+            assert_eq!(method, ContinueWithAuthParams::IDENTIFIER);
+            params["authChallengeResponse"].clone()
+        };
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.set_proxy_credentials(Some(Credentials {
+            username: "proxy".to_string(),
+            password: "secret".to_string(),
+        }));
+        while manager.poll().is_some() {}
+
+        let response = answer(&mut manager, "Proxy");
+        assert_eq!(response["response"], "ProvideCredentials");
+        assert_eq!(response["username"], "proxy");
+        // the proxy's credentials aren't handed out to servers
+        let response = answer(&mut manager, "Server");
+        assert_eq!(response["response"], "Default");
+        assert!(response.get("username").is_none());
+    }
+
+    #[test]
+    fn budget_enables_interception() {
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.set_request_budget(Some(RequestBudget::default()));
+        assert_eq!(
+            next_request(&mut manager).0,
+            SetCacheDisabledParams::IDENTIFIER
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            fetch::EnableParams::IDENTIFIER
+        );
+        manager.set_request_budget(None);
+        assert_eq!(
+            next_request(&mut manager).0,
+            SetCacheDisabledParams::IDENTIFIER
+        );
+        assert_eq!(next_request(&mut manager).0, DisableParams::IDENTIFIER);
+    }
+
+    #[test]
+    fn resumes_session() {
+        let mut manager = NetworkManager::new(false, Duration::from_secs(1));
+        manager.set_offline_mode(true);
+        manager.authenticate(Credentials {
+            username: "user".to_string(),
+            password: "pass".to_string(),
+        });
+        manager.resume_session();
+        assert_eq!(
+            next_request(&mut manager).0,
+            SetCacheDisabledParams::IDENTIFIER
+        );
+        assert_eq!(
+            next_request(&mut manager).0,
+            fetch::EnableParams::IDENTIFIER
+        );
+        let (method, params) = next_request(&mut manager);
+        assert_eq!(method, EmulateNetworkConditionsParams::IDENTIFIER);
+        assert_eq!(params["offline"], true);
+        assert!(manager.poll().is_none());
+    }
 }
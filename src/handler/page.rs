@@ -1,42 +1,59 @@
-use std::sync::Arc;
+use std::cell::RefCell;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, Weak};
+use std::task::{Context, Poll};
+use std::time::Duration;
 
 use futures::channel::mpsc::{channel, Receiver, Sender};
 use futures::channel::oneshot::channel as oneshot_channel;
+use futures::lock::{Mutex, MutexGuard};
 use futures::stream::Fuse;
 use futures::{SinkExt, StreamExt};
+use futures_timer::Delay;
+use pin_project_lite::pin_project;
+use serde::Serialize;
 
 use chromiumoxide_cdp::cdp::browser_protocol::browser::{GetVersionParams, GetVersionReturns};
+use chromiumoxide_cdp::cdp::browser_protocol::css::EventStyleSheetAdded;
 use chromiumoxide_cdp::cdp::browser_protocol::dom::{
     NodeId, QuerySelectorAllParams, QuerySelectorParams, Rgba,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
     ClearDeviceMetricsOverrideParams, SetDefaultBackgroundColorOverrideParams,
-    SetDeviceMetricsOverrideParams,
+    SetDeviceMetricsOverrideParams, SetEmulatedVisionDeficiencyParams,
+    SetEmulatedVisionDeficiencyType,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::input::{
-    DispatchKeyEventParams, DispatchKeyEventType, DispatchMouseEventParams, DispatchMouseEventType,
-    MouseButton,
+    DispatchKeyEventParams, DispatchKeyEventType, DispatchMouseEventParams,
+    DispatchMouseEventPointerType, DispatchMouseEventReturns, DispatchMouseEventType,
+    DispatchTouchEventParams, DispatchTouchEventType, MouseButton, TouchPoint,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::network::{GetResponseBodyParams, RequestId};
 use chromiumoxide_cdp::cdp::browser_protocol::page::{
-    GetLayoutMetricsParams, GetLayoutMetricsReturns, Viewport,
+    CaptureScreenshotParams, FrameId, GetLayoutMetricsParams, GetLayoutMetricsReturns,
+    NavigateParams, NavigateReturns, Viewport,
 };
 use chromiumoxide_cdp::cdp::browser_protocol::target::{ActivateTargetParams, SessionId, TargetId};
 use chromiumoxide_cdp::cdp::js_protocol::runtime::{
     CallFunctionOnParams, CallFunctionOnReturns, EvaluateParams, ExecutionContextId, RemoteObjectId,
 };
-use chromiumoxide_types::{Command, CommandResponse};
+use chromiumoxide_types::{Command, CommandResponse, Method, MethodId};
 
 use crate::cmd::{to_command_response, CommandMessage};
 use crate::error::{CdpError, Result};
 use crate::handler::commandfuture::CommandFuture;
 use crate::handler::domworld::DOMWorldKind;
+use crate::handler::frame::{FrameInfo, WaitUntil};
 use crate::handler::httpfuture::HttpFuture;
 use crate::handler::target::{GetExecutionContext, TargetMessage};
 use crate::handler::target_message_future::TargetMessageFuture;
 use crate::js::EvaluationResult;
 use crate::layout::Point;
-use crate::page::ScreenshotParams;
-use crate::{keys, ArcHttpRequest};
+use crate::listeners::EventStream;
+use crate::page::{ClickOptions, PenOptions, ScreenshotParams};
+use crate::{keys, utils, ArcHttpRequest};
 
 #[derive(Debug)]
 pub struct PageHandle {
@@ -51,6 +68,9 @@ impl PageHandle {
             target_id,
             session_id,
             sender: commands,
+            actions: Default::default(),
+            in_transaction: false,
+            handles: Default::default(),
         };
         Self {
             rx: rx.fuse(),
@@ -63,11 +83,125 @@ impl PageHandle {
     }
 }
 
+/// `Input.dispatchMouseEvent` with the pen properties that are missing from
+/// the generated `DispatchMouseEventParams`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DispatchPenEventParams {
+    #[serde(flatten)]
+    params: DispatchMouseEventParams,
+    force: f64,
+    tilt_x: i64,
+    tilt_y: i64,
+    twist: i64,
+}
+
+impl Method for DispatchPenEventParams {
+    fn identifier(&self) -> MethodId {
+        DispatchMouseEventParams::IDENTIFIER.into()
+    }
+}
+
+impl Command for DispatchPenEventParams {
+    type Response = DispatchMouseEventReturns;
+}
+
+/// The largest surface in pixels chromium renders in one piece
+const MAX_TEXTURE_SIZE: f64 = 16384.;
+
+/// Serializes the input sequences of a page, so that concurrent tasks don't
+/// interleave their keystrokes and clicks.
+#[derive(Debug, Default)]
+pub(crate) struct ActionLock {
+    /// Whether single input sequences wait for the lock
+    enabled: AtomicBool,
+    lock: Mutex<()>,
+}
+
+impl ActionLock {
+    /// Whether the lock is held by a transaction that is currently being
+    /// polled on this thread, i.e. by the task that is asking for the lock
+    fn is_held_by_current_task(self: &Arc<Self>) -> bool {
+        let lock = Arc::as_ptr(self);
+        TRANSACTIONS.with(|locks| locks.borrow().contains(&lock))
+    }
+}
+
+thread_local! {
+    /// The action locks of the transactions being polled on this thread
+    static TRANSACTIONS: RefCell<Vec<*const ActionLock>> = const { RefCell::new(Vec::new()) };
+}
+
+pin_project! {
+    /// Polls the actions of a transaction while marking its action lock as
+    /// held by the polling task, so that handles to the page that weren't
+    /// created by the transaction don't wait for the lock it holds.
+    #[derive(Debug)]
+    pub(crate) struct Transaction<Fut> {
+        actions: Arc<ActionLock>,
+        #[pin]
+        fut: Fut,
+    }
+}
+
+impl<Fut: Future> Future for Transaction<Fut> {
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        /// Unmarks the lock again, even if polling panicked
+        struct Polling;
+
+        impl Drop for Polling {
+            fn drop(&mut self) {
+                TRANSACTIONS.with(|locks| locks.borrow_mut().pop());
+            }
+        }
+
+        let this = self.project();
+        TRANSACTIONS.with(|locks| locks.borrow_mut().push(Arc::as_ptr(this.actions)));
+        let _polling = Polling;
+        this.fut.poll(cx)
+    }
+}
+
+/// Tracks the `Page` handles of a page that were handed out, and the state
+/// they share.
+#[derive(Debug, Default)]
+pub(crate) struct PageHandles {
+    /// The token shared by all `Page` handles, dropped with the last one
+    token: StdMutex<Weak<PageToken>>,
+    /// Whether to close the page once the last handle is dropped
+    close_on_drop: AtomicBool,
+    /// The stylesheets added since the css coverage was started
+    style_sheets: StdMutex<Option<EventStream<EventStyleSheetAdded>>>,
+}
+
+/// Held by every `Page` handle, notifies the target once the last handle is
+/// dropped.
+#[derive(Debug)]
+pub(crate) struct PageToken {
+    sender: Sender<TargetMessage>,
+    handles: Arc<PageHandles>,
+}
+
+impl Drop for PageToken {
+    fn drop(&mut self) {
+        if self.handles.close_on_drop.load(Ordering::Relaxed) {
+            // a new sender always has room for one message
+            let _ = self.sender.clone().try_send(TargetMessage::Released);
+        }
+    }
+}
+
 #[derive(Debug)]
 pub(crate) struct PageInner {
     target_id: TargetId,
     session_id: SessionId,
     sender: Sender<TargetMessage>,
+    actions: Arc<ActionLock>,
+    /// Whether this is the handle of a transaction that holds the action lock
+    in_transaction: bool,
+    handles: Arc<PageHandles>,
 }
 
 impl PageInner {
@@ -83,7 +217,35 @@ impl PageInner {
 
     /// This creates navigation future with the final http response when the page is loaded
     pub(crate) fn wait_for_navigation(&self) -> TargetMessageFuture<ArcHttpRequest> {
-        TargetMessageFuture::<ArcHttpRequest>::wait_for_navigation(self.sender.clone())
+        self.wait_for_navigation_until(WaitUntil::default())
+    }
+
+    /// This creates navigation future with the final http response when the
+    /// page reached the `wait_until` lifecycle state
+    pub(crate) fn wait_for_navigation_until(
+        &self,
+        wait_until: WaitUntil,
+    ) -> TargetMessageFuture<ArcHttpRequest> {
+        TargetMessageFuture::<ArcHttpRequest>::wait_for_navigation(self.sender.clone(), wait_until)
+    }
+
+    /// Navigates and resolves once the navigated frame reached the
+    /// `wait_until` lifecycle state
+    pub(crate) async fn navigate(
+        &self,
+        params: NavigateParams,
+        wait_until: WaitUntil,
+    ) -> Result<CommandResponse<NavigateReturns>> {
+        let (tx, rx) = oneshot_channel();
+        let method = params.identifier();
+        let msg = CommandMessage::with_session(params, tx, Some(self.session_id.clone()))?;
+
+        self.sender
+            .clone()
+            .send(TargetMessage::Navigate(msg, wait_until))
+            .await?;
+        let resp = rx.await??;
+        to_command_response::<NavigateParams>(resp, method)
     }
 
     /// This creates HTTP future with navigation and responds with the final
@@ -109,6 +271,109 @@ impl PageInner {
         &self.sender
     }
 
+    /// The same page, but sending its commands within another session of
+    /// the target, like the one of an out-of-process iframe
+    pub(crate) fn with_session(&self, session_id: SessionId) -> Self {
+        Self {
+            target_id: self.target_id.clone(),
+            session_id,
+            sender: self.sender.clone(),
+            actions: self.actions.clone(),
+            in_transaction: self.in_transaction,
+            handles: self.handles.clone(),
+        }
+    }
+
+    /// The token of the `Page` handles, a new one if all of them were
+    /// dropped
+    pub(crate) fn token(&self) -> Arc<PageToken> {
+        let mut token = self.handles.token.lock().unwrap();
+        token.upgrade().unwrap_or_else(|| {
+            let new = Arc::new(PageToken {
+                sender: self.sender.clone(),
+                handles: self.handles.clone(),
+            });
+            *token = Arc::downgrade(&new);
+            new
+        })
+    }
+
+    /// Whether all `Page` handles were dropped
+    pub(crate) fn is_released(&self) -> bool {
+        self.handles.token.lock().unwrap().strong_count() == 0
+    }
+
+    /// Sets whether to close the page once all `Page` handles are dropped
+    pub(crate) fn set_close_on_drop(&self, enabled: bool) {
+        self.handles.close_on_drop.store(enabled, Ordering::Relaxed);
+    }
+
+    pub(crate) fn closes_on_drop(&self) -> bool {
+        self.handles.close_on_drop.load(Ordering::Relaxed)
+    }
+
+    /// Replaces the listener for the stylesheets of the css coverage
+    pub(crate) fn set_style_sheets(
+        &self,
+        listener: Option<EventStream<EventStyleSheetAdded>>,
+    ) -> Option<EventStream<EventStyleSheetAdded>> {
+        std::mem::replace(&mut *self.handles.style_sheets.lock().unwrap(), listener)
+    }
+
+    /// A handle to the same page whose input sequences don't wait for the
+    /// action lock, which is held by the transaction it's used in
+    pub(crate) fn in_transaction(&self) -> Self {
+        Self {
+            in_transaction: true,
+            ..self.with_session(self.session_id.clone())
+        }
+    }
+
+    /// Sets whether single input sequences wait for the action lock
+    pub(crate) fn set_action_lock(&self, enabled: bool) {
+        self.actions.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Waits for the action lock of a transaction, unless the lock is
+    /// already held by the transaction of the current task
+    pub(crate) async fn lock_transaction(&self) -> Option<MutexGuard<'_, ()>> {
+        if self.actions.is_held_by_current_task() {
+            return None;
+        }
+        Some(self.actions.lock.lock().await)
+    }
+
+    /// Performs the actions of a transaction that holds the action lock
+    pub(crate) fn transaction<Fut: Future>(&self, fut: Fut) -> Transaction<Fut> {
+        Transaction {
+            actions: self.actions.clone(),
+            fut,
+        }
+    }
+
+    /// Waits for the action lock before an input sequence, unless the lock
+    /// is disabled or already held by the transaction of this handle or of
+    /// the current task
+    async fn lock_actions(&self) -> Option<MutexGuard<'_, ()>> {
+        if self.in_transaction
+            || !self.actions.enabled.load(Ordering::Relaxed)
+            || self.actions.is_held_by_current_task()
+        {
+            return None;
+        }
+        Some(self.actions.lock.lock().await)
+    }
+
+    /// The session of the frame if it is an out-of-process iframe
+    pub async fn frame_session(&self, frame_id: FrameId) -> Result<Option<SessionId>> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(TargetMessage::FrameSession(frame_id, tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
     /// Returns the first element in the node which matches the given CSS
     /// selector.
     pub async fn find_element(&self, selector: impl Into<String>, node: NodeId) -> Result<NodeId> {
@@ -145,38 +410,145 @@ impl PageInner {
 
     /// Moves the mouse to this point (dispatches a mouseMoved event)
     pub async fn move_mouse(&self, point: Point) -> Result<&Self> {
+        let _lock = self.lock_actions().await;
+        self.dispatch_mouse_move(point).await?;
+        Ok(self)
+    }
+
+    async fn dispatch_mouse_move(&self, point: Point) -> Result<()> {
         self.execute(DispatchMouseEventParams::new(
             DispatchMouseEventType::MouseMoved,
             point.x,
             point.y,
         ))
         .await?;
-        Ok(self)
+        Ok(())
     }
 
     /// Performs a mouse click event at the point's location
     pub async fn click(&self, point: Point) -> Result<&Self> {
+        self.click_with(point, &ClickOptions::default()).await
+    }
+
+    /// Performs a mouse click event at the point's location as configured by
+    /// the `options`
+    pub async fn click_with(&self, point: Point, options: &ClickOptions) -> Result<&Self> {
+        let _lock = self.lock_actions().await;
+        self.dispatch_mouse_move(point).await?;
+
         let cmd = DispatchMouseEventParams::builder()
             .x(point.x)
             .y(point.y)
-            .button(MouseButton::Left)
-            .click_count(1);
+            .button(options.button.clone())
+            .modifiers(options.modifiers);
 
-        self.move_mouse(point)
-            .await?
-            .execute(
+        for click_count in 1..=options.click_count {
+            self.execute(
                 cmd.clone()
                     .r#type(DispatchMouseEventType::MousePressed)
+                    .buttons(options.buttons())
+                    .click_count(click_count)
                     .build()
                     .unwrap(),
             )
             .await?;
 
-        self.execute(
-            cmd.r#type(DispatchMouseEventType::MouseReleased)
-                .build()
-                .unwrap(),
-        )
+            if let Some(delay) = options.delay {
+                Delay::new(delay).await;
+            }
+
+            self.execute(
+                cmd.clone()
+                    .r#type(DispatchMouseEventType::MouseReleased)
+                    .click_count(click_count)
+                    .build()
+                    .unwrap(),
+            )
+            .await?;
+        }
+        Ok(self)
+    }
+
+    /// Dispatches a pen event at the point's location, with the pen touching
+    /// the surface if `down` is set
+    pub async fn dispatch_pen(
+        &self,
+        r#type: DispatchMouseEventType,
+        point: Point,
+        down: bool,
+        options: &PenOptions,
+    ) -> Result<&Self> {
+        let _lock = self.lock_actions().await;
+        let params = DispatchMouseEventParams::builder()
+            .r#type(r#type)
+            .x(point.x)
+            .y(point.y)
+            .button(if down {
+                MouseButton::Left
+            } else {
+                MouseButton::None
+            })
+            .buttons(down as i64)
+            .click_count(down as i64)
+            .pointer_type(DispatchMouseEventPointerType::Pen)
+            .build()
+            .unwrap();
+        self.execute(DispatchPenEventParams {
+            params,
+            force: if down { options.pressure } else { 0. },
+            tilt_x: options.tilt_x,
+            tilt_y: options.tilt_y,
+            twist: options.twist,
+        })
+        .await?;
+        Ok(self)
+    }
+
+    /// Performs a touch tap at the point's location
+    pub async fn tap(&self, point: Point) -> Result<&Self> {
+        let _lock = self.lock_actions().await;
+        self.execute(DispatchTouchEventParams::new(
+            DispatchTouchEventType::TouchStart,
+            vec![TouchPoint::new(point.x, point.y)],
+        ))
+        .await?;
+        self.execute(DispatchTouchEventParams::new(
+            DispatchTouchEventType::TouchEnd,
+            Vec::new(),
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// Touches the screen with the fingers of the first frame, moves them
+    /// through all other frames with `step_delay` in between and lifts them
+    pub async fn touch_gesture(
+        &self,
+        mut frames: Vec<Vec<TouchPoint>>,
+        step_delay: Duration,
+    ) -> Result<&Self> {
+        if frames.is_empty() {
+            return Ok(self);
+        }
+        let _lock = self.lock_actions().await;
+        let moves = frames.split_off(1);
+        self.execute(DispatchTouchEventParams::new(
+            DispatchTouchEventType::TouchStart,
+            frames.remove(0),
+        ))
+        .await?;
+        for points in moves {
+            Delay::new(step_delay).await;
+            self.execute(DispatchTouchEventParams::new(
+                DispatchTouchEventType::TouchMove,
+                points,
+            ))
+            .await?;
+        }
+        self.execute(DispatchTouchEventParams::new(
+            DispatchTouchEventType::TouchEnd,
+            Vec::new(),
+        ))
         .await?;
         Ok(self)
     }
@@ -190,8 +562,9 @@ impl PageInner {
     /// actual Enter key instead use `page.press_key(
     /// keys::get_key_definition("Enter").unwrap())`.
     pub async fn type_str(&self, input: impl AsRef<str>) -> Result<&Self> {
+        let _lock = self.lock_actions().await;
         for c in input.as_ref().split("").filter(|s| !s.is_empty()) {
-            self.press_key(c).await?;
+            self.dispatch_key_press(c).await?;
         }
         Ok(self)
     }
@@ -199,7 +572,12 @@ impl PageInner {
     /// Uses the `DispatchKeyEvent` mechanism to simulate pressing keyboard
     /// keys.
     pub async fn press_key(&self, key: impl AsRef<str>) -> Result<&Self> {
-        let key = key.as_ref();
+        let _lock = self.lock_actions().await;
+        self.dispatch_key_press(key.as_ref()).await?;
+        Ok(self)
+    }
+
+    async fn dispatch_key_press(&self, key: &str) -> Result<()> {
         let key_definition = keys::get_key_definition(key)
             .ok_or_else(|| CdpError::msg(format!("Key not found: {key}")))?;
         let mut cmd = DispatchKeyEventParams::builder();
@@ -227,9 +605,60 @@ impl PageInner {
             .await?;
         self.execute(cmd.r#type(DispatchKeyEventType::KeyUp).build().unwrap())
             .await?;
+        Ok(())
+    }
+
+    /// Presses the key of the shortcut with its modifiers held down.
+    ///
+    /// Editing `commands`, like `selectAll`, are sent along with the key
+    /// since chromium doesn't perform them for synthetic key events on macOS.
+    pub async fn shortcut(&self, shortcut: &str, commands: &[&str]) -> Result<&Self> {
+        let mac = self.version().await?.user_agent.contains("Macintosh");
+        let (modifiers, key) = keys::parse_shortcut(shortcut, mac)
+            .ok_or_else(|| CdpError::msg(format!("Invalid shortcut: {shortcut}")))?;
+
+        let _lock = self.lock_actions().await;
+        let mut bits = 0;
+        for modifier in &modifiers {
+            bits |= modifier.bit();
+            self.dispatch_key(modifier.key(), DispatchKeyEventType::RawKeyDown, bits, &[])
+                .await?;
+        }
+        self.dispatch_key(key.key, DispatchKeyEventType::RawKeyDown, bits, commands)
+            .await?;
+        self.dispatch_key(key.key, DispatchKeyEventType::KeyUp, bits, &[])
+            .await?;
+        for modifier in modifiers.iter().rev() {
+            bits &= !modifier.bit();
+            self.dispatch_key(modifier.key(), DispatchKeyEventType::KeyUp, bits, &[])
+                .await?;
+        }
         Ok(self)
     }
 
+    async fn dispatch_key(
+        &self,
+        key: &str,
+        r#type: DispatchKeyEventType,
+        modifiers: i64,
+        commands: &[&str],
+    ) -> Result<()> {
+        let key_definition = keys::get_key_definition(key)
+            .ok_or_else(|| CdpError::msg(format!("Key not found: {key}")))?;
+        let mut cmd = DispatchKeyEventParams::builder()
+            .r#type(r#type)
+            .modifiers(modifiers)
+            .key(key_definition.key)
+            .code(key_definition.code)
+            .windows_virtual_key_code(key_definition.key_code)
+            .native_virtual_key_code(key_definition.key_code);
+        if !commands.is_empty() {
+            cmd = cmd.commands(commands.iter().map(|c| c.to_string()).collect::<Vec<_>>());
+        }
+        self.execute(cmd.build().unwrap()).await?;
+        Ok(())
+    }
+
     /// Calls function with given declaration on the remote object with the
     /// matching id
     pub async fn call_js_fn(
@@ -309,19 +738,38 @@ impl PageInner {
     pub async fn execution_context_for_world(
         &self,
         dom_world: DOMWorldKind,
+    ) -> Result<Option<ExecutionContextId>> {
+        self.frame_execution_context(None, dom_world).await
+    }
+
+    /// The execution context of the frame, the main frame if `None`
+    pub async fn frame_execution_context(
+        &self,
+        frame_id: Option<FrameId>,
+        dom_world: DOMWorldKind,
     ) -> Result<Option<ExecutionContextId>> {
         let (tx, rx) = oneshot_channel();
         self.sender
             .clone()
             .send(TargetMessage::GetExecutionContext(GetExecutionContext {
                 dom_world,
-                frame_id: None,
+                frame_id,
                 tx,
             }))
             .await?;
         Ok(rx.await?)
     }
 
+    /// Returns a snapshot of the page's frame hierarchy
+    pub async fn frame_tree(&self) -> Result<Option<FrameInfo>> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(TargetMessage::FrameTree(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
     /// Returns metrics relating to the layout of the page
     pub async fn layout_metrics(&self) -> Result<GetLayoutMetricsReturns> {
         Ok(self
@@ -330,18 +778,34 @@ impl PageInner {
             .result)
     }
 
+    /// Returns the device metrics the page currently emulates
+    pub(crate) async fn device_metrics(&self) -> Result<Option<SetDeviceMetricsOverrideParams>> {
+        let (tx, rx) = oneshot_channel();
+        self.sender
+            .clone()
+            .send(TargetMessage::DeviceMetrics(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
     pub async fn screenshot(&self, params: impl Into<ScreenshotParams>) -> Result<Vec<u8>> {
         self.activate().await?;
         let params = params.into();
         let full_page = params.full_page();
         let omit_background = params.omit_background();
+        let vision_deficiency = params.vision_deficiency;
 
         let mut cdp_params = params.cdp_params;
 
+        // the page's own emulated device metrics, restored after a full page
+        // capture
+        let mut previous_metrics = None;
+        let mut content_size = None;
         if full_page {
             let metrics = self.layout_metrics().await?;
             let width = metrics.content_size.width;
             let height = metrics.content_size.height;
+            content_size = Some((width, height));
 
             cdp_params.clip = Some(Viewport {
                 x: 0.,
@@ -351,9 +815,10 @@ impl PageInner {
                 scale: 1.,
             });
 
+            previous_metrics = self.device_metrics().await?;
             self.execute(SetDeviceMetricsOverrideParams::new(
                 width as i64,
-                height as i64,
+                height.min(MAX_TEXTURE_SIZE) as i64,
                 1.,
                 false,
             ))
@@ -372,7 +837,24 @@ impl PageInner {
             .await?;
         }
 
-        let res = self.execute(cdp_params).await?.result;
+        if let Some(deficiency) = vision_deficiency.clone() {
+            self.execute(SetEmulatedVisionDeficiencyParams::new(deficiency))
+                .await?;
+        }
+
+        let res = match content_size {
+            Some((width, height)) if height > MAX_TEXTURE_SIZE => {
+                self.capture_tiles(cdp_params, width, height).await
+            }
+            _ => self.capture(cdp_params).await,
+        };
+
+        if vision_deficiency.is_some() {
+            self.execute(SetEmulatedVisionDeficiencyParams::new(
+                SetEmulatedVisionDeficiencyType::None,
+            ))
+            .await?;
+        }
 
         if omit_background {
             self.execute(SetDefaultBackgroundColorOverrideParams { color: None })
@@ -380,10 +862,126 @@ impl PageInner {
         }
 
         if full_page {
-            self.execute(ClearDeviceMetricsOverrideParams {}).await?;
+            if let Some(metrics) = previous_metrics {
+                self.execute(metrics).await?;
+            } else {
+                self.execute(ClearDeviceMetricsOverrideParams {}).await?;
+            }
+        }
+
+        res
+    }
+
+    async fn capture(&self, params: CaptureScreenshotParams) -> Result<Vec<u8>> {
+        Ok(base64::decode(&self.execute(params).await?.result.data)?)
+    }
+
+    /// Captures a page that is taller than the texture limit of chromium in
+    /// tiles of at most `MAX_TEXTURE_SIZE` and stitches them together.
+    #[cfg(feature = "image")]
+    async fn capture_tiles(
+        &self,
+        params: CaptureScreenshotParams,
+        width: f64,
+        height: f64,
+    ) -> Result<Vec<u8>> {
+        use chromiumoxide_cdp::cdp::browser_protocol::page::CaptureScreenshotFormat;
+
+        let mut canvas = image::RgbaImage::new(width as u32, height as u32);
+        let mut y = 0.;
+        while y < height {
+            let tile_height = (height - y).min(MAX_TEXTURE_SIZE);
+            self.evaluate_expression(format!("window.scrollTo(0, {y})"))
+                .await?;
+            let tile = CaptureScreenshotParams {
+                format: Some(CaptureScreenshotFormat::Png),
+                quality: None,
+                clip: Some(Viewport {
+                    x: 0.,
+                    y,
+                    width,
+                    height: tile_height,
+                    scale: 1.,
+                }),
+                ..params.clone()
+            };
+            let tile = image::load_from_memory(&self.capture(tile).await?)?;
+            image::imageops::replace(&mut canvas, &tile.to_rgba8(), 0, y as i64);
+            y += tile_height;
+        }
+        self.evaluate_expression("window.scrollTo(0, 0)").await?;
+
+        let mut out = std::io::Cursor::new(Vec::new());
+        match params.format.unwrap_or(CaptureScreenshotFormat::Png) {
+            CaptureScreenshotFormat::Png => canvas.write_to(&mut out, image::ImageFormat::Png)?,
+            CaptureScreenshotFormat::Jpeg => {
+                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                    &mut out,
+                    params.quality.unwrap_or(80).clamp(0, 100) as u8,
+                );
+                image::DynamicImage::ImageRgba8(canvas)
+                    .to_rgb8()
+                    .write_with_encoder(encoder)?
+            }
+        }
+        Ok(out.into_inner())
+    }
+
+    /// Without the `image` feature tiles can't be stitched, so only the part
+    /// of the page within the texture limit of chromium is captured.
+    #[cfg(not(feature = "image"))]
+    async fn capture_tiles(
+        &self,
+        mut params: CaptureScreenshotParams,
+        width: f64,
+        height: f64,
+    ) -> Result<Vec<u8>> {
+        tracing::warn!(
+            "Page is {height}px tall, capturing only the first {MAX_TEXTURE_SIZE}px, enable the `image` feature to capture all of it"
+        );
+        params.clip = Some(Viewport {
+            x: 0.,
+            y: 0.,
+            width,
+            height: MAX_TEXTURE_SIZE,
+            scale: 1.,
+        });
+        self.capture(params).await
+    }
+
+    /// Returns the body of the response to the request, decoding it if it
+    /// was transferred as base64
+    pub(crate) async fn response_body(&self, request_id: RequestId) -> Result<Vec<u8>> {
+        let res = self
+            .execute(GetResponseBodyParams::new(request_id))
+            .await?
+            .result;
+        if res.base64_encoded {
+            Ok(base64::decode(&res.body)?)
+        } else {
+            Ok(res.body.into_bytes())
         }
+    }
 
-        Ok(base64::decode(&res.data)?)
+    /// Returns the body of the response as text, decoding it with its
+    /// charset if the browser didn't already decode it
+    pub(crate) async fn response_text(
+        &self,
+        request_id: RequestId,
+        content_type: Option<&str>,
+    ) -> Result<String> {
+        let res = self
+            .execute(GetResponseBodyParams::new(request_id))
+            .await?
+            .result;
+        if res.base64_encoded {
+            Ok(utils::decode_text(
+                &base64::decode(&res.body)?,
+                content_type,
+            ))
+        } else {
+            Ok(res.body)
+        }
     }
 }
 
@@ -400,3 +998,91 @@ pub(crate) async fn execute<T: Command>(
     let resp = rx.await??;
     to_command_response::<T>(resp, method)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::FutureExt;
+
+    #[test]
+    fn serializes_pen_properties() {
+        let params = DispatchPenEventParams {
+            params: DispatchMouseEventParams::builder()
+                .r#type(DispatchMouseEventType::MousePressed)
+                .x(1.)
+                .y(2.)
+                .pointer_type(DispatchMouseEventPointerType::Pen)
+                .build()
+                .unwrap(),
+            force: 0.5,
+            tilt_x: 30,
+            tilt_y: -10,
+            twist: 90,
+        };
+        let json = serde_json::to_value(&params).unwrap();
+        assert_eq!(json["type"], "mousePressed");
+        assert_eq!(json["pointerType"], "pen");
+        assert_eq!(json["force"], 0.5);
+        assert_eq!(json["tiltX"], 30);
+        assert_eq!(json["tiltY"], -10);
+        assert_eq!(json["twist"], 90);
+        assert_eq!(params.identifier(), "Input.dispatchMouseEvent");
+    }
+
+    #[test]
+    fn transactions_hold_the_action_lock() {
+        futures::executor::block_on(async {
+            let handle = PageHandle::new("target".to_string().into(), "session".to_string().into());
+            let page = handle.inner();
+            assert!(page.lock_actions().await.is_none());
+            page.set_action_lock(true);
+            assert!(page.lock_actions().await.is_some());
+
+            let _lock = page.lock_transaction().await;
+            assert!(page.actions.lock.try_lock().is_none());
+            assert!(page.in_transaction().lock_actions().await.is_none());
+
+            page.set_action_lock(false);
+            assert!(page.lock_actions().await.is_none());
+        });
+    }
+
+    #[test]
+    fn transactions_are_reentrant_for_their_task() {
+        futures::executor::block_on(async {
+            let handle = PageHandle::new("target".to_string().into(), "session".to_string().into());
+            let page = handle.inner();
+            page.set_action_lock(true);
+
+            let _lock = page.lock_transaction().await.unwrap();
+            page.transaction(async {
+                // like an element that was found outside of the transaction
+                assert!(page.lock_actions().await.is_none());
+                assert!(page.lock_transaction().await.is_none());
+            })
+            .await;
+            assert!(page.lock_actions().now_or_never().is_none());
+        });
+    }
+
+    #[test]
+    fn releases_page_with_last_handle() {
+        let mut handle = PageHandle::new("target".to_string().into(), "session".to_string().into());
+        let page = handle.inner().clone();
+        assert!(page.is_released());
+
+        let token = page.token();
+        let second = page.in_transaction().token();
+        assert!(Arc::ptr_eq(&token, &second));
+        drop(token);
+        assert!(!page.is_released());
+
+        page.set_close_on_drop(true);
+        drop(second);
+        assert!(page.is_released());
+        assert!(matches!(
+            handle.rx.next().now_or_never(),
+            Some(Some(TargetMessage::Released))
+        ));
+    }
+}
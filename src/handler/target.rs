@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::time::Instant;
@@ -7,32 +7,48 @@ use futures::channel::oneshot::Sender;
 use futures::stream::Stream;
 use futures::task::{Context, Poll};
 
-use chromiumoxide_cdp::cdp::browser_protocol::page::{FrameId, GetFrameTreeParams};
+use chromiumoxide_cdp::cdp::browser_protocol::emulation::SetDeviceMetricsOverrideParams;
+use chromiumoxide_cdp::cdp::browser_protocol::page::{
+    self as cdppage, AddScriptToEvaluateOnNewDocumentParams, EventFrameDetached, FrameId,
+    GetFrameTreeParams, ReloadParams,
+};
 use chromiumoxide_cdp::cdp::browser_protocol::{
     browser::BrowserContextId,
-    log as cdplog, performance,
-    target::{AttachToTargetParams, SessionId, SetAutoAttachParams, TargetId, TargetInfo},
+    inspector, log as cdplog, performance,
+    target::{
+        AttachToTargetParams, EventAttachedToTarget, SessionId, SetAutoAttachParams, TargetId,
+        TargetInfo,
+    },
 };
 use chromiumoxide_cdp::cdp::events::CdpEvent;
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    self, AddBindingParams, RemoveBindingParams, RunIfWaitingForDebuggerParams,
+};
 use chromiumoxide_cdp::cdp::CdpEventMessage;
-use chromiumoxide_types::{Command, Method, Request, Response};
+use chromiumoxide_types::{Command, Method, MethodId, Request, Response};
 
+use crate::auth::Credentials;
+use crate::binding::Binding;
 use crate::cdp::browser_protocol::target::CloseTargetParams;
 use crate::cmd::CommandChain;
 use crate::cmd::CommandMessage;
+use crate::dialog::DialogPolicy;
 use crate::error::{CdpError, Result};
 use crate::handler::browser::BrowserContext;
 use crate::handler::domworld::DOMWorldKind;
 use crate::handler::emulation::EmulationManager;
 use crate::handler::frame::{
-    FrameEvent, FrameManager, NavigationError, NavigationId, NavigationOk,
+    FrameEvent, FrameManager, NavigationError, NavigationGuard, NavigationId, NavigationOk,
+};
+use crate::handler::frame::{FrameInfo, FrameNavigationRequest, WaitUntil, UTILITY_WORLD_NAME};
+use crate::handler::network::{
+    NetworkConditions, NetworkEvent, NetworkManager, RequestBudget, ResourceStats,
 };
-use crate::handler::frame::{FrameNavigationRequest, UTILITY_WORLD_NAME};
-use crate::handler::network::{NetworkEvent, NetworkManager};
 use crate::handler::page::PageHandle;
 use crate::handler::viewport::Viewport;
 use crate::handler::{PageInner, REQUEST_TIMEOUT};
 use crate::listeners::{EventListenerRequest, EventListeners};
+use crate::route::{ContextRoute, NavigationPolicy, Route};
 use crate::{page::Page, ArcHttpRequest};
 use chromiumoxide_cdp::cdp::js_protocol::runtime::ExecutionContextId;
 use std::time::Duration;
@@ -74,8 +90,18 @@ pub struct Target {
     /// Handles all the https
     network_manager: NetworkManager,
     emulation_manager: EmulationManager,
+    /// How dialogs of the page are closed
+    dialog_policy: DialogPolicy,
     /// The identifier of the session this target is attached to
     session_id: Option<SessionId>,
+    /// The sessions of the out-of-process iframes of the page, by the
+    /// identifier of their frame
+    frame_sessions: HashMap<FrameId, SessionId>,
+    /// The workers of the page, by the identifier of their session
+    workers: HashMap<SessionId, WorkerTarget>,
+    /// The scripts and bindings added to the page, replayed into the
+    /// sessions of its out-of-process iframes
+    page_scripts: PageScripts,
     /// The handle of the browser page of this target
     page: Option<PageHandle>,
     /// Drives this target towards initialization
@@ -84,10 +110,33 @@ pub struct Target {
     queued_events: VecDeque<TargetEvent>,
     /// All registered event subscriptions
     event_listeners: EventListeners,
-    /// Senders that need to be notified once the main frame has loaded
-    wait_for_frame_navigation: Vec<Sender<ArcHttpRequest>>,
+    /// Senders that need to be notified once the main frame has reached the
+    /// lifecycle state they are waiting for
+    wait_for_frame_navigation: Vec<(WaitUntil, Sender<Result<ArcHttpRequest>>)>,
     /// The sender who requested the page.
     initiator: Option<Sender<Result<Page>>>,
+    /// The progress of the handler's page hooks for this page
+    page_hooks: PageHooks,
+    /// Whether the renderer of the page crashed, commands fail until the
+    /// page is reloaded
+    crashed: bool,
+    /// Whether the page is reloaded once its renderer crashed
+    reload_on_crash: bool,
+}
+
+/// The progress of the handler's page hooks for a page, see
+/// `Browser::on_new_page`
+#[derive(Debug, Default)]
+enum PageHooks {
+    /// There are no hooks to run
+    #[default]
+    None,
+    /// The hooks run once the page is initialized
+    Pending,
+    /// The hooks are running, the page isn't handed to its initiator yet
+    Running,
+    /// A hook failed, the error is handed to the initiator instead
+    Failed(CdpError),
 }
 
 impl Target {
@@ -108,12 +157,19 @@ impl Target {
             frame_manager: FrameManager::new(request_timeout),
             network_manager,
             emulation_manager: EmulationManager::new(request_timeout),
+            dialog_policy: DialogPolicy::default(),
             session_id: None,
+            frame_sessions: Default::default(),
+            workers: Default::default(),
+            page_scripts: Default::default(),
             page: None,
             init_state: TargetInit::AttachToTarget,
             wait_for_frame_navigation: Default::default(),
             queued_events: Default::default(),
             event_listeners: Default::default(),
+            page_hooks: Default::default(),
+            crashed: false,
+            reload_on_crash: false,
             initiator: None,
             browser_context,
         }
@@ -187,14 +243,96 @@ impl Target {
         self.info.opener_id.as_ref()
     }
 
+    /// Replaces the info after the browser reported a change, like a new url
+    pub(crate) fn set_info(&mut self, info: TargetInfo) {
+        self.info = info;
+    }
+
+    /// Whether the connection to the browser was lost and this target waits
+    /// to be reported again
+    pub(crate) fn is_detached(&self) -> bool {
+        matches!(self.init_state, TargetInit::Detached)
+    }
+
+    /// The connection to the browser was lost together with all sessions of
+    /// this target, the commands that weren't sent yet fail
+    pub(crate) fn on_connection_lost(&mut self) {
+        self.session_id = None;
+        self.frame_sessions.clear();
+        self.workers.clear();
+        for event in self.queued_events.drain(..) {
+            match event {
+                TargetEvent::Command(cmd) | TargetEvent::Navigate(cmd, _) => {
+                    let _ = cmd.sender.send(Err(CdpError::Disconnected));
+                }
+                _ => {}
+            }
+        }
+        for (_, tx) in self.wait_for_frame_navigation.drain(..) {
+            let _ = tx.send(Err(CdpError::Disconnected));
+        }
+        if !matches!(self.init_state, TargetInit::Closing) {
+            self.init_state = TargetInit::Detached;
+        }
+    }
+
+    /// The target was reported again after the connection was re-established,
+    /// attaches to it and initializes it again
+    pub(crate) fn reattach(&mut self) {
+        self.crashed = false;
+        self.network_manager.resume_session();
+        self.init_state = TargetInit::AttachToTarget;
+    }
+
+    /// The page keeps sending its commands with the session it was created
+    /// with, those are sent with the current session of the target instead
+    fn resume_session(
+        current: Option<&SessionId>,
+        page_session: &SessionId,
+        session_id: &mut Option<SessionId>,
+    ) {
+        if let Some(current) = current {
+            if session_id.as_ref() == Some(page_session) {
+                *session_id = Some(current.clone());
+            }
+        }
+    }
+
     pub fn frame_manager_mut(&mut self) -> &mut FrameManager {
         &mut self.frame_manager
     }
 
+    /// Emulate a loss of connectivity for all requests of the page
+    pub fn set_offline_mode(&mut self, offline: bool) {
+        self.network_manager.set_offline_mode(offline);
+    }
+
+    /// Answers the challenges of the proxy of the page's browser context
+    pub(crate) fn set_proxy_credentials(&mut self, credentials: Option<Credentials>) {
+        self.network_manager.set_proxy_credentials(credentials);
+    }
+
+    /// Adds a route of the page's browser context
+    pub(crate) fn add_context_route(&mut self, route: &ContextRoute) {
+        self.network_manager.add_context_route(route.to_route());
+    }
+
+    /// Removes the routes of the page's browser context with the pattern
+    pub(crate) fn remove_context_route(&mut self, pattern: &str) {
+        self.network_manager.remove_context_route(pattern);
+    }
+
     pub fn event_listeners_mut(&mut self) -> &mut EventListeners {
         &mut self.event_listeners
     }
 
+    /// Polls the event listeners of the page's workers
+    pub fn poll_worker_listeners(&mut self, cx: &mut Context<'_>) {
+        for worker in self.workers.values_mut() {
+            worker.event_listeners.poll(cx);
+        }
+    }
+
     /// Received a response to a command issued by this target
     pub fn on_response(&mut self, resp: Response, method: &str) {
         if let Some(cmds) = self.init_state.commands_mut() {
@@ -216,40 +354,205 @@ impl Target {
         }
     }
 
+    /// Queues a command to send within the session
+    fn queue_command<T: Command>(&mut self, session_id: Option<SessionId>, cmd: T) {
+        self.queue_request(
+            session_id,
+            (cmd.identifier(), serde_json::to_value(cmd).unwrap()),
+        );
+    }
+
+    /// Queues a request to send within the session
+    fn queue_request(
+        &mut self,
+        session_id: Option<SessionId>,
+        (method, params): (MethodId, serde_json::Value),
+    ) {
+        self.queued_events.push_back(TargetEvent::Request(Request {
+            method,
+            session_id: session_id.map(Into::into),
+            params,
+        }));
+    }
+
+    /// Queues a request to send within the page's session and the sessions
+    /// of its out-of-process iframes
+    fn queue_request_to_all(&mut self, request: (MethodId, serde_json::Value)) {
+        let sessions: Vec<_> = self.frame_sessions.values().cloned().collect();
+        for session_id in sessions {
+            self.queue_request(Some(session_id), request.clone());
+        }
+        self.queue_request(self.session_id.clone(), request);
+    }
+
+    /// Whether the session is the one of an out-of-process iframe
+    fn is_frame_session(&self, session_id: Option<&str>) -> bool {
+        session_id.is_some_and(|id| {
+            self.frame_sessions
+                .values()
+                .any(|session| session.as_ref() == id)
+        })
+    }
+
+    /// A target was attached to this page's session, or to the session of one
+    /// of its out-of-process iframes.
+    ///
+    /// Targets attached with `SetAutoAttachParams` wait until they are told
+    /// to run. Out-of-process iframes are initialized like the page itself,
+    /// their events are routed to this target by the `Handler`, so that
+    /// their frames become part of the page's frame tree.
+    fn on_attached_to_target(&mut self, event: &EventAttachedToTarget) {
+        let session_id = Some(event.session_id.clone());
+        if is_worker(&event.target_info.r#type) {
+            self.workers.insert(
+                event.session_id.clone(),
+                WorkerTarget {
+                    info: event.target_info.clone(),
+                    event_listeners: Default::default(),
+                },
+            );
+            // reports the worker's console messages and exceptions
+            self.queue_command(session_id.clone(), runtime::EnableParams::default());
+        } else if event.target_info.r#type == "iframe" {
+            self.frame_sessions.insert(
+                event.target_info.target_id.as_ref().to_string().into(),
+                event.session_id.clone(),
+            );
+            self.queue_command(session_id.clone(), cdppage::EnableParams::default());
+            self.queue_command(session_id.clone(), GetFrameTreeParams::default());
+            self.queue_command(
+                session_id.clone(),
+                cdppage::SetLifecycleEventsEnabledParams::new(true),
+            );
+            self.queue_command(session_id.clone(), runtime::EnableParams::default());
+            // the iframe's requests are handled like the page's, and its
+            // documents get the page's scripts and bindings
+            for request in self.network_manager.frame_session_commands() {
+                self.queue_request(session_id.clone(), request);
+            }
+            for source in self.config.init_scripts.clone() {
+                self.queue_command(
+                    session_id.clone(),
+                    AddScriptToEvaluateOnNewDocumentParams::new(source),
+                );
+            }
+            for request in self.page_scripts.requests.clone() {
+                self.queue_request(session_id.clone(), request);
+            }
+            self.queue_command(
+                session_id.clone(),
+                SetAutoAttachParams::builder()
+                    .flatten(true)
+                    .auto_attach(true)
+                    .wait_for_debugger_on_start(true)
+                    .build()
+                    .unwrap(),
+            );
+        }
+        if event.waiting_for_debugger {
+            self.queue_command(session_id, RunIfWaitingForDebuggerParams::default());
+        }
+    }
+
+    /// The session of an out-of-process iframe was detached, which removes
+    /// its frames
+    fn on_detached_from_target(&mut self, session_id: &SessionId) {
+        self.workers.remove(session_id);
+        let frame_id = self
+            .frame_sessions
+            .iter()
+            .find(|(_, session)| *session == session_id)
+            .map(|(frame_id, _)| frame_id.clone());
+        if let Some(frame_id) = frame_id {
+            self.frame_sessions.remove(&frame_id);
+            self.frame_manager
+                .on_frame_detached(&EventFrameDetached { frame_id });
+        }
+    }
+
     pub fn on_event(&mut self, event: CdpEventMessage) {
-        let CdpEventMessage { params, method, .. } = event;
+        let CdpEventMessage {
+            params,
+            method,
+            session_id,
+        } = event;
+        // the events of workers are only reported to their own listeners
+        if let Some(worker) = session_id
+            .as_deref()
+            .and_then(|id| self.workers.get_mut(id))
+        {
+            chromiumoxide_cdp::consume_event!(match params {
+               |ev| worker.event_listeners.start_send(ev),
+               |json| { let _ = worker.event_listeners.try_send_custom(&method, json);}
+            });
+            return;
+        }
+        let from_frame_session = self.is_frame_session(session_id.as_deref());
+        // replies to the events of out-of-process iframes are sent within
+        // their session
+        let frame_session: Option<SessionId> = session_id
+            .clone()
+            .filter(|_| from_frame_session)
+            .map(Into::into);
         match &params {
+            CdpEvent::TargetAttachedToTarget(ev) => self.on_attached_to_target(ev),
+            CdpEvent::TargetDetachedFromTarget(ev) => self.on_detached_from_target(&ev.session_id),
             // `FrameManager` events
             CdpEvent::PageFrameAttached(ev) => self
                 .frame_manager
                 .on_frame_attached(ev.frame_id.clone(), Some(ev.parent_frame_id.clone())),
-            CdpEvent::PageFrameDetached(ev) => self.frame_manager.on_frame_detached(ev),
+            // the page detaches frames that moved to their own process, they
+            // are detached with their session
+            CdpEvent::PageFrameDetached(ev)
+                if from_frame_session || !self.frame_sessions.contains_key(&ev.frame_id) =>
+            {
+                self.frame_manager.on_frame_detached(ev)
+            }
             CdpEvent::PageFrameNavigated(ev) => self.frame_manager.on_frame_navigated(&ev.frame),
             CdpEvent::PageNavigatedWithinDocument(ev) => {
                 self.frame_manager.on_frame_navigated_within_document(ev)
             }
-            CdpEvent::RuntimeExecutionContextCreated(ev) => {
-                self.frame_manager.on_frame_execution_context_created(ev)
-            }
-            CdpEvent::RuntimeExecutionContextDestroyed(ev) => {
-                self.frame_manager.on_frame_execution_context_destroyed(ev)
-            }
-            CdpEvent::RuntimeExecutionContextsCleared(_) => {
-                self.frame_manager.on_execution_contexts_cleared()
-            }
-            CdpEvent::RuntimeBindingCalled(ev) => {
-                // TODO check if binding registered and payload is json
-                self.frame_manager.on_runtime_binding_called(ev)
+            CdpEvent::RuntimeExecutionContextCreated(ev) => self
+                .frame_manager
+                .on_frame_execution_context_created(ev, frame_session.clone()),
+            CdpEvent::RuntimeExecutionContextDestroyed(ev) => self
+                .frame_manager
+                .on_frame_execution_context_destroyed(ev, frame_session.clone()),
+            CdpEvent::RuntimeExecutionContextsCleared(_) => self
+                .frame_manager
+                .on_execution_contexts_cleared(frame_session.clone()),
+            CdpEvent::RuntimeBindingCalled(ev) => self
+                .frame_manager
+                .on_runtime_binding_called(ev, frame_session),
+            CdpEvent::PageFrameRequestedNavigation(ev) => {
+                self.frame_manager.on_frame_requested_navigation(ev)
             }
             CdpEvent::PageLifecycleEvent(ev) => self.frame_manager.on_page_lifecycle_event(ev),
             CdpEvent::PageFrameStartedLoading(ev) => {
                 self.frame_manager.on_frame_started_loading(ev);
             }
+            CdpEvent::InspectorTargetCrashed(_) if !from_frame_session => self.on_target_crashed(),
+            CdpEvent::PageJavascriptDialogOpening(_) => {
+                if let Some(cmd) = self.dialog_policy.command() {
+                    // the dialog is handled within the session that opened it
+                    let session_id = session_id
+                        .map(Into::into)
+                        .or_else(|| self.session_id.clone());
+                    self.queue_command(session_id, cmd);
+                }
+            }
 
             // `NetworkManager` events
-            CdpEvent::FetchRequestPaused(ev) => self.network_manager.on_fetch_request_paused(ev),
-            CdpEvent::FetchAuthRequired(ev) => self.network_manager.on_fetch_auth_required(ev),
+            CdpEvent::FetchRequestPaused(ev) => self.network_manager.on_fetch_request_paused(
+                ev,
+                self.frame_manager.main_frame().map(|f| f.id()),
+                frame_session.as_ref(),
+            ),
+            CdpEvent::FetchAuthRequired(ev) => self
+                .network_manager
+                .on_fetch_auth_required(ev, frame_session.as_ref()),
             CdpEvent::NetworkRequestWillBeSent(ev) => {
+                self.frame_manager.on_request_will_be_sent(ev);
                 self.network_manager.on_request_will_be_sent(ev)
             }
             CdpEvent::NetworkRequestServedFromCache(ev) => {
@@ -270,6 +573,25 @@ impl Target {
         });
     }
 
+    /// Called when the renderer of the page crashed
+    fn on_target_crashed(&mut self) {
+        for err in self.frame_manager.on_target_crashed() {
+            self.queued_events
+                .push_back(TargetEvent::NavigationResult(Err(err)));
+        }
+        if let Some(initiator) = self.initiator.take() {
+            let _ = initiator.send(Err(CdpError::TargetCrashed));
+        }
+        if self.reload_on_crash {
+            self.queue_command(self.session_id.clone(), ReloadParams::default());
+        } else {
+            self.crashed = true;
+            for (_, tx) in self.wait_for_frame_navigation.drain(..) {
+                let _ = tx.send(Err(CdpError::TargetCrashed));
+            }
+        }
+    }
+
     /// Called when a init command timed out
     fn on_initialization_failed(&mut self) -> TargetEvent {
         if let Some(initiator) = self.initiator.take() {
@@ -339,9 +661,7 @@ impl Target {
                     cx,
                     now,
                     cmds,
-                    TargetInit::InitializingPage(Self::page_init_commands(
-                        self.config.request_timeout
-                    ))
+                    TargetInit::InitializingPage(Self::page_init_commands(&self.config))
                 );
             }
             TargetInit::InitializingPage(cmds) => {
@@ -350,10 +670,8 @@ impl Target {
                     cx,
                     now,
                     cmds,
-                    match self.config.viewport.as_ref() {
-                        Some(viewport) => TargetInit::InitializingEmulation(
-                            self.emulation_manager.init_commands(viewport)
-                        ),
+                    match self.emulation_manager.init_commands(&self.config) {
+                        Some(cmds) => TargetInit::InitializingEmulation(cmds),
                         None => TargetInit::Initialized,
                     }
                 );
@@ -362,9 +680,21 @@ impl Target {
                 advance_state!(self, cx, now, cmds, TargetInit::Initialized);
             }
             TargetInit::Initialized => {
-                if let Some(initiator) = self.initiator.take() {
-                    // make sure that the main frame of the page has finished loading
-                    if self
+                if matches!(self.page_hooks, PageHooks::Pending) {
+                    if let Some(page) = self.get_or_create_page() {
+                        let page = page.clone();
+                        self.page_hooks = PageHooks::Running;
+                        return Some(TargetEvent::RunPageHooks(page));
+                    }
+                }
+                if matches!(self.page_hooks, PageHooks::Running) {
+                    // the page is handed out once the hooks are done
+                } else if let Some(initiator) = self.initiator.take() {
+                    // hand out the error of a failed hook, or the page once its
+                    // main frame has finished loading
+                    if let PageHooks::Failed(err) = std::mem::take(&mut self.page_hooks) {
+                        let _ = initiator.send(Err(err));
+                    } else if self
                         .frame_manager
                         .main_frame()
                         .map(|frame| frame.is_loaded())
@@ -381,25 +711,74 @@ impl Target {
                 }
             }
             TargetInit::Closing => return None,
+            // the page's messages are still handled, its commands fail
+            TargetInit::Detached => {}
         };
         loop {
             if let Some(frame) = self.frame_manager.main_frame() {
-                if frame.is_loaded() {
-                    while let Some(tx) = self.wait_for_frame_navigation.pop() {
-                        let _ = tx.send(frame.http_request().cloned());
+                for n in (0..self.wait_for_frame_navigation.len()).rev() {
+                    if frame.has_reached(self.wait_for_frame_navigation[n].0) {
+                        let (_, tx) = self.wait_for_frame_navigation.swap_remove(n);
+                        let _ = tx.send(Ok(frame.http_request().cloned()));
                     }
                 }
             }
 
-            // Drain queued messages first.
-            if let Some(ev) = self.queued_events.pop_front() {
-                return Some(ev);
+            // Drain queued messages first, they are sent once attached again.
+            if !self.is_detached() {
+                if let Some(ev) = self.queued_events.pop_front() {
+                    return Some(ev);
+                }
             }
 
+            let detached = self.is_detached();
             if let Some(handle) = self.page.as_mut() {
                 while let Poll::Ready(Some(msg)) = Pin::new(&mut handle.rx).poll_next(cx) {
                     match msg {
-                        TargetMessage::Command(cmd) => {
+                        TargetMessage::Command(cmd) if detached => {
+                            let _ = cmd.sender.send(Err(CdpError::Disconnected));
+                        }
+                        TargetMessage::Navigate(msg, _) if detached => {
+                            let _ = msg.sender.send(Err(CdpError::Disconnected));
+                        }
+                        TargetMessage::WaitForNavigation(_, tx) if detached => {
+                            let _ = tx.send(Err(CdpError::Disconnected));
+                        }
+                        TargetMessage::Command(cmd) if self.crashed => {
+                            // only a reload recovers the crashed page
+                            if cmd.method == ReloadParams::IDENTIFIER {
+                                self.crashed = false;
+                                self.queued_events.push_back(TargetEvent::Command(cmd));
+                            } else {
+                                let _ = cmd.sender.send(Err(CdpError::TargetCrashed));
+                            }
+                        }
+                        TargetMessage::Navigate(msg, _) if self.crashed => {
+                            let _ = msg.sender.send(Err(CdpError::TargetCrashed));
+                        }
+                        TargetMessage::WaitForNavigation(_, tx) if self.crashed => {
+                            let _ = tx.send(Err(CdpError::TargetCrashed));
+                        }
+                        TargetMessage::Command(mut cmd) => {
+                            self.emulation_manager.on_command(&cmd.method, &cmd.params);
+                            Self::resume_session(
+                                self.session_id.as_ref(),
+                                handle.inner().session_id(),
+                                &mut cmd.session_id,
+                            );
+                            if cmd.session_id == self.session_id
+                                && self.page_scripts.on_command(&cmd.method, &cmd.params)
+                            {
+                                // the iframes that are already attached get
+                                // them as well
+                                for session_id in self.frame_sessions.values() {
+                                    self.queued_events.push_back(TargetEvent::Request(Request {
+                                        method: cmd.method.clone(),
+                                        session_id: Some(session_id.clone().into()),
+                                        params: cmd.params.clone(),
+                                    }));
+                                }
+                            }
                             self.queued_events.push_back(TargetEvent::Command(cmd));
                         }
                         TargetMessage::MainFrame(tx) => {
@@ -414,6 +793,25 @@ impl Target {
                                     .collect(),
                             );
                         }
+                        TargetMessage::FrameTree(tx) => {
+                            let _ = tx.send(self.frame_manager.frame_tree());
+                        }
+                        TargetMessage::Workers(tx) => {
+                            let _ = tx.send(
+                                self.workers
+                                    .iter()
+                                    .map(|(session, worker)| (session.clone(), worker.info.clone()))
+                                    .collect(),
+                            );
+                        }
+                        TargetMessage::AddWorkerEventListener(session_id, req) => {
+                            if let Some(worker) = self.workers.get_mut(&session_id) {
+                                worker.event_listeners.add_listener(req);
+                            }
+                        }
+                        TargetMessage::FrameSession(frame_id, tx) => {
+                            let _ = tx.send(self.frame_sessions.get(&frame_id).cloned());
+                        }
                         TargetMessage::Url(tx) => {
                             let _ = tx.send(
                                 self.frame_manager
@@ -421,24 +819,109 @@ impl Target {
                                     .and_then(|f| f.url().map(str::to_string)),
                             );
                         }
-                        TargetMessage::WaitForNavigation(tx) => {
+                        TargetMessage::Navigate(mut msg, wait_until) => {
+                            Self::resume_session(
+                                self.session_id.as_ref(),
+                                handle.inner().session_id(),
+                                &mut msg.session_id,
+                            );
+                            self.queued_events
+                                .push_back(TargetEvent::Navigate(msg, wait_until));
+                        }
+                        TargetMessage::WaitForNavigation(wait_until, tx) => {
                             if let Some(frame) = self.frame_manager.main_frame() {
                                 // TODO submit a navigation watcher: waitForFrameNavigation
 
                                 // TODO return the watchers navigationResponse
-                                if frame.is_loaded() {
-                                    let _ = tx.send(frame.http_request().cloned());
+                                if frame.has_reached(wait_until) {
+                                    let _ = tx.send(Ok(frame.http_request().cloned()));
                                 } else {
-                                    self.wait_for_frame_navigation.push(tx);
+                                    self.wait_for_frame_navigation.push((wait_until, tx));
                                 }
                             } else {
-                                self.wait_for_frame_navigation.push(tx);
+                                self.wait_for_frame_navigation.push((wait_until, tx));
                             }
                         }
                         TargetMessage::AddEventListener(req) => {
                             // register a new listener
                             self.event_listeners.add_listener(req);
                         }
+                        TargetMessage::EmulateNetworkConditions(conditions) => {
+                            self.network_manager.set_network_conditions(conditions);
+                        }
+                        TargetMessage::SetOffline(offline) => {
+                            self.network_manager.set_offline_mode(offline);
+                        }
+                        TargetMessage::SetRequestBudget(budget) => {
+                            self.network_manager.set_request_budget(budget);
+                        }
+                        TargetMessage::SetNavigationGuard(guard) => {
+                            self.frame_manager.set_navigation_guard(guard);
+                        }
+                        TargetMessage::SetNavigationPolicy(policy) => {
+                            self.network_manager.set_navigation_policy(policy);
+                        }
+                        TargetMessage::SetHostMapping(mapping) => {
+                            self.network_manager.set_host_mapping(mapping);
+                        }
+                        TargetMessage::SetOriginHeaders(origin, headers) => {
+                            self.network_manager.set_origin_headers(origin, headers);
+                        }
+                        TargetMessage::DeviceMetrics(tx) => {
+                            let _ = tx.send(self.emulation_manager.device_metrics.clone());
+                        }
+                        TargetMessage::ResourceStats(tx) => {
+                            let _ = tx.send(self.network_manager.resource_stats().clone());
+                        }
+                        TargetMessage::TargetInfo(tx) => {
+                            let _ = tx.send(self.info.clone());
+                        }
+                        TargetMessage::Opener(tx) => match self.info.opener_id.clone() {
+                            Some(opener) => self
+                                .queued_events
+                                .push_back(TargetEvent::GetPage(opener, tx)),
+                            None => {
+                                let _ = tx.send(None);
+                            }
+                        },
+                        TargetMessage::AddRoute(route) => {
+                            self.network_manager.add_route(route);
+                        }
+                        TargetMessage::RemoveRoute(pattern) => {
+                            self.network_manager.remove_route(&pattern);
+                        }
+                        TargetMessage::AddBinding(binding) => {
+                            self.frame_manager.add_binding(binding);
+                        }
+                        TargetMessage::RemoveBinding(name) => {
+                            self.frame_manager.remove_binding(&name);
+                        }
+                        TargetMessage::SetDialogPolicy(policy) => {
+                            self.dialog_policy = policy;
+                        }
+                        TargetMessage::SetReloadOnCrash(reload) => {
+                            self.reload_on_crash = reload;
+                        }
+                        TargetMessage::PageHooksDone(err) => {
+                            self.page_hooks = match err {
+                                Some(err) => PageHooks::Failed(err),
+                                None => PageHooks::None,
+                            };
+                            // hand the page to its initiator
+                            cx.waker().wake_by_ref();
+                        }
+                        TargetMessage::Released => {
+                            // a new handle may have been handed out since
+                            let page = handle.inner();
+                            if page.closes_on_drop() && page.is_released() {
+                                let close = CloseTargetParams::new(self.info.target_id.clone());
+                                self.queued_events.push_back(TargetEvent::Request(Request {
+                                    method: close.identifier(),
+                                    session_id: None,
+                                    params: serde_json::to_value(close).unwrap(),
+                                }));
+                            }
+                        }
                         TargetMessage::GetExecutionContext(ctx) => {
                             let GetExecutionContext {
                                 dom_world,
@@ -469,15 +952,16 @@ impl Target {
                 }
             }
 
+            if detached {
+                return None;
+            }
+
+            self.network_manager.check_request_budget(now);
             while let Some(event) = self.network_manager.poll() {
                 match event {
-                    NetworkEvent::SendCdpRequest((method, params)) => {
-                        // send a message to the browser
-                        self.queued_events.push_back(TargetEvent::Request(Request {
-                            method,
-                            session_id: self.session_id.clone().map(Into::into),
-                            params,
-                        }))
+                    NetworkEvent::SendCdpRequest(request) => self.queue_request_to_all(request),
+                    NetworkEvent::Reply(session_id, request) => {
+                        self.queue_request(session_id.or_else(|| self.session_id.clone()), request)
                     }
                     NetworkEvent::Request(_) => {}
                     NetworkEvent::Response(_) => {}
@@ -487,9 +971,16 @@ impl Target {
                     NetworkEvent::RequestFinished(request) => {
                         self.frame_manager.on_http_request_finished(request);
                     }
+                    NetworkEvent::BudgetExceeded(exceeded) => {
+                        if let Some(err) = self.frame_manager.fail_navigation(exceeded) {
+                            self.queued_events
+                                .push_back(TargetEvent::NavigationResult(Err(err)));
+                        }
+                    }
                 }
             }
 
+            self.frame_manager.poll_binding_calls(cx);
             while let Some(event) = self.frame_manager.poll(now) {
                 match event {
                     FrameEvent::NavigationResult(res) => {
@@ -500,6 +991,9 @@ impl Target {
                         self.queued_events
                             .push_back(TargetEvent::NavigationRequest(id, req));
                     }
+                    FrameEvent::SendCdpRequest(session_id, request) => {
+                        self.queue_request(session_id.or_else(|| self.session_id.clone()), request)
+                    }
                 }
             }
 
@@ -515,7 +1009,13 @@ impl Target {
         self.initiator = Some(tx);
     }
 
-    pub(crate) fn page_init_commands(timeout: Duration) -> CommandChain {
+    /// Runs the handler's page hooks once the page is initialized, before
+    /// it's handed to its initiator
+    pub(crate) fn run_page_hooks(&mut self) {
+        self.page_hooks = PageHooks::Pending;
+    }
+
+    pub(crate) fn page_init_commands(config: &TargetConfig) -> CommandChain {
         let attach = SetAutoAttachParams::builder()
             .flatten(true)
             .auto_attach(true)
@@ -524,23 +1024,81 @@ impl Target {
             .unwrap();
         let enable_performance = performance::EnableParams::default();
         let enable_log = cdplog::EnableParams::default();
-        CommandChain::new(
-            vec![
-                (attach.identifier(), serde_json::to_value(attach).unwrap()),
-                (
-                    enable_performance.identifier(),
-                    serde_json::to_value(enable_performance).unwrap(),
-                ),
-                (
-                    enable_log.identifier(),
-                    serde_json::to_value(enable_log).unwrap(),
-                ),
-            ],
-            timeout,
-        )
+        let enable_inspector = inspector::EnableParams::default();
+        let mut cmds = vec![
+            (attach.identifier(), serde_json::to_value(attach).unwrap()),
+            (
+                enable_performance.identifier(),
+                serde_json::to_value(enable_performance).unwrap(),
+            ),
+            (
+                enable_log.identifier(),
+                serde_json::to_value(enable_log).unwrap(),
+            ),
+            (
+                enable_inspector.identifier(),
+                serde_json::to_value(enable_inspector).unwrap(),
+            ),
+        ];
+        cmds.extend(config.init_scripts.iter().map(|source| {
+            let script = AddScriptToEvaluateOnNewDocumentParams::new(source.clone());
+            (script.identifier(), serde_json::to_value(script).unwrap())
+        }));
+        CommandChain::new(cmds, config.request_timeout)
     }
 }
 
+/// The `Runtime.addBinding` and `Page.addScriptToEvaluateOnNewDocument`
+/// requests that were sent within the page's session.
+///
+/// Scripts removed with `Page.removeScriptToEvaluateOnNewDocument` are still
+/// replayed, their identifiers are only valid within the page's session.
+#[derive(Debug, Default)]
+struct PageScripts {
+    requests: Vec<(MethodId, serde_json::Value)>,
+}
+
+impl PageScripts {
+    /// Keeps track of the scripts and bindings from the commands that are
+    /// sent on behalf of the page, returns whether the command added one
+    fn on_command(&mut self, method: &str, params: &serde_json::Value) -> bool {
+        if method == RemoveBindingParams::IDENTIFIER {
+            self.requests.retain(|(method, binding)| {
+                method != AddBindingParams::IDENTIFIER || binding["name"] != params["name"]
+            });
+            return false;
+        }
+        if method != AddBindingParams::IDENTIFIER
+            && method != AddScriptToEvaluateOnNewDocumentParams::IDENTIFIER
+        {
+            return false;
+        }
+        if self
+            .requests
+            .iter()
+            .any(|request| request.0 == method && request.1 == *params)
+        {
+            return false;
+        }
+        self.requests
+            .push((method.to_string().into(), params.clone()));
+        true
+    }
+}
+
+/// A worker attached to a page
+#[derive(Debug)]
+struct WorkerTarget {
+    info: TargetInfo,
+    /// The listeners for the events of the worker's session
+    event_listeners: EventListeners,
+}
+
+/// Whether the type of a target is one of the worker types
+pub(crate) fn is_worker(r#type: &str) -> bool {
+    matches!(r#type, "worker" | "shared_worker" | "service_worker")
+}
+
 #[derive(Debug, Clone)]
 pub struct TargetConfig {
     pub ignore_https_errors: bool,
@@ -549,15 +1107,25 @@ pub struct TargetConfig {
     pub viewport: Option<Viewport>,
     pub request_intercept: bool,
     pub cache_enabled: bool,
+    /// The timezone to emulate, like `Europe/Berlin`
+    pub timezone_id: Option<String>,
+    /// The locale to emulate, like `de-DE`
+    pub locale: Option<String>,
+    /// Scripts to evaluate in every new document before its own scripts
+    pub init_scripts: Vec<String>,
 }
 
 impl TargetConfig {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         ignore_https_errors: bool,
         request_timeout: Duration,
         viewport: Option<Viewport>,
         request_intercept: bool,
         cache_enabled: bool,
+        timezone_id: Option<String>,
+        locale: Option<String>,
+        init_scripts: Vec<String>,
     ) -> Self {
         Self {
             ignore_https_errors,
@@ -565,6 +1133,9 @@ impl TargetConfig {
             viewport,
             request_intercept,
             cache_enabled,
+            timezone_id,
+            locale,
+            init_scripts,
         }
     }
 }
@@ -577,6 +1148,9 @@ impl Default for TargetConfig {
             viewport: Default::default(),
             request_intercept: false,
             cache_enabled: true,
+            timezone_id: None,
+            locale: None,
+            init_scripts: Vec::new(),
         }
     }
 }
@@ -646,6 +1220,12 @@ pub(crate) enum TargetEvent {
     NavigationResult(Result<NavigationOk, NavigationError>),
     /// A new command arrived via a channel
     Command(CommandMessage),
+    /// A new navigation request arrived via a channel
+    Navigate(CommandMessage, WaitUntil),
+    /// The page is initialized and the handler's page hooks need to run
+    RunPageHooks(Arc<PageInner>),
+    /// The page of another target is requested
+    GetPage(TargetId, Sender<Option<Page>>),
 }
 
 // TODO this can be moved into the classes?
@@ -658,6 +1238,9 @@ pub enum TargetInit {
     AttachToTarget,
     Initialized,
     Closing,
+    /// The connection to the browser was lost, the target waits to be
+    /// reported again
+    Detached,
 }
 
 impl TargetInit {
@@ -670,6 +1253,7 @@ impl TargetInit {
             TargetInit::AttachToTarget => None,
             TargetInit::Initialized => None,
             TargetInit::Closing => None,
+            TargetInit::Detached => None,
         }
     }
 }
@@ -704,11 +1288,328 @@ pub enum TargetMessage {
     AllFrames(Sender<Vec<FrameId>>),
     /// Return the url of this target's page
     Url(Sender<Option<String>>),
-    /// A Message that resolves when the frame finished loading a new url
-    WaitForNavigation(Sender<ArcHttpRequest>),
+    /// Return a snapshot of the frame hierarchy of this target's page
+    FrameTree(Sender<Option<FrameInfo>>),
+    /// Return the session of an out-of-process iframe of this target's page
+    FrameSession(FrameId, Sender<Option<SessionId>>),
+    /// Return the sessions and infos of the workers of this target's page
+    Workers(Sender<Vec<(SessionId, TargetInfo)>>),
+    /// A request to submit a new listener for the events of a worker
+    AddWorkerEventListener(SessionId, EventListenerRequest),
+    /// Navigate the page and resolve the command once the navigated frame
+    /// reached the given lifecycle state
+    Navigate(CommandMessage, WaitUntil),
+    /// A Message that resolves when the frame reached the given lifecycle
+    /// state after loading a new url
+    WaitForNavigation(WaitUntil, Sender<Result<ArcHttpRequest>>),
     /// A request to submit a new listener that gets notified with every
     /// received event
     AddEventListener(EventListenerRequest),
+    /// Emulate the network conditions for all requests of the page
+    EmulateNetworkConditions(NetworkConditions),
+    /// Emulate a loss of connectivity, keeping the emulated network
+    /// conditions
+    SetOffline(bool),
+    /// Limits the requests of the page, `None` removes the limits
+    SetRequestBudget(Option<RequestBudget>),
+    /// The number of requests per type of resource
+    ResourceStats(Sender<ResourceStats>),
+    /// Return the info of this target
+    TargetInfo(Sender<TargetInfo>),
+    /// Return the page of the target that opened this target
+    Opener(Sender<Option<Page>>),
+    /// Return the device metrics the page currently emulates
+    DeviceMetrics(Sender<Option<SetDeviceMetricsOverrideParams>>),
+    /// Stop the navigation loops of the main frame
+    SetNavigationGuard(NavigationGuard),
+    /// Restrict the urls the main frame may navigate to
+    SetNavigationPolicy(NavigationPolicy),
+    /// Send the requests for each host to another host
+    SetHostMapping(HashMap<String, String>),
+    /// Add headers to the requests to an origin
+    SetOriginHeaders(String, HashMap<String, String>),
+    /// Intercept all requests that match the route
+    AddRoute(Route),
+    /// Stop intercepting the requests of the routes with this pattern
+    RemoveRoute(String),
+    /// Handle the calls of the binding exposed to the page's scripts
+    AddBinding(Binding),
+    /// Stop handling the calls of the binding with this name
+    RemoveBinding(String),
+    /// Close the dialogs of the page according to the policy
+    SetDialogPolicy(DialogPolicy),
+    /// Reload the page once its renderer crashed
+    SetReloadOnCrash(bool),
     /// Get the `ExecutionContext` if available
     GetExecutionContext(GetExecutionContext),
+    /// The last `Page` handle was dropped
+    Released,
+    /// The handler's page hooks are done, with the error of the one that
+    /// failed
+    PageHooksDone(Option<CdpError>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::handler::tests::target_info;
+
+    fn event(session_id: Option<&str>, method: &str, params: serde_json::Value) -> CdpEventMessage {
+        serde_json::from_value(serde_json::json!({
+            "method": method,
+            "sessionId": session_id,
+            "params": params,
+        }))
+        .unwrap()
+    }
+
+    /// An initialized page target with the session `page-session`
+    fn initialized_page() -> Target {
+        let mut target = Target::new(
+            serde_json::from_value(target_info("page", "page")).unwrap(),
+            TargetConfig::new(
+                true,
+                Duration::from_millis(REQUEST_TIMEOUT),
+                None,
+                false,
+                true,
+                None,
+                None,
+                Vec::new(),
+            ),
+            BrowserContext::default(),
+        );
+        target.set_session_id("page-session".to_string().into());
+        target.init_state = TargetInit::Initialized;
+        target
+    }
+
+    /// Polls the target until it's idle, returning its events
+    fn poll_target(target: &mut Target) -> Vec<TargetEvent> {
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        std::iter::from_fn(|| target.poll(&mut cx, Instant::now())).collect()
+    }
+
+    #[test]
+    fn rejects_commands_of_crashed_pages() {
+        use futures::channel::oneshot::channel;
+
+        let mut target = initialized_page();
+        let sender = target.get_or_create_page().unwrap().sender().clone();
+        target.on_event(event(
+            Some("page-session"),
+            "Inspector.targetCrashed",
+            serde_json::json!({}),
+        ));
+
+        let (tx, mut command) = channel();
+        sender
+            .clone()
+            .try_send(TargetMessage::Command(
+                CommandMessage::new(cdppage::EnableParams::default(), tx).unwrap(),
+            ))
+            .unwrap();
+        let (tx, mut navigation) = channel();
+        sender
+            .clone()
+            .try_send(TargetMessage::WaitForNavigation(WaitUntil::default(), tx))
+            .unwrap();
+        assert!(!poll_target(&mut target)
+            .iter()
+            .any(|ev| matches!(ev, TargetEvent::Command(_))));
+        assert!(matches!(
+            command.try_recv(),
+            Ok(Some(Err(CdpError::TargetCrashed)))
+        ));
+        assert!(matches!(
+            navigation.try_recv(),
+            Ok(Some(Err(CdpError::TargetCrashed)))
+        ));
+
+        let (tx, _reload) = channel();
+        sender
+            .clone()
+            .try_send(TargetMessage::Command(
+                CommandMessage::new(ReloadParams::default(), tx).unwrap(),
+            ))
+            .unwrap();
+        let events = poll_target(&mut target);
+        assert!(events.iter().any(|ev| matches!(
+            ev,
+            TargetEvent::Command(cmd) if cmd.method == ReloadParams::IDENTIFIER
+        )));
+        assert!(!target.crashed);
+    }
+
+    #[test]
+    fn runs_out_of_process_iframes() {
+        let info = serde_json::from_value(target_info("page", "page")).unwrap();
+        let config = TargetConfig::new(
+            true,
+            Duration::from_millis(REQUEST_TIMEOUT),
+            None,
+            false,
+            true,
+            None,
+            None,
+            Vec::new(),
+        );
+        let mut target = Target::new(info, config, BrowserContext::default());
+        target.set_session_id("page-session".to_string().into());
+
+        target.on_event(event(
+            Some("page-session"),
+            "Target.attachedToTarget",
+            serde_json::json!({
+                "sessionId": "frame-session",
+                "targetInfo": target_info("frame", "iframe"),
+                "waitingForDebugger": true,
+            }),
+        ));
+        let frame_id: FrameId = "frame".to_string().into();
+        assert!(target.frame_sessions.contains_key(&frame_id));
+        assert!(target.queued_events.iter().all(|ev| matches!(
+            ev,
+            TargetEvent::Request(req) if req.session_id.as_deref() == Some("frame-session")
+        )));
+        assert!(target.queued_events.iter().any(|ev| matches!(
+            ev,
+            TargetEvent::Request(req) if req.method == RunIfWaitingForDebuggerParams::IDENTIFIER
+        )));
+
+        target.on_event(event(
+            Some("page-session"),
+            "Target.detachedFromTarget",
+            serde_json::json!({ "sessionId": "frame-session" }),
+        ));
+        assert!(target.frame_sessions.is_empty());
+    }
+
+    #[test]
+    fn replays_page_state_into_out_of_process_iframes() {
+        use chromiumoxide_cdp::cdp::browser_protocol::network::{
+            EmulateNetworkConditionsParams, ErrorReason,
+        };
+        use chromiumoxide_cdp::cdp::browser_protocol::{fetch, network};
+        use futures::channel::oneshot::channel;
+
+        let requests = |events: &[TargetEvent], session: &str| -> Vec<String> {
+            events
+                .iter()
+                .filter_map(|ev| match ev {
+                    TargetEvent::Request(req) if req.session_id.as_deref() == Some(session) => {
+                        Some(req.method.to_string())
+                    }
+                    _ => None,
+                })
+                .collect()
+        };
+        let mut target = initialized_page();
+        let sender = target.get_or_create_page().unwrap().sender().clone();
+        target.network_manager.add_route(Route::new(
+            "*",
+            Box::new(|req| req.abort(ErrorReason::BlockedByClient)),
+        ));
+        let (tx, _binding) = channel();
+        let mut binding = CommandMessage::new(AddBindingParams::new("binding"), tx).unwrap();
+        binding.session_id = Some("page-session".to_string().into());
+        sender
+            .clone()
+            .try_send(TargetMessage::Command(binding))
+            .unwrap();
+        poll_target(&mut target);
+
+        target.on_event(event(
+            Some("page-session"),
+            "Target.attachedToTarget",
+            serde_json::json!({
+                "sessionId": "frame-session",
+                "targetInfo": target_info("frame", "iframe"),
+                "waitingForDebugger": true,
+            }),
+        ));
+        let sent = requests(&poll_target(&mut target), "frame-session");
+        for method in [
+            network::EnableParams::IDENTIFIER,
+            fetch::EnableParams::IDENTIFIER,
+            AddBindingParams::IDENTIFIER,
+        ] {
+            assert!(sent.iter().any(|sent| sent == method), "{method} not sent");
+        }
+        assert_eq!(
+            sent.last().unwrap(),
+            RunIfWaitingForDebuggerParams::IDENTIFIER
+        );
+
+        // the iframe's requests are answered within its session
+        target.on_event(event(
+            Some("frame-session"),
+            "Fetch.requestPaused",
+            serde_json::json!({
+                "requestId": "1",
+                "request": {
+                    "url": "https://example.net/",
+                    "method": "GET",
+                    "headers": {},
+                    "initialPriority": "High",
+                    "referrerPolicy": "no-referrer"
+                },
+                "frameId": "frame",
+                "resourceType": "Document"
+            }),
+        ));
+        assert_eq!(
+            requests(&poll_target(&mut target), "frame-session"),
+            [fetch::FailRequestParams::IDENTIFIER]
+        );
+
+        target.set_offline_mode(true);
+        let events = poll_target(&mut target);
+        for session in ["page-session", "frame-session"] {
+            assert_eq!(
+                requests(&events, session),
+                [EmulateNetworkConditionsParams::IDENTIFIER]
+            );
+        }
+    }
+
+    #[test]
+    fn tracks_workers() {
+        let mut target = Target::new(
+            serde_json::from_value(target_info("page", "page")).unwrap(),
+            TargetConfig::new(
+                true,
+                Duration::from_millis(REQUEST_TIMEOUT),
+                None,
+                false,
+                true,
+                None,
+                None,
+                Vec::new(),
+            ),
+            BrowserContext::default(),
+        );
+        target.on_event(event(
+            Some("page-session"),
+            "Target.attachedToTarget",
+            serde_json::json!({
+                "sessionId": "worker-session",
+                "targetInfo": target_info("worker", "service_worker"),
+                "waitingForDebugger": false,
+            }),
+        ));
+        assert!(target.workers.contains_key("worker-session"));
+        // the worker's contexts are not the page's
+        target.on_event(event(
+            Some("worker-session"),
+            "Runtime.executionContextsCleared",
+            serde_json::json!({}),
+        ));
+        target.on_event(event(
+            Some("page-session"),
+            "Target.detachedFromTarget",
+            serde_json::json!({ "sessionId": "worker-session" }),
+        ));
+        assert!(target.workers.is_empty());
+    }
 }
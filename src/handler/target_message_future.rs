@@ -7,6 +7,7 @@ use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 
+use crate::handler::frame::WaitUntil;
 use crate::handler::target::TargetMessage;
 use crate::{error::Result, ArcHttpRequest};
 
@@ -15,7 +16,7 @@ type TargetSender = mpsc::Sender<TargetMessage>;
 pin_project! {
     pub struct TargetMessageFuture<T> {
         #[pin]
-        rx_request: oneshot::Receiver<T>,
+        rx_request: oneshot::Receiver<Result<T>>,
         #[pin]
         target_sender: mpsc::Sender<TargetMessage>,
 
@@ -27,7 +28,7 @@ impl<T> TargetMessageFuture<T> {
     pub fn new(
         target_sender: TargetSender,
         message: TargetMessage,
-        rx_request: oneshot::Receiver<T>,
+        rx_request: oneshot::Receiver<Result<T>>,
     ) -> Self {
         Self {
             target_sender,
@@ -36,10 +37,13 @@ impl<T> TargetMessageFuture<T> {
         }
     }
 
-    pub fn wait_for_navigation(target_sender: TargetSender) -> TargetMessageFuture<ArcHttpRequest> {
+    pub fn wait_for_navigation(
+        target_sender: TargetSender,
+        wait_until: WaitUntil,
+    ) -> TargetMessageFuture<ArcHttpRequest> {
         let (tx, rx_request) = oneshot_channel();
 
-        let message = TargetMessage::WaitForNavigation(tx);
+        let message = TargetMessage::WaitForNavigation(wait_until, tx);
 
         TargetMessageFuture::new(target_sender, message, rx_request)
     }
@@ -64,7 +68,11 @@ impl<T> Future for TargetMessageFuture<T> {
                 Poll::Pending => Poll::Pending,
             }
         } else {
-            this.rx_request.as_mut().poll(cx).map_err(Into::into)
+            match this.rx_request.as_mut().poll(cx) {
+                Poll::Ready(Ok(res)) => Poll::Ready(res),
+                Poll::Ready(Err(err)) => Poll::Ready(Err(err.into())),
+                Poll::Pending => Poll::Pending,
+            }
         }
     }
 }
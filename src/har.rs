@@ -0,0 +1,824 @@
+//! Recording network traffic as [HAR](http://www.softwareishard.com/blog/har-12-spec/).
+//!
+//! A [`HarRecorder`] records all requests of the pages it was attached to,
+//! including their headers, timings, redirects and response bodies, and
+//! exports them as a HAR 1.2 document that existing analysis tools can
+//! import.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::har::HarRecorder;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut recorder = HarRecorder::start(&page).await?;
+//!     page.goto("https://example.com").await?;
+//!     recorder.save("example.har").await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use futures::channel::mpsc::{unbounded, UnboundedReceiver, UnboundedSender};
+use futures::{FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::BrowserContextId;
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    EventLoadingFailed, EventLoadingFinished, EventRequestWillBeSent, EventResponseReceived,
+    Headers, RequestId, ResourceTiming, Response,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::target::GetTargetInfoParams;
+
+use crate::browser::Browser;
+use crate::error::Result;
+use crate::listeners::EventStream;
+use crate::page::Page;
+use crate::utils;
+
+/// A HAR document.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Har {
+    pub log: HarLog,
+}
+
+/// The root of the exported data.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarLog {
+    /// The version of the HAR format, `1.2`
+    pub version: String,
+    pub creator: HarCreator,
+    pub pages: Vec<HarPage>,
+    pub entries: Vec<HarEntry>,
+}
+
+/// The application that created the log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarCreator {
+    pub name: String,
+    pub version: String,
+}
+
+impl Default for HarCreator {
+    fn default() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+/// A page whose requests were recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPage {
+    pub started_date_time: String,
+    pub id: String,
+    pub title: String,
+    pub page_timings: HarPageTimings,
+}
+
+/// The timings of the page load, `-1` if unknown.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPageTimings {
+    pub on_content_load: f64,
+    pub on_load: f64,
+}
+
+impl Default for HarPageTimings {
+    fn default() -> Self {
+        Self {
+            on_content_load: -1.,
+            on_load: -1.,
+        }
+    }
+}
+
+/// A single request and its response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    /// The id of the [`HarPage`] this request belongs to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pageref: Option<String>,
+    pub started_date_time: String,
+    /// The total time of the request in milliseconds
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+    pub cache: HarCache,
+    pub timings: HarTimings,
+    #[serde(rename = "serverIPAddress", skip_serializing_if = "Option::is_none")]
+    pub server_ip_address: Option<String>,
+}
+
+/// A recorded request.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarHeader>,
+    pub query_string: Vec<HarHeader>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub post_data: Option<HarPostData>,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+/// A recorded response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    pub status: i64,
+    pub status_text: String,
+    pub http_version: String,
+    pub cookies: Vec<HarCookie>,
+    pub headers: Vec<HarHeader>,
+    pub content: HarContent,
+    #[serde(rename = "redirectURL")]
+    pub redirect_url: String,
+    pub headers_size: i64,
+    pub body_size: i64,
+}
+
+/// A cookie sent with a request or set by a response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarCookie {
+    pub name: String,
+    pub value: String,
+}
+
+/// A name-value pair, used for headers and query parameters.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarHeader {
+    pub name: String,
+    pub value: String,
+}
+
+/// The body of a request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    pub mime_type: String,
+    pub text: String,
+}
+
+/// The body of a response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    /// The length of the decoded body in bytes
+    pub size: i64,
+    pub mime_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// `base64` if the `text` is base64 encoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encoding: Option<String>,
+}
+
+/// Info about the cache usage of a request, not recorded.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HarCache {}
+
+/// The phases of a request in milliseconds, `-1` if a phase does not apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HarTimings {
+    pub blocked: f64,
+    pub dns: f64,
+    pub connect: f64,
+    pub send: f64,
+    pub wait: f64,
+    pub receive: f64,
+    pub ssl: f64,
+}
+
+impl HarTimings {
+    /// Computes the timings of a request from the browser's timing info and
+    /// the time it finished loading.
+    fn new(timing: &ResourceTiming, finished: f64) -> Self {
+        let phase = |start: f64, end: f64| if start >= 0. { end - start } else { -1. };
+        let blocked = [timing.dns_start, timing.connect_start, timing.send_start]
+            .into_iter()
+            .find(|start| *start >= 0.)
+            .unwrap_or_default();
+        Self {
+            blocked,
+            dns: phase(timing.dns_start, timing.dns_end),
+            connect: phase(timing.connect_start, timing.connect_end),
+            send: timing.send_end - timing.send_start,
+            wait: timing.receive_headers_end - timing.send_end,
+            receive: ((finished - timing.request_time) * 1000. - timing.receive_headers_end)
+                .max(0.),
+            ssl: phase(timing.ssl_start, timing.ssl_end),
+        }
+    }
+
+    /// Timings of a request whose phases are unknown, like one served from
+    /// the cache.
+    fn unknown(duration: f64) -> Self {
+        Self {
+            blocked: -1.,
+            dns: -1.,
+            connect: -1.,
+            send: 0.,
+            wait: duration,
+            receive: 0.,
+            ssl: -1.,
+        }
+    }
+
+    /// The total time of the request, `ssl` is already part of `connect`.
+    fn total(&self) -> f64 {
+        [
+            self.blocked,
+            self.dns,
+            self.connect,
+            self.send,
+            self.wait,
+            self.receive,
+        ]
+        .into_iter()
+        .filter(|t| *t > 0.)
+        .sum()
+    }
+}
+
+/// A request that is still in flight.
+#[derive(Debug)]
+struct PendingEntry {
+    page: usize,
+    started_date_time: String,
+    /// When the request was sent, in seconds of the browser's monotonic clock
+    started: f64,
+    request: HarRequest,
+    response: Option<Response>,
+}
+
+impl PendingEntry {
+    /// Turns this request into an entry once it finished, failed or was
+    /// redirected with the `redirect` response.
+    fn finish(self, redirect: Option<&Response>, finished: f64) -> (usize, HarEntry) {
+        let PendingEntry {
+            page,
+            started_date_time,
+            started,
+            mut request,
+            response: received,
+        } = self;
+        let response = redirect.or(received.as_ref());
+        if let Some(protocol) = response.and_then(|response| response.protocol.as_deref()) {
+            // the request is sent with the protocol the response arrived with
+            request.http_version = http_version(Some(protocol));
+        }
+        let timings = response
+            .and_then(|response| response.timing.as_ref())
+            .map(|timing| HarTimings::new(timing, finished))
+            .unwrap_or_else(|| HarTimings::unknown(((finished - started) * 1000.).max(0.)));
+        let entry = HarEntry {
+            pageref: Some(page_id(page)),
+            started_date_time,
+            time: timings.total(),
+            request,
+            response: response.map(har_response).unwrap_or_else(|| HarResponse {
+                status: 0,
+                status_text: String::new(),
+                http_version: String::new(),
+                cookies: Vec::new(),
+                headers: Vec::new(),
+                content: HarContent {
+                    size: 0,
+                    mime_type: String::new(),
+                    text: None,
+                    encoding: None,
+                },
+                redirect_url: String::new(),
+                headers_size: -1,
+                body_size: -1,
+            }),
+            cache: HarCache::default(),
+            timings,
+            server_ip_address: response.and_then(|r| r.remote_ip_address.clone()),
+        };
+        (page, entry)
+    }
+}
+
+/// A page whose network events are recorded.
+#[derive(Debug)]
+struct RecordedPage {
+    page: Page,
+    requests: EventStream<EventRequestWillBeSent>,
+    responses: EventStream<EventResponseReceived>,
+    finished: EventStream<EventLoadingFinished>,
+    failed: EventStream<EventLoadingFailed>,
+}
+
+impl RecordedPage {
+    /// Subscribes to the network events of the page, returns it with the
+    /// HAR page that is still missing its id.
+    async fn new(page: &Page) -> Result<(Self, HarPage)> {
+        let recorded = RecordedPage {
+            page: page.clone(),
+            requests: page.event_listener().await?,
+            responses: page.event_listener().await?,
+            finished: page.event_listener().await?,
+            failed: page.event_listener().await?,
+        };
+        let har_page = HarPage {
+            started_date_time: iso8601(now()),
+            id: String::new(),
+            title: page.url().await?.unwrap_or_default(),
+            page_timings: HarPageTimings::default(),
+        };
+        Ok((recorded, har_page))
+    }
+}
+
+/// A finished entry and, if the response body can still be retrieved, the
+/// request it belongs to.
+#[derive(Debug)]
+struct FinishedEntry {
+    page: usize,
+    request_id: Option<RequestId>,
+    entry: HarEntry,
+}
+
+/// Records the network traffic of pages.
+#[derive(Debug)]
+pub struct HarRecorder {
+    pages: Vec<RecordedPage>,
+    har_pages: Vec<HarPage>,
+    pending: HashMap<(usize, RequestId), PendingEntry>,
+    finished: Vec<FinishedEntry>,
+    content: bool,
+    /// Pages of the recorded contexts that were opened after the recording
+    /// started
+    new_pages: UnboundedReceiver<(RecordedPage, HarPage)>,
+    new_pages_tx: UnboundedSender<(RecordedPage, HarPage)>,
+}
+
+impl HarRecorder {
+    /// A recorder that is not attached to any page yet.
+    pub fn new() -> Self {
+        let (new_pages_tx, new_pages) = unbounded();
+        Self {
+            pages: Vec::new(),
+            har_pages: Vec::new(),
+            pending: Default::default(),
+            finished: Vec::new(),
+            content: true,
+            new_pages,
+            new_pages_tx,
+        }
+    }
+
+    /// Starts recording the requests of the page.
+    pub async fn start(page: &Page) -> Result<Self> {
+        let mut recorder = Self::new();
+        recorder.record(page).await?;
+        Ok(recorder)
+    }
+
+    /// Whether to include the response bodies in the HAR, default is true.
+    pub fn with_content(mut self, content: bool) -> Self {
+        self.content = content;
+        self
+    }
+
+    /// Starts recording the requests of another page as well.
+    pub async fn record(&mut self, page: &Page) -> Result<&mut Self> {
+        let (recorded, har_page) = RecordedPage::new(page).await?;
+        self.push(recorded, har_page);
+        Ok(self)
+    }
+
+    fn push(&mut self, recorded: RecordedPage, mut har_page: HarPage) {
+        har_page.id = page_id(self.pages.len());
+        self.har_pages.push(har_page);
+        self.pages.push(recorded);
+    }
+
+    /// Starts recording the requests of all pages in the browser context,
+    /// including the pages that are opened later.
+    ///
+    /// New pages are recorded from a hook registered with
+    /// [`Browser::on_new_page`], so requests of pages opened by the sites
+    /// may be missing if they started loading before the hook ran.
+    pub async fn record_context(
+        &mut self,
+        browser: &Browser,
+        context: &BrowserContextId,
+    ) -> Result<&mut Self> {
+        let tx = self.new_pages_tx.clone();
+        let hook_context = context.clone();
+        browser
+            .on_new_page(move |page| {
+                let tx = tx.clone();
+                let context = hook_context.clone();
+                async move {
+                    // the hook outlives the recorder
+                    if tx.is_closed() || !in_context(&page, &context).await? {
+                        return Ok(());
+                    }
+                    let _ = tx.unbounded_send(RecordedPage::new(&page).await?);
+                    Ok(())
+                }
+            })
+            .await?;
+        for page in browser.pages().await? {
+            if !self.is_recorded(&page) && in_context(&page, context).await? {
+                self.record(&page).await?;
+            }
+        }
+        Ok(self)
+    }
+
+    fn is_recorded(&self, page: &Page) -> bool {
+        self.pages
+            .iter()
+            .any(|recorded| recorded.page.target_id() == page.target_id())
+    }
+
+    /// Processes all network events that are ready.
+    fn drain(&mut self) {
+        // a page opened while the context was enumerated is reported twice
+        while let Ok((recorded, har_page)) = self.new_pages.try_recv() {
+            if !self.is_recorded(&recorded.page) {
+                self.push(recorded, har_page);
+            }
+        }
+        for idx in 0..self.pages.len() {
+            while let Some(Some(event)) = self.pages[idx].requests.next().now_or_never() {
+                self.on_request(idx, &event);
+            }
+            while let Some(Some(event)) = self.pages[idx].responses.next().now_or_never() {
+                if let Some(pending) = self.pending.get_mut(&(idx, event.request_id.clone())) {
+                    pending.response = Some(event.response.clone());
+                }
+            }
+            while let Some(Some(event)) = self.pages[idx].finished.next().now_or_never() {
+                if let Some(pending) = self.pending.remove(&(idx, event.request_id.clone())) {
+                    let (page, mut entry) = pending.finish(None, *event.timestamp.inner());
+                    entry.response.body_size = event.encoded_data_length as i64;
+                    self.finished.push(FinishedEntry {
+                        page,
+                        request_id: Some(event.request_id.clone()),
+                        entry,
+                    });
+                }
+            }
+            while let Some(Some(event)) = self.pages[idx].failed.next().now_or_never() {
+                if let Some(pending) = self.pending.remove(&(idx, event.request_id.clone())) {
+                    let received = pending.response.is_some();
+                    let (page, mut entry) = pending.finish(None, *event.timestamp.inner());
+                    if !received {
+                        entry.response.status_text = event.error_text.clone();
+                    }
+                    self.finished.push(FinishedEntry {
+                        page,
+                        request_id: None,
+                        entry,
+                    });
+                }
+            }
+        }
+    }
+
+    fn on_request(&mut self, page: usize, event: &EventRequestWillBeSent) {
+        let key = (page, event.request_id.clone());
+        if let Some(redirect) = event.redirect_response.as_ref() {
+            // the request id is reused for the redirected request
+            if let Some(pending) = self.pending.remove(&key) {
+                let (page, mut entry) = pending.finish(Some(redirect), *event.timestamp.inner());
+                entry.response.redirect_url = event.request.url.clone();
+                self.finished.push(FinishedEntry {
+                    page,
+                    request_id: None,
+                    entry,
+                });
+            }
+        }
+        let request = &event.request;
+        let headers = har_headers(&request.headers);
+        let post_data = request.post_data.clone().map(|text| HarPostData {
+            mime_type: find_header(&headers, "content-type")
+                .unwrap_or_default()
+                .to_string(),
+            text,
+        });
+        let query_string = url::Url::parse(&request.url)
+            .map(|url| {
+                url.query_pairs()
+                    .map(|(name, value)| HarHeader {
+                        name: name.into_owned(),
+                        value: value.into_owned(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let request = HarRequest {
+            method: request.method.clone(),
+            url: request.url.clone(),
+            // only known once the response arrived
+            http_version: http_version(None),
+            cookies: Vec::new(),
+            headers,
+            query_string,
+            body_size: post_data
+                .as_ref()
+                .map(|data| data.text.len() as i64)
+                .unwrap_or_default(),
+            post_data,
+            headers_size: -1,
+        };
+        self.pending.insert(
+            key,
+            PendingEntry {
+                page,
+                started_date_time: iso8601(*event.wall_time.inner()),
+                started: *event.timestamp.inner(),
+                request,
+                response: None,
+            },
+        );
+    }
+
+    /// Returns the HAR with all requests that finished so far.
+    ///
+    /// The response bodies are retrieved from the browser, which only keeps
+    /// them for a limited time.
+    pub async fn har(&mut self) -> Result<Har> {
+        self.drain();
+        let mut entries = Vec::with_capacity(self.finished.len());
+        for finished in &self.finished {
+            let mut entry = finished.entry.clone();
+            if let (true, Some(request_id)) = (self.content, finished.request_id.as_ref()) {
+                if let Ok(body) = self.pages[finished.page]
+                    .page
+                    .response_body(request_id.clone())
+                    .await
+                {
+                    entry.response.content.size = body.len() as i64;
+                    match String::from_utf8(body) {
+                        Ok(text) => entry.response.content.text = Some(text),
+                        Err(err) => {
+                            entry.response.content.text = Some(base64::encode(err.as_bytes()));
+                            entry.response.content.encoding = Some("base64".to_string());
+                        }
+                    }
+                }
+            }
+            entries.push(entry);
+        }
+        entries.sort_by(|a, b| a.started_date_time.cmp(&b.started_date_time));
+        Ok(Har {
+            log: HarLog {
+                version: "1.2".to_string(),
+                creator: HarCreator::default(),
+                pages: self.har_pages.clone(),
+                entries,
+            },
+        })
+    }
+
+    /// Writes the HAR with all requests that finished so far to `output`.
+    pub async fn save(&mut self, output: impl AsRef<Path>) -> Result<Har> {
+        let har = self.har().await?;
+        utils::write(output.as_ref(), serde_json::to_vec_pretty(&har)?).await?;
+        Ok(har)
+    }
+}
+
+impl Default for HarRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Whether the page belongs to the browser context.
+async fn in_context(page: &Page, context: &BrowserContextId) -> Result<bool> {
+    let info = page
+        .execute(GetTargetInfoParams {
+            target_id: Some(page.target_id().clone()),
+        })
+        .await?
+        .result
+        .target_info;
+    Ok(info.browser_context_id.as_ref() == Some(context))
+}
+
+fn page_id(page: usize) -> String {
+    format!("page_{}", page + 1)
+}
+
+fn har_response(response: &Response) -> HarResponse {
+    let headers = har_headers(&response.headers);
+    HarResponse {
+        status: response.status,
+        status_text: response.status_text.clone(),
+        http_version: http_version(response.protocol.as_deref()),
+        cookies: Vec::new(),
+        content: HarContent {
+            size: response.encoded_data_length as i64,
+            mime_type: response.mime_type.clone(),
+            text: None,
+            encoding: None,
+        },
+        redirect_url: find_header(&headers, "location")
+            .unwrap_or_default()
+            .to_string(),
+        headers,
+        headers_size: -1,
+        body_size: -1,
+    }
+}
+
+fn har_headers(headers: &Headers) -> Vec<HarHeader> {
+    headers
+        .inner()
+        .as_object()
+        .map(|headers| {
+            headers
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some(HarHeader {
+                        name: name.clone(),
+                        value: value.as_str()?.to_string(),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn find_header<'a>(headers: &'a [HarHeader], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|header| header.name.eq_ignore_ascii_case(name))
+        .map(|header| header.value.as_str())
+}
+
+/// Converts the protocol reported by the browser into an HTTP version.
+fn http_version(protocol: Option<&str>) -> String {
+    match protocol {
+        Some("h2") => "HTTP/2.0".to_string(),
+        Some("h3") => "HTTP/3.0".to_string(),
+        Some(protocol) if !protocol.is_empty() => protocol.to_uppercase(),
+        _ => "HTTP/1.1".to_string(),
+    }
+}
+
+fn now() -> f64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs_f64())
+        .unwrap_or_default()
+}
+
+/// Formats seconds since the unix epoch as an ISO 8601 date in UTC.
+fn iso8601(secs: f64) -> String {
+    let millis = (secs * 1000.).round() as i64;
+    let days = millis.div_euclid(86_400_000);
+    let ms_of_day = millis.rem_euclid(86_400_000);
+
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        ms_of_day / 3_600_000,
+        ms_of_day / 60_000 % 60,
+        ms_of_day / 1000 % 60,
+        ms_of_day % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(iso8601(0.), "1970-01-01T00:00:00.000Z");
+        assert_eq!(iso8601(1_600_000_000.123), "2020-09-13T12:26:40.123Z");
+        assert_eq!(iso8601(951_782_400.), "2000-02-29T00:00:00.000Z");
+    }
+
+    #[test]
+    fn computes_timings() {
+        let timing = ResourceTiming::builder()
+            .request_time(10.)
+            .proxy_start(-1.)
+            .proxy_end(-1.)
+            .dns_start(1.)
+            .dns_end(3.)
+            .connect_start(3.)
+            .connect_end(10.)
+            .ssl_start(5.)
+            .ssl_end(10.)
+            .worker_start(-1.)
+            .worker_ready(-1.)
+            .worker_fetch_start(-1.)
+            .worker_respond_with_settled(-1.)
+            .send_start(11.)
+            .send_end(12.)
+            .push_start(0.)
+            .push_end(0.)
+            .receive_headers_end(50.)
+            .build()
+            .unwrap();
+        let timings = HarTimings::new(&timing, 10.1);
+        assert_eq!(timings.blocked, 1.);
+        assert_eq!(timings.dns, 2.);
+        assert_eq!(timings.connect, 7.);
+        assert_eq!(timings.ssl, 5.);
+        assert_eq!(timings.send, 1.);
+        assert_eq!(timings.wait, 38.);
+        assert!((timings.receive - 50.).abs() < 1e-6);
+        assert!((timings.total() - 99.).abs() < 1e-6);
+    }
+
+    fn response(protocol: Option<&str>) -> Response {
+        let mut response = serde_json::json!({
+            "url": "https://example.com/",
+            "status": 301,
+            "statusText": "Moved Permanently",
+            "headers": {"Location": "https://example.org/"},
+            "mimeType": "text/html",
+            "connectionReused": false,
+            "connectionId": 0,
+            "encodedDataLength": 0,
+            "securityState": "secure",
+        });
+        if let Some(protocol) = protocol {
+            response["protocol"] = protocol.into();
+        }
+        serde_json::from_value(response).unwrap()
+    }
+
+    fn pending(response: Option<Response>) -> PendingEntry {
+        PendingEntry {
+            page: 0,
+            started_date_time: iso8601(0.),
+            started: 0.,
+            request: HarRequest {
+                method: "GET".to_string(),
+                url: "https://example.com/".to_string(),
+                http_version: http_version(None),
+                cookies: Vec::new(),
+                headers: Vec::new(),
+                query_string: Vec::new(),
+                post_data: None,
+                headers_size: -1,
+                body_size: 0,
+            },
+            response,
+        }
+    }
+
+    #[test]
+    fn serializes_har_field_names() {
+        let (_, entry) = pending(Some(response(Some("h2")))).finish(None, 0.);
+        let json = serde_json::to_value(entry).unwrap();
+        assert_eq!(json["pageref"], "page_1");
+        assert_eq!(json["startedDateTime"], "1970-01-01T00:00:00.000Z");
+        assert_eq!(json["response"]["redirectURL"], "https://example.org/");
+        assert_eq!(json["response"]["httpVersion"], "HTTP/2.0");
+        assert_eq!(json["request"]["queryString"], serde_json::json!([]));
+        assert!(json.get("serverIPAddress").is_none());
+    }
+
+    #[test]
+    fn takes_http_version_from_response() {
+        let (_, entry) = pending(Some(response(Some("h3")))).finish(None, 0.);
+        assert_eq!(entry.request.http_version, "HTTP/3.0");
+        assert_eq!(entry.response.http_version, "HTTP/3.0");
+
+        let (_, entry) = pending(None).finish(Some(&response(Some("http/1.0"))), 0.);
+        assert_eq!(entry.request.http_version, "HTTP/1.0");
+
+        let (_, entry) = pending(Some(response(None))).finish(None, 0.);
+        assert_eq!(entry.request.http_version, "HTTP/1.1");
+        assert_eq!(entry.response.http_version, "HTTP/1.1");
+    }
+}
@@ -64,6 +64,10 @@
 use crate::handler::http::HttpRequest;
 use std::sync::Arc;
 
+// lets the unit tests use the derives, which refer to `::chromiumoxide`
+#[cfg(all(test, feature = "derive"))]
+extern crate self as chromiumoxide;
+
 /// reexport the generated cdp types
 pub use chromiumoxide_cdp::cdp;
 pub use chromiumoxide_types::{self as types, Binary, Command, Method, MethodType};
@@ -74,20 +78,86 @@ pub use crate::element::Element;
 pub use crate::error::Result;
 pub use crate::handler::Handler;
 pub use crate::page::Page;
+#[cfg(feature = "testing")]
+pub use chromiumoxide_macros::test;
+/// Derives the traits of a custom event, see
+/// [`Page::event_listener`](crate::page::Page::event_listener).
+///
+/// ```no_run
+/// # use chromiumoxide::error::Result;
+/// # use chromiumoxide::page::Page;
+/// # use chromiumoxide::types::MethodType;
+/// # use futures::StreamExt;
+/// # use serde::Deserialize;
+/// #[derive(Debug, Deserialize, chromiumoxide::CustomEvent)]
+/// #[event(method = "Vendor.somethingHappened")]
+/// struct SomethingHappened {
+///     name: String,
+/// }
+///
+/// # async fn demo(page: Page) -> Result<()> {
+/// assert_eq!(SomethingHappened::method_id(), "Vendor.somethingHappened");
+/// let mut events = page.event_listener::<SomethingHappened>().await?;
+/// while let Some(event) = events.next().await {
+///     println!("{}", event.name);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[cfg(feature = "derive")]
+pub use chromiumoxide_macros::CustomEvent;
 
+pub mod accessibility;
+pub mod assertions;
 pub mod auth;
+pub mod binding;
 pub mod browser;
+pub mod cache;
+pub mod client_cert;
 pub(crate) mod cmd;
 pub mod conn;
+pub mod console;
+pub mod context;
+pub mod coverage;
+pub mod devices;
+pub mod dialog;
 pub mod element;
 pub mod error;
+pub mod fetch;
+#[cfg(feature = "fetcher")]
 pub mod fetcher;
+pub mod file_chooser;
+pub mod frame;
+pub mod gestures;
 pub mod handler;
+pub mod har;
 pub mod js;
 pub mod keys;
 pub mod layout;
 pub mod listeners;
+pub mod locales;
+pub mod media;
+pub mod monitor;
+pub mod mutation;
+pub mod notification;
 pub mod page;
+pub mod page_error;
+pub mod paginate;
+pub mod permission;
+pub mod proxy;
+pub mod response;
+pub mod route;
+pub mod scheduler;
+pub mod sitemap;
+pub mod storage_state;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tracer;
+pub mod user_agent;
 pub(crate) mod utils;
+#[cfg(feature = "image")]
+pub mod visual_hash;
+pub mod websocket;
+pub mod worker;
 
 pub type ArcHttpRequest = Option<Arc<HttpRequest>>;
@@ -1,46 +1,104 @@
+use std::any::{Any, TypeId};
 use std::collections::{HashMap, VecDeque};
 use std::fmt;
-use std::marker::PhantomData;
+use std::ops::Deref;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 
 use futures::channel::mpsc::{SendError, UnboundedReceiver, UnboundedSender};
+use futures::task::AtomicWaker;
 use futures::{Sink, Stream};
 
 use chromiumoxide_cdp::cdp::{Event, EventKind, IntoEventKind};
 use chromiumoxide_types::MethodId;
 
+/// An event together with its sequence number.
+///
+/// The events of a session, like those of a page or of the browser, are
+/// numbered in the order they were received, so events of different streams
+/// can be put back in order.
+#[derive(Debug)]
+pub struct Sequenced<T: ?Sized> {
+    sequence: u64,
+    event: Arc<T>,
+}
+
+impl<T: ?Sized> Sequenced<T> {
+    pub(crate) fn new(sequence: u64, event: Arc<T>) -> Self {
+        Self { sequence, event }
+    }
+
+    /// The number of the event within its session, later events have higher
+    /// numbers
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn event(&self) -> &Arc<T> {
+        &self.event
+    }
+
+    pub fn into_event(self) -> Arc<T> {
+        self.event
+    }
+}
+
+impl<T: ?Sized> Clone for Sequenced<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sequence: self.sequence,
+            event: Arc::clone(&self.event),
+        }
+    }
+}
+
+impl<T: ?Sized> Deref for Sequenced<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.event
+    }
+}
+
 /// All the currently active listeners
 #[derive(Debug, Default)]
 pub struct EventListeners {
-    /// Tracks the listeners for each event identified by the key
-    listeners: HashMap<MethodId, Vec<EventListener>>,
+    /// The listeners of each event identified by the key, grouped by the type
+    /// they receive the event as
+    listeners: HashMap<MethodId, Vec<Box<dyn Listeners>>>,
+    /// The sequence number of the last event
+    sequence: u64,
 }
 
 impl EventListeners {
     /// Register a subscription for a method
     pub fn add_listener(&mut self, req: EventListenerRequest) {
         let EventListenerRequest {
-            listener,
-            method,
-            kind,
+            listener, method, ..
         } = req;
-        let subs = self.listeners.entry(method).or_insert_with(Vec::new);
-        subs.push(EventListener {
-            listener,
-            kind,
-            queued_events: Default::default(),
-        });
+        let groups = self.listeners.entry(method).or_default();
+        if let Some(group) = groups
+            .iter_mut()
+            .find(|group| group.event_type() == listener.event_type())
+        {
+            group.append(listener);
+        } else {
+            groups.push(listener);
+        }
     }
 
     /// Queue in a event that should be send to all listeners
     pub fn start_send<T: Event>(&mut self, event: T) {
-        if let Some(subscriptions) = self.listeners.get_mut(&T::method_id()) {
-            let event: Arc<dyn Event> = Arc::new(event);
-            subscriptions
-                .iter_mut()
-                .for_each(|sub| sub.start_send(Arc::clone(&event)));
+        self.sequence += 1;
+        if let Some(group) = self
+            .listeners
+            .get_mut(&T::method_id())
+            .into_iter()
+            .flatten()
+            .find_map(|group| group.as_any_mut().downcast_mut::<TypedListeners<T>>())
+        {
+            group.start_send(Sequenced::new(self.sequence, Arc::new(event)));
         }
     }
 
@@ -51,26 +109,18 @@ impl EventListeners {
         method: &str,
         val: serde_json::Value,
     ) -> serde_json::Result<()> {
-        if let Some(subscriptions) = self.listeners.get_mut(method) {
-            let mut event = None;
-            if let Some(json_to_arc_event) = subscriptions
-                .iter()
-                .filter_map(|sub| {
-                    if let EventKind::Custom(conv) = &sub.kind {
-                        Some(conv)
-                    } else {
-                        None
-                    }
-                })
-                .next()
-            {
-                event = Some(json_to_arc_event(val)?);
-            }
-            if let Some(event) = event {
-                subscriptions
-                    .iter_mut()
-                    .filter(|sub| sub.kind.is_custom())
-                    .for_each(|sub| sub.start_send(Arc::clone(&event)));
+        self.sequence += 1;
+        if let Some(groups) = self.listeners.get_mut(method) {
+            let mut groups = groups
+                .iter_mut()
+                .filter(|group| group.is_custom())
+                .peekable();
+            while let Some(group) = groups.next() {
+                if groups.peek().is_some() {
+                    group.send_custom(self.sequence, val.clone())?;
+                } else {
+                    return group.send_custom(self.sequence, val);
+                }
             }
         }
         Ok(())
@@ -79,66 +129,214 @@ impl EventListeners {
     /// Drains all queued events and does the housekeeping when the receiver
     /// part of a subscription is dropped
     pub fn poll(&mut self, cx: &mut Context<'_>) {
-        for subscriptions in self.listeners.values_mut() {
-            for n in (0..subscriptions.len()).rev() {
-                let mut sub = subscriptions.swap_remove(n);
-                match sub.poll(cx) {
-                    Poll::Ready(Err(err)) => {
-                        if !err.is_disconnected() {
-                            subscriptions.push(sub)
-                        }
-                    }
-                    _ => subscriptions.push(sub),
-                }
+        for groups in self.listeners.values_mut() {
+            groups.retain_mut(|group| group.poll(cx));
+        }
+    }
+}
+
+/// The listeners that receive an event as the type `T`
+struct TypedListeners<T> {
+    listeners: Vec<Box<dyn Listener<T>>>,
+    /// How to convert the json of a custom event
+    kind: EventKind,
+}
+
+impl<T> TypedListeners<T> {
+    fn start_send(&mut self, event: Sequenced<T>) {
+        for listener in &mut self.listeners {
+            listener.start_send(&event);
+        }
+    }
+}
+
+/// The type erased `TypedListeners` of an event
+trait Listeners: Send {
+    /// The type the listeners receive the event as
+    fn event_type(&self) -> TypeId;
+
+    fn is_custom(&self) -> bool;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    /// Adds the listeners of the same type
+    fn append(&mut self, other: Box<dyn Listeners>);
+
+    /// Converts the json of a custom event once for all listeners
+    fn send_custom(&mut self, sequence: u64, val: serde_json::Value) -> serde_json::Result<()>;
+
+    /// Drains the queued events, returns whether there are listeners left
+    fn poll(&mut self, cx: &mut Context<'_>) -> bool;
+}
+
+impl<T: Event> Listeners for TypedListeners<T> {
+    fn event_type(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn is_custom(&self) -> bool {
+        self.kind.is_custom()
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn append(&mut self, other: Box<dyn Listeners>) {
+        if let Ok(other) = other.into_any().downcast::<Self>() {
+            self.listeners.extend(other.listeners);
+        }
+    }
+
+    fn send_custom(&mut self, sequence: u64, val: serde_json::Value) -> serde_json::Result<()> {
+        if let EventKind::Custom(conv) = &self.kind {
+            match conv(val)?.into_any_arc().downcast::<T>() {
+                Ok(event) => self.start_send(Sequenced::new(sequence, event)),
+                Err(_) => tracing::warn!("Custom event {} has the wrong type", T::method_id()),
             }
         }
+        Ok(())
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> bool {
+        self.listeners
+            .retain_mut(|listener| match listener.poll(cx) {
+                Poll::Ready(Err(err)) => !err.is_disconnected(),
+                _ => true,
+            });
+        !self.listeners.is_empty()
+    }
+}
+
+impl fmt::Debug for dyn Listeners {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Listeners").finish()
     }
 }
 
 pub struct EventListenerRequest {
-    listener: UnboundedSender<Arc<dyn Event>>,
+    listener: Box<dyn Listeners>,
     method: MethodId,
-    kind: EventKind,
+    control: SubscriptionControl,
 }
 
 impl EventListenerRequest {
-    pub fn new<T: IntoEventKind>(listener: UnboundedSender<Arc<dyn Event>>) -> Self {
+    pub fn new<T: IntoEventKind>(listener: UnboundedSender<Sequenced<T>>) -> Self {
+        Self::with_listener(EventListener::new(listener, |event: &Sequenced<T>| {
+            Some(event.clone())
+        }))
+    }
+
+    /// Only the events that match the predicate are sent to the listener
+    pub fn filtered<T: IntoEventKind>(
+        listener: UnboundedSender<Sequenced<T>>,
+        predicate: impl Fn(&T) -> bool + Send + 'static,
+    ) -> Self {
+        Self::with_listener(EventListener::new(listener, move |event: &Sequenced<T>| {
+            predicate(event).then(|| event.clone())
+        }))
+    }
+
+    /// The events are mapped before they are sent to the listener, events
+    /// mapped to `None` are skipped
+    pub fn mapped<T: IntoEventKind, U: Send + 'static>(
+        listener: UnboundedSender<U>,
+        map: impl Fn(&T) -> Option<U> + Send + 'static,
+    ) -> Self {
+        Self::with_listener(EventListener::new(listener, move |event: &Sequenced<T>| {
+            map(event)
+        }))
+    }
+
+    fn with_listener<T: IntoEventKind, M: Send + 'static>(listener: EventListener<T, M>) -> Self {
+        let control = listener.control.clone();
         Self {
-            listener,
+            listener: Box::new(TypedListeners {
+                listeners: vec![Box::new(listener)],
+                kind: T::event_kind(),
+            }),
             method: T::method_id(),
-            kind: T::event_kind(),
+            control,
         }
     }
+
+    /// Pauses and resumes the subscription, see [`EventStream::pause`]
+    pub fn control(&self) -> &SubscriptionControl {
+        &self.control
+    }
 }
 
 impl fmt::Debug for EventListenerRequest {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EventListenerRequest")
             .field("method", &self.method)
-            .field("kind", &self.kind)
             .finish()
     }
 }
 
-/// Represents a single event listener
-pub struct EventListener {
+/// A listener of the events of type `T`
+trait Listener<T>: Send {
+    /// queue in a new event
+    fn start_send(&mut self, event: &Sequenced<T>);
+
+    /// Drains all queued events and begins the process of sending them to the
+    /// sink.
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError>>;
+}
+
+/// Turns an event into the message of a listener
+type EventMap<T, M> = Box<dyn Fn(&Sequenced<T>) -> Option<M> + Send>;
+
+/// Represents a single event listener, which receives the events of type `T`
+/// as the messages `M` of its channel
+pub struct EventListener<T, M = Sequenced<T>> {
     /// the sender half of the event channel
-    listener: UnboundedSender<Arc<dyn Event>>,
+    listener: UnboundedSender<M>,
     /// currently queued events
-    queued_events: VecDeque<Arc<dyn Event>>,
-    /// For what kind of event this event is for
-    kind: EventKind,
+    queued_events: VecDeque<M>,
+    /// Turns an event into the message to send, if any
+    map: EventMap<T, M>,
+    /// Whether the subscription is paused
+    control: SubscriptionControl,
 }
 
-impl EventListener {
-    /// queue in a new event
-    pub fn start_send(&mut self, event: Arc<dyn Event>) {
-        self.queued_events.push_back(event)
+impl<T, M> EventListener<T, M> {
+    fn new(
+        listener: UnboundedSender<M>,
+        map: impl Fn(&Sequenced<T>) -> Option<M> + Send + 'static,
+    ) -> Self {
+        Self {
+            listener,
+            queued_events: Default::default(),
+            map: Box::new(map),
+            control: Default::default(),
+        }
     }
+}
 
-    /// Drains all queued events and begins the process of sending them to the
-    /// sink.
-    pub fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
+impl<T, M: Send> Listener<T> for EventListener<T, M> {
+    fn start_send(&mut self, event: &Sequenced<T>) {
+        let policy = self.control.policy();
+        if policy == Some(PausePolicy::Drop) {
+            return;
+        }
+        if let Some(msg) = (self.map)(event) {
+            self.queued_events.push_back(msg);
+            if let Some(PausePolicy::Buffer(capacity)) = policy {
+                while self.queued_events.len() > capacity {
+                    self.queued_events.pop_front();
+                }
+            }
+        }
+    }
+
+    fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError>> {
         loop {
             match Sink::poll_ready(Pin::new(&mut self.listener), cx) {
                 Poll::Ready(Ok(_)) => {}
@@ -150,6 +348,13 @@ impl EventListener {
                     return Poll::Pending;
                 }
             }
+            if self.control.is_paused() {
+                // woken up again on resume
+                self.control.inner.waker.register(cx.waker());
+                if self.control.is_paused() {
+                    return Poll::Ready(Ok(()));
+                }
+            }
             if let Some(event) = self.queued_events.pop_front() {
                 if let Err(err) = Sink::start_send(Pin::new(&mut self.listener), event) {
                     return Poll::Ready(Err(err));
@@ -161,16 +366,67 @@ impl EventListener {
     }
 }
 
-impl fmt::Debug for EventListener {
+impl<T, M> fmt::Debug for EventListener<T, M> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("EventListener").finish()
     }
 }
 
+/// What a paused subscription does with its events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PausePolicy {
+    /// The events are discarded until the subscription is resumed
+    #[default]
+    Drop,
+    /// Up to this many events are kept and delivered once the subscription
+    /// is resumed, beyond that the oldest are discarded
+    Buffer(usize),
+}
+
+/// Pauses and resumes an event subscription, shared by the stream and its
+/// listener in the handler.
+///
+/// Pausing doesn't remove the listener, so no events are missed around the
+/// pause other than those discarded by the [`PausePolicy`].
+#[derive(Debug, Clone, Default)]
+pub struct SubscriptionControl {
+    inner: Arc<ControlInner>,
+}
+
+#[derive(Debug, Default)]
+struct ControlInner {
+    /// The policy of the pause, `None` if not paused
+    paused: Mutex<Option<PausePolicy>>,
+    /// The handler, woken up to deliver the buffered events on resume
+    waker: AtomicWaker,
+}
+
+impl SubscriptionControl {
+    /// Stops delivering events until [`SubscriptionControl::resume`] is
+    /// called, the events are dropped or buffered according to the policy
+    pub fn pause(&self, policy: PausePolicy) {
+        *self.inner.paused.lock().unwrap() = Some(policy);
+    }
+
+    /// Delivers the buffered events and all events from now on
+    pub fn resume(&self) {
+        self.inner.paused.lock().unwrap().take();
+        self.inner.waker.wake();
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.policy().is_some()
+    }
+
+    fn policy(&self) -> Option<PausePolicy> {
+        *self.inner.paused.lock().unwrap()
+    }
+}
+
 /// The receiver part of an event subscription
 pub struct EventStream<T: IntoEventKind> {
-    events: UnboundedReceiver<Arc<dyn Event>>,
-    _marker: PhantomData<T>,
+    events: UnboundedReceiver<Sequenced<T>>,
+    control: SubscriptionControl,
 }
 
 impl<T: IntoEventKind> fmt::Debug for EventStream<T> {
@@ -180,38 +436,145 @@ impl<T: IntoEventKind> fmt::Debug for EventStream<T> {
 }
 
 impl<T: IntoEventKind> EventStream<T> {
-    pub fn new(events: UnboundedReceiver<Arc<dyn Event>>) -> Self {
+    pub fn new(events: UnboundedReceiver<Sequenced<T>>) -> Self {
         Self {
             events,
-            _marker: PhantomData,
+            control: Default::default(),
         }
     }
+
+    /// Uses the control of the subscription the events are received from
+    pub(crate) fn with_control(mut self, control: SubscriptionControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Pauses the subscription, for example while the consumer can't keep up.
+    ///
+    /// Events that are already queued for the stream can still be received.
+    pub fn pause(&self, policy: PausePolicy) {
+        self.control.pause(policy)
+    }
+
+    /// Resumes the paused subscription.
+    pub fn resume(&self) {
+        self.control.resume()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    /// A handle to pause and resume the subscription, also after the stream
+    /// was moved.
+    pub fn control(&self) -> &SubscriptionControl {
+        &self.control
+    }
+
+    /// Yields the events together with their sequence numbers.
+    pub fn sequenced(self) -> SequencedEventStream<T> {
+        SequencedEventStream { events: self }
+    }
 }
 
 impl<T: IntoEventKind + Unpin> Stream for EventStream<T> {
     type Item = Arc<T>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let pin = self.get_mut();
-        match Stream::poll_next(Pin::new(&mut pin.events), cx) {
-            Poll::Ready(Some(event)) => {
-                if let Ok(e) = event.into_any_arc().downcast() {
-                    Poll::Ready(Some(e))
-                } else {
-                    Poll::Pending
-                }
-            }
-            Poll::Ready(None) => Poll::Ready(None),
-            Poll::Pending => Poll::Pending,
+        Stream::poll_next(Pin::new(&mut self.get_mut().events), cx)
+            .map(|event| event.map(Sequenced::into_event))
+    }
+}
+
+/// An event subscription that yields the events with their sequence numbers,
+/// see [`EventStream::sequenced`].
+pub struct SequencedEventStream<T: IntoEventKind> {
+    events: EventStream<T>,
+}
+
+impl<T: IntoEventKind> SequencedEventStream<T> {
+    /// A handle to pause and resume the subscription, see
+    /// [`EventStream::pause`].
+    pub fn control(&self) -> &SubscriptionControl {
+        &self.events.control
+    }
+}
+
+impl<T: IntoEventKind> fmt::Debug for SequencedEventStream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SequencedEventStream").finish()
+    }
+}
+
+impl<T: IntoEventKind + Unpin> Stream for SequencedEventStream<T> {
+    type Item = Sequenced<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Stream::poll_next(Pin::new(&mut self.get_mut().events.events), cx)
+    }
+}
+
+/// The receiver part of an event subscription whose events are mapped in the
+/// handler, see [`Page::event_listener_mapped`](crate::page::Page::event_listener_mapped).
+pub struct MappedEventStream<U> {
+    events: UnboundedReceiver<U>,
+    control: SubscriptionControl,
+}
+
+impl<U> MappedEventStream<U> {
+    pub fn new(events: UnboundedReceiver<U>) -> Self {
+        Self {
+            events,
+            control: Default::default(),
         }
     }
+
+    /// Uses the control of the subscription the events are received from
+    pub(crate) fn with_control(mut self, control: SubscriptionControl) -> Self {
+        self.control = control;
+        self
+    }
+
+    /// Pauses the subscription, see [`EventStream::pause`].
+    pub fn pause(&self, policy: PausePolicy) {
+        self.control.pause(policy)
+    }
+
+    pub fn resume(&self) {
+        self.control.resume()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.control.is_paused()
+    }
+
+    /// A handle to pause and resume the subscription.
+    pub fn control(&self) -> &SubscriptionControl {
+        &self.control
+    }
+}
+
+impl<U> fmt::Debug for MappedEventStream<U> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MappedEventStream").finish()
+    }
+}
+
+impl<U> Stream for MappedEventStream<U> {
+    type Item = U;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Stream::poll_next(Pin::new(&mut self.get_mut().events), cx)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use futures::{SinkExt, StreamExt};
 
-    use chromiumoxide_cdp::cdp::browser_protocol::animation::EventAnimationCanceled;
+    use chromiumoxide_cdp::cdp::browser_protocol::animation::{
+        EventAnimationCanceled, EventAnimationStarted,
+    };
     use chromiumoxide_cdp::cdp::CustomEvent;
     use chromiumoxide_types::MethodType;
 
@@ -225,8 +588,9 @@ mod tests {
         let event = EventAnimationCanceled {
             id: "id".to_string(),
         };
-        let msg: Arc<dyn Event> = Arc::new(event.clone());
-        tx.send(msg).await.unwrap();
+        tx.send(Sequenced::new(1, Arc::new(event.clone())))
+            .await
+            .unwrap();
         let next = stream.next().await.unwrap();
         assert_eq!(&*next, &event);
     }
@@ -253,8 +617,9 @@ mod tests {
         let event = MyCustomEvent {
             name: "my event".to_string(),
         };
-        let msg: Arc<dyn Event> = Arc::new(event.clone());
-        tx.send(msg).await.unwrap();
+        tx.send(Sequenced::new(1, Arc::new(event.clone())))
+            .await
+            .unwrap();
         let next = stream.next().await.unwrap();
         assert_eq!(&*next, &event);
     }
@@ -268,11 +633,7 @@ mod tests {
             id: "id".to_string(),
         };
 
-        listeners.add_listener(EventListenerRequest {
-            method: EventAnimationCanceled::method_id(),
-            kind: EventAnimationCanceled::event_kind(),
-            listener: tx,
-        });
+        listeners.add_listener(EventListenerRequest::new::<EventAnimationCanceled>(tx));
 
         listeners.start_send(event.clone());
 
@@ -291,4 +652,199 @@ mod tests {
         let next = stream.next().await.unwrap();
         assert_eq!(&*next, &event);
     }
+
+    #[async_std::test]
+    async fn delivers_events_by_type() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Clone, Eq, PartialEq, Deserialize)]
+        struct Canceled {
+            id: String,
+        }
+
+        impl MethodType for Canceled {
+            fn method_id() -> MethodId {
+                EventAnimationCanceled::method_id()
+            }
+        }
+        impl CustomEvent for Canceled {}
+
+        let mut listeners = EventListeners::default();
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        listeners.add_listener(EventListenerRequest::new::<EventAnimationCanceled>(tx));
+        let (tx, custom) = futures::channel::mpsc::unbounded();
+        listeners.add_listener(EventListenerRequest::new::<Canceled>(tx));
+        let (tx, other_custom) = futures::channel::mpsc::unbounded();
+        listeners.add_listener(EventListenerRequest::new::<Canceled>(tx));
+
+        listeners.start_send(EventAnimationCanceled {
+            id: "a".to_string(),
+        });
+        listeners
+            .try_send_custom(
+                &EventAnimationCanceled::method_id(),
+                serde_json::json!({"id": "b"}),
+            )
+            .unwrap();
+        futures::future::poll_fn(|cx| {
+            listeners.poll(cx);
+            Poll::Ready(())
+        })
+        .await;
+        drop(listeners);
+
+        let ids: Vec<_> = EventStream::<EventAnimationCanceled>::new(rx)
+            .map(|event| event.id.clone())
+            .collect()
+            .await;
+        assert_eq!(ids, ["a"]);
+        for rx in [custom, other_custom] {
+            let ids: Vec<_> = EventStream::<Canceled>::new(rx)
+                .map(|event| event.id.clone())
+                .collect()
+                .await;
+            assert_eq!(ids, ["b"]);
+        }
+    }
+
+    #[cfg(feature = "derive")]
+    #[async_std::test]
+    async fn derives_custom_events() {
+        use serde::Deserialize;
+
+        #[derive(Debug, Deserialize, crate::CustomEvent)]
+        #[event(method = "Custom.somethingHappened")]
+        struct SomethingHappened {
+            name: String,
+        }
+
+        assert_eq!(
+            SomethingHappened::method_id(),
+            MethodId::from("Custom.somethingHappened")
+        );
+        let mut listeners = EventListeners::default();
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        listeners.add_listener(EventListenerRequest::new::<SomethingHappened>(tx));
+        listeners
+            .try_send_custom(
+                &SomethingHappened::method_id(),
+                serde_json::json!({"name": "a"}),
+            )
+            .unwrap();
+        futures::future::poll_fn(|cx| {
+            listeners.poll(cx);
+            Poll::Ready(())
+        })
+        .await;
+        drop(listeners);
+
+        let names: Vec<_> = EventStream::<SomethingHappened>::new(rx)
+            .map(|event| event.name.clone())
+            .collect()
+            .await;
+        assert_eq!(names, ["a"]);
+    }
+
+    #[async_std::test]
+    async fn filters_and_maps_events() {
+        let mut listeners = EventListeners::default();
+        let (tx, filtered) = futures::channel::mpsc::unbounded();
+        listeners.add_listener(EventListenerRequest::filtered::<EventAnimationCanceled>(
+            tx,
+            |event| event.id != "b",
+        ));
+        let (tx, mapped) = futures::channel::mpsc::unbounded();
+        listeners.add_listener(EventListenerRequest::mapped(
+            tx,
+            |event: &EventAnimationCanceled| (event.id != "a").then_some(event.id.len()),
+        ));
+
+        for id in ["a", "b", "cc"] {
+            listeners.start_send(EventAnimationCanceled { id: id.to_string() });
+        }
+        futures::future::poll_fn(|cx| {
+            listeners.poll(cx);
+            Poll::Ready(())
+        })
+        .await;
+        drop(listeners);
+
+        let events: Vec<_> = EventStream::<EventAnimationCanceled>::new(filtered)
+            .sequenced()
+            .map(|event| (event.sequence(), event.id.clone()))
+            .collect()
+            .await;
+        assert_eq!(events, [(1, "a".to_string()), (3, "cc".to_string())]);
+        let lengths: Vec<_> = MappedEventStream::new(mapped).collect().await;
+        assert_eq!(lengths, [1, 2]);
+    }
+
+    #[async_std::test]
+    async fn pauses_subscriptions() {
+        let mut listeners = EventListeners::default();
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let req = EventListenerRequest::new::<EventAnimationCanceled>(tx);
+        let stream = EventStream::new(rx).with_control(req.control().clone());
+        listeners.add_listener(req);
+        let mut send = |ids: &[&str]| {
+            for id in ids {
+                listeners.start_send(EventAnimationCanceled { id: id.to_string() });
+            }
+            futures::executor::block_on(futures::future::poll_fn(|cx| {
+                listeners.poll(cx);
+                Poll::Ready(())
+            }));
+        };
+
+        send(&["a"]);
+        stream.pause(PausePolicy::Drop);
+        send(&["b"]);
+        stream.pause(PausePolicy::Buffer(2));
+        send(&["c", "d", "e"]);
+        assert!(stream.is_paused());
+        stream.resume();
+        send(&["f"]);
+        drop(listeners);
+
+        let ids: Vec<_> = stream.map(|event| event.id.clone()).collect().await;
+        assert_eq!(ids, ["a", "d", "e", "f"]);
+    }
+
+    #[async_std::test]
+    async fn numbers_events() {
+        let (tx, rx) = futures::channel::mpsc::unbounded();
+        let mut listeners = EventListeners::default();
+        listeners.add_listener(EventListenerRequest::new::<EventAnimationCanceled>(tx));
+
+        // events without listeners are numbered as well
+        listeners.start_send(EventAnimationStarted {
+            animation: serde_json::from_value(serde_json::json!({
+                "id": "1",
+                "name": "",
+                "pausedState": false,
+                "playState": "running",
+                "playbackRate": 1.0,
+                "startTime": 0.0,
+                "currentTime": 0.0,
+                "type": "CSSAnimation",
+            }))
+            .unwrap(),
+        });
+        for id in ["a", "b"] {
+            listeners.start_send(EventAnimationCanceled { id: id.to_string() });
+        }
+        futures::future::poll_fn(|cx| {
+            listeners.poll(cx);
+            Poll::Ready(())
+        })
+        .await;
+
+        let events: Vec<_> = EventStream::<EventAnimationCanceled>::new(rx)
+            .sequenced()
+            .take(2)
+            .map(|event| (event.sequence(), event.id.clone()))
+            .collect()
+            .await;
+        assert_eq!(events, [(2, "a".to_string()), (3, "b".to_string())]);
+    }
 }
@@ -0,0 +1,298 @@
+//! Presets of regional settings and detection of a document's language.
+//!
+//! A [`Locale`] bundles the `Accept-Language` header, the language reported
+//! by `navigator.language`, the locale of `Intl` and the timezone of a
+//! region. [`Page::emulate_locale`](crate::page::Page::emulate_locale)
+//! applies all of them to a page, so geo-targeted sites serve the content of
+//! that region. [`Page::detect_language`](crate::page::Page::detect_language)
+//! tells which language the page's content is in.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::locales;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.emulate_locale(&locales::DE_DE).await?;
+//!     page.goto("https://example.com").await?;
+//!
+//!     let language = page.detect_language().await?;
+//!     if language.language() != Some("de") {
+//!         println!("no german content available");
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use serde::Deserialize;
+
+/// The regional settings of a locale to emulate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Locale {
+    /// The BCP 47 tag of the locale, like `de-DE`
+    pub locale: &'static str,
+    /// The `Accept-Language` header, whose first language is also reported
+    /// by `navigator.language`
+    pub accept_language: &'static str,
+    /// The IANA id of the timezone, like `Europe/Berlin`
+    pub timezone_id: &'static str,
+}
+
+/// English (United States)
+pub const EN_US: Locale = Locale {
+    locale: "en-US",
+    accept_language: "en-US,en;q=0.9",
+    timezone_id: "America/New_York",
+};
+
+/// English (United Kingdom)
+pub const EN_GB: Locale = Locale {
+    locale: "en-GB",
+    accept_language: "en-GB,en;q=0.9",
+    timezone_id: "Europe/London",
+};
+
+/// German (Germany)
+pub const DE_DE: Locale = Locale {
+    locale: "de-DE",
+    accept_language: "de-DE,de;q=0.9,en;q=0.8",
+    timezone_id: "Europe/Berlin",
+};
+
+/// French (France)
+pub const FR_FR: Locale = Locale {
+    locale: "fr-FR",
+    accept_language: "fr-FR,fr;q=0.9,en;q=0.8",
+    timezone_id: "Europe/Paris",
+};
+
+/// Spanish (Spain)
+pub const ES_ES: Locale = Locale {
+    locale: "es-ES",
+    accept_language: "es-ES,es;q=0.9,en;q=0.8",
+    timezone_id: "Europe/Madrid",
+};
+
+/// Italian (Italy)
+pub const IT_IT: Locale = Locale {
+    locale: "it-IT",
+    accept_language: "it-IT,it;q=0.9,en;q=0.8",
+    timezone_id: "Europe/Rome",
+};
+
+/// Portuguese (Brazil)
+pub const PT_BR: Locale = Locale {
+    locale: "pt-BR",
+    accept_language: "pt-BR,pt;q=0.9,en;q=0.8",
+    timezone_id: "America/Sao_Paulo",
+};
+
+/// Dutch (Netherlands)
+pub const NL_NL: Locale = Locale {
+    locale: "nl-NL",
+    accept_language: "nl-NL,nl;q=0.9,en;q=0.8",
+    timezone_id: "Europe/Amsterdam",
+};
+
+/// Japanese (Japan)
+pub const JA_JP: Locale = Locale {
+    locale: "ja-JP",
+    accept_language: "ja-JP,ja;q=0.9,en;q=0.8",
+    timezone_id: "Asia/Tokyo",
+};
+
+/// Chinese (China)
+pub const ZH_CN: Locale = Locale {
+    locale: "zh-CN",
+    accept_language: "zh-CN,zh;q=0.9,en;q=0.8",
+    timezone_id: "Asia/Shanghai",
+};
+
+/// Korean (South Korea)
+pub const KO_KR: Locale = Locale {
+    locale: "ko-KR",
+    accept_language: "ko-KR,ko;q=0.9,en;q=0.8",
+    timezone_id: "Asia/Seoul",
+};
+
+/// Russian (Russia)
+pub const RU_RU: Locale = Locale {
+    locale: "ru-RU",
+    accept_language: "ru-RU,ru;q=0.9,en;q=0.8",
+    timezone_id: "Europe/Moscow",
+};
+
+/// All locale presets of this module.
+pub const ALL: &[Locale] = &[
+    EN_US, EN_GB, DE_DE, FR_FR, ES_ES, IT_IT, PT_BR, NL_NL, JA_JP, ZH_CN, KO_KR, RU_RU,
+];
+
+/// Returns the preset with the given tag, like `de-DE`, compared
+/// case-insensitively.
+pub fn find(locale: &str) -> Option<&'static Locale> {
+    ALL.iter()
+        .find(|preset| preset.locale.eq_ignore_ascii_case(locale))
+}
+
+/// The language of a document, see `Page::detect_language`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+pub struct DocumentLanguage {
+    /// The language declared by the `lang` attribute of the document
+    /// element or a `Content-Language` meta tag
+    pub declared: Option<String>,
+    /// The ISO 639-1 code of the language detected from the content
+    #[serde(skip)]
+    pub detected: Option<&'static str>,
+}
+
+impl DocumentLanguage {
+    /// The primary ISO 639-1 code of the language, preferring the detected
+    /// language over the declared one, which is often left at a template's
+    /// default.
+    pub fn language(&self) -> Option<&str> {
+        self.detected.or_else(|| {
+            self.declared
+                .as_deref()
+                .and_then(|lang| lang.split(['-', '_']).next())
+                .filter(|lang| !lang.is_empty())
+        })
+    }
+}
+
+/// Common words of languages written in the latin alphabet
+const STOPWORDS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "the", "and", "of", "to", "is", "in", "that", "for", "with", "you",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "der", "die", "und", "das", "ist", "nicht", "mit", "ein", "auf", "sie",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "le", "la", "les", "et", "des", "est", "une", "pour", "dans", "vous",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "el", "los", "las", "y", "del", "es", "una", "para", "por", "con",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "il", "di", "che", "e", "della", "sono", "una", "per", "non", "gli",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            "o", "os", "e", "do", "da", "uma", "para", "com", "não", "você",
+        ],
+    ),
+    (
+        "nl",
+        &[
+            "de", "het", "een", "en", "van", "is", "niet", "op", "met", "voor",
+        ],
+    ),
+];
+
+/// Detects the language of the text, from its script for languages with
+/// their own and from common words for languages written in latin.
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let mut scripts = [0usize; 8];
+    for c in text.chars() {
+        let script = match c {
+            '\u{3040}'..='\u{30ff}' => 0,
+            '\u{ac00}'..='\u{d7af}' | '\u{1100}'..='\u{11ff}' => 1,
+            '\u{4e00}'..='\u{9fff}' => 2,
+            '\u{0400}'..='\u{04ff}' => 3,
+            '\u{0600}'..='\u{06ff}' => 4,
+            '\u{0370}'..='\u{03ff}' => 5,
+            '\u{0590}'..='\u{05ff}' => 6,
+            '\u{0e00}'..='\u{0e7f}' => 7,
+            _ => continue,
+        };
+        scripts[script] += 1;
+    }
+    let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+    // kana is mixed with han characters in japanese
+    if scripts[0] > 0 && scripts[0] + scripts[2] > letters / 4 {
+        return Some("ja");
+    }
+    if let Some((script, count)) = scripts.iter().enumerate().max_by_key(|(_, count)| **count) {
+        if *count > letters / 4 && *count > 0 {
+            return Some(["ja", "ko", "zh", "ru", "ar", "el", "he", "th"][script]);
+        }
+    }
+
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let (language, hits) = STOPWORDS
+        .iter()
+        .map(|(language, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|word| stopwords.contains(&word.as_str()))
+                .count();
+            (*language, hits)
+        })
+        .max_by_key(|(_, hits)| *hits)?;
+    // too few common words to tell
+    (hits >= 3 && hits * 20 >= words.len()).then_some(language)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_presets_by_tag() {
+        assert_eq!(find("de-de"), Some(&DE_DE));
+        assert_eq!(find("xx-XX"), None);
+    }
+
+    #[test]
+    fn detects_languages() {
+        assert_eq!(
+            detect_language("The order was shipped and is on the way to you."),
+            Some("en")
+        );
+        assert_eq!(
+            detect_language("Die Bestellung ist unterwegs und wird mit der Post geliefert, sie kommt nicht heute."),
+            Some("de")
+        );
+        assert_eq!(
+            detect_language(
+                "Votre commande est en route et la livraison est pour demain dans la matinée."
+            ),
+            Some("fr")
+        );
+        assert_eq!(detect_language("ご注文の商品は発送されました"), Some("ja"));
+        assert_eq!(detect_language("Ваш заказ отправлен"), Some("ru"));
+        assert_eq!(detect_language("404"), None);
+    }
+
+    #[test]
+    fn prefers_detected_language() {
+        let mut language = DocumentLanguage {
+            declared: Some("en-US".to_string()),
+            detected: None,
+        };
+        assert_eq!(language.language(), Some("en"));
+        language.detected = Some("de");
+        assert_eq!(language.language(), Some("de"));
+    }
+}
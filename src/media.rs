@@ -0,0 +1,156 @@
+//! Observing the media players of a page.
+//!
+//! Chromium reports the state of every `<audio>` and `<video>` player through
+//! the `Media` domain: the properties of a player like its codecs and
+//! dimensions, playback events like buffering, log messages and pipeline
+//! errors like failed decodes. A [`MediaEventStream`] merges all of these
+//! into a single stream of [`MediaEvent`]s.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::media::MediaEvent;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut media = page.media_events().await?;
+//!     page.goto("https://example.com/video").await?;
+//!     while let Some(event) = media.next().await {
+//!         if let MediaEvent::ErrorsRaised(ev) = event {
+//!             println!("player {:?} failed: {:?}", ev.player_id, ev.errors);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::{SelectAll, Stream, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::media::{
+    EventPlayerErrorsRaised, EventPlayerEventsAdded, EventPlayerMessagesLogged,
+    EventPlayerPropertiesChanged, EventPlayersCreated, PlayerId,
+};
+
+use crate::listeners::EventStream;
+
+/// An event of the `Media` domain.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MediaEvent {
+    /// New players were created, e.g. by adding a `<video>` to the document
+    PlayersCreated(Arc<EventPlayersCreated>),
+    /// Properties of a player changed, like its codecs or dimensions
+    PropertiesChanged(Arc<EventPlayerPropertiesChanged>),
+    /// A player emitted playback events, like buffering or pausing
+    EventsAdded(Arc<EventPlayerEventsAdded>),
+    /// A player logged messages
+    MessagesLogged(Arc<EventPlayerMessagesLogged>),
+    /// A player raised errors, like failures to decode the media
+    ErrorsRaised(Arc<EventPlayerErrorsRaised>),
+}
+
+impl MediaEvent {
+    /// The player this event is about, `None` for `PlayersCreated` which may
+    /// cover several players.
+    pub fn player_id(&self) -> Option<&PlayerId> {
+        match self {
+            MediaEvent::PlayersCreated(_) => None,
+            MediaEvent::PropertiesChanged(ev) => Some(&ev.player_id),
+            MediaEvent::EventsAdded(ev) => Some(&ev.player_id),
+            MediaEvent::MessagesLogged(ev) => Some(&ev.player_id),
+            MediaEvent::ErrorsRaised(ev) => Some(&ev.player_id),
+        }
+    }
+
+    /// Whether this event reports errors of a player.
+    pub fn is_error(&self) -> bool {
+        matches!(self, MediaEvent::ErrorsRaised(_))
+    }
+}
+
+/// A stream of all the `Media` domain events of a page.
+#[must_use = "streams do nothing unless polled"]
+pub struct MediaEventStream {
+    events: SelectAll<Pin<Box<dyn Stream<Item = MediaEvent> + Send>>>,
+}
+
+impl MediaEventStream {
+    pub(crate) fn new(
+        created: EventStream<EventPlayersCreated>,
+        properties: EventStream<EventPlayerPropertiesChanged>,
+        events: EventStream<EventPlayerEventsAdded>,
+        messages: EventStream<EventPlayerMessagesLogged>,
+        errors: EventStream<EventPlayerErrorsRaised>,
+    ) -> Self {
+        let mut all = SelectAll::new();
+        all.push(created.map(MediaEvent::PlayersCreated).boxed());
+        all.push(properties.map(MediaEvent::PropertiesChanged).boxed());
+        all.push(events.map(MediaEvent::EventsAdded).boxed());
+        all.push(messages.map(MediaEvent::MessagesLogged).boxed());
+        all.push(errors.map(MediaEvent::ErrorsRaised).boxed());
+        Self { events: all }
+    }
+}
+
+impl fmt::Debug for MediaEventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MediaEventStream").finish()
+    }
+}
+
+impl Stream for MediaEventStream {
+    type Item = MediaEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().events.poll_next_unpin(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc::unbounded;
+    use futures::SinkExt;
+
+    use crate::listeners::Sequenced;
+    use chromiumoxide_cdp::cdp::browser_protocol::media::{PlayerError, PlayerErrorType};
+
+    use super::*;
+
+    #[async_std::test]
+    async fn merges_media_events() {
+        let (_created_tx, created) = unbounded();
+        let (_properties_tx, properties) = unbounded();
+        let (_events_tx, events) = unbounded();
+        let (_messages_tx, messages) = unbounded();
+        let (mut errors_tx, errors) = unbounded();
+        let mut stream = MediaEventStream::new(
+            EventStream::new(created),
+            EventStream::new(properties),
+            EventStream::new(events),
+            EventStream::new(messages),
+            EventStream::new(errors),
+        );
+
+        let error = EventPlayerErrorsRaised {
+            player_id: PlayerId::new("player"),
+            errors: vec![PlayerError::new(
+                PlayerErrorType::PipelineError,
+                "PIPELINE_ERROR_DECODE",
+            )],
+        };
+        errors_tx
+            .send(Sequenced::new(1, Arc::new(error.clone())))
+            .await
+            .unwrap();
+
+        let event = stream.next().await.unwrap();
+        assert!(event.is_error());
+        assert_eq!(event.player_id(), Some(&PlayerId::new("player")));
+        assert_eq!(event, MediaEvent::ErrorsRaised(Arc::new(error)));
+    }
+}
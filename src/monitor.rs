@@ -0,0 +1,255 @@
+//! Watching pages for changes.
+//!
+//! [`Page::monitor`](crate::page::Page::monitor) extracts content from a page
+//! on a schedule, revisiting a url each time or looking at the page as it
+//! is, and compares it with the content of the previous run. Runs that found
+//! a change are handed out as [`MonitorEvent`]s by the [`Monitor`] stream.
+//!
+//! With the `image` feature the monitor can also compare a perceptual hash of
+//! the page, see [`MonitorOptions::visual_threshold`], to report pages that
+//! look different while the extracted content stayed the same. Without the
+//! feature, runs with a threshold fail.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::monitor::{MonitorEvent, MonitorOptions};
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut changes = page.monitor(
+//!         |page| async move { page.find_element("#price").await?.inner_text().await },
+//!         MonitorOptions {
+//!             url: Some("https://example.com/product".to_string()),
+//!             interval: Duration::from_secs(15 * 60),
+//!             ..Default::default()
+//!         },
+//!     );
+//!     while let Some(event) = changes.next().await {
+//!         if let MonitorEvent::Changed { previous, current } = event? {
+//!             println!("price changed from {previous:?} to {current:?}");
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::BoxStream;
+use futures::{Future, Stream, StreamExt};
+use futures_timer::Delay;
+
+use crate::error::Result;
+use crate::page::Page;
+#[cfg(feature = "image")]
+use crate::visual_hash::VisualHash;
+
+/// How a [`Monitor`] revisits the page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonitorOptions {
+    /// The url to navigate to before every run, `None` extracts from the page
+    /// as it is
+    pub url: Option<String>,
+    /// How long to wait between runs
+    pub interval: Duration,
+    /// Report runs whose perceptual hash is further than this from the
+    /// previous run's, see `VisualHash::distance`. Requires the `image`
+    /// feature
+    pub visual_threshold: Option<u32>,
+}
+
+impl Default for MonitorOptions {
+    fn default() -> Self {
+        Self {
+            url: None,
+            interval: Duration::from_secs(60),
+            visual_threshold: None,
+        }
+    }
+}
+
+/// A change found by a [`Monitor`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum MonitorEvent<T> {
+    /// The content extracted by the first run
+    Initial(T),
+    /// The extracted content differs from the previous run's
+    Changed { previous: T, current: T },
+    /// The page looks different than in the previous run while the extracted
+    /// content stayed the same, see [`MonitorOptions::visual_threshold`]
+    VisuallyChanged { current: T, distance: u32 },
+}
+
+/// A stream of the changes of a monitored page.
+///
+/// Runs that failed yield their error, the monitor keeps going and compares
+/// the next successful run with the last successful one.
+#[must_use = "streams do nothing unless polled"]
+pub struct Monitor<T> {
+    inner: BoxStream<'static, Result<MonitorEvent<T>>>,
+}
+
+impl<T: Clone + PartialEq + Send + 'static> Monitor<T> {
+    pub(crate) fn new<F, Fut>(page: Page, extract: F, options: MonitorOptions) -> Self
+    where
+        F: FnMut(Page) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let state = State {
+            page,
+            extract,
+            options,
+            previous: None,
+            #[cfg(feature = "image")]
+            previous_hash: None,
+            first: true,
+        };
+        let inner = futures::stream::unfold(state, |mut state| async move {
+            let item = state.next().await;
+            Some((item, state))
+        })
+        .boxed();
+        Self { inner }
+    }
+}
+
+impl<T> fmt::Debug for Monitor<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Monitor").finish()
+    }
+}
+
+impl<T> Stream for Monitor<T> {
+    type Item = Result<MonitorEvent<T>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.poll_next_unpin(cx)
+    }
+}
+
+struct State<F, T> {
+    page: Page,
+    extract: F,
+    options: MonitorOptions,
+    /// The content extracted by the last successful run
+    previous: Option<T>,
+    /// The perceptual hash of the last successful run
+    #[cfg(feature = "image")]
+    previous_hash: Option<VisualHash>,
+    first: bool,
+}
+
+impl<F, Fut, T> State<F, T>
+where
+    F: FnMut(Page) -> Fut,
+    Fut: Future<Output = Result<T>>,
+    T: Clone + PartialEq,
+{
+    /// Runs until a change is found or a run failed.
+    async fn next(&mut self) -> Result<MonitorEvent<T>> {
+        loop {
+            if !self.first {
+                Delay::new(self.options.interval).await;
+            }
+            self.first = false;
+            if let Some(event) = self.run().await? {
+                return Ok(event);
+            }
+        }
+    }
+
+    async fn run(&mut self) -> Result<Option<MonitorEvent<T>>> {
+        if let Some(url) = self.options.url.clone() {
+            self.page.goto(url).await?;
+        }
+        let current = (self.extract)(self.page.clone()).await?;
+        let distance = match self.options.visual_threshold {
+            Some(threshold) => self
+                .visual_distance()
+                .await?
+                .filter(|distance| *distance > threshold),
+            None => None,
+        };
+        let previous = self.previous.replace(current.clone());
+        Ok(compare(previous, current, distance))
+    }
+
+    /// The distance of the page's perceptual hash from the previous run's
+    #[cfg(feature = "image")]
+    async fn visual_distance(&mut self) -> Result<Option<u32>> {
+        let hash = self.page.visual_hash().await?;
+        Ok(self
+            .previous_hash
+            .replace(hash)
+            .map(|previous| previous.distance(&hash)))
+    }
+
+    #[cfg(not(feature = "image"))]
+    async fn visual_distance(&mut self) -> Result<Option<u32>> {
+        Err(crate::error::CdpError::msg(
+            "MonitorOptions::visual_threshold requires the `image` feature",
+        ))
+    }
+}
+
+/// The change between the previous and the current run, `distance` is the
+/// visual distance if it exceeded the threshold
+fn compare<T: PartialEq>(
+    previous: Option<T>,
+    current: T,
+    distance: Option<u32>,
+) -> Option<MonitorEvent<T>> {
+    match previous {
+        None => Some(MonitorEvent::Initial(current)),
+        Some(previous) if previous != current => Some(MonitorEvent::Changed { previous, current }),
+        Some(_) => distance.map(|distance| MonitorEvent::VisuallyChanged { current, distance }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_changes() {
+        assert_eq!(compare(None, 1, Some(5)), Some(MonitorEvent::Initial(1)));
+        assert_eq!(
+            compare(Some(1), 2, None),
+            Some(MonitorEvent::Changed {
+                previous: 1,
+                current: 2
+            })
+        );
+        assert_eq!(compare(Some(1), 1, None), None);
+        assert_eq!(
+            compare(Some(1), 1, Some(5)),
+            Some(MonitorEvent::VisuallyChanged {
+                current: 1,
+                distance: 5
+            })
+        );
+    }
+
+    #[cfg(not(feature = "image"))]
+    #[test]
+    fn visual_threshold_requires_image_feature() {
+        let mut state = State {
+            page: crate::handler::tests::detached_page(),
+            extract: |_| async { Ok(1) },
+            options: MonitorOptions {
+                visual_threshold: Some(10),
+                ..Default::default()
+            },
+            previous: None,
+            first: true,
+        };
+        let err = futures::executor::block_on(state.run()).unwrap_err();
+        assert!(err.to_string().contains("`image` feature"));
+    }
+}
@@ -0,0 +1,336 @@
+//! Observing changes to the DOM.
+//!
+//! [`Element::observe_mutations`](crate::element::Element::observe_mutations)
+//! installs a
+//! [MutationObserver](https://developer.mozilla.org/en-US/docs/Web/API/MutationObserver)
+//! on the element that reports its records through a `Runtime` binding. The
+//! records are handed out as a [`MutationStream`], so live-updating pages can
+//! be scraped without polling.
+//!
+//! The observer lives in the page's document and does not survive a
+//! navigation.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut mutations = page.observe_mutations("#feed").await?;
+//!     while let Some(record) = mutations.next().await {
+//!         for node in &record.added_nodes {
+//!             println!("new entry: {:?}", node.text_content);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::future::{self, Either};
+use futures::{Future, Stream, StreamExt};
+use futures_timer::Delay;
+use serde::{Deserialize, Serialize};
+
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{EventBindingCalled, RemoveBindingParams};
+
+use crate::error::{CdpError, Result};
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+
+/// How long the `Element::wait_for_*` helpers wait by default
+pub const DEFAULT_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Which mutations to observe, see
+/// [MutationObserver.observe()](https://developer.mozilla.org/en-US/docs/Web/API/MutationObserver/observe).
+///
+/// By default child nodes being added or removed and changed attributes are
+/// observed for the element and all its descendants.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationObserverOptions {
+    /// Observe nodes being added or removed
+    pub child_list: bool,
+    /// Observe changes to attributes
+    pub attributes: bool,
+    /// Observe changes to the data of text and comment nodes
+    pub character_data: bool,
+    /// Observe all descendants as well, not only the element itself
+    pub subtree: bool,
+    /// Record the previous value of changed attributes
+    pub attribute_old_value: bool,
+    /// Record the previous data of changed text and comment nodes
+    pub character_data_old_value: bool,
+    /// Only observe the attributes with these names
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub attribute_filter: Option<Vec<String>>,
+}
+
+impl Default for MutationObserverOptions {
+    fn default() -> Self {
+        Self {
+            child_list: true,
+            attributes: true,
+            character_data: false,
+            subtree: true,
+            attribute_old_value: false,
+            character_data_old_value: false,
+            attribute_filter: None,
+        }
+    }
+}
+
+/// The kind of a mutation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MutationKind {
+    /// Child nodes were added or removed
+    #[serde(rename = "childList")]
+    ChildList,
+    /// An attribute changed
+    #[serde(rename = "attributes")]
+    Attributes,
+    /// The data of a text or comment node changed
+    #[serde(rename = "characterData")]
+    CharacterData,
+}
+
+/// A snapshot of a node that was part of a mutation.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationNode {
+    /// The name of the node, like `DIV` or `#text`
+    pub node_name: String,
+    /// The `id` of an element
+    pub id: Option<String>,
+    /// The `class` attribute of an element
+    pub class_name: Option<String>,
+    /// The text content of the node and its descendants
+    pub text_content: Option<String>,
+}
+
+/// A single change to the DOM, see
+/// [MutationRecord](https://developer.mozilla.org/en-US/docs/Web/API/MutationRecord).
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MutationRecord {
+    /// What kind of mutation this is
+    #[serde(rename = "type")]
+    pub kind: MutationKind,
+    /// The node that was mutated
+    pub target: MutationNode,
+    /// The name of the changed attribute
+    pub attribute_name: Option<String>,
+    /// The previous value of the attribute or character data, if requested
+    pub old_value: Option<String>,
+    /// The nodes that were added
+    pub added_nodes: Vec<MutationNode>,
+    /// The nodes that were removed
+    pub removed_nodes: Vec<MutationNode>,
+}
+
+/// Returns a new name for the binding an observer reports its records to.
+pub(crate) fn next_binding_name() -> String {
+    static OBSERVERS: AtomicUsize = AtomicUsize::new(0);
+    format!(
+        "__chromiumoxideMutations{}",
+        OBSERVERS.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+/// Installs the observer on `this` and reports all records to the binding
+/// as a JSON array.
+pub(crate) const OBSERVE_MUTATIONS_JS: &str = r#"function(binding, options) {
+    const describe = (node) => ({
+        nodeName: node.nodeName,
+        id: node.id || null,
+        className: typeof node.className === 'string' && node.className ? node.className : null,
+        textContent: node.textContent,
+    });
+    const observer = new MutationObserver((records) => {
+        window[binding](JSON.stringify(records.map((record) => ({
+            type: record.type,
+            target: describe(record.target),
+            attributeName: record.attributeName,
+            oldValue: record.oldValue,
+            addedNodes: Array.from(record.addedNodes, describe),
+            removedNodes: Array.from(record.removedNodes, describe),
+        }))));
+    });
+    observer.observe(this, options);
+    window[binding + 'Observer'] = observer;
+}"#;
+
+/// The mutation records reported to the `binding`, if the event was emitted
+/// by that binding.
+fn records_of(binding: &str, event: &EventBindingCalled) -> Vec<MutationRecord> {
+    if event.name != binding {
+        return Vec::new();
+    }
+    serde_json::from_str(&event.payload).unwrap_or_else(|err| {
+        tracing::warn!("Failed to deserialize mutation records: {}", err);
+        Vec::new()
+    })
+}
+
+/// A stream of the mutations reported by an observer.
+///
+/// Call [`MutationStream::disconnect`] to stop observing.
+#[must_use = "streams do nothing unless polled"]
+pub struct MutationStream {
+    binding: String,
+    events: EventStream<EventBindingCalled>,
+    queued: VecDeque<MutationRecord>,
+    page: Arc<PageInner>,
+}
+
+impl MutationStream {
+    pub(crate) fn new(
+        binding: String,
+        events: EventStream<EventBindingCalled>,
+        page: Arc<PageInner>,
+    ) -> Self {
+        Self {
+            binding,
+            events,
+            queued: Default::default(),
+            page,
+        }
+    }
+
+    /// Disconnects the observer and removes its binding.
+    pub async fn disconnect(self) -> Result<()> {
+        self.page
+            .evaluate_expression(format!(
+                "window['{0}Observer'] && window['{0}Observer'].disconnect()",
+                self.binding
+            ))
+            .await?;
+        self.page
+            .execute(RemoveBindingParams::new(self.binding))
+            .await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for MutationStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MutationStream")
+            .field("binding", &self.binding)
+            .field("queued", &self.queued)
+            .finish()
+    }
+}
+
+impl Stream for MutationStream {
+    type Item = MutationRecord;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        loop {
+            if let Some(record) = pin.queued.pop_front() {
+                return Poll::Ready(Some(record));
+            }
+            match pin.events.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => {
+                    pin.queued.extend(records_of(&pin.binding, &event));
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Evaluates `check` initially and after every mutation reported by the
+/// stream until it returns a value or the `timeout` elapsed, then
+/// disconnects the observer.
+pub(crate) async fn wait_for<T, F, Fut>(
+    mut mutations: MutationStream,
+    timeout: Duration,
+    mut check: F,
+) -> Result<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>>>,
+{
+    let wait = async {
+        loop {
+            if let Some(value) = check().await? {
+                return Ok(value);
+            }
+            if mutations.next().await.is_none() {
+                return Err(CdpError::msg("Mutation observer closed"));
+            }
+        }
+    };
+    let res = match future::select(Box::pin(wait), Delay::new(timeout)).await {
+        Either::Left((res, _)) => res,
+        Either::Right(_) => Err(CdpError::Timeout),
+    };
+    if let Err(err) = mutations.disconnect().await {
+        tracing::debug!("Failed to disconnect mutation observer: {}", err);
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use chromiumoxide_cdp::cdp::js_protocol::runtime::ExecutionContextId;
+
+    use super::*;
+
+    #[test]
+    fn serializes_options() {
+        let options = MutationObserverOptions {
+            attribute_filter: Some(vec!["class".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            serde_json::to_value(options).unwrap(),
+            serde_json::json!({
+                "childList": true,
+                "attributes": true,
+                "characterData": false,
+                "subtree": true,
+                "attributeOldValue": false,
+                "characterDataOldValue": false,
+                "attributeFilter": ["class"],
+            })
+        );
+    }
+
+    #[test]
+    fn parses_records_of_binding() {
+        let payload = serde_json::json!([{
+            "type": "childList",
+            "target": {"nodeName": "UL", "id": "feed", "className": null, "textContent": "a"},
+            "attributeName": null,
+            "oldValue": null,
+            "addedNodes": [{"nodeName": "LI", "id": null, "className": "entry", "textContent": "a"}],
+            "removedNodes": [],
+        }]);
+        let event = EventBindingCalled {
+            name: "binding".to_string(),
+            payload: payload.to_string(),
+            execution_context_id: ExecutionContextId::new(1),
+        };
+        assert!(records_of("otherBinding", &event).is_empty());
+
+        let records = records_of("binding", &event);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].kind, MutationKind::ChildList);
+        assert_eq!(records[0].target.id.as_deref(), Some("feed"));
+        assert_eq!(
+            records[0].added_nodes[0].class_name.as_deref(),
+            Some("entry")
+        );
+    }
+}
@@ -0,0 +1,201 @@
+//! Observing notifications and simulating push messages.
+//!
+//! Headless browsers don't display notifications, so
+//! [`Page::notifications`](crate::page::Page::notifications) wraps the
+//! `Notification` constructor and
+//! `ServiceWorkerRegistration.prototype.showNotification` of the page's
+//! documents and reports every notification they show as a [`Notification`].
+//! Notifications a service worker shows on its own, like in its `push`
+//! handler, don't pass through the page and aren't reported.
+//!
+//! Together with
+//! [`Page::grant_notifications`](crate::page::Page::grant_notifications) and
+//! [`Page::deliver_push_message`](crate::page::Page::deliver_push_message) the
+//! whole push flow of a site can be tested without a push service.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.goto("https://example.com").await?;
+//!     page.grant_notifications().await?;
+//!     let mut notifications = page.notifications().await?;
+//!     page.evaluate("new Notification('Hello', { body: 'from the page' })").await?;
+//!     let notification = notifications.next().await.unwrap();
+//!     assert_eq!(notification.title, "Hello");
+//!
+//!     page.deliver_push_message(r#"{"title":"Pushed"}"#).await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use serde_json::Value;
+
+use chromiumoxide_cdp::cdp::js_protocol::runtime::EventBindingCalled;
+
+use crate::listeners::EventStream;
+
+/// The name of the binding the shim reports notifications to
+pub(crate) const NOTIFICATION_BINDING: &str = "__chromiumoxideNotification";
+
+/// Wraps the ways a document can show a notification, reporting them to the
+/// binding before the notification is shown
+pub(crate) const NOTIFICATION_SHIM_JS: &str = r#"(() => {
+    const binding = '__chromiumoxideNotification';
+    if (window.__chromiumoxideNotificationShim) {
+        return;
+    }
+    window.__chromiumoxideNotificationShim = true;
+    const report = (source, title, options) => {
+        const opts = options || {};
+        let data = null;
+        try {
+            data = JSON.parse(JSON.stringify(opts.data === undefined ? null : opts.data));
+        } catch (e) {}
+        try {
+            window[binding](JSON.stringify({
+                source,
+                title: String(title),
+                body: opts.body || '',
+                tag: opts.tag || '',
+                icon: opts.icon || null,
+                data,
+                url: location.href,
+                timestamp: Date.now(),
+            }));
+        } catch (e) {}
+    };
+    if (window.Notification) {
+        window.Notification = new Proxy(window.Notification, {
+            construct(target, args, newTarget) {
+                report('page', args[0], args[1]);
+                return Reflect.construct(target, args, newTarget);
+            },
+        });
+    }
+    if (window.ServiceWorkerRegistration) {
+        const proto = ServiceWorkerRegistration.prototype;
+        const showNotification = proto.showNotification;
+        proto.showNotification = function (title, options) {
+            report('serviceWorkerRegistration', title, options);
+            return showNotification.call(this, title, options);
+        };
+    }
+})()"#;
+
+/// How a page showed a notification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NotificationSource {
+    /// `new Notification(..)`
+    Page,
+    /// `ServiceWorkerRegistration.showNotification(..)`
+    ServiceWorkerRegistration,
+}
+
+/// A notification shown by a page.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    pub source: NotificationSource,
+    pub title: String,
+    pub body: String,
+    pub tag: String,
+    pub icon: Option<String>,
+    /// The `data` option, `null` if it isn't serializable to JSON
+    pub data: Value,
+    /// The url of the document that showed the notification
+    pub url: String,
+    /// Milliseconds since the epoch
+    pub timestamp: f64,
+}
+
+impl Notification {
+    /// Parses the notification reported by the shim.
+    fn from_event(event: &EventBindingCalled) -> Option<Self> {
+        if event.name != NOTIFICATION_BINDING {
+            return None;
+        }
+        serde_json::from_str(&event.payload)
+            .map_err(|err| tracing::warn!("Failed to deserialize notification: {}", err))
+            .ok()
+    }
+}
+
+/// A stream of the notifications shown by a page.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct NotificationStream {
+    events: EventStream<EventBindingCalled>,
+}
+
+impl NotificationStream {
+    pub(crate) fn new(events: EventStream<EventBindingCalled>) -> Self {
+        Self { events }
+    }
+}
+
+impl Stream for NotificationStream {
+    type Item = Notification;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        loop {
+            match pin.events.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => {
+                    if let Some(notification) = Notification::from_event(&event) {
+                        return Poll::Ready(Some(notification));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_notifications() {
+        let payload = serde_json::json!({
+            "source": "serviceWorkerRegistration",
+            "title": "New message",
+            "body": "Hi!",
+            "tag": "chat",
+            "icon": null,
+            "data": {"id": 7},
+            "url": "https://example.com/",
+            "timestamp": 1000.0,
+        });
+        let event: EventBindingCalled = serde_json::from_value(serde_json::json!({
+            "name": NOTIFICATION_BINDING,
+            "payload": payload.to_string(),
+            "executionContextId": 1,
+        }))
+        .unwrap();
+        let notification = Notification::from_event(&event).unwrap();
+        assert_eq!(
+            notification.source,
+            NotificationSource::ServiceWorkerRegistration
+        );
+        assert_eq!(notification.title, "New message");
+        assert_eq!(notification.data, serde_json::json!({"id": 7}));
+
+        let other = EventBindingCalled {
+            name: "other".to_string(),
+            ..event
+        };
+        assert_eq!(Notification::from_event(&other), None);
+    }
+}
@@ -1,45 +1,121 @@
-use std::path::Path;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Weak};
+use std::time::{Duration, Instant};
 
 use futures::channel::mpsc::unbounded;
 use futures::channel::oneshot::channel as oneshot_channel;
-use futures::{stream, SinkExt, StreamExt};
-
+use futures::stream::BoxStream;
+use futures::{stream, AsyncWrite, AsyncWriteExt, Future, FutureExt, SinkExt, StreamExt};
+use futures_timer::Delay;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use chromiumoxide_cdp::cdp::browser_protocol::accessibility::{
+    EnableParams as AxEnableParams, GetFullAxTreeParams,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::browser::{GrantPermissionsParams, PermissionType};
 use chromiumoxide_cdp::cdp::browser_protocol::dom::*;
 use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
-    MediaFeature, SetEmulatedMediaParams, SetTimezoneOverrideParams,
+    ClearGeolocationOverrideParams, MediaFeature, ScreenOrientation, ScreenOrientationType,
+    SetCpuThrottlingRateParams, SetDeviceMetricsOverrideParams, SetEmulatedMediaParams,
+    SetEmulatedVisionDeficiencyParams, SetGeolocationOverrideParams, SetLocaleOverrideParams,
+    SetTimezoneOverrideParams, SetTouchEmulationEnabledParams,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::input::{
+    DispatchMouseEventType, MouseButton, TouchPoint,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::inspector::EventTargetCrashed;
+use chromiumoxide_cdp::cdp::browser_protocol::io::{self, ReadParams, StreamHandle};
 use chromiumoxide_cdp::cdp::browser_protocol::network::{
-    Cookie, CookieParam, DeleteCookiesParams, GetCookiesParams, SetCookiesParams,
+    Cookie, CookieParam, DeleteCookiesParams, GetCookiesParams, RequestId, SetCookiesParams,
     SetUserAgentOverrideParams,
 };
+use chromiumoxide_cdp::cdp::browser_protocol::overlay::HighlightConfig;
 use chromiumoxide_cdp::cdp::browser_protocol::page::*;
 use chromiumoxide_cdp::cdp::browser_protocol::performance::{GetMetricsParams, Metric};
-use chromiumoxide_cdp::cdp::browser_protocol::target::{SessionId, TargetId};
+use chromiumoxide_cdp::cdp::browser_protocol::service_worker;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{
+    GetTargetInfoParams, SessionId, TargetId, TargetInfo,
+};
 use chromiumoxide_cdp::cdp::js_protocol;
 use chromiumoxide_cdp::cdp::js_protocol::debugger::GetScriptSourceParams;
 use chromiumoxide_cdp::cdp::js_protocol::runtime::{
-    AddBindingParams, CallArgument, CallFunctionOnParams, EvaluateParams, ExecutionContextId,
-    RemoteObjectType, ScriptId,
+    AddBindingParams, CallArgument, CallFunctionOnParams, EvaluateParams, EventBindingCalled,
+    EventConsoleApiCalled, EventExceptionThrown, ExecutionContextId, RemoteObjectId,
+    RemoteObjectType, RemoveBindingParams, ScriptId,
 };
 use chromiumoxide_cdp::cdp::{browser_protocol, IntoEventKind};
 use chromiumoxide_types::*;
 
+use crate::accessibility::{self, AccessibilityNode, AccessibilitySnapshotOptions};
+use crate::binding::{self, Binding, BindingCall, BindingError};
+use crate::console::ConsoleStream;
+use crate::coverage::{self, CoverageEntry};
+use crate::devices::Device;
+use crate::dialog::{DialogPolicy, DialogStream};
 use crate::element::Element;
 use crate::error::{CdpError, Result};
+use crate::fetch::{self, FetchOptions, FetchResponse};
+use crate::file_chooser::FileChooser;
+use crate::frame::Frame;
+use crate::gestures::{
+    self, GestureOptions, PinchGestureOptions, ScrollGestureOptions, TapGestureOptions,
+};
 use crate::handler::commandfuture::CommandFuture;
 use crate::handler::domworld::DOMWorldKind;
+pub use crate::handler::emulation::{
+    ColorScheme, ForcedColors, MediaOptions, MediaType, ReducedMotion,
+};
+use crate::handler::frame::FrameInfo;
+pub use crate::handler::frame::{NavigationGuard, NavigationLoop, WaitUntil};
 use crate::handler::httpfuture::HttpFuture;
+pub use crate::handler::network::{
+    BudgetExceeded, NetworkConditions, RequestBudget, ResourceStats, ResourceType,
+};
 use crate::handler::target::TargetMessage;
-use crate::handler::PageInner;
+use crate::handler::{PageInner, PageToken};
 use crate::js::{Evaluation, EvaluationResult};
 use crate::layout::Point;
-use crate::listeners::{EventListenerRequest, EventStream};
+use crate::listeners::{EventListenerRequest, EventStream, MappedEventStream};
+use crate::locales::{self, DocumentLanguage, Locale};
+use crate::media::MediaEventStream;
+use crate::monitor::{Monitor, MonitorOptions};
+use crate::mutation::{MutationObserverOptions, MutationStream};
+use crate::notification::{self, NotificationStream};
+use crate::page_error::PageErrorStream;
+use crate::paginate::{PaginateOptions, Pagination};
+use crate::response::ResponseStream;
+use crate::route::{InterceptedRequest, NavigationPolicy, Route, RouteAction};
+use crate::sitemap::{self, SitemapEntry};
+use crate::user_agent::UserAgent;
+use crate::websocket::WebSocketEventStream;
+use crate::worker::{Worker, WorkerStream};
 use crate::{utils, ArcHttpRequest};
+pub use chromiumoxide_cdp::cdp::browser_protocol::emulation::SetEmulatedVisionDeficiencyType as VisionDeficiency;
 
 #[derive(Debug, Clone)]
 pub struct Page {
     inner: Arc<PageInner>,
+    token: Arc<PageToken>,
+}
+
+/// A handle to a [`Page`] that doesn't count as one of its handles, see
+/// [`Page::downgrade`].
+#[derive(Debug, Clone)]
+pub struct WeakPage {
+    inner: Weak<PageInner>,
+    token: Weak<PageToken>,
+}
+
+impl WeakPage {
+    /// Returns the page if any of its [`Page`] handles is still alive.
+    pub fn upgrade(&self) -> Option<Page> {
+        Some(Page {
+            inner: self.inner.upgrade()?,
+            token: self.token.upgrade()?,
+        })
+    }
 }
 
 impl Page {
@@ -122,207 +198,1472 @@ impl Page {
     ///     # Ok(())
     /// # }
     /// ```
+    ///
+    /// With the `derive` feature the traits of a custom event are derived:
+    ///
+    #[cfg_attr(feature = "derive", doc = "```no_run")]
+    #[cfg_attr(not(feature = "derive"), doc = "```ignore")]
+    /// # use serde::Deserialize;
+    /// #[derive(Debug, Deserialize, chromiumoxide::CustomEvent)]
+    /// #[event(method = "Custom.Event")]
+    /// struct MyCustomEvent {
+    ///     name: String,
+    /// }
+    /// ```
     pub async fn event_listener<T: IntoEventKind>(&self) -> Result<EventStream<T>> {
         let (tx, rx) = unbounded();
+        let req = EventListenerRequest::new::<T>(tx);
+        let control = req.control().clone();
         self.inner
             .sender()
             .clone()
-            .send(TargetMessage::AddEventListener(
-                EventListenerRequest::new::<T>(tx),
-            ))
+            .send(TargetMessage::AddEventListener(req))
             .await?;
 
-        Ok(EventStream::new(rx))
+        Ok(EventStream::new(rx).with_control(control))
     }
 
-    pub async fn expose_function(
+    /// Returns a stream of the events of type `T` that match the predicate.
+    ///
+    /// The predicate is evaluated in the handler, so the events it rejects
+    /// are never queued for the stream, which keeps the overhead of
+    /// listening to high volume events like those of the network low.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::network::{EventResponseReceived, ResourceType};
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut documents = page
+    ///         .event_listener_filtered::<EventResponseReceived>(|event| {
+    ///             event.r#type == ResourceType::Document
+    ///         })
+    ///         .await?;
+    ///     while let Some(event) = documents.next().await {
+    ///         //..
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn event_listener_filtered<T: IntoEventKind>(
         &self,
-        name: impl Into<String>,
-        function: impl AsRef<str>,
-    ) -> Result<()> {
-        let name = name.into();
-        let expression = utils::evaluation_string(function, &["exposedFun", name.as_str()]);
-
-        self.execute(AddBindingParams::new(name)).await?;
-        self.execute(AddScriptToEvaluateOnNewDocumentParams::new(
-            expression.clone(),
-        ))
-        .await?;
-
-        // TODO add execution context tracking for frames
-        //let frames = self.frames().await?;
+        predicate: impl Fn(&T) -> bool + Send + 'static,
+    ) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
+        let req = EventListenerRequest::filtered::<T>(tx, predicate);
+        let control = req.control().clone();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AddEventListener(req))
+            .await?;
 
-        Ok(())
+        Ok(EventStream::new(rx).with_control(control))
     }
 
-    /// This resolves once the navigation finished and the page is loaded.
+    /// Returns a stream of the events of type `T` mapped by `map`, skipping
+    /// the events it maps to `None`.
     ///
-    /// This is necessary after an interaction with the page that may trigger a
-    /// navigation (`click`, `press_key`) in order to wait until the new browser
-    /// page is loaded
-    pub async fn wait_for_navigation_response(&self) -> Result<ArcHttpRequest> {
-        self.inner.wait_for_navigation().await
+    /// Like the predicate of [`Page::event_listener_filtered`] the events are
+    /// mapped in the handler.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::cdp::browser_protocol::network::EventResponseReceived;
+    /// # use futures::StreamExt;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let mut failed_urls = page
+    ///         .event_listener_mapped(|event: &EventResponseReceived| {
+    ///             (event.response.status >= 400).then(|| event.response.url.clone())
+    ///         })
+    ///         .await?;
+    ///     while let Some(url) = failed_urls.next().await {
+    ///         //..
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn event_listener_mapped<T: IntoEventKind, U: Send + 'static>(
+        &self,
+        map: impl Fn(&T) -> Option<U> + Send + 'static,
+    ) -> Result<MappedEventStream<U>> {
+        let (tx, rx) = unbounded();
+        let req = EventListenerRequest::mapped::<T, U>(tx, map);
+        let control = req.control().clone();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AddEventListener(req))
+            .await?;
+
+        Ok(MappedEventStream::new(rx).with_control(control))
     }
 
-    /// Same as `wait_for_navigation_response` but returns `Self` instead
-    pub async fn wait_for_navigation(&self) -> Result<&Self> {
-        self.inner.wait_for_navigation().await?;
-        Ok(self)
+    /// Returns a stream of the JavaScript dialogs the page opens, which need
+    /// to be accepted or dismissed before the page continues, see the
+    /// [`dialog`](crate::dialog) module.
+    pub async fn on_dialog(&self) -> Result<DialogStream> {
+        Ok(DialogStream::new(
+            self.clone(),
+            self.event_listener::<EventJavascriptDialogOpening>()
+                .await?,
+        ))
     }
 
-    /// Navigate directly to the given URL.
+    /// Accepts or dismisses all JavaScript dialogs of the page as soon as
+    /// they open, `DialogPolicy::Manual` leaves them open for `on_dialog`.
     ///
-    /// This resolves directly after the requested URL is fully loaded.
-    pub async fn goto(&self, params: impl Into<NavigateParams>) -> Result<&Self> {
-        let res = self.execute(params.into()).await?;
-        if let Some(err) = res.result.error_text {
-            return Err(CdpError::ChromeMessage(err));
-        }
-
+    /// Dialogs are still reported by `on_dialog`, but can no longer be
+    /// handled there.
+    pub async fn set_dialog_policy(&self, policy: DialogPolicy) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetDialogPolicy(policy))
+            .await?;
         Ok(self)
     }
 
-    /// The identifier of the `Target` this page belongs to
-    pub fn target_id(&self) -> &TargetId {
-        self.inner.target_id()
-    }
-
-    /// The identifier of the `Session` target of this page is attached to
-    pub fn session_id(&self) -> &SessionId {
-        self.inner.session_id()
+    /// Dismisses all JavaScript dialogs of the page as soon as they open.
+    pub async fn auto_dismiss_dialogs(&self) -> Result<&Self> {
+        self.set_dialog_policy(DialogPolicy::Dismiss).await
     }
 
-    /// Returns the current url of the page
-    pub async fn url(&self) -> Result<Option<String>> {
+    /// Returns handles to the running workers of the page, see the
+    /// [`worker`](crate::worker) module.
+    pub async fn workers(&self) -> Result<Vec<Worker>> {
         let (tx, rx) = oneshot_channel();
         self.inner
             .sender()
             .clone()
-            .send(TargetMessage::Url(tx))
+            .send(TargetMessage::Workers(tx))
             .await?;
-        Ok(rx.await?)
+        Ok(rx
+            .await?
+            .into_iter()
+            .map(|(session_id, info)| Worker::new(&self.inner, session_id, info))
+            .collect())
     }
 
-    /// Return the main frame of the page
-    pub async fn mainframe(&self) -> Result<Option<FrameId>> {
-        let (tx, rx) = oneshot_channel();
+    /// Returns a stream of the workers the page starts from now on.
+    pub async fn on_worker_created(&self) -> Result<WorkerStream> {
+        Ok(WorkerStream::new(
+            Arc::clone(&self.inner),
+            self.event_listener::<browser_protocol::target::EventAttachedToTarget>()
+                .await?,
+        ))
+    }
+
+    /// Intercepts the next file chooser the page opens instead of showing
+    /// it, see the [`file_chooser`](crate::file_chooser) module.
+    ///
+    /// Interception starts before this waits for the chooser, so this needs
+    /// to be polled before the action that opens the chooser, e.g. by
+    /// joining both.
+    pub async fn wait_for_file_chooser(&self) -> Result<FileChooser> {
+        let mut events = self.event_listener::<EventFileChooserOpened>().await?;
+        self.execute(SetInterceptFileChooserDialogParams::new(true))
+            .await?;
+        let event = events.next().await;
+        self.execute(SetInterceptFileChooserDialogParams::new(false))
+            .await?;
+        let event =
+            event.ok_or_else(|| CdpError::msg("Page closed before a file chooser opened"))?;
+        Ok(FileChooser::new(self.clone(), event))
+    }
+
+    /// Observes the first element matching the selector and all its
+    /// descendants for added or removed nodes and changed attributes, see
+    /// `Element::observe_mutations`.
+    pub async fn observe_mutations(&self, selector: impl Into<String>) -> Result<MutationStream> {
+        self.find_element(selector)
+            .await?
+            .observe_mutations(MutationObserverOptions::default())
+            .await
+    }
+
+    /// Emulates the network conditions, like a slow connection, for all
+    /// requests of the page until they are replaced.
+    ///
+    /// The conditions are kept across navigations, use
+    /// `NetworkConditions::NO_THROTTLING` to disable throttling again.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, NetworkConditions};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_network_conditions(NetworkConditions::SLOW_3G)
+    ///         .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_network_conditions(&self, conditions: NetworkConditions) -> Result<&Self> {
         self.inner
             .sender()
             .clone()
-            .send(TargetMessage::MainFrame(tx))
+            .send(TargetMessage::EmulateNetworkConditions(conditions))
             .await?;
-        Ok(rx.await?)
+        Ok(self)
     }
 
-    /// Return the frames of the page
-    pub async fn frames(&self) -> Result<Vec<FrameId>> {
-        let (tx, rx) = oneshot_channel();
+    /// Emulates a loss of connectivity while `offline` is `true`, requests of
+    /// the page then fail as if the network was disconnected.
+    ///
+    /// The page itself stays controllable while offline. Network conditions
+    /// emulated with `Page::emulate_network_conditions` are restored once
+    /// the page is back online.
+    pub async fn set_offline(&self, offline: bool) -> Result<&Self> {
         self.inner
             .sender()
             .clone()
-            .send(TargetMessage::AllFrames(tx))
+            .send(TargetMessage::SetOffline(offline))
             .await?;
-        Ok(rx.await?)
+        Ok(self)
     }
 
-    /// Allows overriding user agent with the given string.
-    pub async fn set_user_agent(
+    /// Adds the headers to all requests of the page to the origin, like
+    /// `https://api.example.com`, but not to any other origin. This replaces
+    /// the headers previously set for the origin, empty headers remove them.
+    ///
+    /// The headers are added through interception and are dropped together
+    /// with the page.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_origin_headers(
+    ///         "https://api.example.com",
+    ///         [("Authorization".to_string(), "Bearer secret".to_string())].into(),
+    ///     )
+    ///     .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_origin_headers(
         &self,
-        params: impl Into<SetUserAgentOverrideParams>,
+        origin: impl AsRef<str>,
+        headers: HashMap<String, String>,
     ) -> Result<&Self> {
-        self.execute(params.into()).await?;
+        let origin = url::Url::parse(origin.as_ref())?
+            .origin()
+            .ascii_serialization();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetOriginHeaders(origin, headers))
+            .await?;
         Ok(self)
     }
 
-    /// Returns the user agent of the browser
-    pub async fn user_agent(&self) -> Result<String> {
-        Ok(self.inner.version().await?.user_agent)
-    }
-
-    /// Returns the root DOM node (and optionally the subtree) of the page.
+    /// Sends the requests of the page for each host in `mapping` to its
+    /// target, which is a host or `host:port`, replacing the previous
+    /// mapping. An empty mapping sends all requests to their hosts again.
     ///
-    /// # Note: This does not return the actual HTML document of the page. To
-    /// retrieve the HTML content of the page see `Page::content`.
-    pub async fn get_document(&self) -> Result<Node> {
-        let resp = self.execute(GetDocumentParams::default()).await?;
-        Ok(resp.result.root)
+    /// The requests are rewritten through interception, so unlike
+    /// `BrowserConfigBuilder::host_resolver_rule` the target receives its
+    /// own host in the `Host` header.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_host_mapping(
+    ///         [("example.com".to_string(), "localhost:8080".to_string())]
+    ///             .into_iter()
+    ///             .collect(),
+    ///     )
+    ///     .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_host_mapping(&self, mapping: HashMap<String, String>) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetHostMapping(mapping))
+            .await?;
+        Ok(self)
     }
 
-    /// Returns the first element in the document which matches the given CSS
-    /// selector.
+    /// Limits the number of requests, the bytes received and the time the
+    /// page may spend on requests from now on. Once a limit is exceeded, all
+    /// further requests of the page are aborted.
     ///
-    /// Execute a query selector on the document's node.
-    pub async fn find_element(&self, selector: impl Into<String>) -> Result<Element> {
-        let root = self.get_document().await?.node_id;
-        let node_id = self.inner.find_element(selector, root).await?;
-        Element::new(Arc::clone(&self.inner), node_id).await
+    /// Setting a budget replaces the previous one and starts counting anew,
+    /// `None` removes all limits. With `RequestBudget::fail_navigation`
+    /// an ongoing navigation fails with `CdpError::BudgetExceeded`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use chromiumoxide::page::{Page, RequestBudget};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_request_budget(Some(RequestBudget {
+    ///         max_requests: Some(200),
+    ///         max_bytes: Some(10 * 1024 * 1024),
+    ///         max_duration: Some(Duration::from_secs(20)),
+    ///         fail_navigation: true,
+    ///     }))
+    ///     .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_request_budget(&self, budget: Option<RequestBudget>) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetRequestBudget(budget))
+            .await?;
+        Ok(self)
     }
 
-    /// Return all `Element`s in the document that match the given selector
-    pub async fn find_elements(&self, selector: impl Into<String>) -> Result<Vec<Element>> {
-        let root = self.get_document().await?.node_id;
-        let node_ids = self.inner.find_elements(selector, root).await?;
-        Element::from_nodes(&self.inner, &node_ids).await
+    /// Returns a stream of the responses the page receives from now on, each
+    /// yielded once its body finished loading, see
+    /// [`Response`](crate::response::Response).
+    pub async fn responses(&self) -> Result<ResponseStream> {
+        Ok(ResponseStream::new(
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            Arc::clone(&self.inner),
+        ))
     }
 
-    /// Describes node given its id
-    pub async fn describe_node(&self, node_id: NodeId) -> Result<Node> {
-        let resp = self
-            .execute(
-                DescribeNodeParams::builder()
-                    .node_id(node_id)
-                    .depth(100)
-                    .build(),
-            )
+    /// Returns the body of the response to the request, e.g. the `request_id`
+    /// of a `Network.loadingFinished` event.
+    ///
+    /// Bodies that were transferred as base64 are decoded.
+    pub async fn response_body(&self, request_id: impl Into<RequestId>) -> Result<Vec<u8>> {
+        self.inner.response_body(request_id.into()).await
+    }
+
+    /// Enables the `Media` domain and returns a stream of the events of all
+    /// media players of the page, like their properties, playback events and
+    /// errors.
+    pub async fn media_events(&self) -> Result<MediaEventStream> {
+        let stream = MediaEventStream::new(
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+        );
+        self.execute(browser_protocol::media::EnableParams::default())
             .await?;
-        Ok(resp.result.node)
-    }
-
-    /// Tries to close page, running its beforeunload hooks, if any.
-    /// Calls Page.close with [`CloseParams`]
-    pub async fn close(self) -> Result<()> {
-        self.execute(CloseParams::default()).await?;
-        Ok(())
+        Ok(stream)
+    }
+
+    /// Returns a stream of the events of the page's WebSockets, like the
+    /// messages they send and receive, see the
+    /// [`websocket`](crate::websocket) module.
+    pub async fn websocket_events(&self) -> Result<WebSocketEventStream> {
+        Ok(WebSocketEventStream::new(
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+            self.event_listener().await?,
+        ))
     }
 
-    /// Performs a single mouse click event at the point's location.
+    /// Intercepts all requests of the page whose url matches the `pattern`
+    /// and lets the `handler` decide whether to continue, fulfill or abort
+    /// them.
     ///
-    /// This scrolls the point into view first, then executes a
-    /// `DispatchMouseEventParams` command of type `MouseLeft` with
-    /// `MousePressed` as single click and then releases the mouse with an
-    /// additional `DispatchMouseEventParams` of type `MouseLeft` with
-    /// `MouseReleased`
+    /// In the pattern `*` matches any sequence of characters and `?` a single
+    /// character. If several routes match a request, the most recently added
+    /// one handles it. Requests that match no route are continued unchanged.
     ///
-    /// Bear in mind that if `click()` triggers a navigation the new page is not
-    /// immediately loaded when `click()` resolves. To wait until navigation is
-    /// finished an additional `wait_for_navigation()` is required:
+    /// The handler is called by the `Handler` while it processes the page's
+    /// events, see the [`route`](crate::route) module.
     ///
     /// # Example
     ///
-    /// Trigger a navigation and wait until the triggered navigation is finished
-    ///
     /// ```no_run
     /// # use chromiumoxide::page::Page;
     /// # use chromiumoxide::error::Result;
-    /// # use chromiumoxide::layout::Point;
-    /// # async fn demo(page: Page, point: Point) -> Result<()> {
-    ///     let html = page.click(point).await?.wait_for_navigation().await?.content();
+    /// # use chromiumoxide::cdp::browser_protocol::network::{ErrorReason, ResourceType};
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.route("*", |req| {
+    ///         if *req.resource_type() == ResourceType::Image {
+    ///             req.abort(ErrorReason::BlockedByClient)
+    ///         } else {
+    ///             req.continue_request()
+    ///         }
+    ///     })
+    ///     .await?;
     ///     # Ok(())
     /// # }
     /// ```
+    pub async fn route<F>(&self, pattern: impl Into<String>, handler: F) -> Result<&Self>
+    where
+        F: FnMut(InterceptedRequest) -> RouteAction + Send + 'static,
+    {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AddRoute(Route::new(
+                pattern,
+                Box::new(handler),
+            )))
+            .await?;
+        Ok(self)
+    }
+
+    /// Stops redirect and reload loops of the main frame of the page, see
+    /// [`NavigationGuard`]. The ongoing navigation then fails with
+    /// [`CdpError::NavigationLoop`].
     ///
     /// # Example
     ///
-    /// Perform custom click
-    ///
     /// ```no_run
-    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::page::{NavigationGuard, Page};
     /// # use chromiumoxide::error::Result;
-    /// # use chromiumoxide::layout::Point;
-    /// # use chromiumoxide_cdp::cdp::browser_protocol::input::{DispatchMouseEventParams, MouseButton, DispatchMouseEventType};
-    /// # async fn demo(page: Page, point: Point) -> Result<()> {
-    ///      // double click
-    ///      let cmd = DispatchMouseEventParams::builder()
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_navigation_guard(NavigationGuard {
+    ///         max_redirects: Some(10),
+    ///         max_reloads: Some(3),
+    ///         deny_meta_refresh: true,
+    ///     })
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_navigation_guard(&self, guard: NavigationGuard) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetNavigationGuard(guard))
+            .await?;
+        Ok(self)
+    }
+
+    /// Restricts the urls the main frame of the page may navigate to,
+    /// including the targets of redirects. Disallowed navigations are
+    /// aborted with `net::ERR_BLOCKED_BY_CLIENT`.
+    ///
+    /// `NavigationPolicy::default()` allows all urls again.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::route::NavigationPolicy;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.set_navigation_policy(
+    ///         NavigationPolicy::allow(["https://example.com/*"]).deny(["*/logout*"]),
+    ///     )
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_navigation_policy(&self, policy: NavigationPolicy) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetNavigationPolicy(policy))
+            .await?;
+        Ok(self)
+    }
+
+    /// Removes all routes that were added with this `pattern`, see
+    /// `Page::route`.
+    ///
+    /// Routes of the page's browser context, see `Browser::route`, are not
+    /// removed.
+    pub async fn unroute(&self, pattern: impl Into<String>) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::RemoveRoute(pattern.into()))
+            .await?;
+        Ok(self)
+    }
+
+    /// Exposes a function `window[name]` to the page's scripts that calls the
+    /// async `callback` and returns a promise that settles with the
+    /// callback's result.
+    ///
+    /// Unlike the handler of `Page::expose_binding`, the callback may wait,
+    /// for example on I/O or on other methods of the page. Its futures are
+    /// driven by the `Handler`, so concurrent calls are answered as they
+    /// finish.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let p = page.clone();
+    ///     page.expose_function("pageTitle", move |_call| {
+    ///         let page = p.clone();
+    ///         async move {
+    ///             let title = page.get_title().await.map_err(|err| err.to_string())?;
+    ///             Ok(title.into())
+    ///         }
+    ///     })
+    ///     .await?;
+    ///     let title: Option<String> = page.evaluate("window.pageTitle()").await?.into_value()?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn expose_function<F, Fut>(
+        &self,
+        name: impl Into<String>,
+        mut callback: F,
+    ) -> Result<&Self>
+    where
+        F: FnMut(BindingCall) -> Fut + Send + 'static,
+        Fut: Future<Output = std::result::Result<serde_json::Value, BindingError>> + Send + 'static,
+    {
+        self.install_binding(Binding::new_async(
+            name,
+            Box::new(move |call| callback(call).boxed()),
+        ))
+        .await
+    }
+
+    /// Exposes a function `window[name]` to the page's scripts that calls the
+    /// `handler` and returns a promise that settles with the handler's result.
+    ///
+    /// The handler receives the arguments together with the execution context
+    /// and frame of the call. Returning a `BindingError` rejects the promise
+    /// with a javascript `Error`. The function is available in all frames and
+    /// survives navigations, see the [`binding`](crate::binding) module.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.expose_binding("whereAmI", |call| {
+    ///         Ok(call.frame_url().unwrap_or_default().into())
+    ///     })
+    ///     .await?;
+    ///     let url: String = page.evaluate("window.whereAmI()").await?.into_value()?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn expose_binding<F>(&self, name: impl Into<String>, handler: F) -> Result<&Self>
+    where
+        F: FnMut(BindingCall) -> std::result::Result<serde_json::Value, BindingError>
+            + Send
+            + 'static,
+    {
+        self.install_binding(Binding::new(name, Box::new(handler)))
+            .await
+    }
+
+    /// Registers the binding with the handler and installs its function in
+    /// all current and future documents.
+    async fn install_binding(&self, binding: Binding) -> Result<&Self> {
+        let name = binding.name().to_string();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AddBinding(binding))
+            .await?;
+        self.execute(AddBindingParams::new(name.clone())).await?;
+
+        let expression = utils::evaluation_string(binding::EXPOSE_BINDING_JS, &[name.as_str()]);
+        self.execute(AddScriptToEvaluateOnNewDocumentParams::new(
+            expression.clone(),
+        ))
+        .await?;
+        self.evaluate_expression(expression).await?;
+        Ok(self)
+    }
+
+    /// Removes the binding `name` that was exposed with
+    /// `Page::expose_binding`.
+    ///
+    /// The function stays defined in documents that are already loaded, but
+    /// its calls are no longer answered.
+    pub async fn remove_binding(&self, name: impl Into<String>) -> Result<&Self> {
+        let name = name.into();
+        self.execute(RemoveBindingParams::new(name.clone())).await?;
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::RemoveBinding(name))
+            .await?;
+        Ok(self)
+    }
+
+    /// This resolves once the navigation finished and the page is loaded.
+    ///
+    /// This is necessary after an interaction with the page that may trigger a
+    /// navigation (`click`, `press_key`) in order to wait until the new browser
+    /// page is loaded
+    pub async fn wait_for_navigation_response(&self) -> Result<ArcHttpRequest> {
+        self.inner.wait_for_navigation().await
+    }
+
+    /// Same as `wait_for_navigation_response` but returns `Self` instead
+    pub async fn wait_for_navigation(&self) -> Result<&Self> {
+        self.inner.wait_for_navigation().await?;
+        Ok(self)
+    }
+
+    /// Waits until the page's main frame reached the `wait_until` lifecycle
+    /// state, like when there is no more network activity.
+    pub async fn wait_for_navigation_until(&self, wait_until: WaitUntil) -> Result<&Self> {
+        self.inner.wait_for_navigation_until(wait_until).await?;
+        Ok(self)
+    }
+
+    /// Navigate directly to the given URL.
+    ///
+    /// This resolves directly after the requested URL is fully loaded.
+    pub async fn goto(&self, params: impl Into<NavigateParams>) -> Result<&Self> {
+        let res = self.execute(params.into()).await?;
+        if let Some(err) = res.result.error_text {
+            return Err(CdpError::ChromeMessage(err));
+        }
+
+        Ok(self)
+    }
+
+    /// Navigate to the given URL as configured by the `options`.
+    ///
+    /// This resolves after the page reached the `wait_until` lifecycle state
+    /// of the `options`, which is useful for pages that keep loading content
+    /// after the `load` event.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{NavigationOptions, Page, WaitUntil};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let options = NavigationOptions::builder()
+    ///         .wait_until(WaitUntil::NetworkIdle0)
+    ///         .build();
+    ///     page.goto_with("https://example.com", options).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn goto_with(
+        &self,
+        url: impl Into<String>,
+        options: NavigationOptions,
+    ) -> Result<&Self> {
+        let mut params = NavigateParams::new(url);
+        let wait_until = options.apply(&mut params);
+
+        let res = self.inner.navigate(params, wait_until).await?;
+        if let Some(err) = res.result.error_text {
+            return Err(CdpError::ChromeMessage(err));
+        }
+        Ok(self)
+    }
+
+    /// Returns a handle to the frame with the given identifier, see
+    /// `Page::frame_tree` for the frames of the page.
+    pub fn frame(&self, id: FrameId) -> Frame {
+        Frame::new(id, Arc::clone(&self.inner))
+    }
+
+    /// The identifier of the `Target` this page belongs to
+    pub fn target_id(&self) -> &TargetId {
+        self.inner.target_id()
+    }
+
+    /// The identifier of the `Session` target of this page is attached to
+    pub fn session_id(&self) -> &SessionId {
+        self.inner.session_id()
+    }
+
+    /// Returns the current url of the page
+    ///
+    /// The url of the main frame is tracked by the handler, so this does not
+    /// require a round trip to the browser.
+    pub async fn url(&self) -> Result<Option<String>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::Url(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns how many requests the page issued so far for each type of
+    /// resource.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, ResourceType};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.goto("https://example.com").await?;
+    ///     let stats = page.resource_stats().await?;
+    ///     println!(
+    ///         "{} of {} requests were images",
+    ///         stats.count(&ResourceType::Image),
+    ///         stats.total()
+    ///     );
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn resource_stats(&self) -> Result<ResourceStats> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::ResourceStats(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns the info of the page's target, like its current url and title
+    /// and whether a client is attached to it, as last reported by the
+    /// browser.
+    pub async fn target_info(&self) -> Result<TargetInfo> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::TargetInfo(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns the page that opened this page, e.g. with `window.open` or a
+    /// link with `target="_blank"`, if it's still open.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(popup: Page) -> Result<()> {
+    ///     // walk up to the page that opened the chain of popups
+    ///     let mut root = popup;
+    ///     while let Some(opener) = root.opener().await? {
+    ///         root = opener;
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn opener(&self) -> Result<Option<Page>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::Opener(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Return the main frame of the page
+    pub async fn mainframe(&self) -> Result<Option<FrameId>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::MainFrame(tx))
+            .await?;
+        Ok(rx.await?)
+    }
+
+    /// Returns a handle to the main frame of the page
+    pub async fn main_frame(&self) -> Result<Option<Frame>> {
+        Ok(self.mainframe().await?.map(|id| self.frame(id)))
+    }
+
+    /// Returns handles to all frames of the page
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     for frame in page.frames().await? {
+    ///         if frame.name().await?.as_deref() == Some("checkout") {
+    ///             frame.find_element("button[type=submit]").await?.click().await?;
+    ///         }
+    ///     }
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn frames(&self) -> Result<Vec<Frame>> {
+        let (tx, rx) = oneshot_channel();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AllFrames(tx))
+            .await?;
+        Ok(rx.await?.into_iter().map(|id| self.frame(id)).collect())
+    }
+
+    /// Returns a snapshot of the page's frame hierarchy, starting at the main
+    /// frame.
+    ///
+    /// The frames are tracked by the handler, so this does not require a
+    /// round trip to the browser.
+    pub async fn frame_tree(&self) -> Result<Option<FrameInfo>> {
+        self.inner.frame_tree().await
+    }
+
+    /// Allows overriding user agent with the given string, or a
+    /// [`UserAgent`](crate::user_agent::UserAgent) that also overrides the
+    /// platform, `Accept-Language` and the client hints.
+    pub async fn set_user_agent(
+        &self,
+        params: impl Into<SetUserAgentOverrideParams>,
+    ) -> Result<&Self> {
+        self.execute(params.into()).await?;
+        Ok(self)
+    }
+
+    /// Returns the user agent of the browser
+    pub async fn user_agent(&self) -> Result<String> {
+        Ok(self.inner.version().await?.user_agent)
+    }
+
+    /// Returns the root DOM node (and optionally the subtree) of the page.
+    ///
+    /// # Note: This does not return the actual HTML document of the page. To
+    /// retrieve the HTML content of the page see `Page::content`.
+    pub async fn get_document(&self) -> Result<Node> {
+        let resp = self.execute(GetDocumentParams::default()).await?;
+        Ok(resp.result.root)
+    }
+
+    /// Returns the first element in the document which matches the given CSS
+    /// selector.
+    ///
+    /// Execute a query selector on the document's node.
+    pub async fn find_element(&self, selector: impl Into<String>) -> Result<Element> {
+        let root = self.get_document().await?.node_id;
+        let node_id = self.inner.find_element(selector, root).await?;
+        Element::new(Arc::clone(&self.inner), node_id).await
+    }
+
+    /// Returns a snapshot of the accessibility tree of the page, or of the
+    /// subtree of `options.root`, as seen by assistive technology.
+    ///
+    /// Returns `None` if the root element isn't part of the accessibility
+    /// tree.
+    pub async fn accessibility_snapshot(
+        &self,
+        options: AccessibilitySnapshotOptions,
+    ) -> Result<Option<AccessibilityNode>> {
+        self.execute(AxEnableParams::default()).await?;
+        let nodes = self.execute(GetFullAxTreeParams::default()).await?;
+        Ok(accessibility::snapshot(
+            nodes.result.nodes,
+            options.root.as_ref().map(|root| &root.backend_node_id),
+            options.interesting_only,
+        ))
+    }
+
+    /// Searches the document for `query`, which is plain text, a CSS
+    /// selector or an XPath expression, and returns the matching elements.
+    ///
+    /// Text is matched case insensitively, the elements containing the text
+    /// are returned.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let search = page.search_text("Order #1234").await?;
+    ///     assert!(search.count > 0, "order number is missing");
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn search_text(&self, query: impl Into<String>) -> Result<TextSearch> {
+        self.search_text_with(query, SearchOptions::default()).await
+    }
+
+    /// Same as `Page::search_text` but with the `options`, e.g. to highlight
+    /// the first match.
+    pub async fn search_text_with(
+        &self,
+        query: impl Into<String>,
+        options: SearchOptions,
+    ) -> Result<TextSearch> {
+        // search results can only be returned once the document has been sent
+        self.get_document().await?;
+        let mut params = PerformSearchParams::new(query);
+        params.include_user_agent_shadow_dom = Some(options.include_user_agent_shadow_dom);
+        let search = self.execute(params).await?.result;
+
+        let mut elements = Vec::new();
+        if search.result_count > 0 {
+            let node_ids = self
+                .execute(GetSearchResultsParams::new(
+                    search.search_id.clone(),
+                    0,
+                    search.result_count,
+                ))
+                .await?
+                .result
+                .node_ids;
+            for node in Element::from_nodes(&self.inner, &node_ids).await? {
+                // text matches are reported as the text nodes
+                let object_id = node
+                    .call_js_fn(
+                        "function() { return this.nodeType === Node.TEXT_NODE ? this.parentElement : this; }",
+                        false,
+                    )
+                    .await?
+                    .result
+                    .object_id;
+                if let Some(object_id) = object_id {
+                    let element = self.element_from_object(object_id).await?;
+                    if !elements
+                        .iter()
+                        .any(|e: &Element| e.backend_node_id == element.backend_node_id)
+                    {
+                        elements.push(element);
+                    }
+                }
+            }
+        }
+        self.execute(DiscardSearchResultsParams::new(search.search_id))
+            .await?;
+
+        if let (Some(config), Some(first)) = (options.highlight, elements.first()) {
+            first.highlight(config).await?;
+        }
+        Ok(TextSearch {
+            count: search.result_count as usize,
+            elements,
+        })
+    }
+
+    /// Return all `Element`s in the document that match the given selector
+    pub async fn find_elements(&self, selector: impl Into<String>) -> Result<Vec<Element>> {
+        let root = self.get_document().await?.node_id;
+        let node_ids = self.inner.find_elements(selector, root).await?;
+        Element::from_nodes(&self.inner, &node_ids).await
+    }
+
+    /// Adds a `<script>` tag to the page and returns it once the script is
+    /// loaded.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, ScriptTag};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.add_script_tag(ScriptTag::url("https://code.jquery.com/jquery-3.7.1.min.js"))
+    ///         .await?;
+    ///     page.add_script_tag(ScriptTag::content("window.answer = 42;").with_type("module"))
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_script_tag(&self, tag: ScriptTag) -> Result<Element> {
+        let (url, content) = tag
+            .source
+            .resolve(|path| format!("\n//# sourceURL={path}"))
+            .await?;
+        let ty = tag.r#type.map(serde_json::Value::from).unwrap_or_default();
+        self.add_tag(ADD_SCRIPT_TAG_JS, [url, content, ty]).await
+    }
+
+    /// Adds a `<link rel="stylesheet">` tag for a url, or a `<style>` tag for
+    /// content, to the page and returns it once the stylesheet is loaded.
+    pub async fn add_style_tag(&self, tag: StyleTag) -> Result<Element> {
+        let (url, content) = tag
+            .source
+            .resolve(|path| format!("\n/*# sourceURL={path}*/"))
+            .await?;
+        self.add_tag(ADD_STYLE_TAG_JS, [url, content]).await
+    }
+
+    async fn add_tag<const N: usize>(
+        &self,
+        function_declaration: &str,
+        args: [serde_json::Value; N],
+    ) -> Result<Element> {
+        let call = CallFunctionOnParams::builder()
+            .function_declaration(function_declaration)
+            .arguments(args.into_iter().map(CallArgument::from))
+            .return_by_value(false)
+            .build()
+            .unwrap();
+        let object_id = self
+            .evaluate_function(call)
+            .await?
+            .object()
+            .object_id
+            .clone()
+            .ok_or_else(|| CdpError::msg("No object Id found for the added tag"))?;
+        self.element_from_object(object_id).await
+    }
+
+    pub(crate) async fn element_from_backend_node(
+        &self,
+        backend_node_id: BackendNodeId,
+    ) -> Result<Element> {
+        // the node can only be pushed once the document has been sent
+        self.get_document().await?;
+        let node_id = self
+            .execute(PushNodesByBackendIdsToFrontendParams::new(vec![
+                backend_node_id,
+            ]))
+            .await?
+            .result
+            .node_ids
+            .pop()
+            .ok_or_else(|| CdpError::msg("No node found for the backend node"))?;
+        Element::new(Arc::clone(&self.inner), node_id).await
+    }
+
+    pub(crate) async fn element_from_object(&self, object_id: RemoteObjectId) -> Result<Element> {
+        // the node can only be requested once the document has been sent
+        self.get_document().await?;
+        let node_id = self
+            .execute(RequestNodeParams::new(object_id))
+            .await?
+            .result
+            .node_id;
+        Element::new(Arc::clone(&self.inner), node_id).await
+    }
+
+    /// Returns a stream of the results of `extract` for every page of
+    /// paginated content, advancing by clicking the element matching
+    /// `next_selector`, see the [`paginate`](crate::paginate) module.
+    pub fn paginate<F, Fut, T>(&self, next_selector: impl Into<String>, extract: F) -> Pagination<T>
+    where
+        F: FnMut(Page) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        self.paginate_with(next_selector, extract, PaginateOptions::default())
+    }
+
+    /// Same as `Page::paginate` but with custom limits.
+    pub fn paginate_with<F, Fut, T>(
+        &self,
+        next_selector: impl Into<String>,
+        extract: F,
+        options: PaginateOptions,
+    ) -> Pagination<T>
+    where
+        F: FnMut(Page) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Send + 'static,
+    {
+        Pagination::new(self.clone(), next_selector.into(), extract, options)
+    }
+
+    /// Returns a stream of the changes of the content that `extract` returns
+    /// for the page on every run of the monitor, see the
+    /// [`monitor`](crate::monitor) module.
+    pub fn monitor<F, Fut, T>(&self, extract: F, options: MonitorOptions) -> Monitor<T>
+    where
+        F: FnMut(Page) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+        T: Clone + PartialEq + Send + 'static,
+    {
+        Monitor::new(self.clone(), extract, options)
+    }
+
+    /// Presses a key combination like `Mod+L` or `Control+Shift+Tab`, holding
+    /// the modifiers down while pressing the key.
+    ///
+    /// `Mod` is `Meta` if the browser runs on macOS and `Control` elsewhere,
+    /// see [`keys::parse_shortcut`](crate::keys::parse_shortcut) for the
+    /// syntax.
+    pub async fn shortcut(&self, shortcut: impl AsRef<str>) -> Result<&Self> {
+        self.inner.shortcut(shortcut.as_ref(), &[]).await?;
+        Ok(self)
+    }
+
+    /// Selects all content of the focused element or the page with `Mod+A`.
+    pub async fn select_all(&self) -> Result<&Self> {
+        self.inner.shortcut("Mod+A", &["selectAll"]).await?;
+        Ok(self)
+    }
+
+    /// Copies the selection with `Mod+C`.
+    pub async fn copy(&self) -> Result<&Self> {
+        self.inner.shortcut("Mod+C", &["copy"]).await?;
+        Ok(self)
+    }
+
+    /// Cuts the selection with `Mod+X`.
+    pub async fn cut(&self) -> Result<&Self> {
+        self.inner.shortcut("Mod+X", &["cut"]).await?;
+        Ok(self)
+    }
+
+    /// Pastes the clipboard with `Mod+V`.
+    pub async fn paste(&self) -> Result<&Self> {
+        self.inner.shortcut("Mod+V", &["paste"]).await?;
+        Ok(self)
+    }
+
+    /// Undoes the last edit with `Mod+Z`.
+    pub async fn undo(&self) -> Result<&Self> {
+        self.inner.shortcut("Mod+Z", &["undo"]).await?;
+        Ok(self)
+    }
+
+    /// Redoes the last undone edit with `Mod+Shift+Z`.
+    pub async fn redo(&self) -> Result<&Self> {
+        self.inner.shortcut("Mod+Shift+Z", &["redo"]).await?;
+        Ok(self)
+    }
+
+    /// Describes node given its id
+    pub async fn describe_node(&self, node_id: NodeId) -> Result<Node> {
+        let resp = self
+            .execute(
+                DescribeNodeParams::builder()
+                    .node_id(node_id)
+                    .depth(100)
+                    .build(),
+            )
+            .await?;
+        Ok(resp.result.node)
+    }
+
+    /// Returns a stream of the messages logged through the page's console,
+    /// with their arguments resolved to JSON.
+    pub async fn console_messages(&self) -> Result<ConsoleStream> {
+        let events = self.event_listener::<EventConsoleApiCalled>().await?;
+        Ok(ConsoleStream::new(self.inner.clone(), events))
+    }
+
+    /// Returns a stream of the exceptions the page's scripts throw but
+    /// don't catch.
+    pub async fn page_errors(&self) -> Result<PageErrorStream> {
+        Ok(PageErrorStream::new(
+            self.event_listener::<EventExceptionThrown>().await?,
+        ))
+    }
+
+    /// Returns a stream that yields when the renderer of the page crashes.
+    ///
+    /// Once crashed, commands of the page fail with
+    /// [`CdpError::TargetCrashed`] until it's reloaded, see
+    /// [`Page::set_reload_on_crash`].
+    pub async fn on_crash(&self) -> Result<EventStream<EventTargetCrashed>> {
+        self.event_listener::<EventTargetCrashed>().await
+    }
+
+    /// Sets whether the page is reloaded as soon as its renderer crashed
+    /// (disabled by default).
+    ///
+    /// Navigations that were ongoing still fail with
+    /// [`CdpError::TargetCrashed`].
+    pub async fn set_reload_on_crash(&self, reload: bool) -> Result<&Self> {
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::SetReloadOnCrash(reload))
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns a [`WeakPage`] that doesn't keep the page's handles alive,
+    /// e.g. to store pages in a cache without leaking them.
+    pub fn downgrade(&self) -> WeakPage {
+        WeakPage {
+            inner: Arc::downgrade(&self.inner),
+            token: Arc::downgrade(&self.token),
+        }
+    }
+
+    /// Sets whether the page is closed once all of its [`Page`] handles are
+    /// dropped (disabled by default).
+    ///
+    /// The handles of [`Browser::pages`](crate::browser::Browser::pages)
+    /// and [`Browser::get_page`](crate::browser::Browser::get_page) count
+    /// as well, but [`WeakPage`]s and elements don't.
+    pub fn set_close_on_drop(&self, enabled: bool) -> &Self {
+        self.inner.set_close_on_drop(enabled);
+        self
+    }
+
+    /// Tries to close page, running its beforeunload hooks, if any.
+    /// Calls Page.close with [`CloseParams`]
+    pub async fn close(self) -> Result<()> {
+        self.execute(CloseParams::default()).await?;
+        Ok(())
+    }
+
+    /// Puts a pen down on the point, dispatching `pointerdown` with a
+    /// `pointerType` of `pen`.
+    pub async fn pen_down(&self, point: Point, options: PenOptions) -> Result<&Self> {
+        self.inner
+            .dispatch_pen(DispatchMouseEventType::MousePressed, point, true, &options)
+            .await?;
+        Ok(self)
+    }
+
+    /// Moves a pen to the point, `down` drags it over the surface and
+    /// otherwise hovers above it.
+    pub async fn pen_move(&self, point: Point, down: bool, options: PenOptions) -> Result<&Self> {
+        self.inner
+            .dispatch_pen(DispatchMouseEventType::MouseMoved, point, down, &options)
+            .await?;
+        Ok(self)
+    }
+
+    /// Lifts a pen up from the point.
+    pub async fn pen_up(&self, point: Point, options: PenOptions) -> Result<&Self> {
+        self.inner
+            .dispatch_pen(
+                DispatchMouseEventType::MouseReleased,
+                point,
+                false,
+                &options,
+            )
+            .await?;
+        Ok(self)
+    }
+
+    /// Draws a stroke with a pen through all `points`, like a signature.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, PenOptions};
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let stroke = (0..50).map(|i| Point::new(100. + i as f64 * 4., 200. + (i as f64 / 5.).sin() * 20.));
+    ///     page.draw_with_pen(stroke, PenOptions { pressure: 0.8, tilt_x: 30, ..Default::default() })
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn draw_with_pen(
+        &self,
+        points: impl IntoIterator<Item = Point>,
+        options: PenOptions,
+    ) -> Result<&Self> {
+        let mut points = points.into_iter();
+        let Some(mut last) = points.next() else {
+            return Ok(self);
+        };
+        self.pen_move(last, false, options).await?;
+        self.pen_down(last, options).await?;
+        for point in points {
+            self.pen_move(point, true, options).await?;
+            last = point;
+        }
+        self.pen_up(last, options).await
+    }
+
+    /// Pinches two fingers around `center`, spreading them apart by `scale`
+    /// to zoom in or moving them together to zoom out with a `scale` below
+    /// 1, see the [`gestures`](crate::gestures) module.
+    pub async fn pinch(&self, center: Point, scale: f64, options: GestureOptions) -> Result<&Self> {
+        self.inner
+            .touch_gesture(
+                gestures::pinch_frames(center, scale, &options),
+                options.step_delay(),
+            )
+            .await?;
+        Ok(self)
+    }
+
+    /// Rotates two fingers clockwise around `center` by `degrees`, see the
+    /// [`gestures`](crate::gestures) module.
+    pub async fn rotate(
+        &self,
+        center: Point,
+        degrees: f64,
+        options: GestureOptions,
+    ) -> Result<&Self> {
+        self.inner
+            .touch_gesture(
+                gestures::rotate_frames(center, degrees, &options),
+                options.step_delay(),
+            )
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns how far the window is scrolled, e.g. to restore the position
+    /// later with `Page::set_scroll_position`.
+    pub async fn scroll_position(&self) -> Result<Point> {
+        let (x, y): (f64, f64) = self
+            .evaluate_expression("[window.scrollX, window.scrollY]")
+            .await?
+            .into_value()?;
+        Ok(Point::new(x, y))
+    }
+
+    /// Scrolls the window to the position, without smooth scrolling.
+    pub async fn set_scroll_position(&self, position: Point) -> Result<&Self> {
+        self.evaluate_function(format!(
+            "() => window.scrollTo({{ left: {}, top: {}, behavior: 'instant' }})",
+            position.x, position.y
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// Lets Chromium synthesize a scroll gesture starting at `point` that
+    /// scrolls the content by `delta`, positive values scroll down and
+    /// right. Resolves once the gesture finished.
+    pub async fn scroll_gesture(
+        &self,
+        point: Point,
+        delta: Point,
+        options: ScrollGestureOptions,
+    ) -> Result<&Self> {
+        self.execute(options.params(point, delta)).await?;
+        Ok(self)
+    }
+
+    /// Lets Chromium synthesize a pinch gesture around `center` that zooms
+    /// in with a `scale` above 1 and out below 1. Resolves once the gesture
+    /// finished.
+    pub async fn pinch_gesture(
+        &self,
+        center: Point,
+        scale: f64,
+        options: PinchGestureOptions,
+    ) -> Result<&Self> {
+        self.execute(options.params(center, scale)).await?;
+        Ok(self)
+    }
+
+    /// Lets Chromium synthesize a tap gesture at `point`. Resolves once the
+    /// gesture finished.
+    pub async fn tap_gesture(&self, point: Point, options: TapGestureOptions) -> Result<&Self> {
+        self.execute(options.params(point)).await?;
+        Ok(self)
+    }
+
+    /// Touches the point for `duration` without moving, like a long press on
+    /// a touch screen.
+    pub async fn long_press(&self, point: Point, duration: Duration) -> Result<&Self> {
+        let frames = vec![vec![TouchPoint::new(point.x, point.y)]; 2];
+        self.inner.touch_gesture(frames, duration).await?;
+        Ok(self)
+    }
+
+    /// Sets whether the input sequences of this page, like typing a string
+    /// or a click, wait for each other (disabled by default).
+    ///
+    /// With the lock, concurrent tasks that share the page can't interleave
+    /// their keystrokes and clicks, but they can still take turns in between
+    /// sequences. Use [`Page::transaction`] to perform several sequences
+    /// without other tasks getting a turn.
+    pub fn set_action_lock(&self, enabled: bool) -> &Self {
+        self.inner.set_action_lock(enabled);
+        self
+    }
+
+    /// Performs the actions of `f` without input sequences of other tasks
+    /// in between, e.g. focusing a field and typing into it.
+    ///
+    /// Transactions always wait for each other, single input sequences only
+    /// wait for them if the [action lock](Page::set_action_lock) is enabled.
+    /// The actions of `f` don't wait for the transaction, including those of
+    /// elements found before it and of nested transactions. Actions that are
+    /// spawned onto another task only skip the wait if they are performed
+    /// through the page that is passed to `f`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.transaction(|page| async move {
+    ///         page.find_element("input#username")
+    ///             .await?
+    ///             .click()
+    ///             .await?
+    ///             .type_str("rust")
+    ///             .await?
+    ///             .press_key("Enter")
+    ///             .await?;
+    ///         Ok(())
+    ///     })
+    ///     .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn transaction<F, Fut, T>(&self, f: F) -> Result<T>
+    where
+        F: FnOnce(Page) -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let _lock = self.inner.lock_transaction().await;
+        self.inner
+            .transaction(f(Page::from(Arc::new(self.inner.in_transaction()))))
+            .await
+    }
+
+    /// Performs a single mouse click event at the point's location.
+    ///
+    /// This scrolls the point into view first, then executes a
+    /// `DispatchMouseEventParams` command of type `MouseLeft` with
+    /// `MousePressed` as single click and then releases the mouse with an
+    /// additional `DispatchMouseEventParams` of type `MouseLeft` with
+    /// `MouseReleased`
+    ///
+    /// Bear in mind that if `click()` triggers a navigation the new page is not
+    /// immediately loaded when `click()` resolves. To wait until navigation is
+    /// finished an additional `wait_for_navigation()` is required:
+    ///
+    /// # Example
+    ///
+    /// Trigger a navigation and wait until the triggered navigation is finished
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # async fn demo(page: Page, point: Point) -> Result<()> {
+    ///     let html = page.click(point).await?.wait_for_navigation().await?.content();
+    ///     # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// Perform custom click
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::input::{DispatchMouseEventParams, MouseButton, DispatchMouseEventType};
+    /// # async fn demo(page: Page, point: Point) -> Result<()> {
+    ///      // double click
+    ///      let cmd = DispatchMouseEventParams::builder()
     ///             .x(point.x)
     ///             .y(point.y)
     ///             .button(MouseButton::Left)
@@ -336,18 +1677,118 @@ impl Page {
     ///         )
     ///         .await?;
     ///
-    ///         page.execute(
-    ///             cmd.r#type(DispatchMouseEventType::MouseReleased)
-    ///                 .build()
-    ///                 .unwrap(),
-    ///         )
-    ///         .await?;
+    ///         page.execute(
+    ///             cmd.r#type(DispatchMouseEventType::MouseReleased)
+    ///                 .build()
+    ///                 .unwrap(),
+    ///         )
+    ///         .await?;
+    ///
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn click(&self, point: Point) -> Result<&Self> {
+        self.inner.click(point).await?;
+        Ok(self)
+    }
+
+    /// Performs a mouse click at the point's location as configured by the
+    /// `options`, e.g. a right click, a double click or a click while
+    /// modifier keys are held down.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{ClickOptions, Modifier, Page};
+    /// # use chromiumoxide::error::Result;
+    /// # use chromiumoxide::layout::Point;
+    /// # use chromiumoxide_cdp::cdp::browser_protocol::input::MouseButton;
+    /// # async fn demo(page: Page, point: Point) -> Result<()> {
+    ///     let options = ClickOptions::builder()
+    ///         .button(MouseButton::Right)
+    ///         .click_count(2)
+    ///         .modifier(Modifier::Shift)
+    ///         .build();
+    ///     page.click_point_with(point, &options).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn click_point_with(&self, point: Point, options: &ClickOptions) -> Result<&Self> {
+        self.inner.click_with(point, options).await?;
+        Ok(self)
+    }
+
+    /// Whether an element of the page is currently displayed in fullscreen
+    /// mode, see `Element::request_full_screen`.
+    pub async fn is_full_screen(&self) -> Result<bool> {
+        Ok(self
+            .evaluate_expression("document.fullscreenElement !== null")
+            .await?
+            .into_value()?)
+    }
+
+    /// Leaves fullscreen mode, if any element is displayed in fullscreen.
+    pub async fn exit_full_screen(&self) -> Result<&Self> {
+        self.evaluate_expression(
+            "document.fullscreenElement ? document.exitFullscreen() : undefined",
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Waits for the next `fullscreenchange` event of the document and
+    /// returns whether an element is displayed in fullscreen mode afterwards.
+    pub async fn wait_for_full_screen_change(&self) -> Result<bool> {
+        Ok(self
+            .evaluate_expression(
+                "new Promise(resolve => document.addEventListener(
+                    'fullscreenchange',
+                    () => resolve(document.fullscreenElement !== null),
+                    { once: true },
+                ))",
+            )
+            .await?
+            .into_value()?)
+    }
+
+    /// Enables or disables bypassing the page's Content Security Policy, for
+    /// example to inject scripts or load media that the policy would block.
+    ///
+    /// Only documents loaded afterwards bypass their policy.
+    pub async fn set_bypass_csp(&self, enabled: bool) -> Result<&Self> {
+        self.execute(SetBypassCspParams::new(enabled)).await?;
+        Ok(self)
+    }
+
+    /// Hides any highlight, see `Element::highlight`.
+    pub async fn hide_highlight(&self) -> Result<&Self> {
+        self.execute(browser_protocol::overlay::HideHighlightParams::default())
+            .await?;
+        Ok(self)
+    }
+
+    /// Performs a touch tap at the point's location by dispatching a
+    /// `touchstart` and a `touchend` event.
     ///
-    ///     # Ok(())
-    /// # }
-    /// ```
-    pub async fn click(&self, point: Point) -> Result<&Self> {
-        self.inner.click(point).await?;
+    /// Pages that only react to touch input require touch emulation, see
+    /// `Page::enable_touch_emulation`.
+    pub async fn tap_point(&self, point: Point) -> Result<&Self> {
+        self.inner.tap(point).await?;
+        Ok(self)
+    }
+
+    /// Enables touch event emulation, so the page behaves like it is running
+    /// on a device with a touch screen.
+    pub async fn enable_touch_emulation(&self) -> Result<&Self> {
+        self.execute(SetTouchEmulationEnabledParams::new(true))
+            .await?;
+        Ok(self)
+    }
+
+    /// Disables touch event emulation.
+    pub async fn disable_touch_emulation(&self) -> Result<&Self> {
+        self.execute(SetTouchEmulationEnabledParams::new(false))
+            .await?;
         Ok(self)
     }
 
@@ -364,8 +1805,40 @@ impl Page {
         self.inner.screenshot(params).await
     }
 
+    /// Take a screenshot of the current page and decode it into an image.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, ScreenshotParams};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let image = page.screenshot_image(ScreenshotParams::default()).await?;
+    ///     let thumbnail = image.thumbnail(320, 240);
+    ///     # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "image")]
+    pub async fn screenshot_image(
+        &self,
+        params: impl Into<ScreenshotParams>,
+    ) -> Result<image::DynamicImage> {
+        Ok(image::load_from_memory(&self.screenshot(params).await?)?)
+    }
+
+    /// Computes a perceptual hash of the viewport for cheap change detection,
+    /// see the [`visual_hash`](crate::visual_hash) module.
+    #[cfg(feature = "image")]
+    pub async fn visual_hash(&self) -> Result<crate::visual_hash::VisualHash> {
+        let image = self.screenshot_image(ScreenshotParams::default()).await?;
+        Ok(crate::visual_hash::VisualHash::of(&image))
+    }
+
     /// Save a screenshot of the page
     ///
+    /// Unless the params set a format, it is inferred from the extension of
+    /// `output`, `.png`, `.jpg` or `.jpeg`, and defaults to png.
+    ///
     /// # Example save a png file of a website
     ///
     /// ```no_run
@@ -392,6 +1865,10 @@ impl Page {
         params: impl Into<ScreenshotParams>,
         output: impl AsRef<Path>,
     ) -> Result<Vec<u8>> {
+        let mut params = params.into();
+        if params.cdp_params.format.is_none() {
+            params.cdp_params.format = screenshot_format(output.as_ref()).map_err(CdpError::msg)?;
+        }
         let img = self.screenshot(params).await?;
         utils::write(output.as_ref(), &img).await?;
         Ok(img)
@@ -403,8 +1880,63 @@ impl Page {
     ///
     /// # Note Generating a pdf is currently only supported in Chrome headless.
     pub async fn pdf(&self, params: PrintToPdfParams) -> Result<Vec<u8>> {
-        let res = self.execute(params).await?;
-        Ok(base64::decode(&res.data)?)
+        let mut pdf = Vec::new();
+        self.pdf_to_writer(params, &mut pdf).await?;
+        Ok(pdf)
+    }
+
+    /// Print the current page as pdf and return the stream of its chunks, so
+    /// that large documents don't need to be held in memory.
+    ///
+    /// The pdf is transferred as a stream of the IO domain, which is closed
+    /// once all chunks were read.
+    ///
+    /// # Note Generating a pdf is currently only supported in Chrome headless.
+    pub async fn pdf_stream(
+        &self,
+        mut params: PrintToPdfParams,
+    ) -> Result<BoxStream<'static, Result<Vec<u8>>>> {
+        params.transfer_mode = Some(PrintToPdfTransferMode::ReturnAsStream);
+        let handle = self
+            .execute(params)
+            .await?
+            .result
+            .stream
+            .ok_or_else(|| CdpError::msg("No stream returned for the pdf"))?;
+        Ok(read_io_stream(self.clone(), handle))
+    }
+
+    /// Print the current page as pdf into `writer` chunk by chunk and return
+    /// the number of bytes written.
+    ///
+    /// # Note Generating a pdf is currently only supported in Chrome headless.
+    pub async fn pdf_to_writer<W: AsyncWrite + Unpin>(
+        &self,
+        params: PrintToPdfParams,
+        mut writer: W,
+    ) -> Result<u64> {
+        let mut chunks = self.pdf_stream(params).await?;
+        let mut written = 0;
+        while let Some(chunk) = chunks.next().await {
+            let chunk = chunk?;
+            writer.write_all(&chunk).await?;
+            written += chunk.len() as u64;
+        }
+        writer.flush().await?;
+        Ok(written)
+    }
+
+    /// Save the current page as pdf to the `output` path chunk by chunk,
+    /// without holding all of it in memory, and return its size in bytes.
+    ///
+    /// # Note Generating a pdf is currently only supported in Chrome headless.
+    pub async fn pdf_to_file(
+        &self,
+        params: PrintToPdfParams,
+        output: impl AsRef<Path>,
+    ) -> Result<u64> {
+        let chunks = self.pdf_stream(params).await?;
+        utils::write_chunks(output.as_ref(), chunks).await
     }
 
     /// Save the current page as pdf as file to the `output` path and return the
@@ -427,6 +1959,85 @@ impl Page {
         Ok(self)
     }
 
+    /// Emulates the `device`'s viewport, scale factor, touch screen and user
+    /// agent, see the [`devices`](crate::devices) module.
+    ///
+    /// Pages that were loaded before need to be reloaded to pick up the new
+    /// user agent.
+    pub async fn emulate(&self, device: &Device) -> Result<&Self> {
+        let orientation = if device.is_landscape {
+            ScreenOrientation::new(ScreenOrientationType::LandscapePrimary, 90)
+        } else {
+            ScreenOrientation::new(ScreenOrientationType::PortraitPrimary, 0)
+        };
+        let metrics = SetDeviceMetricsOverrideParams::builder()
+            .width(device.width)
+            .height(device.height)
+            .device_scale_factor(device.device_scale_factor)
+            .mobile(device.is_mobile)
+            .screen_orientation(orientation)
+            .build()
+            .unwrap();
+        let mut touch = SetTouchEmulationEnabledParams::new(device.has_touch);
+        if device.has_touch {
+            touch.max_touch_points = Some(5);
+        }
+        self.execute(metrics).await?;
+        self.execute(touch).await?;
+        self.set_user_agent(UserAgent::new(device.user_agent).with_client_hints())
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates the CSS media type and media features, like a dark color
+    /// scheme, for the media queries of the page until they are replaced.
+    /// `MediaOptions::default()` stops emulating them.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{ColorScheme, MediaOptions, Page, ReducedMotion};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_media(MediaOptions {
+    ///         color_scheme: Some(ColorScheme::Dark),
+    ///         reduced_motion: Some(ReducedMotion::Reduce),
+    ///         ..Default::default()
+    ///     })
+    ///     .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_media(&self, options: MediaOptions) -> Result<&Self> {
+        self.execute(SetEmulatedMediaParams::from(options)).await?;
+        Ok(self)
+    }
+
+    /// Renders the page as seen with the vision deficiency until it is
+    /// replaced, `VisionDeficiency::None` renders the page normally again.
+    ///
+    /// See `ScreenshotParamsBuilder::vision_deficiency` to emulate it for a
+    /// single screenshot.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, ScreenshotParams, VisionDeficiency};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.emulate_vision_deficiency(VisionDeficiency::Deuteranopia)
+    ///         .await?;
+    ///     let screenshot = page.screenshot(ScreenshotParams::default()).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn emulate_vision_deficiency(&self, deficiency: VisionDeficiency) -> Result<&Self> {
+        self.execute(SetEmulatedVisionDeficiencyParams::new(deficiency))
+            .await?;
+        Ok(self)
+    }
+
     /// Emulates the given media type or media feature for CSS media queries
     pub async fn emulate_media_features(&self, features: Vec<MediaFeature>) -> Result<&Self> {
         self.execute(SetEmulatedMediaParams::builder().features(features).build())
@@ -434,6 +2045,218 @@ impl Page {
         Ok(self)
     }
 
+    /// Emulates the position reported by the Geolocation API and grants the
+    /// page's origin the permission to access it.
+    ///
+    /// The `accuracy` is given in meters.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.goto("https://example.com").await?;
+    ///     page.set_geolocation(52.52, 13.405, 10.).await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn set_geolocation(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        accuracy: f64,
+    ) -> Result<&Self> {
+        self.grant_to_origin(vec![PermissionType::Geolocation])
+            .await?;
+
+        self.execute(
+            SetGeolocationOverrideParams::builder()
+                .latitude(latitude)
+                .longitude(longitude)
+                .accuracy(accuracy)
+                .build(),
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Grants the permissions to the origin of the page's document.
+    async fn grant_to_origin(&self, permissions: Vec<PermissionType>) -> Result<()> {
+        let info = self
+            .execute(GetTargetInfoParams {
+                target_id: Some(self.target_id().clone()),
+            })
+            .await?
+            .result
+            .target_info;
+        let mut grant = GrantPermissionsParams::new(permissions);
+        grant.origin = page_origin(&info.url);
+        grant.browser_context_id = info.browser_context_id;
+        self.execute(grant).await?;
+        Ok(())
+    }
+
+    /// Grants the page's origin the permission to show notifications, so
+    /// `Notification.permission` is `granted` without a prompt.
+    pub async fn grant_notifications(&self) -> Result<&Self> {
+        self.grant_to_origin(vec![PermissionType::Notifications])
+            .await?;
+        Ok(self)
+    }
+
+    /// Returns a stream of the notifications the page's documents show, see
+    /// the [`notification`](crate::notification) module.
+    pub async fn notifications(&self) -> Result<NotificationStream> {
+        let events = self.event_listener::<EventBindingCalled>().await?;
+        self.execute(AddBindingParams::new(notification::NOTIFICATION_BINDING))
+            .await?;
+        self.execute(AddScriptToEvaluateOnNewDocumentParams::new(
+            notification::NOTIFICATION_SHIM_JS,
+        ))
+        .await?;
+        self.evaluate_expression(notification::NOTIFICATION_SHIM_JS)
+            .await?;
+        Ok(NotificationStream::new(events))
+    }
+
+    /// Delivers a push message with the `data` to the service worker that
+    /// controls the page, as if it was sent by a push service.
+    ///
+    /// Waits until the page's service worker is ready, then dispatches the
+    /// `push` event to it.
+    pub async fn deliver_push_message(&self, data: impl Into<String>) -> Result<&Self> {
+        let (origin, registration_id) = self.service_worker_registration().await?;
+        self.execute(service_worker::DeliverPushMessageParams::new(
+            origin,
+            registration_id,
+            data,
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// Dispatches a `sync` event with the `tag` to the service worker that
+    /// controls the page, as if the browser came back online.
+    ///
+    /// With `last_chance` the event's `lastChance` is `true`, so the browser
+    /// won't retry the sync if it fails.
+    pub async fn dispatch_sync_event(
+        &self,
+        tag: impl Into<String>,
+        last_chance: bool,
+    ) -> Result<&Self> {
+        let (origin, registration_id) = self.service_worker_registration().await?;
+        self.execute(service_worker::DispatchSyncEventParams::new(
+            origin,
+            registration_id,
+            tag,
+            last_chance,
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// Dispatches a `periodicsync` event with the `tag` to the service worker
+    /// that controls the page, regardless of the registered interval.
+    pub async fn dispatch_periodic_sync_event(&self, tag: impl Into<String>) -> Result<&Self> {
+        let (origin, registration_id) = self.service_worker_registration().await?;
+        self.execute(service_worker::DispatchPeriodicSyncEventParams::new(
+            origin,
+            registration_id,
+            tag,
+        ))
+        .await?;
+        Ok(self)
+    }
+
+    /// Waits until the page's service worker is ready and returns the origin
+    /// and the id of its registration.
+    async fn service_worker_registration(
+        &self,
+    ) -> Result<(String, service_worker::RegistrationId)> {
+        let url = self.url().await?.unwrap_or_default();
+        let origin = page_origin(&url)
+            .ok_or_else(|| CdpError::msg(format!("{url} has no service worker")))?;
+        let scope: String = self
+            .evaluate_expression("navigator.serviceWorker.ready.then(r => r.scope)")
+            .await?
+            .into_value()?;
+
+        let mut registrations = self
+            .event_listener::<service_worker::EventWorkerRegistrationUpdated>()
+            .await?;
+        // enabling the domain reports all existing registrations
+        self.execute(service_worker::EnableParams::default())
+            .await?;
+        while let Some(event) = registrations.next().await {
+            let registration = event
+                .registrations
+                .iter()
+                .find(|reg| !reg.is_deleted && reg.scope_url == scope);
+            if let Some(registration) = registration {
+                return Ok((origin, registration.registration_id.clone()));
+            }
+        }
+        Err(CdpError::msg("Page closed"))
+    }
+
+    /// Removes the position emulated with `Page::set_geolocation`, so the
+    /// position of the host is reported again.
+    pub async fn clear_geolocation(&self) -> Result<&Self> {
+        self.execute(ClearGeolocationOverrideParams::default())
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates the timezone with the IANA id, like `Europe/Berlin`, for
+    /// dates and `Intl` of the page. An empty id restores the timezone of
+    /// the host.
+    pub async fn set_timezone(&self, timezone_id: impl Into<String>) -> Result<&Self> {
+        self.execute(SetTimezoneOverrideParams::new(timezone_id))
+            .await?;
+        Ok(self)
+    }
+
+    /// Emulates the locale, like `de-DE`, for `Intl` and the formatting of
+    /// dates and numbers of the page. An empty locale restores the locale of
+    /// the host.
+    ///
+    /// This doesn't change the `Accept-Language` header of requests.
+    pub async fn set_locale(&self, locale: impl Into<String>) -> Result<&Self> {
+        self.execute(SetLocaleOverrideParams {
+            locale: Some(locale.into()),
+        })
+        .await?;
+        Ok(self)
+    }
+
+    /// Emulates the regional settings of the locale: the `Accept-Language`
+    /// header and `navigator.language`, `Intl` formatting and the timezone,
+    /// see the [`locales`](crate::locales) module.
+    ///
+    /// This overrides the user agent with the browser's own one, so a
+    /// device is emulated afterwards.
+    pub async fn emulate_locale(&self, locale: &Locale) -> Result<&Self> {
+        let mut user_agent = SetUserAgentOverrideParams::new(self.user_agent().await?);
+        user_agent.accept_language = Some(locale.accept_language.to_string());
+        self.execute(user_agent).await?;
+        self.set_locale(locale.locale).await?;
+        self.set_timezone(locale.timezone_id).await?;
+        Ok(self)
+    }
+
+    /// Detects the language of the page's content and returns it with the
+    /// declared language of the document.
+    pub async fn detect_language(&self) -> Result<DocumentLanguage> {
+        let (mut language, text): (DocumentLanguage, String) = self
+            .evaluate_function(DETECT_LANGUAGE_JS)
+            .await?
+            .into_value()?;
+        language.detected = locales::detect_language(&text);
+        Ok(language)
+    }
+
     /// Overrides default host system timezone
     pub async fn emulate_timezone(
         &self,
@@ -498,6 +2321,123 @@ impl Page {
         Ok(self)
     }
 
+    /// Starts recording which parts of the page's scripts are executed, see
+    /// the [`coverage`](crate::coverage) module.
+    pub async fn start_js_coverage(&self) -> Result<&Self> {
+        // the sources of the scripts are only available with the debugger,
+        // which must not stop the page at `debugger` statements
+        self.execute(js_protocol::debugger::EnableParams::default())
+            .await?;
+        self.execute(js_protocol::debugger::SetSkipAllPausesParams::new(true))
+            .await?;
+        self.execute(js_protocol::profiler::EnableParams::default())
+            .await?;
+        self.execute(
+            js_protocol::profiler::StartPreciseCoverageParams::builder()
+                .call_count(true)
+                .detailed(true)
+                .build(),
+        )
+        .await?;
+        Ok(self)
+    }
+
+    /// Stops recording the coverage of the page's scripts and returns the
+    /// executed parts of each script with a url.
+    pub async fn stop_js_coverage(&self) -> Result<Vec<CoverageEntry>> {
+        let scripts = self
+            .execute(js_protocol::profiler::TakePreciseCoverageParams::default())
+            .await?
+            .result
+            .result;
+        self.execute(js_protocol::profiler::StopPreciseCoverageParams::default())
+            .await?;
+        self.execute(js_protocol::profiler::DisableParams::default())
+            .await?;
+
+        let mut entries = Vec::with_capacity(scripts.len());
+        for script in scripts.into_iter().filter(|script| !script.url.is_empty()) {
+            // the scripts of previous documents may be gone
+            let Ok(source) = self
+                .execute(GetScriptSourceParams::new(script.script_id.clone()))
+                .await
+            else {
+                continue;
+            };
+            let text = source.result.script_source;
+            entries.push(CoverageEntry {
+                ranges: coverage::js_ranges(&text, &script.functions),
+                url: script.url,
+                text,
+            });
+        }
+        self.execute(js_protocol::debugger::DisableParams::default())
+            .await?;
+        Ok(entries)
+    }
+
+    /// Starts recording which rules of the page's stylesheets are used, see
+    /// the [`coverage`](crate::coverage) module.
+    pub async fn start_css_coverage(&self) -> Result<&Self> {
+        // the css domain requires the dom domain to be enabled
+        self.execute(browser_protocol::dom::EnableParams::default())
+            .await?;
+        // enabling the domain reports the existing stylesheets, later ones
+        // are reported as they are added
+        let added = self
+            .event_listener::<browser_protocol::css::EventStyleSheetAdded>()
+            .await?;
+        self.inner.set_style_sheets(Some(added));
+        self.execute(browser_protocol::css::EnableParams::default())
+            .await?;
+        self.execute(browser_protocol::css::StartRuleUsageTrackingParams::default())
+            .await?;
+        Ok(self)
+    }
+
+    /// Stops recording the usage of the page's rules and returns the used
+    /// parts of each stylesheet.
+    pub async fn stop_css_coverage(&self) -> Result<Vec<CoverageEntry>> {
+        use browser_protocol::css;
+
+        let rules = self
+            .execute(css::StopRuleUsageTrackingParams::default())
+            .await?
+            .result
+            .rule_usage;
+
+        // all events preceding the response were already dispatched
+        let mut sheets = Vec::new();
+        if let Some(mut added) = self.inner.set_style_sheets(None) {
+            while let Some(Some(event)) = added.next().now_or_never() {
+                sheets.push(event.header.clone());
+            }
+        }
+        self.execute(css::DisableParams::default()).await?;
+
+        let mut entries = Vec::with_capacity(sheets.len());
+        for sheet in sheets {
+            let Ok(text) = self
+                .execute(css::GetStyleSheetTextParams::new(
+                    sheet.style_sheet_id.clone(),
+                ))
+                .await
+            else {
+                continue;
+            };
+            let text = text.result.text;
+            let used = rules
+                .iter()
+                .filter(|rule| rule.style_sheet_id == sheet.style_sheet_id);
+            entries.push(CoverageEntry {
+                ranges: coverage::css_ranges(&text, used),
+                url: sheet.source_url,
+                text,
+            });
+        }
+        Ok(entries)
+    }
+
     /// Enables Debugger. Enabled by default.
     pub async fn enable_debugger(&self) -> Result<&Self> {
         self.execute(js_protocol::debugger::EnableParams::default())
@@ -629,6 +2569,18 @@ impl Page {
         Ok(self)
     }
 
+    /// Deletes all cookies that match the tab's current URL.
+    pub async fn clear_cookies(&self) -> Result<&Self> {
+        let cookies = self.get_cookies().await?;
+        self.delete_cookies_unchecked(cookies.into_iter().map(|cookie| {
+            let mut params = DeleteCookiesParams::new(cookie.name);
+            params.domain = Some(cookie.domain);
+            params.path = Some(cookie.path);
+            params
+        }))
+        .await
+    }
+
     /// Convenience method that prevents another channel roundtrip to get the
     /// url and validate it
     async fn delete_cookies_unchecked(
@@ -657,7 +2609,93 @@ impl Page {
         }
     }
 
+    /// Returns the title of the document, `None` if it has no title.
+    pub async fn title(&self) -> Result<Option<String>> {
+        self.get_title().await
+    }
+
+    /// Extracts the title, the `<meta>` tags and the canonical and favicon
+    /// links of the document in a single evaluation.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let metadata = page.metadata().await?;
+    ///     let description = metadata.meta("description");
+    ///     let og_image = metadata.meta("og:image");
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn metadata(&self) -> Result<PageMetadata> {
+        Ok(self
+            .evaluate_function(PAGE_METADATA_JS)
+            .await?
+            .into_value()?)
+    }
+
+    /// Sends a request from within the page, with the page's cookies and
+    /// origin, and returns the complete response, see the
+    /// [`fetch`](crate::fetch) module.
+    ///
+    /// The `url` is resolved relative to the page's url.
+    pub async fn fetch(
+        &self,
+        url: impl Into<String>,
+        options: FetchOptions,
+    ) -> Result<FetchResponse> {
+        self.call_js_fn_into(
+            fetch::FETCH_JS,
+            vec![
+                serde_json::Value::String(url.into()),
+                serde_json::to_value(options)?,
+            ],
+        )
+        .await
+    }
+
+    /// Returns the entries of the `/sitemap.xml` of the page's origin,
+    /// following sitemap indexes, see the [`sitemap`](crate::sitemap) module.
+    pub async fn sitemap(&self) -> Result<Vec<SitemapEntry>> {
+        self.sitemap_from("/sitemap.xml").await
+    }
+
+    /// Returns the entries of the sitemap at `url`, which is resolved
+    /// relative to the page's url.
+    pub async fn sitemap_from(&self, url: impl Into<String>) -> Result<Vec<SitemapEntry>> {
+        self.call_js_fn_into(
+            sitemap::FETCH_SITEMAP_JS,
+            vec![
+                serde_json::Value::String(url.into()),
+                sitemap::MAX_SITEMAPS.into(),
+            ],
+        )
+        .await
+    }
+
+    /// Returns the `content` of the first `<meta>` tag whose `name` or
+    /// `property` matches `name` (case-insensitive).
+    pub async fn meta(&self, name: impl AsRef<str>) -> Result<Option<String>> {
+        Ok(self.metadata().await?.meta(name).map(str::to_string))
+    }
+
+    /// Returns the absolute url of the `<link rel="canonical">` of the
+    /// document, if any.
+    pub async fn canonical_url(&self) -> Result<Option<String>> {
+        Ok(self.metadata().await?.canonical_url)
+    }
+
+    /// Returns the absolute url of the `<link rel="icon">` of the document,
+    /// if any.
+    pub async fn favicon_url(&self) -> Result<Option<String>> {
+        Ok(self.metadata().await?.favicon_url)
+    }
+
     /// Retrieve current values of run-time metrics.
+    ///
+    /// See `Page::performance_metrics` for the typed values.
     pub async fn metrics(&self) -> Result<Vec<Metric>> {
         Ok(self
             .execute(GetMetricsParams::default())
@@ -666,11 +2704,42 @@ impl Page {
             .metrics)
     }
 
+    /// Retrieve current values of run-time metrics, like the size of the
+    /// JS heap or the time spent on layouts.
+    pub async fn performance_metrics(&self) -> Result<PerformanceMetrics> {
+        Ok(self.metrics().await?.into())
+    }
+
+    /// Slows down the CPU of the page by the factor `rate`, `1.0` disables
+    /// throttling.
+    pub async fn set_cpu_throttling_rate(&self, rate: f64) -> Result<&Self> {
+        self.execute(SetCpuThrottlingRateParams::new(rate)).await?;
+        Ok(self)
+    }
+
     /// Returns metrics relating to the layout of the page
     pub async fn layout_metrics(&self) -> Result<GetLayoutMetricsReturns> {
         self.inner.layout_metrics().await
     }
 
+    /// Resolves once the page rendered `frames` more animation frames.
+    ///
+    /// Waiting for at least one frame ensures that changes made to the DOM
+    /// were painted, e.g. before taking a screenshot.
+    pub async fn wait_for_animation_frame(&self, frames: usize) -> Result<&Self> {
+        self.evaluate_expression(format!("({})({})", ANIMATION_FRAMES_JS, frames))
+            .await?;
+        Ok(self)
+    }
+
+    /// Resolves once the page's event loop is idle, see
+    /// [requestIdleCallback](https://developer.mozilla.org/en-US/docs/Web/API/Window/requestIdleCallback).
+    pub async fn wait_for_idle_callback(&self) -> Result<&Self> {
+        self.evaluate_expression(format!("({})()", IDLE_CALLBACK_JS))
+            .await?;
+        Ok(self)
+    }
+
     /// This evaluates strictly as expression.
     ///
     /// Same as `Page::evaluate` but no fallback or any attempts to detect
@@ -846,6 +2915,131 @@ impl Page {
         self.inner.evaluate_function(evaluate).await
     }
 
+    /// Calls the javascript function with the given arguments within the
+    /// page's context and returns the result.
+    ///
+    /// Arguments are serialized as they are, so there is no need to format
+    /// them into the function's body. Elements can be passed as arguments
+    /// too.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let sum: usize = page
+    ///         .call_js_fn(
+    ///             "(a, b) => a + b",
+    ///             vec![serde_json::json!(1), serde_json::json!(2)],
+    ///         )
+    ///         .await?
+    ///         .into_value()?;
+    ///     assert_eq!(sum, 3);
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_js_fn<I, A>(
+        &self,
+        function_declaration: impl Into<String>,
+        args: I,
+    ) -> Result<EvaluationResult>
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<CallArgument>,
+    {
+        let call = CallFunctionOnParams::builder()
+            .function_declaration(function_declaration)
+            .arguments(args.into_iter().map(Into::into))
+            .build()
+            .unwrap();
+        self.evaluate_function(call).await
+    }
+
+    /// Calls the javascript function with the given arguments within the
+    /// page's context and deserializes its return value.
+    ///
+    /// A function that returns nothing deserializes from `null`.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let greeting: String = page
+    ///         .call_js_fn_into("name => `Hello ${name}`", vec![serde_json::json!("O'Brien")])
+    ///         .await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn call_js_fn_into<T, I, A>(
+        &self,
+        function_declaration: impl Into<String>,
+        args: I,
+    ) -> Result<T>
+    where
+        T: DeserializeOwned,
+        I: IntoIterator<Item = A>,
+        A: Into<CallArgument>,
+    {
+        let result = self.call_js_fn(function_declaration, args).await?;
+        Ok(serde_json::from_value(
+            result.value().cloned().unwrap_or_default(),
+        )?)
+    }
+
+    /// Repeatedly calls the javascript function with the given arguments until
+    /// it returns a truthy value, which is returned.
+    ///
+    /// Returned promises are awaited. This fails with `CdpError::Timeout` if
+    /// the function did not return a truthy value within the `options`
+    /// timeout.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::{Page, PollingOptions};
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     let count: usize = page
+    ///         .wait_for_function(
+    ///             "min => document.querySelectorAll('li').length >= min && document.querySelectorAll('li').length",
+    ///             vec![serde_json::json!(10)],
+    ///             PollingOptions::default(),
+    ///         )
+    ///         .await?
+    ///         .into_value()?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn wait_for_function<I, A>(
+        &self,
+        function_declaration: impl Into<String>,
+        args: I,
+        options: PollingOptions,
+    ) -> Result<EvaluationResult>
+    where
+        I: IntoIterator<Item = A>,
+        A: Into<CallArgument>,
+    {
+        let function_declaration = function_declaration.into();
+        let args: Vec<CallArgument> = args.into_iter().map(Into::into).collect();
+        let deadline = Instant::now() + options.timeout;
+        loop {
+            let result = self
+                .call_js_fn(function_declaration.clone(), args.clone())
+                .await?;
+            if result.value().map(utils::is_truthy).unwrap_or_default() {
+                return Ok(result);
+            }
+            if Instant::now() + options.interval > deadline {
+                return Err(CdpError::Timeout);
+            }
+            Delay::new(options.interval).await;
+        }
+    }
+
     /// Returns the default execution context identifier of this page that
     /// represents the context for JavaScript execution.
     pub async fn execution_context(&self) -> Result<Option<ExecutionContextId>> {
@@ -870,6 +3064,31 @@ impl Page {
         Ok(self.execute(script.into()).await?.result.identifier)
     }
 
+    /// Evaluates `source` in every new document of the page, including child
+    /// frames and documents loaded by later navigations, before any of the
+    /// document's own scripts run.
+    ///
+    /// The script does not run in the current document, see
+    /// `BrowserConfigBuilder::with_init_script` to register it for every
+    /// page from the start.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use chromiumoxide::page::Page;
+    /// # use chromiumoxide::error::Result;
+    /// # async fn demo(page: Page) -> Result<()> {
+    ///     page.add_init_script("Object.defineProperty(navigator, 'webdriver', { get: () => false })")
+    ///         .await?;
+    ///     page.goto("https://example.com").await?;
+    ///     # Ok(())
+    /// # }
+    /// ```
+    pub async fn add_init_script(&self, source: impl Into<String>) -> Result<ScriptIdentifier> {
+        self.evaluate_on_new_document(AddScriptToEvaluateOnNewDocumentParams::new(source))
+            .await
+    }
+
     /// Set the content of the frame.
     ///
     /// # Example
@@ -913,22 +3132,7 @@ impl Page {
 
     /// Returns the HTML content of the page
     pub async fn content(&self) -> Result<String> {
-        Ok(self
-            .evaluate(
-                "{
-          let retVal = '';
-          if (document.doctype) {
-            retVal = new XMLSerializer().serializeToString(document.doctype);
-          }
-          if (document.documentElement) {
-            retVal += document.documentElement.outerHTML;
-          }
-          retVal
-      }
-      ",
-            )
-            .await?
-            .into_value()?)
+        Ok(self.evaluate(CONTENT_JS).await?.into_value()?)
     }
 
     /// Returns source for the script with given id.
@@ -945,7 +3149,8 @@ impl Page {
 
 impl From<Arc<PageInner>> for Page {
     fn from(inner: Arc<PageInner>) -> Self {
-        Self { inner }
+        let token = inner.token();
+        Self { inner, token }
     }
 }
 
@@ -953,9 +3158,324 @@ fn validate_cookie_url(url: &str) -> Result<()> {
     if url.starts_with("data:") {
         Err(CdpError::msg("Data URL page can not have cookie"))
     } else if url == "about:blank" {
+        Err(CdpError::msg("Blank page can not have cookie"))
+    } else {
         Ok(())
+    }
+}
+
+/// The serialized origin of the url, `None` for opaque origins like the one
+/// of `about:blank`, which can't be granted permissions
+fn page_origin(url: &str) -> Option<String> {
+    url::Url::parse(url)
+        .ok()
+        .map(|url| url.origin())
+        .filter(|origin| origin.is_tuple())
+        .map(|origin| origin.ascii_serialization())
+}
+
+/// Resolves after `n` calls of `requestAnimationFrame`.
+/// Serializes the doctype and the document element
+pub(crate) const CONTENT_JS: &str = "{
+    let retVal = '';
+    if (document.doctype) {
+        retVal = new XMLSerializer().serializeToString(document.doctype);
+    }
+    if (document.documentElement) {
+        retVal += document.documentElement.outerHTML;
+    }
+    retVal
+}";
+
+/// The declared language and a sample of the text of the document
+const DETECT_LANGUAGE_JS: &str = r#"() => {
+    const meta = document.querySelector('meta[http-equiv="content-language" i]');
+    const declared = document.documentElement.lang || (meta && meta.content) || null;
+    const text = document.body ? document.body.innerText.slice(0, 5000) : '';
+    return [{ declared }, text];
+}"#;
+
+const ANIMATION_FRAMES_JS: &str = r#"(n) => new Promise((resolve) => {
+    const next = (remaining) => {
+        if (remaining <= 0) {
+            resolve();
+        } else {
+            requestAnimationFrame(() => next(remaining - 1));
+        }
+    };
+    next(n);
+})"#;
+
+/// Resolves once `requestIdleCallback` fired, falling back to a timeout for
+/// browsers without it.
+const IDLE_CALLBACK_JS: &str = r#"() => new Promise((resolve) => {
+    if (typeof requestIdleCallback === 'function') {
+        requestIdleCallback(() => resolve());
     } else {
-        Err(CdpError::msg("Blank page can not have cookie"))
+        setTimeout(resolve, 0);
+    }
+})"#;
+
+/// Extracts the document metadata as `PageMetadata`.
+const ADD_SCRIPT_TAG_JS: &str = r#"(url, content, type) => new Promise((resolve, reject) => {
+    const script = document.createElement('script');
+    if (type) script.type = type;
+    if (url) {
+        script.src = url;
+        script.onload = () => resolve(script);
+        script.onerror = () => reject(new Error(`Failed to load script ${url}`));
+    } else {
+        script.text = content;
+    }
+    (document.head || document.documentElement).appendChild(script);
+    if (!url) resolve(script);
+})"#;
+
+const ADD_STYLE_TAG_JS: &str = r#"(url, content) => new Promise((resolve, reject) => {
+    let style;
+    if (url) {
+        style = document.createElement('link');
+        style.rel = 'stylesheet';
+        style.href = url;
+        style.onload = () => resolve(style);
+        style.onerror = () => reject(new Error(`Failed to load stylesheet ${url}`));
+    } else {
+        style = document.createElement('style');
+        style.textContent = content;
+    }
+    (document.head || document.documentElement).appendChild(style);
+    if (!url) resolve(style);
+})"#;
+
+const PAGE_METADATA_JS: &str = r#"() => {
+    const meta = {};
+    for (const el of document.querySelectorAll('meta[name], meta[property]')) {
+        const key = (el.getAttribute('name') || el.getAttribute('property')).toLowerCase();
+        if (!(key in meta)) {
+            meta[key] = el.getAttribute('content') || '';
+        }
+    }
+    const link = rel => {
+        const el = document.querySelector(`link[rel~="${rel}" i][href]`);
+        return el ? el.href : null;
+    };
+    return {
+        title: document.title || null,
+        canonicalUrl: link('canonical'),
+        faviconUrl: link('icon'),
+        meta,
+    };
+}"#;
+
+/// Options of `Page::search_text_with`.
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Highlights the first match with the config
+    pub highlight: Option<HighlightConfig>,
+    /// Whether to search the shadow DOM of built-in elements, like the
+    /// controls of a `<video>`
+    pub include_user_agent_shadow_dom: bool,
+}
+
+/// The result of `Page::search_text`.
+#[derive(Debug)]
+pub struct TextSearch {
+    /// How often the query matched
+    pub count: usize,
+    /// The matched elements, the elements containing the text for text
+    /// matches, in document order
+    pub elements: Vec<Element>,
+}
+
+impl TextSearch {
+    /// Whether the query matched at all
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+}
+
+/// Metadata of a document, see `Page::metadata`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageMetadata {
+    /// The title of the document.
+    pub title: Option<String>,
+    /// The absolute url of the `<link rel="canonical">`.
+    pub canonical_url: Option<String>,
+    /// The absolute url of the `<link rel="icon">`.
+    pub favicon_url: Option<String>,
+    /// The `content` of all `<meta>` tags, keyed by their lowercase `name` or
+    /// `property`.
+    pub meta: HashMap<String, String>,
+}
+
+impl PageMetadata {
+    /// Returns the `content` of the `<meta>` tag with the given `name` or
+    /// `property` (case-insensitive).
+    pub fn meta(&self, name: impl AsRef<str>) -> Option<&str> {
+        self.meta
+            .get(&name.as_ref().to_lowercase())
+            .map(String::as_str)
+    }
+}
+
+/// Run-time metrics of a page, see `Page::performance_metrics`.
+///
+/// Durations are in seconds, sizes in bytes.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PerformanceMetrics {
+    /// The time the metrics were taken at, in seconds of the monotonic clock.
+    pub timestamp: f64,
+    /// The number of documents in the page.
+    pub documents: f64,
+    /// The number of frames in the page.
+    pub frames: f64,
+    /// The number of events listeners in the page.
+    pub js_event_listeners: f64,
+    /// The number of DOM nodes in the page.
+    pub nodes: f64,
+    /// The number of full or partial page layouts.
+    pub layout_count: f64,
+    /// The number of page style recalculations.
+    pub recalc_style_count: f64,
+    /// The combined duration of all page layouts.
+    pub layout_duration: f64,
+    /// The combined duration of all page style recalculations.
+    pub recalc_style_duration: f64,
+    /// The combined duration of JavaScript execution.
+    pub script_duration: f64,
+    /// The combined duration of all tasks performed by the browser.
+    pub task_duration: f64,
+    /// The used JavaScript heap size.
+    pub js_heap_used_size: f64,
+    /// The total JavaScript heap size.
+    pub js_heap_total_size: f64,
+    /// All metrics reported by the browser, keyed by their name.
+    pub all: HashMap<String, f64>,
+}
+
+impl PerformanceMetrics {
+    /// Returns the metric with the name as reported by the browser, like
+    /// `JSHeapUsedSize`.
+    pub fn get(&self, name: impl AsRef<str>) -> Option<f64> {
+        self.all.get(name.as_ref()).copied()
+    }
+}
+
+impl From<Vec<Metric>> for PerformanceMetrics {
+    fn from(metrics: Vec<Metric>) -> Self {
+        let all: HashMap<_, _> = metrics.into_iter().map(|m| (m.name, m.value)).collect();
+        let get = |name: &str| all.get(name).copied().unwrap_or_default();
+        Self {
+            timestamp: get("Timestamp"),
+            documents: get("Documents"),
+            frames: get("Frames"),
+            js_event_listeners: get("JSEventListeners"),
+            nodes: get("Nodes"),
+            layout_count: get("LayoutCount"),
+            recalc_style_count: get("RecalcStyleCount"),
+            layout_duration: get("LayoutDuration"),
+            recalc_style_duration: get("RecalcStyleDuration"),
+            script_duration: get("ScriptDuration"),
+            task_duration: get("TaskDuration"),
+            js_heap_used_size: get("JSHeapUsedSize"),
+            js_heap_total_size: get("JSHeapTotalSize"),
+            all,
+        }
+    }
+}
+
+/// How often and how long to poll in `Page::wait_for_function`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollingOptions {
+    /// The time between two evaluations (defaults to 100ms).
+    pub interval: Duration,
+    /// The time after which polling fails (defaults to 30s).
+    pub timeout: Duration,
+}
+
+impl PollingOptions {
+    pub fn new(interval: Duration, timeout: Duration) -> Self {
+        Self { interval, timeout }
+    }
+}
+
+impl Default for PollingOptions {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_millis(100),
+            timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Options for navigating to a new URL.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NavigationOptions {
+    /// Referrer URL.
+    pub referrer: Option<String>,
+    /// Intended transition type.
+    pub transition_type: Option<TransitionType>,
+    /// Referrer-policy used for the navigation.
+    pub referrer_policy: Option<ReferrerPolicy>,
+    /// When the navigation is considered finished (defaults to `load`).
+    pub wait_until: WaitUntil,
+}
+
+impl NavigationOptions {
+    pub fn builder() -> NavigationOptionsBuilder {
+        Default::default()
+    }
+
+    /// Sets the options on the `params` and returns when the navigation is
+    /// considered finished.
+    pub(crate) fn apply(self, params: &mut NavigateParams) -> WaitUntil {
+        if self.referrer.is_some() {
+            params.referrer = self.referrer;
+        }
+        if self.transition_type.is_some() {
+            params.transition_type = self.transition_type;
+        }
+        if self.referrer_policy.is_some() {
+            params.referrer_policy = self.referrer_policy;
+        }
+        self.wait_until
+    }
+}
+
+/// Builder for [`NavigationOptions`].
+#[derive(Debug, Default)]
+pub struct NavigationOptionsBuilder {
+    options: NavigationOptions,
+}
+
+impl NavigationOptionsBuilder {
+    /// Referrer URL.
+    pub fn referrer(mut self, referrer: impl Into<String>) -> Self {
+        self.options.referrer = Some(referrer.into());
+        self
+    }
+
+    /// Intended transition type.
+    pub fn transition_type(mut self, transition_type: impl Into<TransitionType>) -> Self {
+        self.options.transition_type = Some(transition_type.into());
+        self
+    }
+
+    /// Referrer-policy used for the navigation.
+    pub fn referrer_policy(mut self, referrer_policy: impl Into<ReferrerPolicy>) -> Self {
+        self.options.referrer_policy = Some(referrer_policy.into());
+        self
+    }
+
+    /// When the navigation is considered finished.
+    pub fn wait_until(mut self, wait_until: WaitUntil) -> Self {
+        self.options.wait_until = wait_until;
+        self
+    }
+
+    pub fn build(self) -> NavigationOptions {
+        self.options
     }
 }
 
@@ -964,10 +3484,13 @@ fn validate_cookie_url(url: &str) -> Result<()> {
 pub struct ScreenshotParams {
     /// Chrome DevTools Protocol screenshot options.
     pub cdp_params: CaptureScreenshotParams,
-    /// Take full page screenshot.
+    /// Take full page screenshot, stitched from tiles if the page is taller
+    /// than chromium can render at once (requires the `image` feature).
     pub full_page: Option<bool>,
     /// Make the background transparent (png only).
     pub omit_background: Option<bool>,
+    /// Emulate a vision deficiency while capturing the screenshot.
+    pub vision_deficiency: Option<VisionDeficiency>,
 }
 
 impl ScreenshotParams {
@@ -995,6 +3518,7 @@ pub struct ScreenshotParamsBuilder {
     cdp_params: CaptureScreenshotParams,
     full_page: Option<bool>,
     omit_background: Option<bool>,
+    vision_deficiency: Option<VisionDeficiency>,
 }
 
 impl ScreenshotParamsBuilder {
@@ -1022,7 +3546,8 @@ impl ScreenshotParamsBuilder {
         self
     }
 
-    /// Full page screen capture.
+    /// Full page screen capture, the page's viewport is resized to the size
+    /// of its content while capturing and restored afterwards.
     pub fn full_page(mut self, full_page: impl Into<bool>) -> Self {
         self.full_page = Some(full_page.into());
         self
@@ -1034,11 +3559,19 @@ impl ScreenshotParamsBuilder {
         self
     }
 
+    /// Emulate a vision deficiency while capturing, which is reset
+    /// afterwards.
+    pub fn vision_deficiency(mut self, deficiency: impl Into<VisionDeficiency>) -> Self {
+        self.vision_deficiency = Some(deficiency.into());
+        self
+    }
+
     pub fn build(self) -> ScreenshotParams {
         ScreenshotParams {
             cdp_params: self.cdp_params,
             full_page: self.full_page,
             omit_background: self.omit_background,
+            vision_deficiency: self.vision_deficiency,
         }
     }
 }
@@ -1051,3 +3584,386 @@ impl From<CaptureScreenshotParams> for ScreenshotParams {
         }
     }
 }
+
+/// The size of the chunks read from streams of the IO domain
+pub(crate) const IO_CHUNK_SIZE: i64 = 1024 * 1024;
+
+/// Reads the chunks of a stream of the IO domain and closes it at its end or
+/// after an error.
+fn read_io_stream(page: Page, handle: StreamHandle) -> BoxStream<'static, Result<Vec<u8>>> {
+    stream::unfold(Some((page, handle)), |state| async move {
+        let (page, handle) = state?;
+        let mut read = ReadParams::new(handle.clone());
+        read.size = Some(IO_CHUNK_SIZE);
+        let chunk = match page.execute(read).await {
+            Ok(resp) => {
+                let resp = resp.result;
+                if resp.base64_encoded.unwrap_or_default() {
+                    base64::decode(&resp.data).map_err(CdpError::from)
+                } else {
+                    Ok(resp.data.into_bytes())
+                }
+                .map(|chunk| (chunk, resp.eof))
+            }
+            Err(err) => Err(err),
+        };
+        match chunk {
+            Ok((chunk, false)) => Some((Ok(chunk), Some((page, handle)))),
+            Ok((chunk, true)) => {
+                let closed = page.execute(io::CloseParams::new(handle)).await;
+                match closed {
+                    Err(err) => Some((Err(err), None)),
+                    Ok(_) if chunk.is_empty() => None,
+                    Ok(_) => Some((Ok(chunk), None)),
+                }
+            }
+            Err(err) => {
+                let _ = page.execute(io::CloseParams::new(handle)).await;
+                Some((Err(err), None))
+            }
+        }
+    })
+    .boxed()
+}
+
+/// The screenshot format for the extension of the file, `None` if the file
+/// has no extension.
+fn screenshot_format(path: &Path) -> Result<Option<CaptureScreenshotFormat>, String> {
+    let extension = match path.extension() {
+        Some(extension) => extension.to_string_lossy().to_ascii_lowercase(),
+        None => return Ok(None),
+    };
+    match extension.as_str() {
+        "png" => Ok(Some(CaptureScreenshotFormat::Png)),
+        "jpg" | "jpeg" => Ok(Some(CaptureScreenshotFormat::Jpeg)),
+        _ => Err(format!("Unsupported screenshot format: .{extension}")),
+    }
+}
+
+/// The properties of a pen dispatched with `Page::pen_down` and friends.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PenOptions {
+    /// The pressure of the pen on the surface, from 0 to 1 (defaults to 0.5)
+    pub pressure: f64,
+    /// The tilt of the pen along the x-axis in degrees, from -90 to 90
+    pub tilt_x: i64,
+    /// The tilt of the pen along the y-axis in degrees, from -90 to 90
+    pub tilt_y: i64,
+    /// The clockwise rotation of the pen around its axis in degrees, from 0
+    /// to 359
+    pub twist: i64,
+}
+
+impl Default for PenOptions {
+    fn default() -> Self {
+        Self {
+            pressure: 0.5,
+            tilt_x: 0,
+            tilt_y: 0,
+            twist: 0,
+        }
+    }
+}
+
+/// A modifier key that is held down while dispatching mouse or key events.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Modifier {
+    Alt,
+    Control,
+    Meta,
+    Shift,
+}
+
+impl Modifier {
+    /// The name of the modifier's key.
+    pub fn key(&self) -> &'static str {
+        match self {
+            Modifier::Alt => "Alt",
+            Modifier::Control => "Control",
+            Modifier::Meta => "Meta",
+            Modifier::Shift => "Shift",
+        }
+    }
+
+    /// The bit of this modifier in the CDP `modifiers` bit field.
+    pub fn bit(&self) -> i64 {
+        match self {
+            Modifier::Alt => 1,
+            Modifier::Control => 2,
+            Modifier::Meta => 4,
+            Modifier::Shift => 8,
+        }
+    }
+}
+
+/// How a mouse click is performed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClickOptions {
+    /// The mouse button to click with (defaults to left).
+    pub button: MouseButton,
+    /// How many consecutive clicks to perform, 2 for a double click
+    /// (defaults to 1).
+    pub click_count: i64,
+    /// Bit field of the modifier keys that are held down, see
+    /// [`Modifier::bit`].
+    pub modifiers: i64,
+    /// How long to wait between pressing and releasing the button.
+    pub delay: Option<Duration>,
+}
+
+impl ClickOptions {
+    pub fn builder() -> ClickOptionsBuilder {
+        Default::default()
+    }
+
+    /// The bit field of the pressed mouse button.
+    pub(crate) fn buttons(&self) -> i64 {
+        match self.button {
+            MouseButton::None => 0,
+            MouseButton::Left => 1,
+            MouseButton::Right => 2,
+            MouseButton::Middle => 4,
+            MouseButton::Back => 8,
+            MouseButton::Forward => 16,
+        }
+    }
+}
+
+impl Default for ClickOptions {
+    fn default() -> Self {
+        Self {
+            button: MouseButton::Left,
+            click_count: 1,
+            modifiers: 0,
+            delay: None,
+        }
+    }
+}
+
+/// Builder for [`ClickOptions`].
+#[derive(Debug, Default)]
+pub struct ClickOptionsBuilder {
+    options: ClickOptions,
+}
+
+impl ClickOptionsBuilder {
+    /// The mouse button to click with.
+    pub fn button(mut self, button: impl Into<MouseButton>) -> Self {
+        self.options.button = button.into();
+        self
+    }
+
+    /// How many consecutive clicks to perform, 2 for a double click and 3 for
+    /// a triple click.
+    pub fn click_count(mut self, click_count: impl Into<i64>) -> Self {
+        self.options.click_count = click_count.into();
+        self
+    }
+
+    /// Holds the modifier key down while clicking.
+    pub fn modifier(mut self, modifier: Modifier) -> Self {
+        self.options.modifiers |= modifier.bit();
+        self
+    }
+
+    /// How long to wait between pressing and releasing the button.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.options.delay = Some(delay);
+        self
+    }
+
+    pub fn build(self) -> ClickOptions {
+        self.options
+    }
+}
+
+/// Where the contents of a tag added with [`Page::add_script_tag`] or
+/// [`Page::add_style_tag`] come from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagSource {
+    /// Load the tag from a url.
+    Url(String),
+    /// Inline the given content.
+    Content(String),
+    /// Inline the content of a local file.
+    Path(PathBuf),
+}
+
+impl TagSource {
+    /// Returns the url and the inline content of the tag as JS arguments,
+    /// `source_url` builds the comment that names inlined files in devtools.
+    async fn resolve(
+        self,
+        source_url: impl Fn(&str) -> String,
+    ) -> Result<(serde_json::Value, serde_json::Value)> {
+        Ok(match self {
+            TagSource::Url(url) => (url.into(), serde_json::Value::Null),
+            TagSource::Content(content) => (serde_json::Value::Null, content.into()),
+            TagSource::Path(path) => {
+                let mut content = String::from_utf8_lossy(&utils::read(&path).await?).into_owned();
+                content.push_str(&source_url(&path.display().to_string()));
+                (serde_json::Value::Null, content.into())
+            }
+        })
+    }
+}
+
+/// A `<script>` tag to add with [`Page::add_script_tag`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptTag {
+    pub source: TagSource,
+    /// The `type` attribute of the script, e.g. `module`.
+    pub r#type: Option<String>,
+}
+
+impl ScriptTag {
+    /// A script loaded from a url.
+    pub fn url(url: impl Into<String>) -> Self {
+        TagSource::Url(url.into()).into()
+    }
+
+    /// An inline script.
+    pub fn content(content: impl Into<String>) -> Self {
+        TagSource::Content(content.into()).into()
+    }
+
+    /// An inline script read from a local file.
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        TagSource::Path(path.into()).into()
+    }
+
+    /// Sets the `type` attribute of the script.
+    pub fn with_type(mut self, ty: impl Into<String>) -> Self {
+        self.r#type = Some(ty.into());
+        self
+    }
+}
+
+impl From<TagSource> for ScriptTag {
+    fn from(source: TagSource) -> Self {
+        Self {
+            source,
+            r#type: None,
+        }
+    }
+}
+
+/// A stylesheet to add with [`Page::add_style_tag`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyleTag {
+    pub source: TagSource,
+}
+
+impl StyleTag {
+    /// A stylesheet loaded from a url.
+    pub fn url(url: impl Into<String>) -> Self {
+        TagSource::Url(url.into()).into()
+    }
+
+    /// An inline stylesheet.
+    pub fn content(content: impl Into<String>) -> Self {
+        TagSource::Content(content.into()).into()
+    }
+
+    /// An inline stylesheet read from a local file.
+    pub fn path(path: impl Into<PathBuf>) -> Self {
+        TagSource::Path(path.into()).into()
+    }
+}
+
+impl From<TagSource> for StyleTag {
+    fn from(source: TagSource) -> Self {
+        Self { source }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_cookie_urls() {
+        assert!(validate_cookie_url("https://example.com/").is_ok());
+        assert!(validate_cookie_url("about:blank").is_err());
+        assert!(validate_cookie_url("data:text/html,<p>").is_err());
+    }
+
+    #[test]
+    fn page_metadata_meta_lookup() {
+        let metadata: PageMetadata = serde_json::from_value(serde_json::json!({
+            "title": "Example",
+            "canonicalUrl": "https://example.com/",
+            "faviconUrl": null,
+            "meta": { "description": "An example", "og:title": "Example" }
+        }))
+        .unwrap();
+        assert_eq!(metadata.meta("Description"), Some("An example"));
+        assert_eq!(metadata.meta("og:title"), Some("Example"));
+        assert_eq!(metadata.meta("keywords"), None);
+        assert_eq!(metadata.favicon_url, None);
+    }
+
+    #[test]
+    fn resolves_tag_sources() {
+        let comment = |path: &str| format!("\n//# sourceURL={path}");
+        let (url, content) = futures::executor::block_on(
+            ScriptTag::url("https://a.test/x.js")
+                .source
+                .resolve(comment),
+        )
+        .unwrap();
+        assert_eq!(url, "https://a.test/x.js");
+        assert!(content.is_null());
+
+        let path = std::env::temp_dir().join("chromiumoxide-tag-source.js");
+        std::fs::write(&path, "window.x = 1;").unwrap();
+        let (url, content) =
+            futures::executor::block_on(ScriptTag::path(&path).source.resolve(comment)).unwrap();
+        assert!(url.is_null());
+        assert_eq!(
+            content,
+            format!("window.x = 1;\n//# sourceURL={}", path.display())
+        );
+    }
+
+    #[test]
+    fn infers_screenshot_formats() {
+        assert_eq!(
+            screenshot_format(Path::new("shot.JPG")).unwrap(),
+            Some(CaptureScreenshotFormat::Jpeg)
+        );
+        assert_eq!(
+            screenshot_format(Path::new("out/shot.png")).unwrap(),
+            Some(CaptureScreenshotFormat::Png)
+        );
+        assert_eq!(screenshot_format(Path::new("shot")).unwrap(), None);
+        assert!(screenshot_format(Path::new("shot.gif")).is_err());
+    }
+
+    #[test]
+    fn click_options_modifiers() {
+        let options = ClickOptions::builder()
+            .button(MouseButton::Right)
+            .click_count(3)
+            .modifier(Modifier::Control)
+            .modifier(Modifier::Shift)
+            .build();
+        assert_eq!(options.modifiers, 10);
+        assert_eq!(options.buttons(), 2);
+        assert_eq!(options.click_count, 3);
+        assert_eq!(ClickOptions::default().buttons(), 1);
+    }
+
+    #[test]
+    fn typed_performance_metrics() {
+        let metrics = PerformanceMetrics::from(vec![
+            Metric::new("JSHeapUsedSize", 1024.),
+            Metric::new("LayoutCount", 3.),
+            Metric::new("FirstMeaningfulPaint", 0.5),
+        ]);
+        assert_eq!(metrics.js_heap_used_size, 1024.);
+        assert_eq!(metrics.layout_count, 3.);
+        assert_eq!(metrics.task_duration, 0.);
+        assert_eq!(metrics.get("FirstMeaningfulPaint"), Some(0.5));
+    }
+}
@@ -0,0 +1,189 @@
+//! Uncaught exceptions of a page.
+//!
+//! [`Page::page_errors`](crate::page::Page::page_errors) returns a
+//! [`PageErrorStream`] of the [`PageError`]s thrown by the page's scripts
+//! and not caught by them, with their stack traces.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut errors = page.page_errors().await?;
+//!     while let Some(error) = errors.next().await {
+//!         eprintln!("{}", error.message);
+//!         for frame in &error.stack {
+//!             eprintln!("    at {} ({}:{})", frame.function_name, frame.url, frame.line_number);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::{Stream, StreamExt};
+
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{EventExceptionThrown, ExceptionDetails};
+
+use crate::listeners::EventStream;
+
+/// A frame of the stack trace of a [`PageError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    /// The name of the function, empty for anonymous functions
+    pub function_name: String,
+    pub url: String,
+    /// 0-based line number
+    pub line_number: i64,
+    /// 0-based column number
+    pub column_number: i64,
+}
+
+impl StackFrame {
+    /// Parses a line of a V8 stack like `    at run (https://a.com/app.js:5:11)`
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim().strip_prefix("at ")?;
+        let (function_name, location) = match line.strip_suffix(')') {
+            Some(line) => {
+                let (function_name, location) = line.split_once(" (")?;
+                (function_name, location)
+            }
+            None => ("", line),
+        };
+        let (location, column_number) = location.rsplit_once(':')?;
+        let (url, line_number) = location.rsplit_once(':')?;
+        Some(Self {
+            function_name: function_name.to_string(),
+            url: url.to_string(),
+            line_number: line_number.parse::<i64>().ok()? - 1,
+            column_number: column_number.parse::<i64>().ok()? - 1,
+        })
+    }
+}
+
+/// An exception that was thrown but not caught by the scripts of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageError {
+    /// The class of the thrown error, like `TypeError`
+    pub name: Option<String>,
+    /// The message of the error, like `TypeError: x is not a function`
+    pub message: String,
+    /// The stack of the error, innermost frame first
+    pub stack: Vec<StackFrame>,
+    /// Milliseconds since the epoch
+    pub timestamp: f64,
+    /// The details as reported by the browser
+    pub details: ExceptionDetails,
+}
+
+impl PageError {
+    /// Creates the error of the event.
+    pub(crate) fn new(event: EventExceptionThrown) -> Self {
+        let details = event.exception_details;
+        let exception = details.exception.as_ref();
+        let name = exception.and_then(|exception| exception.class_name.clone());
+        // the description of errors is their `stack` property
+        let description = exception.and_then(|exception| exception.description.as_deref());
+        let message = description
+            .and_then(|description| description.lines().next())
+            .unwrap_or(&details.text)
+            .to_string();
+        let stack = match &details.stack_trace {
+            Some(trace) => trace
+                .call_frames
+                .iter()
+                .map(|frame| StackFrame {
+                    function_name: frame.function_name.clone(),
+                    url: frame.url.clone(),
+                    line_number: frame.line_number,
+                    column_number: frame.column_number,
+                })
+                .collect(),
+            None => description
+                .map(|description| description.lines().filter_map(StackFrame::parse).collect())
+                .unwrap_or_default(),
+        };
+        Self {
+            name,
+            message,
+            stack,
+            timestamp: *event.timestamp.inner(),
+            details,
+        }
+    }
+}
+
+/// A stream of the uncaught exceptions of a page.
+#[must_use = "streams do nothing unless polled"]
+#[derive(Debug)]
+pub struct PageErrorStream {
+    events: EventStream<EventExceptionThrown>,
+}
+
+impl PageErrorStream {
+    pub(crate) fn new(events: EventStream<EventExceptionThrown>) -> Self {
+        Self { events }
+    }
+}
+
+impl Stream for PageErrorStream {
+    type Item = PageError;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut()
+            .events
+            .poll_next_unpin(cx)
+            .map(|event| event.map(|event| PageError::new(Arc::unwrap_or_clone(event))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_errors() {
+        let event: EventExceptionThrown = serde_json::from_value(serde_json::json!({
+            "timestamp": 1000.5,
+            "exceptionDetails": {
+                "exceptionId": 1,
+                "text": "Uncaught",
+                "lineNumber": 4,
+                "columnNumber": 10,
+                "exception": {
+                    "type": "object",
+                    "subtype": "error",
+                    "className": "TypeError",
+                    "description": "TypeError: x is not a function\n    at run (https://example.com/app.js:5:11)\n    at https://example.com/app.js:9:1",
+                },
+            },
+        }))
+        .unwrap();
+        let error = PageError::new(event);
+        assert_eq!(error.name.as_deref(), Some("TypeError"));
+        assert_eq!(error.message, "TypeError: x is not a function");
+        assert_eq!(
+            error.stack,
+            [
+                StackFrame {
+                    function_name: "run".to_string(),
+                    url: "https://example.com/app.js".to_string(),
+                    line_number: 4,
+                    column_number: 10,
+                },
+                StackFrame {
+                    function_name: String::new(),
+                    url: "https://example.com/app.js".to_string(),
+                    line_number: 8,
+                    column_number: 0,
+                }
+            ]
+        );
+        assert_eq!(error.timestamp, 1000.5);
+    }
+}
@@ -0,0 +1,210 @@
+//! Scraping paginated content.
+//!
+//! [`Page::paginate`](crate::page::Page::paginate) encodes the common loop of
+//! extracting data from a page, clicking its "next" button and waiting for
+//! the content to change. The extracted results are handed out as a
+//! [`Pagination`] stream.
+//!
+//! The stream ends when the "next" button is missing or disabled, when the
+//! content did not change within the timeout after clicking it, when a
+//! previously visited page shows up again or when the maximum number of
+//! pages was reached.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.goto("https://example.com/articles").await?;
+//!     let mut pages = page.paginate("a.next", |page| async move {
+//!         let mut titles = Vec::new();
+//!         for el in page.find_elements("h2").await? {
+//!             titles.push(el.inner_text().await?.unwrap_or_default());
+//!         }
+//!         Ok(titles)
+//!     });
+//!     while let Some(titles) = pages.next().await {
+//!         println!("{:?}", titles?);
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use futures::stream::BoxStream;
+use futures::{Future, Stream, StreamExt};
+use futures_timer::Delay;
+
+use crate::error::Result;
+use crate::page::Page;
+
+/// Limits of a [`Pagination`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaginateOptions {
+    /// The maximum number of pages to extract
+    pub max_pages: usize,
+    /// How long to wait for the content to change after clicking "next"
+    pub timeout: Duration,
+    /// The interval in which the content is checked for changes
+    pub polling_interval: Duration,
+}
+
+impl Default for PaginateOptions {
+    fn default() -> Self {
+        Self {
+            max_pages: 100,
+            timeout: Duration::from_secs(10),
+            polling_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// A stream of the results extracted from each page of paginated content.
+///
+/// An error of the extraction is yielded and ends the stream.
+#[must_use = "streams do nothing unless polled"]
+pub struct Pagination<T> {
+    inner: BoxStream<'static, Result<T>>,
+}
+
+impl<T: Send + 'static> Pagination<T> {
+    pub(crate) fn new<F, Fut>(
+        page: Page,
+        next_selector: String,
+        extract: F,
+        options: PaginateOptions,
+    ) -> Self
+    where
+        F: FnMut(Page) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T>> + Send + 'static,
+    {
+        let state = State {
+            page,
+            next_selector,
+            extract,
+            options,
+            extracted: 0,
+            visited: HashSet::new(),
+            done: false,
+        };
+        let inner = futures::stream::unfold(state, |mut state| async move {
+            let item = state.next().await?;
+            Some((item, state))
+        })
+        .boxed();
+        Self { inner }
+    }
+}
+
+impl<T> fmt::Debug for Pagination<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Pagination").finish()
+    }
+}
+
+impl<T> Stream for Pagination<T> {
+    type Item = Result<T>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().inner.poll_next_unpin(cx)
+    }
+}
+
+struct State<F> {
+    page: Page,
+    next_selector: String,
+    extract: F,
+    options: PaginateOptions,
+    /// How many pages were extracted so far
+    extracted: usize,
+    /// The fingerprints of the contents of all visited pages
+    visited: HashSet<u64>,
+    done: bool,
+}
+
+impl<F, Fut, T> State<F>
+where
+    F: FnMut(Page) -> Fut,
+    Fut: Future<Output = Result<T>>,
+{
+    /// Advances to the next page, unless this is the first one, and extracts
+    /// it.
+    async fn next(&mut self) -> Option<Result<T>> {
+        if self.done || self.extracted >= self.options.max_pages {
+            return None;
+        }
+        if self.extracted == 0 {
+            if let Ok(fingerprint) = fingerprint(&self.page).await {
+                self.visited.insert(fingerprint);
+            }
+        } else if !self.advance().await {
+            self.done = true;
+            return None;
+        }
+        self.extracted += 1;
+        let item = (self.extract)(self.page.clone()).await;
+        if item.is_err() {
+            self.done = true;
+        }
+        Some(item)
+    }
+
+    /// Clicks "next" and waits until content that wasn't seen before is
+    /// shown, returns `false` if there is no next page.
+    async fn advance(&mut self) -> bool {
+        let next = match self.page.find_element(self.next_selector.clone()).await {
+            Ok(next) => next,
+            Err(_) => return false,
+        };
+        let disabled = next.attribute("disabled").await.ok().flatten().is_some()
+            || next
+                .attribute("aria-disabled")
+                .await
+                .ok()
+                .flatten()
+                .as_deref()
+                == Some("true");
+        if disabled {
+            return false;
+        }
+        let before = match fingerprint(&self.page).await {
+            Ok(before) => before,
+            Err(_) => return false,
+        };
+        if next.click().await.is_err() {
+            return false;
+        }
+
+        let deadline = Instant::now() + self.options.timeout;
+        loop {
+            // the content can't be retrieved while a navigation is ongoing
+            if let Ok(after) = fingerprint(&self.page).await {
+                if after != before {
+                    // guard against "next" leading back to a visited page
+                    return self.visited.insert(after);
+                }
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            Delay::new(self.options.polling_interval).await;
+        }
+    }
+}
+
+/// A hash of the page's content.
+async fn fingerprint(page: &Page) -> Result<u64> {
+    let content = page.content().await?;
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    Ok(hasher.finish())
+}
@@ -0,0 +1,182 @@
+//! Typed browser permissions.
+//!
+//! A [`Permission`] names a permission like the
+//! [Permissions API](https://developer.mozilla.org/en-US/docs/Web/API/Permissions_API)
+//! does and is granted with
+//! [`Browser::grant_permissions`](crate::browser::Browser::grant_permissions).
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::browser::Browser;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::permission::Permission;
+//! # async fn demo(browser: Browser) -> Result<()> {
+//!     browser
+//!         .grant_permissions(
+//!             &[Permission::ClipboardRead, Permission::ClipboardWrite],
+//!             Some("https://example.com"),
+//!         )
+//!         .await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::PermissionType;
+
+/// A permission that can be granted to an origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Permission {
+    AccessibilityEvents,
+    BackgroundFetch,
+    BackgroundSync,
+    /// `navigator.mediaDevices.getUserMedia({ video: true })`
+    Camera,
+    /// Controlling the pan, tilt and zoom of the camera
+    CameraPanTiltZoom,
+    /// `navigator.clipboard.read()` and `readText()`
+    ClipboardRead,
+    /// `navigator.clipboard.write()` and `writeText()`
+    ClipboardWrite,
+    DurableStorage,
+    Geolocation,
+    IdleDetection,
+    /// `navigator.mediaDevices.getUserMedia({ audio: true })`
+    Microphone,
+    Midi,
+    /// MIDI with system exclusive messages
+    MidiSysex,
+    Nfc,
+    Notifications,
+    PaymentHandler,
+    PeriodicBackgroundSync,
+    ProtectedMediaIdentifier,
+    /// The accelerometer, gyroscope and magnetometer
+    Sensors,
+    WakeLockScreen,
+    WakeLockSystem,
+}
+
+impl Permission {
+    /// The name of the permission in the Permissions API, like
+    /// `clipboard-read`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Permission::AccessibilityEvents => "accessibility-events",
+            Permission::BackgroundFetch => "background-fetch",
+            Permission::BackgroundSync => "background-sync",
+            Permission::Camera => "camera",
+            Permission::CameraPanTiltZoom => "camera-pan-tilt-zoom",
+            Permission::ClipboardRead => "clipboard-read",
+            Permission::ClipboardWrite => "clipboard-write",
+            Permission::DurableStorage => "persistent-storage",
+            Permission::Geolocation => "geolocation",
+            Permission::IdleDetection => "idle-detection",
+            Permission::Microphone => "microphone",
+            Permission::Midi => "midi",
+            Permission::MidiSysex => "midi-sysex",
+            Permission::Nfc => "nfc",
+            Permission::Notifications => "notifications",
+            Permission::PaymentHandler => "payment-handler",
+            Permission::PeriodicBackgroundSync => "periodic-background-sync",
+            Permission::ProtectedMediaIdentifier => "protected-media-identifier",
+            Permission::Sensors => "sensors",
+            Permission::WakeLockScreen => "screen-wake-lock",
+            Permission::WakeLockSystem => "system-wake-lock",
+        }
+    }
+
+    /// All permissions.
+    pub fn all() -> &'static [Permission] {
+        &[
+            Permission::AccessibilityEvents,
+            Permission::BackgroundFetch,
+            Permission::BackgroundSync,
+            Permission::Camera,
+            Permission::CameraPanTiltZoom,
+            Permission::ClipboardRead,
+            Permission::ClipboardWrite,
+            Permission::DurableStorage,
+            Permission::Geolocation,
+            Permission::IdleDetection,
+            Permission::Microphone,
+            Permission::Midi,
+            Permission::MidiSysex,
+            Permission::Nfc,
+            Permission::Notifications,
+            Permission::PaymentHandler,
+            Permission::PeriodicBackgroundSync,
+            Permission::ProtectedMediaIdentifier,
+            Permission::Sensors,
+            Permission::WakeLockScreen,
+            Permission::WakeLockSystem,
+        ]
+    }
+}
+
+impl From<Permission> for PermissionType {
+    fn from(permission: Permission) -> Self {
+        match permission {
+            Permission::AccessibilityEvents => PermissionType::AccessibilityEvents,
+            Permission::BackgroundFetch => PermissionType::BackgroundFetch,
+            Permission::BackgroundSync => PermissionType::BackgroundSync,
+            Permission::Camera => PermissionType::VideoCapture,
+            Permission::CameraPanTiltZoom => PermissionType::VideoCapturePanTiltZoom,
+            Permission::ClipboardRead => PermissionType::ClipboardReadWrite,
+            Permission::ClipboardWrite => PermissionType::ClipboardSanitizedWrite,
+            Permission::DurableStorage => PermissionType::DurableStorage,
+            Permission::Geolocation => PermissionType::Geolocation,
+            Permission::IdleDetection => PermissionType::IdleDetection,
+            Permission::Microphone => PermissionType::AudioCapture,
+            Permission::Midi => PermissionType::Midi,
+            Permission::MidiSysex => PermissionType::MidiSysex,
+            Permission::Nfc => PermissionType::Nfc,
+            Permission::Notifications => PermissionType::Notifications,
+            Permission::PaymentHandler => PermissionType::PaymentHandler,
+            Permission::PeriodicBackgroundSync => PermissionType::PeriodicBackgroundSync,
+            Permission::ProtectedMediaIdentifier => PermissionType::ProtectedMediaIdentifier,
+            Permission::Sensors => PermissionType::Sensors,
+            Permission::WakeLockScreen => PermissionType::WakeLockScreen,
+            Permission::WakeLockSystem => PermissionType::WakeLockSystem,
+        }
+    }
+}
+
+impl FromStr for Permission {
+    type Err = String;
+
+    /// Parses the name of a permission in the Permissions API.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Permission::all()
+            .iter()
+            .find(|permission| permission.name() == s)
+            .copied()
+            .ok_or_else(|| format!("Unknown permission {s}"))
+    }
+}
+
+impl fmt::Display for Permission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names() {
+        for permission in Permission::all() {
+            assert_eq!(permission.name().parse(), Ok(*permission));
+        }
+        assert_eq!(
+            PermissionType::from("clipboard-write".parse::<Permission>().unwrap()),
+            PermissionType::ClipboardSanitizedWrite
+        );
+        assert!("clipboard".parse::<Permission>().is_err());
+    }
+}
@@ -0,0 +1,265 @@
+//! Rotating proxies across browser contexts.
+//!
+//! Every browser context can route its requests through its own proxy. A
+//! [`ProxyRotation`] creates pages in a context whose proxy was handed out
+//! by a [`ProxyProvider`] and replaces the context with one using the next
+//! proxy after a number of pages, or when a proxy is reported as failing.
+//! Replacing a context closes its pages, so pages should be done before the
+//! next one is created.
+//!
+//! Proxies that require credentials are answered automatically, see
+//! [`Proxy::with_credentials`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::browser::Browser;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::proxy::{Proxy, ProxyRotation, RoundRobin};
+//! # async fn demo(browser: Browser) -> Result<()> {
+//!     let proxies = RoundRobin::new(vec![
+//!         Proxy::new("http://proxy-1.example.com:8080"),
+//!         Proxy::new("socks5://proxy-2.example.com:1080"),
+//!     ]);
+//!     let mut rotation = ProxyRotation::new(&browser, proxies).pages_per_context(10);
+//!     for url in ["https://example.com/a", "https://example.com/b"] {
+//!         let page = rotation.new_page(url).await?;
+//!         if page.get_title().await?.as_deref() == Some("Access denied") {
+//!             rotation.report_failure().await?;
+//!         }
+//!     }
+//!     rotation.close().await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::BrowserContextId;
+use chromiumoxide_cdp::cdp::browser_protocol::target::CreateTargetParams;
+
+use crate::auth::Credentials;
+use crate::browser::Browser;
+use crate::context::ContextOptions;
+use crate::error::{CdpError, Result};
+use crate::page::Page;
+
+/// A proxy server for the requests of a browser context.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Proxy {
+    /// The proxy server, like `http://proxy.example.com:8080` or
+    /// `socks5://proxy.example.com:1080`
+    pub server: String,
+    /// Hosts that bypass the proxy, like `localhost,*.internal`
+    pub bypass_list: Option<String>,
+    /// The credentials the proxy requires
+    pub credentials: Option<Credentials>,
+}
+
+impl Proxy {
+    pub fn new(server: impl Into<String>) -> Self {
+        Self {
+            server: server.into(),
+            bypass_list: None,
+            credentials: None,
+        }
+    }
+
+    pub fn with_bypass_list(mut self, bypass_list: impl Into<String>) -> Self {
+        self.bypass_list = Some(bypass_list.into());
+        self
+    }
+
+    pub fn with_credentials(
+        mut self,
+        username: impl Into<String>,
+        password: impl Into<String>,
+    ) -> Self {
+        self.credentials = Some(Credentials {
+            username: username.into(),
+            password: password.into(),
+        });
+        self
+    }
+}
+
+/// Hands out the proxies of new browser contexts.
+pub trait ProxyProvider: Send + Sync {
+    /// The proxy of the next browser context, `None` if there is none left.
+    fn next_proxy(&self) -> Option<Proxy>;
+
+    /// Called when the proxy was reported as failing, e.g. to stop handing
+    /// it out.
+    fn report_failure(&self, _proxy: &Proxy) {}
+}
+
+impl<F> ProxyProvider for F
+where
+    F: Fn() -> Option<Proxy> + Send + Sync,
+{
+    fn next_proxy(&self) -> Option<Proxy> {
+        self()
+    }
+}
+
+/// Hands out a list of proxies in turn.
+#[derive(Debug)]
+pub struct RoundRobin {
+    proxies: Vec<Proxy>,
+    next: AtomicUsize,
+}
+
+impl RoundRobin {
+    pub fn new(proxies: Vec<Proxy>) -> Self {
+        Self {
+            proxies,
+            next: AtomicUsize::new(0),
+        }
+    }
+}
+
+impl ProxyProvider for RoundRobin {
+    fn next_proxy(&self) -> Option<Proxy> {
+        if self.proxies.is_empty() {
+            return None;
+        }
+        let next = self.next.fetch_add(1, Ordering::Relaxed);
+        self.proxies.get(next % self.proxies.len()).cloned()
+    }
+}
+
+/// The context the rotation currently creates pages in
+#[derive(Debug)]
+struct RotatedContext {
+    id: BrowserContextId,
+    proxy: Proxy,
+    pages: usize,
+}
+
+/// Creates pages in browser contexts with rotating proxies.
+pub struct ProxyRotation<'a, P> {
+    browser: &'a Browser,
+    provider: P,
+    pages_per_context: usize,
+    context: Option<RotatedContext>,
+}
+
+impl<'a, P: ProxyProvider> ProxyRotation<'a, P> {
+    pub fn new(browser: &'a Browser, provider: P) -> Self {
+        Self {
+            browser,
+            provider,
+            pages_per_context: 1,
+            context: None,
+        }
+    }
+
+    /// How many pages are created in a context before it's replaced by one
+    /// with the next proxy (defaults to 1, a new proxy for every page).
+    pub fn pages_per_context(mut self, pages_per_context: usize) -> Self {
+        self.pages_per_context = pages_per_context.max(1);
+        self
+    }
+
+    /// The proxy of the current context.
+    pub fn current_proxy(&self) -> Option<&Proxy> {
+        self.context.as_ref().map(|context| &context.proxy)
+    }
+
+    /// The identifier of the current context.
+    pub fn context_id(&self) -> Option<&BrowserContextId> {
+        self.context.as_ref().map(|context| &context.id)
+    }
+
+    /// Whether the next page needs a new context
+    fn needs_rotation(&self) -> bool {
+        self.context
+            .as_ref()
+            .map(|context| context.pages >= self.pages_per_context)
+            .unwrap_or(true)
+    }
+
+    /// Creates a page that navigates to the url in the current context,
+    /// replacing the context first if it already has its pages.
+    pub async fn new_page(&mut self, url: impl Into<String>) -> Result<Page> {
+        if self.needs_rotation() {
+            self.rotate().await?;
+        }
+        let context = self
+            .context
+            .as_mut()
+            .expect("the context was created by rotating");
+        let mut params = CreateTargetParams::new(url);
+        params.browser_context_id = Some(context.id.clone());
+        context.pages += 1;
+        self.browser.new_page(params).await
+    }
+
+    /// Replaces the current context, closing its pages, by one with the next
+    /// proxy of the provider.
+    pub async fn rotate(&mut self) -> Result<&Proxy> {
+        self.close().await?;
+        let proxy = self
+            .provider
+            .next_proxy()
+            .ok_or_else(|| CdpError::msg("The provider has no proxy left"))?;
+        let context = self
+            .browser
+            .new_context(ContextOptions::default().proxy(proxy.clone()))
+            .await?;
+        let context = self.context.insert(RotatedContext {
+            id: context.id().clone(),
+            proxy,
+            pages: 0,
+        });
+        Ok(&context.proxy)
+    }
+
+    /// Reports the current proxy to the provider as failing and replaces
+    /// the context.
+    pub async fn report_failure(&mut self) -> Result<&Proxy> {
+        if let Some(context) = &self.context {
+            self.provider.report_failure(&context.proxy);
+        }
+        self.rotate().await
+    }
+
+    /// Disposes the current context and closes its pages.
+    pub async fn close(&mut self) -> Result<()> {
+        if let Some(context) = self.context.take() {
+            self.browser.dispose_browser_context(context.id).await?;
+        }
+        Ok(())
+    }
+}
+
+impl<P> fmt::Debug for ProxyRotation<'_, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ProxyRotation")
+            .field("pages_per_context", &self.pages_per_context)
+            .field("context", &self.context)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hands_out_proxies_in_turn() {
+        let proxies = RoundRobin::new(vec![Proxy::new("http://a:1"), Proxy::new("http://b:1")]);
+        let servers: Vec<_> = (0..3)
+            .map(|_| proxies.next_proxy().unwrap().server)
+            .collect();
+        assert_eq!(servers, ["http://a:1", "http://b:1", "http://a:1"]);
+        assert!(RoundRobin::new(Vec::new()).next_proxy().is_none());
+
+        let provider = || Some(Proxy::new("http://c:1").with_bypass_list("localhost"));
+        assert_eq!(
+            provider.next_proxy().unwrap().bypass_list.as_deref(),
+            Some("localhost")
+        );
+    }
+}
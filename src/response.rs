@@ -0,0 +1,272 @@
+//! The responses a page received, together with their bodies.
+//!
+//! A [`ResponseStream`] correlates the `Network.responseReceived` and
+//! `Network.loadingFinished` events of a page and yields a [`Response`] once
+//! its body was loaded completely, so it can be retrieved right away.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut responses = page.responses().await?;
+//!     page.goto("https://example.com").await?;
+//!     while let Some(response) = responses.next().await {
+//!         if response.url().ends_with("/api/user") {
+//!             let user: serde_json::Value = response.json().await?;
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::{Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    self, EventLoadingFailed, EventLoadingFinished, EventResponseReceived, RequestId, ResourceType,
+};
+
+use crate::error::{CdpError, Result};
+use crate::handler::PageInner;
+use crate::listeners::EventStream;
+
+/// A response the page received, whose body has finished loading.
+#[derive(Clone)]
+pub struct Response {
+    event: Arc<EventResponseReceived>,
+    /// The number of bytes received over the network
+    encoded_data_length: f64,
+    page: Arc<PageInner>,
+}
+
+impl Response {
+    /// The identifier of the request this response belongs to.
+    pub fn request_id(&self) -> &RequestId {
+        &self.event.request_id
+    }
+
+    /// The url of the response.
+    pub fn url(&self) -> &str {
+        &self.event.response.url
+    }
+
+    /// The HTTP status code of the response.
+    pub fn status(&self) -> i64 {
+        self.event.response.status
+    }
+
+    /// The HTTP status text of the response.
+    pub fn status_text(&self) -> &str {
+        &self.event.response.status_text
+    }
+
+    /// Whether the status code is in the range 200-299.
+    pub fn ok(&self) -> bool {
+        (200..300).contains(&self.status())
+    }
+
+    /// The mime type of the response.
+    pub fn mime_type(&self) -> &str {
+        &self.event.response.mime_type
+    }
+
+    /// How the page used the requested resource.
+    pub fn resource_type(&self) -> &ResourceType {
+        &self.event.r#type
+    }
+
+    /// Whether the response is a document loaded into a frame.
+    pub fn is_document(&self) -> bool {
+        self.resource_type().is_document()
+    }
+
+    /// Whether the response belongs to a request issued by a script with
+    /// `XMLHttpRequest` or `fetch`.
+    pub fn is_xhr(&self) -> bool {
+        self.resource_type().is_xhr()
+    }
+
+    /// Whether the response is an image.
+    pub fn is_image(&self) -> bool {
+        self.resource_type().is_image()
+    }
+
+    /// The headers of the response.
+    pub fn headers(&self) -> HashMap<String, String> {
+        self.event
+            .response
+            .headers
+            .inner()
+            .as_object()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .filter_map(|(name, value)| Some((name.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// The value of the header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.event
+            .response
+            .headers
+            .inner()
+            .as_object()?
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_str())
+    }
+
+    /// The content coding the body was transferred with, like `gzip` or
+    /// `br`, `None` if it was transferred as is.
+    pub fn content_encoding(&self) -> Option<&str> {
+        self.header("Content-Encoding")
+            .map(str::trim)
+            .filter(|encoding| !encoding.is_empty() && !encoding.eq_ignore_ascii_case("identity"))
+    }
+
+    /// The total number of bytes received over the network for the
+    /// response, including its headers.
+    pub fn encoded_data_length(&self) -> u64 {
+        self.encoded_data_length as u64
+    }
+
+    /// The complete `Network.Response` as reported by the browser.
+    pub fn raw(&self) -> &network::Response {
+        &self.event.response
+    }
+
+    /// Retrieves the body of the response, decoded from its content
+    /// encoding.
+    pub async fn body(&self) -> Result<Vec<u8>> {
+        self.page.response_body(self.event.request_id.clone()).await
+    }
+
+    /// Retrieves the body of the response exactly as it was transferred,
+    /// for example to verify its hash.
+    ///
+    /// Chromium only keeps the decoded body, so this fails for responses
+    /// with a `content_encoding`. Sending requests with the header
+    /// `Accept-Encoding: identity`, for example with
+    /// `Page::set_origin_headers`, asks servers to transfer bodies as is.
+    pub async fn raw_body(&self) -> Result<Vec<u8>> {
+        if let Some(encoding) = self.content_encoding() {
+            return Err(CdpError::msg(format!(
+                "The raw body of {} is not available, it was transferred with the content encoding {}",
+                self.url(),
+                encoding
+            )));
+        }
+        self.body().await
+    }
+
+    /// Retrieves the body of the response as text.
+    ///
+    /// Bodies the browser transferred as binary are decoded with the charset
+    /// of the `Content-Type` header or `<meta>` tag if the `charset` feature
+    /// is enabled, and as UTF-8 otherwise.
+    pub async fn text(&self) -> Result<String> {
+        self.page
+            .response_text(self.event.request_id.clone(), self.header("Content-Type"))
+            .await
+    }
+
+    /// Retrieves the body of the response and deserializes it from JSON.
+    pub async fn json<T: DeserializeOwned>(&self) -> Result<T> {
+        Ok(serde_json::from_slice(&self.body().await?)?)
+    }
+}
+
+impl fmt::Debug for Response {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Response")
+            .field("request_id", self.request_id())
+            .field("url", &self.url())
+            .field("status", &self.status())
+            .finish()
+    }
+}
+
+/// A stream of the responses of a page, each yielded once its body finished
+/// loading.
+///
+/// Responses whose loading failed are dropped.
+#[must_use = "streams do nothing unless polled"]
+pub struct ResponseStream {
+    received: EventStream<EventResponseReceived>,
+    finished: EventStream<EventLoadingFinished>,
+    failed: EventStream<EventLoadingFailed>,
+    /// Responses that were received but are still loading
+    pending: HashMap<RequestId, Arc<EventResponseReceived>>,
+    page: Arc<PageInner>,
+}
+
+impl ResponseStream {
+    pub(crate) fn new(
+        received: EventStream<EventResponseReceived>,
+        finished: EventStream<EventLoadingFinished>,
+        failed: EventStream<EventLoadingFailed>,
+        page: Arc<PageInner>,
+    ) -> Self {
+        Self {
+            received,
+            finished,
+            failed,
+            pending: Default::default(),
+            page,
+        }
+    }
+}
+
+impl fmt::Debug for ResponseStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseStream")
+            .field("pending", &self.pending.len())
+            .finish()
+    }
+}
+
+impl Stream for ResponseStream {
+    type Item = Response;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let pin = self.get_mut();
+        while let Poll::Ready(Some(event)) = pin.received.poll_next_unpin(cx) {
+            pin.pending.insert(event.request_id.clone(), event);
+        }
+        while let Poll::Ready(Some(event)) = pin.failed.poll_next_unpin(cx) {
+            pin.pending.remove(&event.request_id);
+        }
+        loop {
+            match pin.finished.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) => {
+                    // the events are delivered on separate channels, so the
+                    // response might not have been received yet
+                    while let Poll::Ready(Some(event)) = pin.received.poll_next_unpin(cx) {
+                        pin.pending.insert(event.request_id.clone(), event);
+                    }
+                    if let Some(received) = pin.pending.remove(&event.request_id) {
+                        return Poll::Ready(Some(Response {
+                            event: received,
+                            encoded_data_length: event.encoded_data_length,
+                            page: Arc::clone(&pin.page),
+                        }));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! Intercepting and routing the requests of a page.
+//!
+//! Routes are registered with [`Page::route`](crate::page::Page::route) and
+//! are built on the `Fetch` domain. Every request whose url matches the
+//! route's pattern is paused and handed to the route's handler as an
+//! [`InterceptedRequest`], which decides whether the request is continued,
+//! fulfilled with a mocked response or aborted.
+//!
+//! Handlers are invoked by the `Handler` while it processes the page's
+//! events, so they must decide right away and must not block. Requests that
+//! match no route are continued unchanged.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::route::RouteResponse;
+//! # use chromiumoxide::cdp::browser_protocol::network::ErrorReason;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.route("*.png", |req| req.abort(ErrorReason::BlockedByClient))
+//!         .await?;
+//!     page.route("*/api/user", |req| {
+//!         req.fulfill(
+//!             RouteResponse::new(200)
+//!                 .header("Content-Type", "application/json")
+//!                 .body(r#"{"name":"chromiumoxide"}"#),
+//!         )
+//!     })
+//!     .await?;
+//!     page.goto("https://example.com").await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::sync::Arc;
+
+use chromiumoxide_cdp::cdp::browser_protocol::fetch::{
+    ContinueRequestParams, EventRequestPaused, FailRequestParams, FulfillRequestParams,
+    HeaderEntry, RequestId,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::network::{ErrorReason, Request, ResourceType};
+use chromiumoxide_cdp::cdp::browser_protocol::page::FrameId;
+
+/// A request that was paused because it matched a route.
+///
+/// The request stays paused until the route's handler returned the
+/// [`RouteAction`] created by one of `continue_request`, `fulfill` or
+/// `abort`.
+#[derive(Debug, Clone)]
+pub struct InterceptedRequest {
+    event: EventRequestPaused,
+}
+
+impl InterceptedRequest {
+    pub(crate) fn new(event: EventRequestPaused) -> Self {
+        Self { event }
+    }
+
+    /// The identifier of the paused request.
+    pub fn request_id(&self) -> &RequestId {
+        &self.event.request_id
+    }
+
+    /// The details of the request.
+    pub fn request(&self) -> &Request {
+        &self.event.request
+    }
+
+    /// The url of the request.
+    pub fn url(&self) -> &str {
+        &self.event.request.url
+    }
+
+    /// The HTTP method of the request.
+    pub fn method(&self) -> &str {
+        &self.event.request.method
+    }
+
+    /// The value of the request header `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.event
+            .request
+            .headers
+            .inner()
+            .as_object()?
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_str())
+    }
+
+    /// The body of a `POST` request.
+    pub fn post_data(&self) -> Option<&str> {
+        self.event.request.post_data.as_deref()
+    }
+
+    /// How the page is going to use the requested resource.
+    pub fn resource_type(&self) -> &ResourceType {
+        &self.event.resource_type
+    }
+
+    /// Whether the request loads a document into a frame.
+    pub fn is_document(&self) -> bool {
+        self.resource_type().is_document()
+    }
+
+    /// Whether the request was issued by a script with `XMLHttpRequest` or
+    /// `fetch`.
+    pub fn is_xhr(&self) -> bool {
+        self.resource_type().is_xhr()
+    }
+
+    /// Whether the request loads an image.
+    pub fn is_image(&self) -> bool {
+        self.resource_type().is_image()
+    }
+
+    /// The frame that initiated the request.
+    pub fn frame_id(&self) -> &FrameId {
+        &self.event.frame_id
+    }
+
+    /// The `Fetch.requestPaused` event this request was created from.
+    pub fn event(&self) -> &EventRequestPaused {
+        &self.event
+    }
+
+    /// Continues the request unchanged.
+    pub fn continue_request(self) -> RouteAction {
+        RouteAction::Continue(ContinueRequestParams::new(self.event.request_id))
+    }
+
+    /// Continues the request with the url, method, post data or headers
+    /// replaced by those set in `overrides`.
+    pub fn continue_with(self, mut overrides: ContinueRequestParams) -> RouteAction {
+        overrides.request_id = self.event.request_id;
+        RouteAction::Continue(overrides)
+    }
+
+    /// Answers the request with `response` without sending it to the server.
+    pub fn fulfill(self, response: RouteResponse) -> RouteAction {
+        RouteAction::Fulfill(response.into_params(self.event.request_id))
+    }
+
+    /// Fails the request with the network error `reason`.
+    pub fn abort(self, reason: ErrorReason) -> RouteAction {
+        RouteAction::Abort(FailRequestParams::new(self.event.request_id, reason))
+    }
+}
+
+/// How to proceed with an [`InterceptedRequest`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum RouteAction {
+    /// Send the request to the server, possibly modified.
+    Continue(ContinueRequestParams),
+    /// Answer the request with a mocked response.
+    Fulfill(FulfillRequestParams),
+    /// Fail the request.
+    Abort(FailRequestParams),
+}
+
+/// A mocked response to answer an [`InterceptedRequest`] with.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteResponse {
+    status: i64,
+    headers: Vec<HeaderEntry>,
+    body: Vec<u8>,
+}
+
+impl RouteResponse {
+    /// An empty response with the HTTP `status` code.
+    pub fn new(status: i64) -> Self {
+        Self {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Adds the response header `name`.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push(HeaderEntry::new(name, value));
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub(crate) fn into_params(self, request_id: RequestId) -> FulfillRequestParams {
+        let mut params = FulfillRequestParams::new(request_id, self.status);
+        if !self.headers.is_empty() {
+            params.response_headers = Some(self.headers);
+        }
+        params.body = Some(base64::encode(self.body).into());
+        params
+    }
+}
+
+impl Default for RouteResponse {
+    fn default() -> Self {
+        Self::new(200)
+    }
+}
+
+/// The handler of a route.
+pub type RouteHandler = Box<dyn FnMut(InterceptedRequest) -> RouteAction + Send>;
+
+/// A url pattern and the handler for the requests that match it.
+pub struct Route {
+    pub(crate) pattern: String,
+    pub(crate) handler: RouteHandler,
+}
+
+impl Route {
+    pub fn new(pattern: impl Into<String>, handler: RouteHandler) -> Self {
+        Self {
+            pattern: pattern.into(),
+            handler,
+        }
+    }
+
+    /// The url pattern of this route.
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// Whether the request's url matches the pattern of this route.
+    pub fn matches(&self, url: &str) -> bool {
+        url_matches(&self.pattern, url)
+    }
+}
+
+/// The handler of a route of a browser context, shared by all of its pages.
+pub type SharedRouteHandler = Arc<dyn Fn(InterceptedRequest) -> RouteAction + Send + Sync>;
+
+/// A route of a browser context that applies to all of its pages, see
+/// [`Browser::route`](crate::browser::Browser::route).
+#[derive(Clone)]
+pub(crate) struct ContextRoute {
+    pub(crate) pattern: String,
+    handler: SharedRouteHandler,
+}
+
+impl ContextRoute {
+    pub(crate) fn new(pattern: impl Into<String>, handler: SharedRouteHandler) -> Self {
+        Self {
+            pattern: pattern.into(),
+            handler,
+        }
+    }
+
+    /// The route of a single page of the context
+    pub(crate) fn to_route(&self) -> Route {
+        let handler = self.handler.clone();
+        Route::new(self.pattern.clone(), Box::new(move |req| handler(req)))
+    }
+}
+
+impl fmt::Debug for ContextRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextRoute")
+            .field("pattern", &self.pattern)
+            .finish()
+    }
+}
+
+impl fmt::Debug for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Route")
+            .field("pattern", &self.pattern)
+            .finish()
+    }
+}
+
+/// The urls the main frame of a page may navigate to, see
+/// [`Page::set_navigation_policy`](crate::page::Page::set_navigation_policy).
+///
+/// Patterns are matched like the patterns of routes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NavigationPolicy {
+    /// The patterns of the allowed urls, all urls are allowed if empty
+    pub allow: Vec<String>,
+    /// The patterns of the denied urls, which take precedence over `allow`
+    pub deny: Vec<String>,
+}
+
+impl NavigationPolicy {
+    /// A policy that only allows the urls that match one of the patterns
+    pub fn allow<I, S>(patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            allow: patterns.into_iter().map(Into::into).collect(),
+            deny: Vec::new(),
+        }
+    }
+
+    /// Denies the urls that match one of the patterns
+    pub fn deny<I, S>(mut self, patterns: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.deny.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Whether the policy allows every url
+    pub fn is_empty(&self) -> bool {
+        self.allow.is_empty() && self.deny.is_empty()
+    }
+
+    /// Whether the main frame may navigate to the url
+    pub fn allows(&self, url: &str) -> bool {
+        !self.deny.iter().any(|pattern| url_matches(pattern, url))
+            && (self.allow.is_empty() || self.allow.iter().any(|pattern| url_matches(pattern, url)))
+    }
+}
+
+/// Matches the url against a pattern in which `*` matches any sequence of
+/// characters and `?` matches a single character, like the patterns of the
+/// `Fetch` domain.
+pub(crate) fn url_matches(pattern: &str, url: &str) -> bool {
+    let pattern = pattern.as_bytes();
+    let url = url.as_bytes();
+    let (mut p, mut u) = (0, 0);
+    // position of the last `*` in the pattern and the url position it matched
+    let mut star = None;
+    while u < url.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                star = Some((p, u));
+                p += 1;
+            }
+            Some(&c) if c == b'?' || c == url[u] => {
+                p += 1;
+                u += 1;
+            }
+            _ => match star {
+                Some((star_p, star_u)) => {
+                    p = star_p + 1;
+                    u = star_u + 1;
+                    star = Some((star_p, star_u + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn applies_navigation_policies() {
+        let policy = NavigationPolicy::allow(["https://example.com/*"]).deny(["*/logout*"]);
+        assert!(policy.allows("https://example.com/docs"));
+        assert!(!policy.allows("https://example.com/logout"));
+        assert!(!policy.allows("https://ads.example.net/"));
+
+        let policy = NavigationPolicy::default().deny(["https://ads.*"]);
+        assert!(policy.allows("https://example.com/"));
+        assert!(!policy.allows("https://ads.example.net/"));
+        assert!(NavigationPolicy::default().allows("https://example.com/"));
+    }
+
+    #[test]
+    fn matches_url_patterns() {
+        assert!(url_matches("*", "https://example.com/"));
+        assert!(url_matches("*.png", "https://example.com/logo.png"));
+        assert!(url_matches(
+            "https://*/api/*",
+            "https://example.com/api/user"
+        ));
+        assert!(url_matches(
+            "https://example.com/?",
+            "https://example.com/a"
+        ));
+        assert!(!url_matches("*.png", "https://example.com/logo.jpg"));
+        assert!(!url_matches(
+            "https://example.com/",
+            "https://example.com/a"
+        ));
+        assert!(!url_matches(
+            "https://example.com/?",
+            "https://example.com/"
+        ));
+    }
+
+    #[test]
+    fn fulfill_encodes_body() {
+        let params = RouteResponse::new(404)
+            .header("Content-Type", "text/plain")
+            .body("missing")
+            .into_params(RequestId::new("1"));
+        assert_eq!(params.response_code, 404);
+        assert_eq!(
+            params.response_headers,
+            Some(vec![HeaderEntry::new("Content-Type", "text/plain")])
+        );
+        assert_eq!(
+            params.body.as_ref().map(AsRef::<str>::as_ref),
+            Some(base64::encode("missing").as_str())
+        );
+    }
+}
@@ -0,0 +1,321 @@
+//! Running page tasks on a schedule.
+//!
+//! A [`Scheduler`] runs tasks, async closures that receive a fresh
+//! [`Page`], in intervals with an optional random jitter. Every run gets its
+//! own page of the browser, which is closed once the run completed or timed
+//! out, and the number of runs that have a page open at the same time is
+//! limited, so scheduled tasks share the browser without exhausting it.
+//!
+//! A task that failed is retried with an exponential backoff: every
+//! consecutive failure doubles the delay until the next run, up to
+//! [`Schedule::max_backoff`].
+//!
+//! The scheduler is a stream of the [`TaskRun`]s of all tasks and only runs
+//! tasks while it is polled.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use std::time::Duration;
+//! # use chromiumoxide::browser::Browser;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::scheduler::{Schedule, Scheduler};
+//! # use futures::StreamExt;
+//! # async fn demo(browser: Browser) -> Result<()> {
+//!     let schedule = Schedule::every(Duration::from_secs(5 * 60))
+//!         .with_jitter(Duration::from_secs(30))
+//!         .with_timeout(Duration::from_secs(60));
+//!     let mut runs = Scheduler::new(&browser)
+//!         .task("frontpage", schedule, |page| async move {
+//!             page.goto("https://example.com").await?;
+//!             println!("{:?}", page.get_title().await?);
+//!             Ok(())
+//!         })
+//!         .run();
+//!     while let Some(run) = runs.next().await {
+//!         if let Err(err) = run.result {
+//!             eprintln!("{} failed: {err}", run.name);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use futures::channel::mpsc;
+use futures::future::{self, BoxFuture, Either};
+use futures::lock::Mutex;
+use futures::stream::{self, BoxStream};
+use futures::{Future, FutureExt, SinkExt, StreamExt};
+use futures_timer::Delay;
+
+use crate::browser::Browser;
+use crate::error::{CdpError, Result};
+use crate::page::Page;
+
+/// When a task of a [`Scheduler`] runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    /// The delay between the end of a run and the start of the next one
+    pub interval: Duration,
+    /// The upper bound of a random delay added to every interval
+    pub jitter: Duration,
+    /// How long a run may take before it fails with `CdpError::Timeout`
+    pub timeout: Option<Duration>,
+    /// The longest delay after consecutive failures
+    pub max_backoff: Duration,
+    /// Whether the first run starts right away instead of after an interval
+    pub run_immediately: bool,
+}
+
+impl Schedule {
+    /// Runs a task right away and then every `interval`.
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: Duration::ZERO,
+            timeout: None,
+            max_backoff: Duration::from_secs(60 * 60).max(interval),
+            run_immediately: true,
+        }
+    }
+
+    pub fn with_jitter(mut self, jitter: Duration) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Waits an interval before the first run.
+    pub fn delayed(mut self) -> Self {
+        self.run_immediately = false;
+        self
+    }
+
+    /// The delay before the next run after `failures` consecutive failures,
+    /// without jitter.
+    pub(crate) fn delay(&self, failures: u32) -> Duration {
+        if failures == 0 {
+            return self.interval;
+        }
+        self.interval
+            .checked_mul(1 << failures.min(31))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+            .max(self.interval)
+    }
+}
+
+/// The outcome of a single run of a scheduled task.
+#[derive(Debug)]
+pub struct TaskRun {
+    /// The name of the task
+    pub name: String,
+    /// When the run started
+    pub started: Instant,
+    /// How long the run took
+    pub duration: Duration,
+    /// The result of the task
+    pub result: Result<()>,
+    /// How many runs in a row have failed, including this one
+    pub failures: u32,
+}
+
+type TaskFn = Box<dyn FnMut(Page) -> BoxFuture<'static, Result<()>> + Send>;
+
+struct Task {
+    name: String,
+    schedule: Schedule,
+    run: TaskFn,
+}
+
+/// Runs page tasks on their schedules, see the [`scheduler`](self) module.
+pub struct Scheduler<'a> {
+    browser: &'a Browser,
+    tasks: Vec<Task>,
+    max_concurrency: usize,
+}
+
+impl<'a> Scheduler<'a> {
+    pub fn new(browser: &'a Browser) -> Self {
+        Self {
+            browser,
+            tasks: Vec::new(),
+            max_concurrency: 4,
+        }
+    }
+
+    /// Limits how many runs have a page open at the same time (defaults to
+    /// 4).
+    pub fn max_concurrency(mut self, max_concurrency: usize) -> Self {
+        self.max_concurrency = max_concurrency.max(1);
+        self
+    }
+
+    /// Adds a task that runs `f` with a new page on the `schedule`.
+    pub fn task<F, Fut>(mut self, name: impl Into<String>, schedule: Schedule, mut f: F) -> Self
+    where
+        F: FnMut(Page) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        self.tasks.push(Task {
+            name: name.into(),
+            schedule,
+            run: Box::new(move |page| f(page).boxed()),
+        });
+        self
+    }
+
+    /// Returns the stream that runs the tasks and yields their runs.
+    pub fn run(self) -> BoxStream<'a, TaskRun> {
+        let (mut permits, rx) = mpsc::channel(self.max_concurrency);
+        for _ in 0..self.max_concurrency {
+            let _ = permits.try_send(());
+        }
+        let slots = Arc::new(Slots {
+            permits,
+            rx: Mutex::new(rx),
+        });
+        let browser = self.browser;
+        stream::select_all(self.tasks.into_iter().map(move |task| {
+            let state = TaskState {
+                task,
+                slots: Arc::clone(&slots),
+                failures: 0,
+                first: true,
+            };
+            stream::unfold(state, move |mut state| async move {
+                let run = state.next(browser).await;
+                Some((run, state))
+            })
+            .boxed()
+        }))
+        .boxed()
+    }
+}
+
+impl fmt::Debug for Scheduler<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field(
+                "tasks",
+                &self.tasks.iter().map(|t| &t.name).collect::<Vec<_>>(),
+            )
+            .field("max_concurrency", &self.max_concurrency)
+            .finish()
+    }
+}
+
+/// A semaphore limiting the number of open pages.
+struct Slots {
+    permits: mpsc::Sender<()>,
+    rx: Mutex<mpsc::Receiver<()>>,
+}
+
+impl Slots {
+    async fn acquire(&self) {
+        self.rx.lock().await.next().await;
+    }
+
+    async fn release(&self) {
+        let _ = self.permits.clone().send(()).await;
+    }
+}
+
+struct TaskState {
+    task: Task,
+    slots: Arc<Slots>,
+    failures: u32,
+    first: bool,
+}
+
+impl TaskState {
+    async fn next(&mut self, browser: &Browser) -> TaskRun {
+        let schedule = &self.task.schedule;
+        if !self.first || !schedule.run_immediately {
+            Delay::new(schedule.delay(self.failures) + jitter(schedule.jitter)).await;
+        }
+        self.first = false;
+
+        self.slots.acquire().await;
+        let started = Instant::now();
+        let result = self.run_once(browser).await;
+        self.slots.release().await;
+
+        if result.is_ok() {
+            self.failures = 0;
+        } else {
+            self.failures += 1;
+        }
+        TaskRun {
+            name: self.task.name.clone(),
+            started,
+            duration: started.elapsed(),
+            result,
+            failures: self.failures,
+        }
+    }
+
+    async fn run_once(&mut self, browser: &Browser) -> Result<()> {
+        let page = browser.new_page("about:blank").await?;
+        let run = (self.task.run)(page.clone());
+        let result = match self.task.schedule.timeout {
+            Some(timeout) => match future::select(run, Delay::new(timeout)).await {
+                Either::Left((result, _)) => result,
+                Either::Right(_) => Err(CdpError::Timeout),
+            },
+            None => run.await,
+        };
+        let closed = page.close().await;
+        result.and(closed)
+    }
+}
+
+/// A random duration of at most `max`.
+fn jitter(max: Duration) -> Duration {
+    if max.is_zero() {
+        return max;
+    }
+    let mut hasher = RandomState::new().build_hasher();
+    if let Ok(now) = SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        hasher.write_u128(now.as_nanos());
+    }
+    max.mul_f64(hasher.finish() as f64 / u64::MAX as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backs_off_exponentially() {
+        let schedule =
+            Schedule::every(Duration::from_secs(10)).with_max_backoff(Duration::from_secs(60));
+        assert_eq!(schedule.delay(0), Duration::from_secs(10));
+        assert_eq!(schedule.delay(1), Duration::from_secs(20));
+        assert_eq!(schedule.delay(2), Duration::from_secs(40));
+        assert_eq!(schedule.delay(3), Duration::from_secs(60));
+        assert_eq!(schedule.delay(100), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn bounds_jitter() {
+        assert_eq!(jitter(Duration::ZERO), Duration::ZERO);
+        for _ in 0..100 {
+            assert!(jitter(Duration::from_secs(1)) <= Duration::from_secs(1));
+        }
+    }
+}
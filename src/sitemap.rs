@@ -0,0 +1,114 @@
+//! Reading the [sitemap](https://www.sitemaps.org/protocol.html) of a site.
+//!
+//! The sitemap is fetched from within the page with its cookies and parsed by
+//! the browser's `DOMParser`. Sitemap indexes are followed and gzip
+//! compressed sitemaps are decompressed, so the result is the flat list of
+//! all urls the site announces, ready to seed a crawl.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.goto("https://example.com").await?;
+//!     for entry in page.sitemap().await? {
+//!         println!("{} (last modified {:?})", entry.loc, entry.lastmod);
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use serde::Deserialize;
+
+/// The maximum number of sitemaps fetched while following sitemap indexes.
+pub const MAX_SITEMAPS: usize = 50;
+
+/// A url listed in a sitemap.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SitemapEntry {
+    /// The url of the page
+    pub loc: String,
+    /// When the page was last modified, in W3C Datetime format
+    pub lastmod: Option<String>,
+    /// How frequently the page is likely to change, like `daily`
+    pub changefreq: Option<String>,
+    /// The priority of the page relative to the other pages of the site,
+    /// between `0.0` and `1.0`
+    pub priority: Option<f64>,
+}
+
+/// Fetches the sitemap at `url` and all sitemaps listed by it, up to
+/// `limit` sitemaps, and returns their url entries.
+pub(crate) const FETCH_SITEMAP_JS: &str = r#"async function(url, limit) {
+    const text = async (url) => {
+        const response = await fetch(url, { credentials: 'include' });
+        if (!response.ok) {
+            throw new Error(`Failed to fetch sitemap ${url}: ${response.status}`);
+        }
+        if (url.endsWith('.gz') && typeof DecompressionStream === 'function') {
+            const stream = response.body.pipeThrough(new DecompressionStream('gzip'));
+            return await new Response(stream).text();
+        }
+        return await response.text();
+    };
+    const child = (el, name) => {
+        const node = Array.from(el.children).find((c) => c.localName === name);
+        return node ? node.textContent.trim() : null;
+    };
+    const entries = [];
+    const queue = [new URL(url, location.href).href];
+    const seen = new Set();
+    while (queue.length && seen.size < limit) {
+        const next = queue.shift();
+        if (seen.has(next)) {
+            continue;
+        }
+        seen.add(next);
+        const doc = new DOMParser().parseFromString(await text(next), 'application/xml');
+        if (doc.querySelector('parsererror')) {
+            throw new Error(`Invalid sitemap ${next}`);
+        }
+        for (const el of Array.from(doc.documentElement.children)) {
+            const loc = child(el, 'loc');
+            if (!loc) {
+                continue;
+            }
+            if (el.localName === 'sitemap') {
+                queue.push(new URL(loc, next).href);
+            } else if (el.localName === 'url') {
+                const priority = parseFloat(child(el, 'priority'));
+                entries.push({
+                    loc,
+                    lastmod: child(el, 'lastmod'),
+                    changefreq: child(el, 'changefreq'),
+                    priority: Number.isNaN(priority) ? null : priority,
+                });
+            }
+        }
+    }
+    return entries;
+}"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserializes_entries() {
+        let entries: Vec<SitemapEntry> = serde_json::from_value(serde_json::json!([
+            {
+                "loc": "https://example.com/",
+                "lastmod": "2024-01-01",
+                "changefreq": "daily",
+                "priority": 0.8
+            },
+            {"loc": "https://example.com/about", "lastmod": null, "changefreq": null, "priority": null}
+        ]))
+        .unwrap();
+        assert_eq!(entries[0].priority, Some(0.8));
+        assert_eq!(entries[0].lastmod.as_deref(), Some("2024-01-01"));
+        assert_eq!(entries[1].loc, "https://example.com/about");
+        assert_eq!(entries[1].priority, None);
+    }
+}
@@ -0,0 +1,141 @@
+//! Saving and restoring the storage of a browser context.
+//!
+//! A [`StorageState`] holds the cookies of a browser context together with
+//! the `localStorage` of the origins of its open pages. Captured once after
+//! logging in, it can be saved to a file and restored into fresh browser
+//! contexts, so later runs skip the login flow.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::browser::Browser;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::storage_state::StorageState;
+//! # use chromiumoxide::cdp::browser_protocol::target::CreateTargetParams;
+//! # async fn demo(browser: Browser) -> Result<()> {
+//!     // after logging in
+//!     browser.storage_state().await?.save("auth.json").await?;
+//!
+//!     // in a later run
+//!     let state = StorageState::load("auth.json").await?;
+//!     let context = browser.new_context_with_storage_state(&state).await?;
+//!     let mut params = CreateTargetParams::new("https://example.com/account");
+//!     params.browser_context_id = Some(context);
+//!     let page = browser.new_page(params).await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{Cookie, CookieParam, TimeSinceEpoch};
+
+use crate::error::Result;
+use crate::utils;
+
+/// The cookies and `localStorage` of a browser context.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageState {
+    /// All cookies of the context
+    pub cookies: Vec<Cookie>,
+    /// The `localStorage` of every origin
+    pub origins: Vec<OriginState>,
+}
+
+impl StorageState {
+    /// Reads a storage state from a JSON file.
+    pub async fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = utils::read(path.as_ref()).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Writes the storage state to a JSON file.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        utils::write(path.as_ref(), serde_json::to_vec_pretty(self)?).await?;
+        Ok(())
+    }
+
+    /// The cookies as parameters to set them again.
+    pub(crate) fn cookie_params(&self) -> Vec<CookieParam> {
+        self.cookies.iter().map(cookie_param).collect()
+    }
+}
+
+/// The `localStorage` of a single origin.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OriginState {
+    /// The origin, like `https://example.com`
+    pub origin: String,
+    /// The key-value pairs in the order they were stored
+    pub local_storage: Vec<(String, String)>,
+}
+
+/// Returns the origin and the `localStorage` of the document as
+/// `OriginState`.
+pub(crate) const CAPTURE_ORIGIN_JS: &str = r#"() => ({
+    origin: location.origin,
+    localStorage: Object.entries(localStorage),
+})"#;
+
+/// Stores the key-value pairs in the `localStorage` of the document.
+pub(crate) const RESTORE_ORIGIN_JS: &str = r#"(items) => {
+    for (const [key, value] of items) {
+        localStorage.setItem(key, value);
+    }
+}"#;
+
+fn cookie_param(cookie: &Cookie) -> CookieParam {
+    let mut param = CookieParam::new(cookie.name.clone(), cookie.value.clone());
+    param.domain = Some(cookie.domain.clone());
+    param.path = Some(cookie.path.clone());
+    param.secure = Some(cookie.secure);
+    param.http_only = Some(cookie.http_only);
+    param.same_site = cookie.same_site.clone();
+    param.priority = Some(cookie.priority.clone());
+    if !cookie.session {
+        param.expires = Some(TimeSinceEpoch::new(cookie.expires));
+    }
+    param
+}
+
+#[cfg(test)]
+mod tests {
+    use chromiumoxide_cdp::cdp::browser_protocol::network::{CookiePriority, CookieSameSite};
+
+    use super::*;
+
+    #[test]
+    fn converts_cookies_to_params() {
+        let mut cookie = Cookie::builder()
+            .name("session")
+            .value("secret")
+            .domain(".example.com")
+            .path("/")
+            .expires(1_700_000_000.)
+            .size(13)
+            .http_only(true)
+            .secure(true)
+            .session(false)
+            .priority(CookiePriority::Medium)
+            .build()
+            .unwrap();
+        cookie.same_site = Some(CookieSameSite::Lax);
+        let state = StorageState {
+            cookies: vec![cookie.clone()],
+            origins: Vec::new(),
+        };
+
+        let param = &state.cookie_params()[0];
+        assert_eq!(param.domain.as_deref(), Some(".example.com"));
+        assert_eq!(param.http_only, Some(true));
+        assert_eq!(param.same_site, Some(CookieSameSite::Lax));
+        assert_eq!(param.expires, Some(TimeSinceEpoch::new(1_700_000_000.)));
+
+        cookie.session = true;
+        assert_eq!(cookie_param(&cookie).expires, None);
+    }
+}
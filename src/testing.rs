@@ -0,0 +1,362 @@
+//! Support for browser tests.
+//!
+//! A [`SharedBrowser`] launches a single headless browser for all its users
+//! and closes it again once the last user is done, so running tests in
+//! parallel does not spawn a browser process per test. Every user gets its
+//! own incognito `BrowserContext`, so cookies, storage and cache are never
+//! shared between tests.
+//!
+//! The `#[chromiumoxide::test]` attribute uses a `SharedBrowser` under the
+//! hood and hands each test a fresh [`Page`]. The context is disposed after
+//! the test finished, even if it panicked.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use chromiumoxide::Page;
+//!
+//! #[chromiumoxide::test]
+//! async fn has_title(page: Page) {
+//!     page.goto("https://example.com").await.unwrap();
+//!     assert_eq!(
+//!         page.get_title().await.unwrap(),
+//!         Some("Example Domain".to_string())
+//!     );
+//! }
+//! ```
+//!
+//! # Example Use a custom fixture
+//!
+//! ```no_run
+//! use chromiumoxide::testing::SharedBrowser;
+//! use chromiumoxide::BrowserConfig;
+//!
+//! static BROWSER: SharedBrowser =
+//!     SharedBrowser::with_config(|| BrowserConfig::builder().window_size(800, 600).build());
+//!
+//! #[async_std::test]
+//! async fn loads_page() {
+//!     let lease = BROWSER.acquire().await.unwrap();
+//!     let page = lease.new_page("https://example.com").await.unwrap();
+//!     // ...
+//!     lease.release().await;
+//! }
+//! ```
+
+use std::panic::{resume_unwind, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use futures::channel::oneshot::channel as oneshot_channel;
+use futures::{Future, FutureExt, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::browser::BrowserContextId;
+use chromiumoxide_cdp::cdp::browser_protocol::target::{
+    CreateBrowserContextParams, CreateTargetParams,
+};
+
+use crate::browser::{Browser, BrowserConfig};
+use crate::error::{CdpError, Result};
+use crate::page::Page;
+
+/// The fixture used by `#[chromiumoxide::test]`.
+static TEST_BROWSER: SharedBrowser = SharedBrowser::new();
+
+/// A browser that is shared by all its users and launched lazily.
+///
+/// The browser is launched when the first user acquires it and closed once
+/// the last user released it.
+#[derive(Debug)]
+pub struct SharedBrowser {
+    /// Creates the config of the browser to launch
+    config: fn() -> std::result::Result<BrowserConfig, String>,
+    /// Ensures that only one browser is launched at a time
+    launch: futures::lock::Mutex<()>,
+    /// The running browser and its users
+    state: Mutex<Option<Users<Arc<Browser>>>>,
+}
+
+impl SharedBrowser {
+    /// A fixture that launches a headless browser with the default
+    /// configuration.
+    pub const fn new() -> Self {
+        Self::with_config(|| BrowserConfig::builder().build())
+    }
+
+    /// A fixture that launches its browser with the config returned by
+    /// `config`.
+    pub const fn with_config(config: fn() -> std::result::Result<BrowserConfig, String>) -> Self {
+        Self {
+            config,
+            launch: futures::lock::Mutex::new(()),
+            state: Mutex::new(None),
+        }
+    }
+
+    /// The number of users that currently hold a [`BrowserLease`].
+    pub fn users(&self) -> usize {
+        self.state()
+            .as_ref()
+            .map(|users| users.count)
+            .unwrap_or_default()
+    }
+
+    /// Returns a lease of the shared browser with a new incognito context,
+    /// launching the browser if it is not running yet.
+    pub async fn acquire(&self) -> Result<BrowserLease<'_>> {
+        let browser = self.checkout().await?;
+        match browser
+            .create_browser_context(CreateBrowserContextParams::default())
+            .await
+        {
+            Ok(context) => Ok(BrowserLease {
+                owner: self,
+                browser: Some(browser),
+                context,
+            }),
+            Err(err) => {
+                self.give_back(browser).await;
+                Err(err)
+            }
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, Option<Users<Arc<Browser>>>> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Registers a new user of the browser.
+    async fn checkout(&self) -> Result<Arc<Browser>> {
+        let _launch = self.launch.lock().await;
+        let joined = Users::join(&mut self.state());
+        if let Some((browser, orphaned)) = joined {
+            dispose_contexts(&browser, orphaned).await;
+            return Ok(browser);
+        }
+        let config = (self.config)().map_err(CdpError::ChromeMessage)?;
+        let browser = Arc::new(launch(config).await?);
+        *self.state() = Some(Users::new(Arc::clone(&browser)));
+        Ok(browser)
+    }
+
+    /// Unregisters a user and closes the browser if it was the last one,
+    /// otherwise disposes the contexts of dropped leases.
+    async fn give_back(&self, browser: Arc<Browser>) {
+        if Users::leave(&mut self.state(), None).is_some() {
+            if let Ok(mut browser) = Arc::try_unwrap(browser) {
+                if let Err(err) = browser.close().await {
+                    tracing::warn!("Failed to close shared browser: {}", err);
+                }
+            }
+        } else {
+            let orphaned = self
+                .state()
+                .as_mut()
+                .map(|users| std::mem::take(&mut users.orphaned))
+                .unwrap_or_default();
+            dispose_contexts(&browser, orphaned).await;
+        }
+    }
+}
+
+/// The running browser of a [`SharedBrowser`] and its users.
+#[derive(Debug)]
+struct Users<B> {
+    browser: B,
+    /// The number of leases
+    count: usize,
+    /// Contexts of leases that were dropped without being released, disposed
+    /// by the next user that acquires or releases a lease
+    orphaned: Vec<BrowserContextId>,
+}
+
+impl<B: Clone> Users<B> {
+    /// The users of a just launched browser, with its first user.
+    fn new(browser: B) -> Self {
+        Self {
+            browser,
+            count: 1,
+            orphaned: Vec::new(),
+        }
+    }
+
+    /// Registers a new user of the running browser and returns the browser
+    /// along with the contexts to dispose, or `None` if no browser is running.
+    fn join(state: &mut Option<Self>) -> Option<(B, Vec<BrowserContextId>)> {
+        let users = state.as_mut()?;
+        users.count += 1;
+        Some((users.browser.clone(), std::mem::take(&mut users.orphaned)))
+    }
+
+    /// Unregisters a user whose context is queued for disposal if it is
+    /// `orphaned`, and returns the browser if it was the last user.
+    fn leave(state: &mut Option<Self>, orphaned: Option<BrowserContextId>) -> Option<B> {
+        match state.as_mut() {
+            Some(users) if users.count > 1 => {
+                users.count -= 1;
+                users.orphaned.extend(orphaned);
+                None
+            }
+            _ => state.take().map(|users| users.browser),
+        }
+    }
+}
+
+/// Disposes the contexts of leases that were dropped without being released.
+async fn dispose_contexts(browser: &Browser, contexts: Vec<BrowserContextId>) {
+    for context in contexts {
+        if let Err(err) = browser.dispose_browser_context(context).await {
+            tracing::warn!("Failed to dispose browser context: {}", err);
+        }
+    }
+}
+
+impl Default for SharedBrowser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A user's share of a [`SharedBrowser`] with its own incognito context.
+///
+/// Call [`BrowserLease::release`] once done, which disposes the context and
+/// closes the browser if this was the last lease. Dropping a lease without
+/// releasing it, like when a test panicked, gives the browser back and leaves
+/// its context to be disposed by the next user of the shared browser.
+#[derive(Debug)]
+pub struct BrowserLease<'a> {
+    owner: &'a SharedBrowser,
+    browser: Option<Arc<Browser>>,
+    context: BrowserContextId,
+}
+
+impl<'a> BrowserLease<'a> {
+    /// The shared browser.
+    pub fn browser(&self) -> &Browser {
+        self.browser
+            .as_ref()
+            .expect("browser is set until released")
+    }
+
+    /// The identifier of this lease's incognito context.
+    pub fn context_id(&self) -> &BrowserContextId {
+        &self.context
+    }
+
+    /// Creates a new page within this lease's incognito context.
+    pub async fn new_page(&self, params: impl Into<CreateTargetParams>) -> Result<Page> {
+        let mut params = params.into();
+        params.browser_context_id = Some(self.context.clone());
+        self.browser().new_page(params).await
+    }
+
+    /// Disposes the incognito context and gives the browser back.
+    pub async fn release(mut self) {
+        if let Some(browser) = self.browser.take() {
+            if let Err(err) = browser.dispose_browser_context(self.context.clone()).await {
+                tracing::warn!("Failed to dispose browser context: {}", err);
+            }
+            self.owner.give_back(browser).await;
+        }
+    }
+}
+
+impl<'a> Drop for BrowserLease<'a> {
+    fn drop(&mut self) {
+        if self.browser.take().is_some() {
+            Users::leave(&mut self.owner.state(), Some(self.context.clone()));
+        }
+    }
+}
+
+/// Runs the browser test `test` to completion on a new runtime.
+///
+/// This is what `#[chromiumoxide::test]` expands to and should not be called
+/// directly.
+pub fn run<F, Fut>(test: F) -> Fut::Output
+where
+    F: FnOnce(Page) -> Fut,
+    Fut: Future,
+{
+    block_on(async move {
+        let lease = TEST_BROWSER
+            .acquire()
+            .await
+            .expect("failed to launch the test browser");
+        let result = match lease.new_page(CreateTargetParams::default()).await {
+            Ok(page) => AssertUnwindSafe(test(page)).catch_unwind().await,
+            Err(err) => Err(Box::new(format!("failed to create page: {err}")) as _),
+        };
+        lease.release().await;
+        match result {
+            Ok(output) => output,
+            Err(panic) => resume_unwind(panic),
+        }
+    })
+}
+
+/// Launches a new browser whose handler is driven on a dedicated thread, so
+/// that it outlives the runtime of the user that launched it.
+async fn launch(config: BrowserConfig) -> Result<Browser> {
+    let (tx, rx) = oneshot_channel();
+    std::thread::Builder::new()
+        .name("chromiumoxide-shared-browser".to_string())
+        .spawn(move || {
+            block_on(async move {
+                match Browser::launch(config).await {
+                    Ok((browser, mut handler)) => {
+                        let _ = tx.send(Ok(browser));
+                        while handler.next().await.is_some() {}
+                    }
+                    Err(err) => {
+                        let _ = tx.send(Err(err));
+                    }
+                }
+            })
+        })?;
+    rx.await?
+}
+
+fn block_on<F: Future>(fut: F) -> F::Output {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            async_std::task::block_on(fut)
+        } else if #[cfg(feature = "tokio-runtime")] {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("failed to build tokio runtime")
+                .block_on(fut)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn last_user_stops_browser() {
+        let mut state = None;
+        assert!(Users::<&str>::join(&mut state).is_none());
+        state = Some(Users::new("browser"));
+        assert!(Users::join(&mut state).is_some());
+        assert_eq!(state.as_ref().unwrap().count, 2);
+
+        assert_eq!(Users::leave(&mut state, None), None);
+        assert_eq!(state.as_ref().unwrap().count, 1);
+        assert_eq!(Users::leave(&mut state, None), Some("browser"));
+        assert!(state.is_none());
+    }
+
+    #[test]
+    fn next_user_disposes_dropped_contexts() {
+        let mut state = Some(Users::new("browser"));
+        Users::join(&mut state);
+        let context = BrowserContextId::from("context".to_string());
+        assert_eq!(Users::leave(&mut state, Some(context.clone())), None);
+
+        let (_, orphaned) = Users::join(&mut state).unwrap();
+        assert_eq!(orphaned, vec![context]);
+        assert!(state.unwrap().orphaned.is_empty());
+    }
+}
@@ -0,0 +1,432 @@
+//! Opt-in recording of high-level page actions.
+//!
+//! A [`Tracer`] wraps a [`Page`] and records every action performed through it
+//! (navigations, clicks, typing and waits) together with its timing,
+//! screenshots taken before and after the action and excerpts of the console
+//! and network activity that happened while the action was running. The
+//! recorded actions can then be rendered into a single self-contained HTML
+//! report, which helps with debugging flaky pipelines.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::tracer::Tracer;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut tracer = Tracer::new(page).await?;
+//!     tracer
+//!         .goto("https://en.wikipedia.org")
+//!         .await?
+//!         .click("input#searchInput")
+//!         .await?
+//!         .type_str("input#searchInput", "Rust programming language")
+//!         .await?;
+//!     tracer.save_report("trace.html").await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Write;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use futures::{Future, FutureExt, StreamExt};
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    EventLoadingFailed, EventResponseReceived,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::page::{CaptureScreenshotFormat, NavigateParams};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::EventConsoleApiCalled;
+
+use crate::error::Result;
+use crate::listeners::EventStream;
+use crate::page::{Page, ScreenshotParams};
+use crate::utils;
+
+/// How a [`Tracer`] records the actions.
+#[derive(Debug, Clone)]
+pub struct TracerConfig {
+    /// Whether to take a screenshot before and after every action, default is
+    /// true
+    pub screenshots: bool,
+    /// The maximum number of console messages kept per action
+    pub max_console_entries: usize,
+    /// The maximum number of network entries kept per action
+    pub max_network_entries: usize,
+}
+
+impl Default for TracerConfig {
+    fn default() -> Self {
+        Self {
+            screenshots: true,
+            max_console_entries: 50,
+            max_network_entries: 50,
+        }
+    }
+}
+
+/// The kind of a recorded action
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Goto,
+    Click,
+    Type,
+    Wait,
+}
+
+impl AsRef<str> for ActionKind {
+    fn as_ref(&self) -> &str {
+        match self {
+            ActionKind::Goto => "goto",
+            ActionKind::Click => "click",
+            ActionKind::Type => "type",
+            ActionKind::Wait => "wait",
+        }
+    }
+}
+
+/// A single action recorded by the [`Tracer`]
+#[derive(Debug, Clone)]
+pub struct TraceAction {
+    /// What kind of action was performed
+    pub kind: ActionKind,
+    /// The target of the action, like the url or the selector
+    pub target: String,
+    /// When the action started, relative to the start of the trace
+    pub start: Duration,
+    /// How long the action took
+    pub duration: Duration,
+    /// Png screenshot taken before the action
+    pub screenshot_before: Option<Vec<u8>>,
+    /// Png screenshot taken after the action
+    pub screenshot_after: Option<Vec<u8>>,
+    /// Console messages emitted while the action was running
+    pub console: Vec<String>,
+    /// Network responses and failures observed while the action was running
+    pub network: Vec<String>,
+    /// The error message if the action failed
+    pub error: Option<String>,
+}
+
+/// Records the actions performed on a [`Page`].
+#[derive(Debug)]
+pub struct Tracer {
+    page: Page,
+    config: TracerConfig,
+    started: Instant,
+    console: EventStream<EventConsoleApiCalled>,
+    responses: EventStream<EventResponseReceived>,
+    failures: EventStream<EventLoadingFailed>,
+    actions: Vec<TraceAction>,
+}
+
+impl Tracer {
+    /// Start tracing the page with the default [`TracerConfig`]
+    pub async fn new(page: Page) -> Result<Self> {
+        Self::with_config(page, TracerConfig::default()).await
+    }
+
+    /// Start tracing the page
+    pub async fn with_config(page: Page, config: TracerConfig) -> Result<Self> {
+        let console = page.event_listener::<EventConsoleApiCalled>().await?;
+        let responses = page.event_listener::<EventResponseReceived>().await?;
+        let failures = page.event_listener::<EventLoadingFailed>().await?;
+        Ok(Self {
+            page,
+            config,
+            started: Instant::now(),
+            console,
+            responses,
+            failures,
+            actions: Vec::new(),
+        })
+    }
+
+    /// The page that is traced
+    pub fn page(&self) -> &Page {
+        &self.page
+    }
+
+    /// All the actions recorded so far
+    pub fn actions(&self) -> &[TraceAction] {
+        &self.actions
+    }
+
+    /// Navigate to the url and record it as `goto` action
+    pub async fn goto(&mut self, params: impl Into<NavigateParams>) -> Result<&mut Self> {
+        let params = params.into();
+        let target = params.url.clone();
+        self.record(ActionKind::Goto, target, |page| async move {
+            page.goto(params).await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Click the first element matching the selector and record it as `click`
+    /// action
+    pub async fn click(&mut self, selector: impl Into<String>) -> Result<&mut Self> {
+        let selector = selector.into();
+        self.record(ActionKind::Click, selector.clone(), |page| async move {
+            page.find_element(selector).await?.click().await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Type the input into the first element matching the selector and record
+    /// it as `type` action
+    pub async fn type_str(
+        &mut self,
+        selector: impl Into<String>,
+        input: impl Into<String>,
+    ) -> Result<&mut Self> {
+        let selector = selector.into();
+        let input = input.into();
+        let target = format!("{selector} <- {input:?}");
+        self.record(ActionKind::Type, target, |page| async move {
+            page.find_element(selector)
+                .await?
+                .click()
+                .await?
+                .type_str(input)
+                .await?;
+            Ok(())
+        })
+        .await
+    }
+
+    /// Wait for the current navigation to finish and record it as `wait`
+    /// action
+    pub async fn wait_for_navigation(&mut self) -> Result<&mut Self> {
+        self.record(
+            ActionKind::Wait,
+            "navigation".to_string(),
+            |page| async move {
+                page.wait_for_navigation().await?;
+                Ok(())
+            },
+        )
+        .await
+    }
+
+    /// Runs the action and records it, the error of a failed action is
+    /// recorded as well before it is returned.
+    async fn record<F, Fut>(&mut self, kind: ActionKind, target: String, f: F) -> Result<&mut Self>
+    where
+        F: FnOnce(Page) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        // discard everything that happened in between actions
+        self.drain_console();
+        self.drain_network();
+
+        let screenshot_before = self.screenshot().await;
+        let start = self.started.elapsed();
+        let now = Instant::now();
+        let res = f(self.page.clone()).await;
+        let duration = now.elapsed();
+        let screenshot_after = self.screenshot().await;
+
+        let action = TraceAction {
+            kind,
+            target,
+            start,
+            duration,
+            screenshot_before,
+            screenshot_after,
+            console: self.drain_console(),
+            network: self.drain_network(),
+            error: res.as_ref().err().map(|err| err.to_string()),
+        };
+        self.actions.push(action);
+        res?;
+        Ok(self)
+    }
+
+    async fn screenshot(&self) -> Option<Vec<u8>> {
+        if !self.config.screenshots {
+            return None;
+        }
+        self.page
+            .screenshot(
+                ScreenshotParams::builder()
+                    .format(CaptureScreenshotFormat::Png)
+                    .build(),
+            )
+            .await
+            .ok()
+    }
+
+    /// Collects all console messages that are ready
+    fn drain_console(&mut self) -> Vec<String> {
+        let mut entries = Vec::new();
+        while let Some(Some(event)) = self.console.next().now_or_never() {
+            if entries.len() < self.config.max_console_entries {
+                let args = event
+                    .args
+                    .iter()
+                    .map(|arg| {
+                        arg.value
+                            .as_ref()
+                            .map(|v| {
+                                v.as_str()
+                                    .map(str::to_string)
+                                    .unwrap_or_else(|| v.to_string())
+                            })
+                            .or_else(|| arg.description.clone())
+                            .unwrap_or_default()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                entries.push(format!("[{}] {args}", event.r#type.as_ref()));
+            }
+        }
+        entries
+    }
+
+    /// Collects all network responses and failures that are ready
+    fn drain_network(&mut self) -> Vec<String> {
+        let mut entries = Vec::new();
+        while let Some(Some(event)) = self.responses.next().now_or_never() {
+            if entries.len() < self.config.max_network_entries {
+                entries.push(format!("{} {}", event.response.status, event.response.url));
+            }
+        }
+        while let Some(Some(event)) = self.failures.next().now_or_never() {
+            if entries.len() < self.config.max_network_entries {
+                entries.push(format!(
+                    "failed {} {}",
+                    event.request_id.as_ref(),
+                    event.error_text
+                ));
+            }
+        }
+        entries
+    }
+
+    /// Renders all recorded actions into a self-contained HTML report
+    pub fn render_html(&self) -> String {
+        render_html(&self.actions)
+    }
+
+    /// Renders the HTML report and writes it to `output`
+    pub async fn save_report(&self, output: impl AsRef<Path>) -> Result<String> {
+        let html = self.render_html();
+        utils::write(output.as_ref(), &html).await?;
+        Ok(html)
+    }
+}
+
+/// Renders the actions into a self-contained HTML document, screenshots are
+/// inlined as base64 encoded data urls.
+pub fn render_html(actions: &[TraceAction]) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>chromiumoxide trace</title>\n<style>\
+body{font-family:sans-serif;margin:2em}\
+.action{border:1px solid #ccc;margin-bottom:1em;padding:1em}\
+.action.failed{border-color:#d33}\
+.error{color:#d33}\
+img{max-width:45%;border:1px solid #eee;margin-right:1em}\
+pre{background:#f6f6f6;padding:.5em;overflow-x:auto}\
+</style>\n</head>\n<body>\n<h1>chromiumoxide trace</h1>\n",
+    );
+    for (idx, action) in actions.iter().enumerate() {
+        let class = if action.error.is_some() {
+            "action failed"
+        } else {
+            "action"
+        };
+        let _ = writeln!(
+            html,
+            "<div class=\"{class}\">\n<h2>#{} {} <code>{}</code></h2>\n<p>started at {} ms, took {} ms</p>",
+            idx + 1,
+            action.kind.as_ref(),
+            escape_html(&action.target),
+            action.start.as_millis(),
+            action.duration.as_millis()
+        );
+        if let Some(err) = action.error.as_ref() {
+            let _ = writeln!(html, "<p class=\"error\">{}</p>", escape_html(err));
+        }
+        for img in [&action.screenshot_before, &action.screenshot_after]
+            .into_iter()
+            .flatten()
+        {
+            let _ = writeln!(
+                html,
+                "<img src=\"data:image/png;base64,{}\">",
+                base64::encode(img)
+            );
+        }
+        for (title, entries) in [("Console", &action.console), ("Network", &action.network)] {
+            if !entries.is_empty() {
+                let _ = writeln!(
+                    html,
+                    "<h3>{title}</h3>\n<pre>{}</pre>",
+                    escape_html(&entries.join("\n"))
+                );
+            }
+        }
+        html.push_str("</div>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_actions() {
+        let actions = vec![
+            TraceAction {
+                kind: ActionKind::Goto,
+                target: "https://example.com".to_string(),
+                start: Duration::from_millis(0),
+                duration: Duration::from_millis(120),
+                screenshot_before: None,
+                screenshot_after: Some(vec![1, 2, 3]),
+                console: vec!["[log] <hello>".to_string()],
+                network: vec!["200 https://example.com/".to_string()],
+                error: None,
+            },
+            TraceAction {
+                kind: ActionKind::Click,
+                target: "a[href='/']".to_string(),
+                start: Duration::from_millis(130),
+                duration: Duration::from_millis(5),
+                screenshot_before: None,
+                screenshot_after: None,
+                console: Vec::new(),
+                network: Vec::new(),
+                error: Some("Requested value not found.".to_string()),
+            },
+        ];
+        let html = render_html(&actions);
+        assert!(html.contains("#1 goto <code>https://example.com</code>"));
+        assert!(html.contains("took 120 ms"));
+        assert!(html.contains("data:image/png;base64,AQID"));
+        assert!(html.contains("[log] &lt;hello&gt;"));
+        assert!(html.contains("<div class=\"action failed\">"));
+        assert!(html.contains("a[href=&#39;/&#39;]"));
+        assert_eq!(html.matches("<img").count(), 1);
+    }
+}
@@ -0,0 +1,222 @@
+//! Overriding the user agent together with its client hints.
+//!
+//! Sites that use User-Agent Client Hints read `navigator.userAgentData` and
+//! the `Sec-CH-UA` headers instead of the user agent string, so overriding
+//! only the string is easy to detect. A [`UserAgent`] also overrides
+//! `navigator.platform`, the `Accept-Language` header and the client hints,
+//! which [`UserAgent::with_client_hints`] derives from the user agent string.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::user_agent::UserAgent;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     page.set_user_agent(
+//!         UserAgent::new(
+//!             "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+//!              (KHTML, like Gecko) Chrome/120.0.6099.109 Safari/537.36",
+//!         )
+//!         .accept_language("en-US,en;q=0.9")
+//!         .with_client_hints(),
+//!     )
+//!     .await?;
+//!     # Ok(())
+//! # }
+//! ```
+
+use chromiumoxide_cdp::cdp::browser_protocol::emulation::{
+    UserAgentBrandVersion, UserAgentMetadata,
+};
+use chromiumoxide_cdp::cdp::browser_protocol::network::SetUserAgentOverrideParams;
+
+/// A user agent override, see
+/// [`Page::set_user_agent`](crate::page::Page::set_user_agent).
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserAgent {
+    pub user_agent: String,
+    /// The `Accept-Language` header, like `de-DE,de;q=0.9`
+    pub accept_language: Option<String>,
+    /// The value of `navigator.platform`, like `Win32`
+    pub platform: Option<String>,
+    /// The client hints
+    pub metadata: Option<UserAgentMetadata>,
+}
+
+impl UserAgent {
+    pub fn new(user_agent: impl Into<String>) -> Self {
+        Self {
+            user_agent: user_agent.into(),
+            accept_language: None,
+            platform: None,
+            metadata: None,
+        }
+    }
+
+    pub fn accept_language(mut self, accept_language: impl Into<String>) -> Self {
+        self.accept_language = Some(accept_language.into());
+        self
+    }
+
+    pub fn platform(mut self, platform: impl Into<String>) -> Self {
+        self.platform = Some(platform.into());
+        self
+    }
+
+    pub fn metadata(mut self, metadata: UserAgentMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Derives `navigator.platform` and the client hints from the user agent
+    /// string, unless they are set already. User agents of browsers that
+    /// don't send client hints, like Firefox or Safari, are left as they are.
+    pub fn with_client_hints(mut self) -> Self {
+        if let Some(hints) = ClientHints::parse(&self.user_agent) {
+            self.platform.get_or_insert(hints.navigator_platform);
+            self.metadata.get_or_insert(hints.metadata);
+        }
+        self
+    }
+}
+
+impl From<UserAgent> for SetUserAgentOverrideParams {
+    fn from(user_agent: UserAgent) -> Self {
+        Self {
+            user_agent: user_agent.user_agent,
+            accept_language: user_agent.accept_language,
+            platform: user_agent.platform,
+            user_agent_metadata: user_agent.metadata,
+        }
+    }
+}
+
+/// The identity a Chromium user agent string claims
+struct ClientHints {
+    navigator_platform: String,
+    metadata: UserAgentMetadata,
+}
+
+impl ClientHints {
+    fn parse(user_agent: &str) -> Option<Self> {
+        let full_version = ["Chrome/", "Chromium/"]
+            .iter()
+            .find_map(|product| user_agent.split(product).nth(1))?
+            .split(|c: char| !c.is_ascii_digit() && c != '.')
+            .next()
+            .filter(|version| !version.is_empty())?;
+        // browsers based on Chromium that name themselves differently
+        if user_agent.contains("Edg/") || user_agent.contains("OPR/") {
+            return None;
+        }
+        let major = full_version.split('.').next().unwrap_or(full_version);
+        let system = user_agent.split('(').nth(1)?.split(')').next()?;
+
+        let mobile = user_agent.contains(" Mobile");
+        let (platform, platform_version, navigator_platform, architecture, model) =
+            if let Some(version) = after(system, "Windows NT ") {
+                // the platform version of Windows 11 isn't part of the user agent
+                (
+                    "Windows",
+                    format!("{version}.0"),
+                    "Win32",
+                    "x86",
+                    String::new(),
+                )
+            } else if let Some(version) = after(system, "Mac OS X ") {
+                let version = version.replace('_', ".");
+                ("macOS", version, "MacIntel", "x86", String::new())
+            } else if let Some(version) = after(system, "Android ") {
+                let model = system
+                    .split("; ")
+                    .skip_while(|part| !part.starts_with("Android"))
+                    .nth(1)
+                    .map(|model| model.split(" Build").next().unwrap_or(model))
+                    .filter(|model| *model != "K")
+                    .unwrap_or_default()
+                    .to_string();
+                ("Android", version.to_string(), "Linux armv8l", "", model)
+            } else if system.contains("CrOS") {
+                (
+                    "Chrome OS",
+                    String::new(),
+                    "Linux x86_64",
+                    "x86",
+                    String::new(),
+                )
+            } else if system.contains("Linux") {
+                ("Linux", String::new(), "Linux x86_64", "x86", String::new())
+            } else {
+                return None;
+            };
+
+        let metadata = UserAgentMetadata {
+            brands: vec![
+                UserAgentBrandVersion::new("Not_A Brand", "8"),
+                UserAgentBrandVersion::new("Chromium", major),
+                UserAgentBrandVersion::new("Google Chrome", major),
+            ],
+            full_version: full_version.to_string(),
+            platform: platform.to_string(),
+            platform_version,
+            architecture: architecture.to_string(),
+            model,
+            mobile,
+        };
+        Some(Self {
+            navigator_platform: navigator_platform.to_string(),
+            metadata,
+        })
+    }
+}
+
+/// The version that follows the prefix within a part of the system info
+fn after<'a>(system: &'a str, prefix: &str) -> Option<&'a str> {
+    system
+        .split(';')
+        .find_map(|part| part.trim().strip_prefix(prefix))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_client_hints() {
+        let user_agent = UserAgent::new(
+            "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 \
+             (KHTML, like Gecko) Chrome/120.0.6099.109 Safari/537.36",
+        )
+        .with_client_hints();
+        assert_eq!(user_agent.platform.as_deref(), Some("Win32"));
+        let metadata = user_agent.metadata.unwrap();
+        assert_eq!(metadata.platform, "Windows");
+        assert_eq!(metadata.platform_version, "10.0.0");
+        assert_eq!(metadata.full_version, "120.0.6099.109");
+        assert_eq!(
+            metadata.brands[1],
+            UserAgentBrandVersion::new("Chromium", "120")
+        );
+        assert!(!metadata.mobile);
+
+        let metadata = UserAgent::new(
+            "Mozilla/5.0 (Linux; Android 13; Pixel 7) AppleWebKit/537.36 \
+             (KHTML, like Gecko) Chrome/116.0.0.0 Mobile Safari/537.36",
+        )
+        .with_client_hints()
+        .metadata
+        .unwrap();
+        assert_eq!(metadata.platform, "Android");
+        assert_eq!(metadata.platform_version, "13");
+        assert_eq!(metadata.model, "Pixel 7");
+        assert!(metadata.mobile);
+
+        let firefox = UserAgent::new(
+            "Mozilla/5.0 (X11; Linux x86_64; rv:109.0) Gecko/20100101 Firefox/115.0",
+        )
+        .with_client_hints();
+        assert_eq!(firefox.metadata, None);
+        assert_eq!(firefox.platform, None);
+    }
+}
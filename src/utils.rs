@@ -14,6 +14,77 @@ pub(crate) async fn write<P: AsRef<Path> + Unpin, C: AsRef<[u8]>>(
     }
 }
 
+/// Create a file with configured runtime and write the chunks of `chunks`
+/// to it as they become available
+pub(crate) async fn write_chunks<P, S>(path: P, mut chunks: S) -> crate::error::Result<u64>
+where
+    P: AsRef<Path> + Unpin,
+    S: futures::Stream<Item = crate::error::Result<Vec<u8>>> + Unpin,
+{
+    use futures::StreamExt;
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            use futures::AsyncWriteExt;
+            let mut file = async_std::fs::File::create(path.as_ref()).await?;
+        } else if #[cfg(feature = "tokio-runtime")] {
+            use tokio::io::AsyncWriteExt;
+            let mut file = tokio::fs::File::create(path.as_ref()).await?;
+        }
+    }
+    let mut written = 0;
+    while let Some(chunk) = chunks.next().await {
+        let chunk = chunk?;
+        file.write_all(&chunk).await?;
+        written += chunk.len() as u64;
+    }
+    file.flush().await?;
+    Ok(written)
+}
+
+/// Read a file with configured runtime
+pub(crate) async fn read<P: AsRef<Path> + Unpin>(path: P) -> std::io::Result<Vec<u8>> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            async_std::fs::read(path.as_ref()).await
+        } else if #[cfg(feature = "tokio-runtime")] {
+            tokio::fs::read(path.as_ref()).await
+        }
+    }
+}
+
+/// Create a directory and its missing parents with configured runtime
+pub(crate) async fn create_dir_all<P: AsRef<Path> + Unpin>(path: P) -> std::io::Result<()> {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            async_std::fs::create_dir_all(path.as_ref()).await
+        } else if #[cfg(feature = "tokio-runtime")] {
+            tokio::fs::create_dir_all(path.as_ref()).await
+        }
+    }
+}
+
+/// List the paths of a directory's entries with configured runtime
+pub(crate) async fn read_dir<P: AsRef<Path> + Unpin>(
+    path: P,
+) -> std::io::Result<Vec<std::path::PathBuf>> {
+    let mut paths = Vec::new();
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "async-std-runtime")] {
+            use futures::StreamExt;
+            let mut entries = async_std::fs::read_dir(path.as_ref()).await?;
+            while let Some(entry) = entries.next().await {
+                paths.push(entry?.path().into());
+            }
+        } else if #[cfg(feature = "tokio-runtime")] {
+            let mut entries = tokio::fs::read_dir(path.as_ref()).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                paths.push(entry.path());
+            }
+        }
+    }
+    Ok(paths)
+}
+
 /// Creates a javascript function string as `(<function>)("<param 1>", "<param
 /// 2>")`
 pub fn evaluation_string(function: impl AsRef<str>, params: &[impl AsRef<str>]) -> String {
@@ -49,6 +120,69 @@ pub fn is_likely_js_function(function: impl AsRef<str>) -> bool {
     false
 }
 
+/// Whether the JSON representation of a javascript value is truthy
+pub(crate) fn is_truthy(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Null => false,
+        serde_json::Value::Bool(b) => *b,
+        serde_json::Value::Number(n) => n.as_f64().map(|n| n != 0.).unwrap_or(true),
+        serde_json::Value::String(s) => !s.is_empty(),
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => true,
+    }
+}
+
+/// Decodes the text with the charset of the `Content-Type` or, for html
+/// documents, of their `<meta>` tag. Without the `charset` feature, or if no
+/// charset is declared, the text is decoded as UTF-8.
+pub(crate) fn decode_text(body: &[u8], content_type: Option<&str>) -> String {
+    cfg_if::cfg_if! {
+        if #[cfg(feature = "charset")] {
+            let label = content_type.and_then(charset_param).map(str::to_string).or_else(|| {
+                content_type
+                    .is_none_or(|content_type| content_type.contains("html"))
+                    .then(|| meta_charset(body))
+                    .flatten()
+            });
+            let encoding = label
+                .and_then(|label| encoding_rs::Encoding::for_label(label.as_bytes()))
+                .unwrap_or(encoding_rs::UTF_8);
+            // a byte order mark takes precedence over the declared charset
+            encoding.decode(body).0.into_owned()
+        } else {
+            let _ = content_type;
+            String::from_utf8_lossy(body).into_owned()
+        }
+    }
+}
+
+/// The `charset` parameter of a `Content-Type` header value
+#[cfg(feature = "charset")]
+fn charset_param(content_type: &str) -> Option<&str> {
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+    })
+}
+
+/// The charset declared by a `<meta charset>` or `<meta http-equiv>` tag in
+/// the first 1024 bytes of an html document
+#[cfg(feature = "charset")]
+fn meta_charset(body: &[u8]) -> Option<String> {
+    let head = String::from_utf8_lossy(&body[..body.len().min(1024)]).to_ascii_lowercase();
+    head.match_indices("<meta").find_map(|(start, _)| {
+        let tag = &head[start..start + head[start..].find('>')?];
+        let value = &tag[tag.find("charset")? + "charset".len()..];
+        let value = value.trim_start().strip_prefix('=')?.trim_start();
+        let value = value.trim_start_matches(['"', '\'']);
+        let end = value
+            .find(|c: char| !(c.is_ascii_alphanumeric() || "-_:.".contains(c)))
+            .unwrap_or(value.len());
+        (end > 0).then(|| value[..end].to_string())
+    })
+}
+
 /// This attempts to strip any leading pair of parentheses from the input
 ///
 /// `()=>` -> `=>`
@@ -89,4 +223,51 @@ mod tests {
         assert!(is_likely_js_function("((abc), (def)) => {}"));
         assert!(is_likely_js_function("() => Promise.resolve(100 / 25)"));
     }
+
+    #[test]
+    fn truthy_values() {
+        use serde_json::json;
+        assert!(is_truthy(&json!(true)));
+        assert!(is_truthy(&json!(1)));
+        assert!(is_truthy(&json!("a")));
+        assert!(is_truthy(&json!([])));
+        assert!(is_truthy(&json!({})));
+        assert!(!is_truthy(&json!(null)));
+        assert!(!is_truthy(&json!(false)));
+        assert!(!is_truthy(&json!(0)));
+        assert!(!is_truthy(&json!(0.0)));
+        assert!(!is_truthy(&json!("")));
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn decodes_declared_charsets() {
+        assert_eq!(
+            charset_param("text/html; charset=\"ISO-8859-1\""),
+            Some("ISO-8859-1")
+        );
+        assert_eq!(charset_param("text/html"), None);
+        assert_eq!(
+            meta_charset(br#"<html><head><meta charset="Shift_JIS"></head>"#).as_deref(),
+            Some("shift_jis")
+        );
+        assert_eq!(
+            meta_charset(
+                br#"<meta http-equiv="Content-Type" content="text/html; charset=windows-1251">"#
+            )
+            .as_deref(),
+            Some("windows-1251")
+        );
+        assert_eq!(meta_charset(b"<meta name=\"viewport\">"), None);
+
+        assert_eq!(
+            decode_text(b"caf\xe9", Some("text/plain; charset=latin1")),
+            "caf\u{e9}"
+        );
+        assert_eq!(
+            decode_text(b"<meta charset=latin1>caf\xe9", Some("text/html")),
+            "<meta charset=latin1>caf\u{e9}"
+        );
+        assert_eq!(decode_text("café".as_bytes(), None), "café");
+    }
 }
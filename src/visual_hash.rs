@@ -0,0 +1,107 @@
+//! Perceptual hashes of page and element captures for change detection.
+//!
+//! A [`VisualHash`] is a difference hash (dHash) of a screenshot: the capture
+//! is shrunk to 9x8 grayscale pixels and every bit records whether a pixel is
+//! brighter than its right neighbour. Captures that look alike have hashes
+//! with a small [`distance`](VisualHash::distance), regardless of
+//! compression artifacts or small rendering differences, so a crawl can skip
+//! extracting content until a page visually changes.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::visual_hash::VisualHash;
+//! # async fn demo(page: Page, previous: VisualHash) -> Result<()> {
+//!     let hash = page.visual_hash().await?;
+//!     if hash.distance(&previous) > 10 {
+//!         let content = page.content().await?;
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::str::FromStr;
+
+use image::imageops::FilterType;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/// A 64 bit perceptual hash of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct VisualHash(u64);
+
+impl VisualHash {
+    /// Computes the difference hash of the image.
+    pub fn of(image: &DynamicImage) -> Self {
+        let pixels = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+        let mut hash = 0;
+        for y in 0..8 {
+            for x in 0..8 {
+                hash <<= 1;
+                if pixels.get_pixel(x, y)[0] > pixels.get_pixel(x + 1, y)[0] {
+                    hash |= 1;
+                }
+            }
+        }
+        Self(hash)
+    }
+
+    /// The bits of the hash.
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// The number of bits that differ between the hashes, from 0 for images
+    /// that look alike up to 64.
+    pub fn distance(&self, other: &VisualHash) -> u32 {
+        (self.0 ^ other.0).count_ones()
+    }
+}
+
+impl From<u64> for VisualHash {
+    fn from(bits: u64) -> Self {
+        Self(bits)
+    }
+}
+
+impl fmt::Display for VisualHash {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for VisualHash {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+
+    fn gradient(width: u32, height: u32, reversed: bool) -> DynamicImage {
+        DynamicImage::ImageLuma8(GrayImage::from_fn(width, height, |x, _| {
+            let value = (x * 255 / (width - 1)) as u8;
+            Luma([if reversed { 255 - value } else { value }])
+        }))
+    }
+
+    #[test]
+    fn hashes_look_alike_images() {
+        let hash = VisualHash::of(&gradient(64, 64, false));
+        assert_eq!(
+            hash.distance(&VisualHash::of(&gradient(640, 320, false))),
+            0
+        );
+        assert_eq!(hash.distance(&VisualHash::of(&gradient(64, 64, true))), 64);
+        assert_eq!(hash.to_string().parse::<VisualHash>().unwrap(), hash);
+    }
+}
@@ -0,0 +1,349 @@
+//! Observing the WebSocket traffic of a page.
+//!
+//! [`Page::websocket_events`](crate::page::Page::websocket_events) merges the
+//! `Network.webSocket*` events of a page into a single stream of
+//! [`WebSocketEvent`]s. Every event carries the id of its socket, messages
+//! also the url of the socket and their decoded [`WebSocketPayload`].
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use chromiumoxide::websocket::WebSocketEvent;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut sockets = page.websocket_events().await?;
+//!     page.goto("https://example.com/chat").await?;
+//!     while let Some(event) = sockets.next().await {
+//!         if let WebSocketEvent::Received(message) = event {
+//!             if let Some(text) = message.payload.text() {
+//!                 println!("{:?}: {}", message.url, text);
+//!             }
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::stream::{SelectAll, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+
+use chromiumoxide_cdp::cdp::browser_protocol::network::{
+    EventWebSocketClosed, EventWebSocketCreated, EventWebSocketFrameError,
+    EventWebSocketFrameReceived, EventWebSocketFrameSent, RequestId, WebSocketFrame,
+};
+
+use crate::listeners::EventStream;
+
+/// The opcode of text frames
+const TEXT_OPCODE: f64 = 1.;
+
+/// The payload of a WebSocket message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketPayload {
+    Text(String),
+    Binary(Vec<u8>),
+}
+
+impl WebSocketPayload {
+    /// Decodes the payload of the frame, binary payloads are base64 encoded
+    /// by the browser.
+    pub fn new(frame: &WebSocketFrame) -> Self {
+        if frame.opcode == TEXT_OPCODE {
+            WebSocketPayload::Text(frame.payload_data.clone())
+        } else {
+            match base64::decode(&frame.payload_data) {
+                Ok(bytes) => WebSocketPayload::Binary(bytes),
+                Err(_) => WebSocketPayload::Binary(frame.payload_data.clone().into_bytes()),
+            }
+        }
+    }
+
+    /// The payload of a text message.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            WebSocketPayload::Text(text) => Some(text),
+            WebSocketPayload::Binary(_) => None,
+        }
+    }
+
+    /// The raw bytes of the payload.
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            WebSocketPayload::Text(text) => text.as_bytes(),
+            WebSocketPayload::Binary(bytes) => bytes,
+        }
+    }
+
+    /// Deserializes the payload from JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> serde_json::Result<T> {
+        serde_json::from_slice(self.as_bytes())
+    }
+}
+
+/// A message sent or received through a WebSocket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebSocketMessage {
+    /// The id of the socket
+    pub request_id: RequestId,
+    /// The url of the socket, `None` if it was created before the stream
+    pub url: Option<String>,
+    /// The opcode of the frame, `1` for text and `2` for binary messages
+    pub opcode: f64,
+    pub payload: WebSocketPayload,
+    /// Seconds since an arbitrary point in the past
+    pub timestamp: f64,
+}
+
+impl WebSocketMessage {
+    fn new(
+        request_id: &RequestId,
+        url: Option<String>,
+        frame: &WebSocketFrame,
+        timestamp: f64,
+    ) -> Self {
+        Self {
+            request_id: request_id.clone(),
+            url,
+            opcode: frame.opcode,
+            payload: WebSocketPayload::new(frame),
+            timestamp,
+        }
+    }
+}
+
+/// An event of a WebSocket of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub enum WebSocketEvent {
+    /// A socket was created
+    Created { request_id: RequestId, url: String },
+    /// The page sent a message
+    Sent(WebSocketMessage),
+    /// The page received a message
+    Received(WebSocketMessage),
+    /// A frame couldn't be sent or received
+    Error {
+        request_id: RequestId,
+        url: Option<String>,
+        message: String,
+    },
+    /// A socket was closed
+    Closed {
+        request_id: RequestId,
+        url: Option<String>,
+    },
+}
+
+impl WebSocketEvent {
+    /// The id of the socket this event is about.
+    pub fn request_id(&self) -> &RequestId {
+        match self {
+            WebSocketEvent::Created { request_id, .. } => request_id,
+            WebSocketEvent::Sent(message) => &message.request_id,
+            WebSocketEvent::Received(message) => &message.request_id,
+            WebSocketEvent::Error { request_id, .. } => request_id,
+            WebSocketEvent::Closed { request_id, .. } => request_id,
+        }
+    }
+
+    /// The url of the socket, if known.
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            WebSocketEvent::Created { url, .. } => Some(url),
+            WebSocketEvent::Sent(message) | WebSocketEvent::Received(message) => {
+                message.url.as_deref()
+            }
+            WebSocketEvent::Error { url, .. } | WebSocketEvent::Closed { url, .. } => {
+                url.as_deref()
+            }
+        }
+    }
+
+    /// The message that was sent or received, if any.
+    pub fn message(&self) -> Option<&WebSocketMessage> {
+        match self {
+            WebSocketEvent::Sent(message) | WebSocketEvent::Received(message) => Some(message),
+            _ => None,
+        }
+    }
+}
+
+/// The `Network` event that is turned into a `WebSocketEvent`
+#[derive(Debug)]
+enum RawEvent {
+    Created(Arc<EventWebSocketCreated>),
+    Sent(Arc<EventWebSocketFrameSent>),
+    Received(Arc<EventWebSocketFrameReceived>),
+    Error(Arc<EventWebSocketFrameError>),
+    Closed(Arc<EventWebSocketClosed>),
+}
+
+/// A stream of the WebSocket events of a page.
+#[must_use = "streams do nothing unless polled"]
+pub struct WebSocketEventStream {
+    events: SelectAll<Pin<Box<dyn Stream<Item = RawEvent> + Send>>>,
+    /// The urls of the open sockets
+    urls: HashMap<RequestId, String>,
+}
+
+impl WebSocketEventStream {
+    pub(crate) fn new(
+        created: EventStream<EventWebSocketCreated>,
+        sent: EventStream<EventWebSocketFrameSent>,
+        received: EventStream<EventWebSocketFrameReceived>,
+        errors: EventStream<EventWebSocketFrameError>,
+        closed: EventStream<EventWebSocketClosed>,
+    ) -> Self {
+        let mut events = SelectAll::new();
+        events.push(created.map(RawEvent::Created).boxed());
+        events.push(sent.map(RawEvent::Sent).boxed());
+        events.push(received.map(RawEvent::Received).boxed());
+        events.push(errors.map(RawEvent::Error).boxed());
+        events.push(closed.map(RawEvent::Closed).boxed());
+        Self {
+            events,
+            urls: Default::default(),
+        }
+    }
+
+    /// Only yields the events of the socket.
+    pub fn socket(self, request_id: RequestId) -> impl Stream<Item = WebSocketEvent> + Send {
+        self.filter(move |event| futures::future::ready(*event.request_id() == request_id))
+    }
+
+    fn on_event(&mut self, event: RawEvent) -> WebSocketEvent {
+        match event {
+            RawEvent::Created(ev) => {
+                self.urls.insert(ev.request_id.clone(), ev.url.clone());
+                WebSocketEvent::Created {
+                    request_id: ev.request_id.clone(),
+                    url: ev.url.clone(),
+                }
+            }
+            RawEvent::Sent(ev) => WebSocketEvent::Sent(WebSocketMessage::new(
+                &ev.request_id,
+                self.urls.get(&ev.request_id).cloned(),
+                &ev.response,
+                *ev.timestamp.inner(),
+            )),
+            RawEvent::Received(ev) => WebSocketEvent::Received(WebSocketMessage::new(
+                &ev.request_id,
+                self.urls.get(&ev.request_id).cloned(),
+                &ev.response,
+                *ev.timestamp.inner(),
+            )),
+            RawEvent::Error(ev) => WebSocketEvent::Error {
+                request_id: ev.request_id.clone(),
+                url: self.urls.get(&ev.request_id).cloned(),
+                message: ev.error_message.clone(),
+            },
+            RawEvent::Closed(ev) => WebSocketEvent::Closed {
+                request_id: ev.request_id.clone(),
+                url: self.urls.remove(&ev.request_id),
+            },
+        }
+    }
+}
+
+impl fmt::Debug for WebSocketEventStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketEventStream")
+            .field("urls", &self.urls)
+            .finish()
+    }
+}
+
+impl Stream for WebSocketEventStream {
+    type Item = WebSocketEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        match this.events.poll_next_unpin(cx) {
+            Poll::Ready(Some(event)) => Poll::Ready(Some(this.on_event(event))),
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::channel::mpsc::unbounded;
+    use futures::SinkExt;
+
+    use crate::listeners::Sequenced;
+
+    use super::*;
+
+    #[async_std::test]
+    async fn tracks_sockets() {
+        let (mut created_tx, created) = unbounded();
+        let (_sent_tx, sent) = unbounded();
+        let (mut received_tx, received) = unbounded();
+        let (_errors_tx, errors) = unbounded();
+        let (mut closed_tx, closed) = unbounded();
+        let mut stream = WebSocketEventStream::new(
+            EventStream::new(created),
+            EventStream::new(sent),
+            EventStream::new(received),
+            EventStream::new(errors),
+            EventStream::new(closed),
+        );
+
+        let ev: EventWebSocketCreated = serde_json::from_value(serde_json::json!({
+            "requestId": "1",
+            "url": "wss://example.com/chat",
+        }))
+        .unwrap();
+        created_tx
+            .send(Sequenced::new(1, Arc::new(ev)))
+            .await
+            .unwrap();
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.url(), Some("wss://example.com/chat"));
+
+        let ev: EventWebSocketFrameReceived = serde_json::from_value(serde_json::json!({
+            "requestId": "1",
+            "timestamp": 1.5,
+            "response": {"opcode": 1, "mask": false, "payloadData": r#"{"id":7}"#},
+        }))
+        .unwrap();
+        received_tx
+            .send(Sequenced::new(1, Arc::new(ev)))
+            .await
+            .unwrap();
+        let event = stream.next().await.unwrap();
+        let message = event.message().unwrap();
+        assert_eq!(message.url.as_deref(), Some("wss://example.com/chat"));
+        let value: serde_json::Value = message.payload.json().unwrap();
+        assert_eq!(value, serde_json::json!({"id": 7}));
+
+        let ev: EventWebSocketClosed = serde_json::from_value(serde_json::json!({
+            "requestId": "1",
+            "timestamp": 2.0,
+        }))
+        .unwrap();
+        closed_tx
+            .send(Sequenced::new(1, Arc::new(ev)))
+            .await
+            .unwrap();
+        let event = stream.next().await.unwrap();
+        assert_eq!(event.url(), Some("wss://example.com/chat"));
+        assert!(stream.urls.is_empty());
+    }
+
+    #[test]
+    fn decodes_binary_payloads() {
+        let frame = WebSocketFrame::new(2., false, base64::encode([0u8, 1, 2]));
+        let payload = WebSocketPayload::new(&frame);
+        assert_eq!(payload, WebSocketPayload::Binary(vec![0, 1, 2]));
+        assert_eq!(payload.text(), None);
+    }
+}
@@ -0,0 +1,180 @@
+//! Dedicated, shared and service workers of a page.
+//!
+//! The workers a page starts are attached to it, like its out-of-process
+//! iframes. [`Page::workers`](crate::page::Page::workers) returns handles to
+//! the running ones and
+//! [`Page::on_worker_created`](crate::page::Page::on_worker_created) reports
+//! new ones. A [`Worker`] evaluates scripts in the worker's global scope and
+//! streams its console messages and uncaught exceptions.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use chromiumoxide::page::Page;
+//! # use chromiumoxide::error::Result;
+//! # use futures::StreamExt;
+//! # async fn demo(page: Page) -> Result<()> {
+//!     let mut workers = page.on_worker_created().await?;
+//!     page.goto("https://example.com").await?;
+//!     if let Some(worker) = workers.next().await {
+//!         let mut console = worker.on_console().await?;
+//!         let name: String = worker.evaluate("self.name").await?.into_value()?;
+//!         while let Some(message) = console.next().await {
+//!             println!("{name}: {:?}", message.args);
+//!         }
+//!     }
+//!     # Ok(())
+//! # }
+//! ```
+
+use std::fmt;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::channel::mpsc::unbounded;
+use futures::stream::{Stream, StreamExt};
+use futures::SinkExt;
+
+use chromiumoxide_cdp::cdp::browser_protocol::target::{
+    EventAttachedToTarget, SessionId, TargetId, TargetInfo,
+};
+use chromiumoxide_cdp::cdp::js_protocol::runtime::{
+    EvaluateParams, EventConsoleApiCalled, EventExceptionThrown,
+};
+use chromiumoxide_cdp::cdp::IntoEventKind;
+
+use crate::console::ConsoleStream;
+use crate::error::{CdpError, Result};
+use crate::handler::target::{is_worker, TargetMessage};
+use crate::handler::PageInner;
+use crate::js::EvaluationResult;
+use crate::listeners::{EventListenerRequest, EventStream};
+
+/// A handle to a worker of a page.
+#[derive(Debug, Clone)]
+pub struct Worker {
+    /// The page, sending its commands within the worker's session
+    inner: Arc<PageInner>,
+    info: Arc<TargetInfo>,
+}
+
+impl Worker {
+    pub(crate) fn new(page: &PageInner, session_id: SessionId, info: TargetInfo) -> Self {
+        Self {
+            inner: Arc::new(page.with_session(session_id)),
+            info: Arc::new(info),
+        }
+    }
+
+    /// The identifier of the worker's target
+    pub fn target_id(&self) -> &TargetId {
+        &self.info.target_id
+    }
+
+    /// Whether this is a `worker`, `shared_worker` or `service_worker`
+    pub fn r#type(&self) -> &str {
+        &self.info.r#type
+    }
+
+    /// The url of the worker's script
+    pub fn url(&self) -> &str {
+        &self.info.url
+    }
+
+    /// The info of the worker's target when it was attached
+    pub fn info(&self) -> &TargetInfo {
+        &self.info
+    }
+
+    /// Evaluates the expression in the worker's global scope and returns
+    /// the result, awaiting returned promises.
+    pub async fn evaluate(&self, evaluate: impl Into<EvaluateParams>) -> Result<EvaluationResult> {
+        let mut evaluate = evaluate.into();
+        if evaluate.await_promise.is_none() {
+            evaluate.await_promise = Some(true);
+        }
+        if evaluate.return_by_value.is_none() {
+            evaluate.return_by_value = Some(true);
+        }
+        let resp = self.inner.execute(evaluate).await?.result;
+        if let Some(exception) = resp.exception_details {
+            return Err(CdpError::JavascriptException(Box::new(exception)));
+        }
+        Ok(EvaluationResult::new(resp.result))
+    }
+
+    /// Returns a stream of the events of the worker's session.
+    pub async fn event_listener<T: IntoEventKind>(&self) -> Result<EventStream<T>> {
+        let (tx, rx) = unbounded();
+        let req = EventListenerRequest::new::<T>(tx);
+        let control = req.control().clone();
+        self.inner
+            .sender()
+            .clone()
+            .send(TargetMessage::AddWorkerEventListener(
+                self.inner.session_id().clone(),
+                req,
+            ))
+            .await?;
+        Ok(EventStream::new(rx).with_control(control))
+    }
+
+    /// Returns a stream of the worker's console messages.
+    pub async fn on_console(&self) -> Result<EventStream<EventConsoleApiCalled>> {
+        self.event_listener().await
+    }
+
+    /// Returns a stream of the worker's console messages, with their
+    /// arguments resolved to JSON.
+    pub async fn console_messages(&self) -> Result<ConsoleStream> {
+        let events = self.on_console().await?;
+        Ok(ConsoleStream::new(self.inner.clone(), events))
+    }
+
+    /// Returns a stream of the exceptions the worker's scripts didn't catch.
+    pub async fn on_error(&self) -> Result<EventStream<EventExceptionThrown>> {
+        self.event_listener().await
+    }
+}
+
+/// A stream of the workers a page starts.
+#[must_use = "streams do nothing unless polled"]
+pub struct WorkerStream {
+    page: Arc<PageInner>,
+    events: EventStream<EventAttachedToTarget>,
+}
+
+impl WorkerStream {
+    pub(crate) fn new(page: Arc<PageInner>, events: EventStream<EventAttachedToTarget>) -> Self {
+        Self { page, events }
+    }
+}
+
+impl fmt::Debug for WorkerStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkerStream").finish()
+    }
+}
+
+impl Stream for WorkerStream {
+    type Item = Worker;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            match this.events.poll_next_unpin(cx) {
+                Poll::Ready(Some(event)) if is_worker(&event.target_info.r#type) => {
+                    return Poll::Ready(Some(Worker::new(
+                        &this.page,
+                        event.session_id.clone(),
+                        event.target_info.clone(),
+                    )))
+                }
+                Poll::Ready(Some(_)) => continue,
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}